@@ -1,22 +1,48 @@
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::ListState;
+use regex::RegexBuilder;
 use tokio::sync::mpsc;
+use unicode_width::UnicodeWidthStr;
 
+use crate::clipboard;
 use crate::editor::TextBuffer;
+use crate::events::{self, EventSocket};
+use crate::filters;
+use crate::inbox::{self, SubmitRequest};
+use crate::notify;
+use crate::webhook;
 use crate::keymap::{
-    self, FilterAction, InsertAction, InteractAction, Keymap, NormalAction, ViewAction,
+    self, FilterAction, InsertAction, InteractAction, KeyCombo, Keymap, NormalAction, SequenceMatch,
+    ViewAction,
 };
 use crate::worktree;
 use crate::persistence;
-use crate::prompt::{Prompt, PromptMode, PromptStatus};
+use crate::process;
+use crate::prompt::{Prompt, PromptMode, PromptPriority, PromptStatus};
 use crate::pty_worker::{self, PtyHandle};
-use crate::worker::{WorkerInput, WorkerMessage};
+use crate::theme::Theme;
+use crate::worker::{AgentCommand, OutputStream, WorkerInput, WorkerMessage};
+
+/// How long a half-typed key sequence waits for its next key.
+const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Line between a resumed prompt's earlier output and the new run's.
+const RESUME_SEPARATOR: &str = "── resumed ──\n";
+
+/// Default for the `input_reminder_secs` setting.
+const DEFAULT_INPUT_REMINDER_SECS: u64 = 60;
+
+/// Most worker messages applied between two frames; the rest wait for the
+/// next one so keys stay responsive during an output burst.
+const MAX_MESSAGES_PER_FRAME: usize = 1024;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
@@ -29,6 +55,137 @@ pub enum AppMode {
     Filter,
 }
 
+/// Panel that `j`/`k` drive in Normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusedPane {
+    List,
+    /// j/k scroll the output panel; the selection stays put.
+    Output,
+}
+
+/// Display order of the prompt list. Only the view is reordered; dispatch
+/// still follows priority and `queue_rank`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortMode {
+    Queue,
+    /// Running, idle, pending, failed, completed.
+    Status,
+    /// Longest elapsed first; prompts that never ran last.
+    Elapsed,
+    /// Newest (highest id) first.
+    Id,
+    /// `display_rank` order, rearranged with J/K regardless of status.
+    Manual,
+}
+
+impl SortMode {
+    pub fn label(&self) -> &str {
+        match self {
+            SortMode::Queue => "queue",
+            SortMode::Status => "status",
+            SortMode::Elapsed => "elapsed",
+            SortMode::Id => "newest",
+            SortMode::Manual => "manual",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SortMode::Queue => SortMode::Status,
+            SortMode::Status => SortMode::Elapsed,
+            SortMode::Elapsed => SortMode::Id,
+            SortMode::Id => SortMode::Manual,
+            SortMode::Manual => SortMode::Queue,
+        }
+    }
+}
+
+fn status_rank(status: &PromptStatus) -> u8 {
+    match status {
+        PromptStatus::Running => 0,
+        PromptStatus::Idle => 1,
+        PromptStatus::Pending => 2,
+        PromptStatus::Failed => 3,
+        PromptStatus::Completed => 4,
+    }
+}
+
+/// A row of the prompt list. Group headers only appear in the grouped view.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListRow {
+    /// Tag group header; `first` is the prompt that stands in for the whole
+    /// group when it is collapsed.
+    Group {
+        tag: String,
+        count: usize,
+        collapsed: bool,
+        first: usize,
+    },
+    Prompt(usize),
+}
+
+/// Remove a worktree via the repo it belongs to.
+fn remove_sibling_worktree(wt_path: &Path) {
+    if let Some(root) = worktree::owning_repo(wt_path) {
+        let _ = worktree::remove_worktree(&root, wt_path);
+    }
+}
+
+/// Group key for the grouped view: the prompt's first tag, or "" if untagged.
+pub fn group_tag(prompt: &Prompt) -> &str {
+    prompt.tags.first().map(String::as_str).unwrap_or("")
+}
+
+/// Diff of a prompt's worktree, shown in the output panel in place of its
+/// output (see `ViewAction::WorktreeDiff`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorktreeDiffView {
+    pub prompt_id: usize,
+    /// `None` while `git diff` runs in the background.
+    pub diff: Option<String>,
+}
+
+/// State of a Ctrl+R reverse history search in insert mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistorySearch {
+    pub query: String,
+    /// Index into `history` of the match shown in the input.
+    pub matched: Option<usize>,
+    /// Input from before the search, restored by Esc.
+    stash: String,
+}
+
+/// What `App::attention_summary` counts. `unseen` includes the failed
+/// ones, which `failed_unseen` breaks out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AttentionSummary {
+    pub running: usize,
+    pub idle: usize,
+    pub unseen: usize,
+    pub failed_unseen: usize,
+}
+
+impl AttentionSummary {
+    /// The nonzero counts, e.g. "3 running · 2 idle · 1 unseen (1 failed)".
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.running > 0 {
+            parts.push(format!("{} running", self.running));
+        }
+        if self.idle > 0 {
+            parts.push(format!("{} idle", self.idle));
+        }
+        if self.unseen > 0 {
+            parts.push(if self.failed_unseen > 0 {
+                format!("{} unseen ({} failed)", self.unseen, self.failed_unseen)
+            } else {
+                format!("{} unseen", self.unseen)
+            });
+        }
+        parts.join(" · ")
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum WorktreeCleanup {
     Manual,
@@ -40,6 +197,55 @@ pub struct App {
     pub next_id: usize,
     pub max_workers: usize,
     pub active_workers: usize,
+    /// When set, no new workers are dispatched; running workers keep going.
+    pub paused: bool,
+    /// Set by `clhorde tui drain`: nothing new is dispatched for the rest of
+    /// the session, and `quit_when_drained` quits once the workers are done.
+    pub draining: bool,
+    pub quit_when_drained: bool,
+    /// Per-turn timeout applied to new prompts (from `default_timeout_secs` setting).
+    pub default_timeout_secs: Option<u64>,
+    /// Attempts given to new prompts before a failure is final (from `max_attempts` setting).
+    pub default_max_attempts: u32,
+    /// Base delay before an automatic retry, doubled per attempt (from `retry_delay_secs` setting).
+    pub retry_delay: Duration,
+    /// Agent program and extra args used when spawning workers.
+    pub agent: AgentCommand,
+    /// Fire desktop notifications when prompts finish (from `notifications` setting).
+    pub notifications: bool,
+    /// Capture mouse clicks/scrolling (from `mouse` setting, off by default).
+    pub mouse: bool,
+    /// Show the attention counts in the terminal title (from `terminal_title` setting).
+    pub terminal_title: bool,
+    /// Ring the terminal bell when prompts finish (from `bell_on_complete` setting).
+    pub bell_on_complete: bool,
+    /// A finished prompt wants the bell; the main loop rings it between
+    /// frames so the byte never lands inside a draw.
+    pub pending_bell: bool,
+    /// Counts behind the last title set, so it's only rewritten on change.
+    title_summary: Option<AttentionSummary>,
+    /// Max concurrent workers per tag (from `tag_limits` setting); `max_workers` still applies.
+    pub tag_limits: HashMap<String, usize>,
+    /// Time a killed worker gets between SIGTERM and SIGKILL (from `kill_grace_ms` setting).
+    pub kill_grace: Duration,
+    /// How long an agent waits on the user before it's flagged as waiting
+    /// (from `input_reminder_secs` setting); `None` turns the reminder off.
+    pub input_reminder: Option<Duration>,
+    /// Minimum time between two worker spawns (from `spawn_interval_ms` setting).
+    pub spawn_interval: Duration,
+    /// When the dispatch loop last started a worker.
+    pub last_spawn: Option<Instant>,
+    /// URL each finished prompt is POSTed to (from `completion_webhook_url` setting).
+    pub completion_webhook_url: Option<String>,
+    /// Read-only NDJSON event stream for external tools (from `event_socket` setting).
+    pub event_socket: Option<EventSocket>,
+    /// False once a prompt file failed to save; the session then carries
+    /// on in memory only and nothing more is written.
+    pub persistence_healthy: Cell<bool>,
+    /// Save error not yet shown in the status bar.
+    persistence_error: RefCell<Option<String>>,
+    /// Raw output kept per PTY worker for the finished transcript (from `pty_scrollback_bytes`).
+    pub pty_scrollback_bytes: usize,
     pub mode: AppMode,
     pub list_state: ListState,
     pub input: TextBuffer,
@@ -50,6 +256,18 @@ pub struct App {
     pub worker_inputs: HashMap<usize, mpsc::UnboundedSender<WorkerInput>>,
     pub interact_input: String,
     pub auto_scroll: bool,
+    /// Whether the output view sat at its bottom row after the last render
+    /// or scroll. `auto_scroll` only follows new output while this holds.
+    pub output_at_bottom: bool,
+    /// Largest `scroll_offset` of the output view at the last render
+    /// (wrapped rows minus panel height).
+    pub output_scroll_max: u16,
+    /// Inner width of the output view at the last render (0 before the
+    /// first), used to find the wrapped row of a search match.
+    pub output_width: u16,
+    /// Wrapped height of the output view, keyed by what it was computed
+    /// from, so an unchanged output isn't re-wrapped every frame.
+    pub output_rows: Option<(OutputRowsKey, u16)>,
     pub suggestions: Vec<String>,
     pub suggestion_index: usize,
     /// Tick counter incremented every 100ms, used for pulsing animations.
@@ -66,12 +284,27 @@ pub struct App {
     pub filter_input: String,
     /// Cached list of prompt indices matching the current filter.
     pub filtered_indices: Vec<usize>,
+    /// Whether the filter's `/regex/` failed to compile (substring fallback).
+    pub filter_regex_invalid: bool,
+    /// Named filters from filters.toml, recalled with `!name`.
+    pub saved_filters: HashMap<String, String>,
+    /// Search query being typed in view mode (Some while the `/` prompt is open).
+    pub output_search_input: Option<String>,
+    /// Worktree diff replacing the output panel until closed or the
+    /// selection changes.
+    pub worktree_diff: Option<WorktreeDiffView>,
+    /// Active output search query (case-insensitive), highlighted in the viewer.
+    pub output_search: Option<String>,
+    /// Line index of the current search match in the output view.
+    pub output_search_line: Option<usize>,
     /// Command history (most recent last).
     pub history: Vec<String>,
     /// Current position in history navigation (None = not navigating).
     pub history_index: Option<usize>,
     /// Stashed input text when entering history navigation.
     pub history_stash: String,
+    /// Active Ctrl+R search (None when not searching).
+    pub history_search: Option<HistorySearch>,
     /// Prompt templates loaded from config.
     pub templates: HashMap<String, String>,
     /// Template suggestion names matching current input.
@@ -80,29 +313,61 @@ pub struct App {
     pub template_suggestion_index: usize,
     /// Whether the quick prompts popup is visible (toggled by Ctrl+P in view mode).
     pub show_quick_prompts_popup: bool,
+    /// Highlighted row among `quick_prompt_matches` while the popup is open.
+    pub quick_prompt_index: usize,
+    /// Text typed into the open quick prompts popup to narrow it.
+    pub quick_prompt_filter: String,
     /// PTY handles for interactive workers (keyed by prompt_id).
     pub pty_handles: HashMap<usize, PtyHandle>,
     /// Size of the output panel (cols, rows) from last render.
     pub output_panel_size: Option<(u16, u16)>,
-    /// Last PTY size sent to workers (for change detection).
-    pub last_pty_size: Option<(u16, u16)>,
+    /// Prompt whose PTY the output panel showed in the last render.
+    pub viewed_pty: Option<usize>,
+    /// Size last sent to each PTY worker (for change detection).
+    pub pty_sizes: HashMap<usize, (u16, u16)>,
     /// Maximum number of prompt files to keep on disk.
     #[allow(dead_code)]
     pub max_saved_prompts: usize,
+    /// Maximum bytes of output stored per prompt file (from `max_saved_output_bytes` setting).
+    pub max_saved_output_bytes: usize,
+    /// Maximum bytes of output held in memory per prompt (from `max_output_bytes` setting).
+    pub max_output_bytes: usize,
     /// Directory for prompt persistence files (None = persistence disabled).
     pub prompts_dir: Option<PathBuf>,
+    /// Where worker count, default mode and pause survive restarts (None = disabled).
+    pub state_path: Option<PathBuf>,
+    /// Directory polled for prompts submitted via `clhorde submit` (None = disabled).
+    pub inbox_dir: Option<PathBuf>,
+    /// Open `<id>.log` files mirroring running prompts' output.
+    pub output_mirror: inbox::OutputMirror,
     /// Whether the next submitted prompt should use a git worktree.
     pub worktree_pending: bool,
     /// Worktree cleanup policy.
     pub worktree_cleanup: WorktreeCleanup,
+    /// Branch name template for new worktrees (e.g. `clhorde/{id}-{slug}`);
+    /// `None` creates detached worktrees.
+    pub worktree_branch_template: Option<String>,
+    /// Commit a worktree's changes when its prompt completes successfully.
+    pub worktree_autocommit: bool,
+    /// Sender into the main loop's message channel, for background jobs
+    /// (set by `run_app`).
+    pub message_tx: Option<mpsc::UnboundedSender<WorkerMessage>>,
     /// Height of the prompt list panel (set during rendering).
     pub list_height: u16,
-    /// Whether `g` was pressed once (waiting for second `g` for gg → go to top).
-    pub pending_g: bool,
+    /// Screen area of the prompt list (set during render, for mouse clicks).
+    pub list_area: Option<Rect>,
+    /// First visible row of the (filtered) prompt list, set during render.
+    pub list_offset: usize,
+    /// Keys typed so far of a multi-key sequence such as `gg`.
+    pub pending_sequence: Vec<KeyCombo>,
+    /// When the last key of `pending_sequence` was typed.
+    pub pending_sequence_at: Option<Instant>,
     /// Panel split ratio (percentage for list panel, 10–90).
     pub list_ratio: u16,
     /// Whether the list panel is collapsed (output maximized).
     pub list_collapsed: bool,
+    /// Panel with keyboard focus in Normal mode, toggled with Tab.
+    pub focused_pane: FocusedPane,
     /// Wall-clock time when the session started.
     pub session_start: Instant,
     /// Whether the full-screen help overlay is visible.
@@ -117,6 +382,32 @@ pub struct App {
     pub visual_select_active: bool,
     /// Whether batch delete confirmation dialog is showing.
     pub confirm_batch_delete: bool,
+    /// Whether the kill-all confirmation dialog is showing.
+    pub confirm_kill_all: bool,
+    /// Prompt whose worktree is about to be discarded, pending y/n.
+    pub confirm_discard_worktree: Option<usize>,
+    /// Prompts whose worktree is being merged by `a`, until `WorktreeApplied`.
+    pub applying_worktrees: HashSet<usize>,
+    /// Prompt being duplicated from Insert mode; its settings apply on submit.
+    pub duplicate_source: Option<usize>,
+    /// Source prompt id and agent session id that the prompt being typed in
+    /// Insert mode continues.
+    pub continue_session: Option<(usize, String)>,
+    /// Tag-edit input being typed in Normal mode (`+tag`/`-tag` words).
+    pub tag_input: Option<String>,
+    /// Colors from the `[theme]` config section.
+    pub theme: Theme,
+    /// Display order of the prompt list (applied in `rebuild_filter`).
+    pub sort_mode: SortMode,
+    /// Whether the list is grouped under tag headers.
+    pub grouped: bool,
+    /// Group tags folded in the grouped view ("" is the untagged group).
+    pub collapsed_tags: HashSet<String>,
+    /// Persistent banner set when a `clhorde submit` request uses a
+    /// different inbox `PROTOCOL_VERSION` than this TUI.
+    pub version_warning: Option<String>,
+    /// Last status written to the inbox for `clhorde tui status`.
+    pub published_status: Option<inbox::InstanceStatus>,
 }
 
 impl App {
@@ -127,6 +418,10 @@ impl App {
         let history = Self::load_history();
         let settings = keymap::load_settings();
         let max_saved_prompts = settings.max_saved_prompts.unwrap_or(100);
+        let kill_grace = Duration::from_millis(settings.kill_grace_ms.unwrap_or(process::DEFAULT_KILL_GRACE_MS));
+        let max_saved_output_bytes = settings
+            .max_saved_output_bytes
+            .unwrap_or(persistence::DEFAULT_MAX_SAVED_OUTPUT_BYTES);
         let list_ratio = (settings.list_ratio.unwrap_or(40) as u16).clamp(10, 90);
         let worktree_cleanup = match settings.worktree_cleanup.as_deref() {
             Some("auto") => WorktreeCleanup::Auto,
@@ -138,27 +433,73 @@ impl App {
         // Restore prompts from disk
         let mut prompts = Vec::new();
         let mut next_id: usize = 1;
+        let mut migrated = 0;
+        let mut orphans = Vec::new();
+        // A pid file nobody owns means the last instance died without
+        // cleaning up, so prompts it left `running` were orphaned
+        let crashed = inbox::default_inbox_dir()
+            .is_some_and(|dir| inbox::read_pid(&dir).is_some() && !inbox::is_running(&dir));
         if let Some(ref dir) = prompts_dir {
-            let saved = persistence::load_all_prompts(dir);
+            let (saved, upgraded) = persistence::load_and_migrate_prompts(dir);
+            // Write migrated files back so they're only upgraded once
+            for (uuid, pf) in saved.iter().filter(|(uuid, _)| upgraded.contains(uuid)) {
+                let _ = persistence::save_prompt(dir, uuid, pf);
+            }
+            migrated = upgraded.len();
             for (uuid, pf) in &saved {
                 let mode = match pf.options.mode.as_str() {
                     "one_shot" => PromptMode::OneShot,
                     _ => PromptMode::Interactive,
                 };
-                // All restored prompts are terminal — processes are dead
+                // Pending prompts (e.g. from `store import`) queue again; all
+                // others are terminal — their processes are dead
                 let status = match pf.state.as_str() {
-                    "failed" => PromptStatus::Failed,
+                    "pending" => PromptStatus::Pending,
+                    "failed" | "killed" => PromptStatus::Failed,
                     _ => PromptStatus::Completed,
                 };
+                let orphan = orphan_of(pf, crashed, process::is_alive, process::command_name);
+                let (status, orphan_error) = match orphan {
+                    Some(Orphan::Alive(pid)) => {
+                        process::terminate(pid, kill_grace);
+                        (PromptStatus::Failed, Some(format!("orphaned on crash (worker {pid} reaped)")))
+                    }
+                    Some(Orphan::Gone) => (PromptStatus::Failed, Some("orphaned on crash".to_string())),
+                    None => (status, None),
+                };
+                if orphan.is_some() {
+                    orphans.push(uuid.clone());
+                }
                 let mut prompt = Prompt::new(next_id, pf.prompt.clone(), pf.options.context.clone(), mode);
                 prompt.uuid = uuid.clone();
                 prompt.queue_rank = pf.queue_rank;
+                prompt.display_rank = pf.display_rank.unwrap_or(pf.queue_rank);
                 prompt.session_id = pf.session_id.clone();
                 prompt.worktree = pf.options.worktree.unwrap_or(false);
                 prompt.worktree_path = pf.worktree_path.clone();
+                prompt.command = pf.command.clone();
+                prompt.resume_session_id = pf.resume_session_id.clone();
+                prompt.stderr = pf.stderr.clone();
                 prompt.tags = pf.tags.clone();
+                prompt.priority = pf
+                    .options
+                    .priority
+                    .as_deref()
+                    .and_then(PromptPriority::from_label)
+                    .unwrap_or(PromptPriority::Normal);
+                prompt.timeout_secs = pf.options.timeout_secs;
+                prompt.max_attempts = pf.options.max_attempts.unwrap_or(1).max(1);
+                prompt.env = pf.options.env.clone();
+                prompt.killed = pf.state == "killed";
+                prompt.depends_on = pf
+                    .depends_on
+                    .as_ref()
+                    .and_then(|dep| saved.iter().position(|(u, _)| u == dep))
+                    .map(|pos| pos + 1);
+                prompt.output = pf.output.clone();
+                prompt.error = orphan_error.or_else(|| pf.error.clone());
+                prompt.seen = status != PromptStatus::Pending;
                 prompt.status = status;
-                prompt.seen = true;
                 prompts.push(prompt);
                 next_id += 1;
             }
@@ -167,16 +508,46 @@ impl App {
             }
 
             // Prune old prompt files
-            persistence::prune_old_prompts(dir, max_saved_prompts);
+            let archive = settings.archive_dir.as_deref().map(persistence::archive_dir);
+            persistence::prune_old_prompts(dir, max_saved_prompts, archive.as_deref());
         }
 
         let filtered_indices: Vec<usize> = (0..prompts.len()).collect();
 
-        Self {
+        let mut app = Self {
             prompts,
             next_id,
             max_workers: 3,
             active_workers: 0,
+            paused: false,
+            draining: false,
+            quit_when_drained: false,
+            default_timeout_secs: settings.default_timeout_secs,
+            default_max_attempts: settings.max_attempts.unwrap_or(1).max(1),
+            retry_delay: Duration::from_secs(settings.retry_delay_secs.unwrap_or(0)),
+            agent: AgentCommand::from_settings(settings.agent_command, settings.agent_args, settings.worker_wrapper),
+            notifications: settings.notifications.unwrap_or(false),
+            mouse: settings.mouse.unwrap_or(false),
+            terminal_title: settings.terminal_title.unwrap_or(false),
+            bell_on_complete: settings.bell_on_complete.unwrap_or(false),
+            pending_bell: false,
+            title_summary: None,
+            tag_limits: settings.tag_limits.unwrap_or_default(),
+            kill_grace,
+            input_reminder: match settings.input_reminder_secs.unwrap_or(DEFAULT_INPUT_REMINDER_SECS) {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            spawn_interval: Duration::from_millis(settings.spawn_interval_ms.unwrap_or(0)),
+            last_spawn: None,
+            completion_webhook_url: settings.completion_webhook_url.clone().filter(|url| !url.trim().is_empty()),
+            event_socket: None,
+            persistence_healthy: Cell::new(true),
+            persistence_error: RefCell::new(None),
+            pty_scrollback_bytes: settings
+                .pty_scrollback_bytes
+                .unwrap_or(pty_worker::DEFAULT_SCROLLBACK_BYTES)
+                .min(pty_worker::MAX_SCROLLBACK_BYTES),
             mode: AppMode::Normal,
             list_state,
             input: TextBuffer::new(),
@@ -186,6 +557,10 @@ impl App {
             worker_inputs: HashMap::new(),
             interact_input: String::new(),
             auto_scroll: true,
+            output_at_bottom: true,
+            output_scroll_max: 0,
+            output_width: 0,
+            output_rows: None,
             suggestions: Vec::new(),
             suggestion_index: 0,
             tick: 0,
@@ -195,25 +570,50 @@ impl App {
             confirm_quit: false,
             filter_text: None,
             filter_input: String::new(),
+            output_search_input: None,
+            worktree_diff: None,
+            output_search: None,
+            output_search_line: None,
             filtered_indices,
+            filter_regex_invalid: false,
+            saved_filters: filters::load_filters(),
             history,
             history_index: None,
             history_stash: String::new(),
+            history_search: None,
             templates,
             template_suggestions: Vec::new(),
             template_suggestion_index: 0,
             show_quick_prompts_popup: false,
+            quick_prompt_index: 0,
+            quick_prompt_filter: String::new(),
             pty_handles: HashMap::new(),
             output_panel_size: None,
-            last_pty_size: None,
+            viewed_pty: None,
+            pty_sizes: HashMap::new(),
             max_saved_prompts,
+            max_saved_output_bytes,
+            max_output_bytes: settings
+                .max_output_bytes
+                .unwrap_or(crate::prompt::DEFAULT_MAX_OUTPUT_BYTES)
+                .max(1),
             prompts_dir,
+            state_path: persistence::default_state_path(),
+            inbox_dir: inbox::default_inbox_dir(),
+            output_mirror: inbox::OutputMirror::default(),
             worktree_pending: false,
             worktree_cleanup,
+            worktree_branch_template: settings.worktree_branch_template.clone(),
+            worktree_autocommit: settings.worktree_autocommit.unwrap_or(false),
+            message_tx: None,
             list_height: 0,
-            pending_g: false,
+            list_area: None,
+            list_offset: 0,
+            pending_sequence: Vec::new(),
+            pending_sequence_at: None,
             list_ratio,
             list_collapsed: false,
+            focused_pane: FocusedPane::List,
             session_start: Instant::now(),
             show_help_overlay: false,
             help_scroll: 0,
@@ -221,13 +621,113 @@ impl App {
             selected_ids: HashSet::new(),
             visual_select_active: false,
             confirm_batch_delete: false,
+            confirm_kill_all: false,
+            confirm_discard_worktree: None,
+            applying_worktrees: HashSet::new(),
+            duplicate_source: None,
+            continue_session: None,
+            tag_input: None,
+            theme: Theme::from_toml(&keymap::load_theme()),
+            sort_mode: SortMode::Queue,
+            grouped: false,
+            collapsed_tags: HashSet::new(),
+            version_warning: None,
+            published_status: None,
+        };
+        app.restore_session_state();
+        // A dependency that failed in an earlier session (or was orphaned
+        // just now) never sends the event that would cascade its failure
+        app.fail_blocked_dependents();
+        let conflicts = app.keymap.quick_prompt_conflicts();
+        if !conflicts.is_empty() {
+            let mut keys: Vec<String> = conflicts.iter().map(|(kc, _)| keymap::key_display(kc)).collect();
+            keys.dedup();
+            let msg = format!("Quick prompt keys shadowed by other bindings: {}", keys.join(", "));
+            app.status_message = Some((msg, Instant::now()));
+        }
+        // Save orphans as failed so they aren't taken for orphans again
+        for prompt in app.prompts.iter().filter(|p| orphans.contains(&p.uuid)) {
+            app.persist_prompt(prompt);
+        }
+        if !orphans.is_empty() {
+            let msg = format!("Marked {} prompt(s) orphaned by a crash as failed", orphans.len());
+            app.status_message = Some((msg, Instant::now()));
+        }
+        if migrated > 0 {
+            let msg = format!("Migrated {migrated} prompt file(s) to format v{}", persistence::SCHEMA_VERSION);
+            app.status_message = Some((msg, Instant::now()));
+        }
+        if let Some(ref path) = settings.event_socket {
+            app.open_event_socket(&persistence::expand_tilde(path, dirs::home_dir().as_deref()));
+        }
+        app
+    }
+
+    fn open_event_socket(&mut self, path: &Path) {
+        match EventSocket::bind(path) {
+            Ok(socket) => self.event_socket = Some(socket),
+            Err(e) => {
+                let msg = format!("Event socket {} unavailable: {e}", path.display());
+                self.status_message = Some((msg, Instant::now()));
+            }
+        }
+    }
+
+    fn emit_event(&self, event: events::Event) {
+        if let Some(ref socket) = self.event_socket {
+            socket.emit(&event);
+        }
+    }
+
+    /// Apply the worker count, default mode and pause state saved by the
+    /// last session, if any.
+    pub fn restore_session_state(&mut self) {
+        let Some(state) = self.state_path.as_deref().and_then(persistence::load_state) else {
+            return;
+        };
+        self.max_workers = state.max_workers.clamp(1, 20);
+        self.default_mode = state.default_mode;
+        self.paused = state.paused;
+    }
+
+    /// Save the worker count, default mode and pause state; called whenever
+    /// one of them changes.
+    fn save_session_state(&self) {
+        if let Some(ref path) = self.state_path {
+            let state = persistence::SessionState {
+                max_workers: self.max_workers,
+                default_mode: self.default_mode,
+                paused: self.paused,
+            };
+            persistence::save_state(path, &state);
         }
     }
 
     /// Save a prompt to disk if persistence is enabled.
     fn persist_prompt(&self, prompt: &Prompt) {
         if let Some(ref dir) = self.prompts_dir {
-            persistence::save_prompt(dir, &prompt.uuid, &persistence::PromptFile::from_prompt(prompt));
+            let mut file = persistence::PromptFile::from_prompt(prompt);
+            // Dependencies are stored by UUID since ids are reassigned on restore
+            file.depends_on = prompt
+                .depends_on
+                .and_then(|dep| self.prompts.iter().find(|p| p.id == dep))
+                .map(|p| p.uuid.clone());
+            file.output = file
+                .output
+                .map(|o| persistence::cap_output(&o, self.max_saved_output_bytes));
+            file.stderr = file
+                .stderr
+                .map(|e| persistence::cap_output(&e, self.max_saved_output_bytes));
+            if !self.persistence_healthy.get() {
+                return;
+            }
+            if let Err(e) = persistence::save_prompt(dir, &prompt.uuid, &file) {
+                // Keep running from memory rather than failing every save
+                self.persistence_healthy.set(false);
+                let message = format!("cannot save to {}: {e}", dir.display());
+                self.emit_event(events::Event::Error { message: message.clone() });
+                *self.persistence_error.borrow_mut() = Some(message);
+            }
         }
     }
 
@@ -245,6 +745,14 @@ impl App {
             .count()
     }
 
+    /// Prompts with a live worker (running or idle).
+    pub fn live_worker_count(&self) -> usize {
+        self.prompts
+            .iter()
+            .filter(|p| p.status == PromptStatus::Running || p.status == PromptStatus::Idle)
+            .count()
+    }
+
     pub fn completed_count(&self) -> usize {
         self.prompts
             .iter()
@@ -252,26 +760,328 @@ impl App {
             .count()
     }
 
-    pub fn add_prompt(&mut self, text: String, cwd: Option<String>, worktree: bool, tags: Vec<String>) {
+    /// Prompts that completed during this session (restored ones excluded).
+    pub fn session_completed_count(&self) -> usize {
+        self.prompts
+            .iter()
+            .filter(|p| p.status == PromptStatus::Completed && p.finished_at.is_some())
+            .count()
+    }
+
+    /// Counts of prompts that may need the user: working, waiting for
+    /// input, or finished without being looked at.
+    pub fn attention_summary(&self) -> AttentionSummary {
+        let mut summary = AttentionSummary::default();
+        for p in &self.prompts {
+            match p.status {
+                PromptStatus::Running => summary.running += 1,
+                PromptStatus::Idle => summary.idle += 1,
+                _ if p.is_unseen() => {
+                    summary.unseen += 1;
+                    if p.status == PromptStatus::Failed {
+                        summary.failed_unseen += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// Terminal title to set after a draw, or `None` when the setting is
+    /// off or the counts haven't changed since the last one was set.
+    pub fn take_title_update(&mut self) -> Option<String> {
+        if !self.terminal_title {
+            return None;
+        }
+        let summary = self.attention_summary();
+        if self.title_summary == Some(summary) {
+            return None;
+        }
+        self.title_summary = Some(summary);
+        let label = summary.label();
+        Some(if label.is_empty() { "clhorde".to_string() } else { format!("clhorde · {label}") })
+    }
+
+    /// Queue a new pending prompt and return its assigned id, so callers
+    /// can correlate the request with the created prompt.
+    pub fn add_prompt(&mut self, text: String, cwd: Option<String>, worktree: bool, tags: Vec<String>) -> usize {
         let mut prompt = Prompt::new(self.next_id, text, cwd, self.default_mode);
         prompt.worktree = worktree;
         prompt.tags = tags;
+        self.queue_prompt(prompt)
+    }
+
+    /// Build a fresh pending copy of an existing prompt (any status) with
+    /// the same cwd, mode, worktree flag and tags. The copy never inherits
+    /// the source's session, so it starts a new conversation.
+    fn duplicate_of(&self, source_id: usize, text: String) -> Option<Prompt> {
+        let source = self.prompts.iter().find(|p| p.id == source_id)?;
+        let mut prompt = Prompt::new(self.next_id, text, source.cwd.clone(), source.mode);
+        prompt.worktree = source.worktree;
+        prompt.tags = source.tags.clone();
+        Some(prompt)
+    }
+
+    /// Rank that puts a new prompt last in the manual sort view.
+    fn next_display_rank(&self) -> f64 {
+        self.prompts.iter().map(|p| p.display_rank).fold(0.0_f64, f64::max) + 1.0
+    }
+
+    /// Append a new prompt to the tail of the queue and persist it.
+    fn queue_prompt(&mut self, mut prompt: Prompt) -> usize {
+        let id = prompt.id;
+        prompt.timeout_secs = self.default_timeout_secs;
+        prompt.max_attempts = self.default_max_attempts;
         let max_rank = self.prompts.iter().map(|p| p.queue_rank).fold(0.0_f64, f64::max);
         prompt.queue_rank = max_rank + 1.0;
+        prompt.display_rank = self.next_display_rank();
         self.next_id += 1;
         self.persist_prompt(&prompt);
+        self.emit_event(events::Event::added(&prompt));
         self.prompts.push(prompt);
         self.rebuild_filter();
         if self.list_state.selected().is_none() {
             self.list_state.select(Some(0));
         }
+        id
+    }
+
+    /// Snapshot published for `clhorde tui status`.
+    pub fn instance_status(&self) -> inbox::InstanceStatus {
+        let counts = PromptStatus::ALL
+            .iter()
+            .map(|status| {
+                let n = self.prompts.iter().filter(|p| p.status == *status).count();
+                (status.label().to_string(), n)
+            })
+            .collect();
+        inbox::InstanceStatus {
+            max_workers: self.max_workers,
+            active_workers: self.active_workers,
+            paused: self.paused,
+            draining: self.draining,
+            counts,
+            stats: self.queue_stats(),
+            prompt_ids: self
+                .prompts
+                .iter()
+                .filter(|p| {
+                    p.finished_at.is_some()
+                        || !matches!(p.status, PromptStatus::Completed | PromptStatus::Failed)
+                })
+                .map(|p| p.id)
+                .collect(),
+        }
+    }
+
+    /// Aggregates over all prompts for `clhorde tui stats`.
+    pub fn queue_stats(&self) -> inbox::QueueStats {
+        let mut stats = inbox::QueueStats::default();
+        let mut elapsed = Vec::new();
+        for p in &self.prompts {
+            match p.status {
+                PromptStatus::Pending => stats.pending += 1,
+                PromptStatus::Running | PromptStatus::Idle => stats.running += 1,
+                PromptStatus::Completed => stats.completed += 1,
+                PromptStatus::Failed if p.is_killed() => stats.killed += 1,
+                PromptStatus::Failed => stats.failed += 1,
+            }
+            let finished = matches!(p.status, PromptStatus::Completed | PromptStatus::Failed);
+            if finished && p.finished_at.is_some() {
+                elapsed.extend(p.elapsed_secs());
+            }
+        }
+        if !elapsed.is_empty() {
+            stats.avg_elapsed_secs = Some(elapsed.iter().sum::<f64>() / elapsed.len() as f64);
+        }
+        stats
+    }
+
+    /// Compare a request's inbox protocol version with ours. Any mismatch
+    /// raises the version banner; requests from a newer binary are refused,
+    /// since fields this TUI doesn't know would be silently dropped.
+    fn check_protocol_version(&mut self, version: u32) -> Result<(), String> {
+        if version == inbox::PROTOCOL_VERSION {
+            return Ok(());
+        }
+        self.version_warning = Some(format!(
+            "clhorde submit (protocol v{version}) and this TUI (v{}) differ — restart clhorde",
+            inbox::PROTOCOL_VERSION
+        ));
+        if version > inbox::PROTOCOL_VERSION {
+            return Err(format!(
+                "request protocol v{version} is newer than the running TUI (v{}); restart clhorde",
+                inbox::PROTOCOL_VERSION
+            ));
+        }
+        Ok(())
+    }
+
+    /// Queue a prompt submitted from outside the TUI, honoring its options.
+    /// Returns an error (and queues nothing) if the dependency is invalid.
+    pub fn accept_submission(&mut self, req: SubmitRequest) -> Result<usize, String> {
+        self.check_protocol_version(req.protocol_version)?;
+        if let Some(dep) = req.depends_on {
+            self.check_dependency(self.next_id, dep)?;
+        }
+        if req.resume_session_id.as_ref().is_some_and(|s| s.trim().is_empty()) {
+            return Err("empty resume session id".to_string());
+        }
+        inbox::validate_env(&req.options.env)?;
+        let (tags, text) = crate::prompt::parse_tags(&req.prompt);
+        let worktree = req.options.worktree.unwrap_or(false);
+        let id = self.add_prompt(text, req.options.context, worktree, tags);
+        let mode = match req.options.mode.as_str() {
+            "one_shot" => Some(PromptMode::OneShot),
+            "interactive" => Some(PromptMode::Interactive),
+            _ => None,
+        };
+        if let Some(mode) = mode {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.mode = mode;
+            }
+            self.persist_prompt_by_id(id);
+        }
+        if let Some(session_id) = req.resume_session_id {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.resume_session_id = Some(session_id.trim().to_string());
+            }
+            self.persist_prompt_by_id(id);
+        }
+        if let Some(max_attempts) = req.options.max_attempts {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.max_attempts = max_attempts.max(1);
+            }
+            self.persist_prompt_by_id(id);
+        }
+        if let Some(secs) = req.options.timeout_secs.filter(|s| *s > 0) {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.timeout_secs = Some(secs);
+            }
+            self.persist_prompt_by_id(id);
+        }
+        if !req.options.env.is_empty() {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.env = req.options.env;
+            }
+            self.persist_prompt_by_id(id);
+        }
+        if let Some(dep) = req.depends_on {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.depends_on = Some(dep);
+            }
+            self.persist_prompt_by_id(id);
+            self.fail_blocked_dependents();
+        }
+        Ok(id)
+    }
+
+    /// Check that `prompt_id` may wait on `dep_id`: the dependency must exist
+    /// and must not (transitively) depend on `prompt_id` itself.
+    pub fn check_dependency(&self, prompt_id: usize, dep_id: usize) -> Result<(), String> {
+        if !self.prompts.iter().any(|p| p.id == dep_id) {
+            return Err(format!("Unknown dependency #{dep_id}"));
+        }
+        let mut current = Some(dep_id);
+        while let Some(id) = current {
+            if id == prompt_id {
+                return Err(format!("Dependency on #{dep_id} would form a cycle"));
+            }
+            current = self.prompts.iter().find(|p| p.id == id).and_then(|p| p.depends_on);
+        }
+        Ok(())
+    }
+
+    /// Whether a prompt's dependency (if any) has completed. A dependency
+    /// that no longer exists (deleted) does not block.
+    fn dependency_satisfied(&self, prompt: &Prompt) -> bool {
+        match prompt.depends_on {
+            Some(dep) => self
+                .prompts
+                .iter()
+                .find(|p| p.id == dep)
+                .is_none_or(|p| p.status == PromptStatus::Completed),
+            None => true,
+        }
+    }
+
+    /// Fail pending prompts whose dependency failed, cascading down chains.
+    fn fail_blocked_dependents(&mut self) {
+        loop {
+            let failed: Vec<(usize, usize)> = self
+                .prompts
+                .iter()
+                .filter(|p| p.status == PromptStatus::Pending)
+                .filter_map(|p| p.depends_on.map(|dep| (p.id, dep)))
+                .filter(|(_, dep)| {
+                    self.prompts
+                        .iter()
+                        .any(|p| p.id == *dep && p.status == PromptStatus::Failed)
+                })
+                .collect();
+            if failed.is_empty() {
+                return;
+            }
+            for (id, dep) in failed {
+                if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                    prompt.status = PromptStatus::Failed;
+                    prompt.error = Some(format!("Dependency #{dep} failed"));
+                }
+                self.persist_prompt_by_id(id);
+                self.mirror_exit(id, Some(1));
+            }
+        }
+    }
+
+    /// Pick up prompts dropped in the inbox by `clhorde submit` and reply
+    /// with their assigned ids.
+    pub fn poll_inbox(&mut self) {
+        let Some(dir) = self.inbox_dir.clone() else {
+            return;
+        };
+        if inbox::take_stop_request(&dir) {
+            // Same as a confirmed quit: workers are terminated on the way out
+            self.should_quit = true;
+        }
+        if inbox::take_kill_all_request(&dir) {
+            self.kill_all_workers();
+        }
+        if let Some(quit) = inbox::take_drain_request(&dir) {
+            self.start_drain(quit);
+        }
+        for (id, name) in inbox::take_signal_requests(&dir) {
+            let message = match self.send_signal(id, &name) {
+                Ok(sig) => format!("Sent SIG{sig} to #{id}"),
+                Err(e) => format!("Rejected signal for #{id}: {e}"),
+            };
+            self.status_message = Some((message, Instant::now()));
+        }
+        let status = self.instance_status();
+        if self.published_status.as_ref() != Some(&status) {
+            inbox::write_status(&dir, &status);
+            self.published_status = Some(status);
+        }
+        for (token, req) in inbox::take_requests(&dir) {
+            match self.accept_submission(req) {
+                Ok(id) => {
+                    inbox::write_accept(&dir, &token, id);
+                    self.status_message = Some((format!("Submitted #{id}"), Instant::now()));
+                }
+                Err(reason) => {
+                    inbox::write_reject(&dir, &token, &reason);
+                    self.status_message = Some((format!("Rejected submission: {reason}"), Instant::now()));
+                }
+            }
+        }
     }
 
     fn parse_cwd_prefix(input: &str) -> (Option<String>, String) {
         if let Some((prefix, rest)) = input.split_once(": ") {
             let prefix = prefix.trim();
-            if !prefix.is_empty() && Path::new(prefix).is_dir() {
-                return (Some(prefix.to_string()), rest.trim().to_string());
+            let dir = persistence::expand_tilde(prefix, dirs::home_dir().as_deref());
+            if !prefix.is_empty() && dir.is_dir() {
+                return (Some(dir.to_string_lossy().to_string()), rest.trim().to_string());
             }
         }
         (None, input.to_string())
@@ -292,88 +1102,240 @@ impl App {
             return;
         }
 
-        let path = Path::new(input);
+        self.suggestions = dir_suggestions(input, dirs::home_dir().as_deref());
+    }
 
-        // If input ends with `/` and is a directory, list its children
-        // Otherwise split into parent + partial filename prefix
-        let (parent, prefix) = if input.ends_with('/') && path.is_dir() {
-            (path.to_path_buf(), String::new())
-        } else {
-            match (path.parent(), path.file_name()) {
-                (Some(p), Some(name)) => {
-                    let parent = if p.as_os_str().is_empty() {
-                        Path::new(".").to_path_buf()
-                    } else {
-                        p.to_path_buf()
-                    };
-                    (parent, name.to_string_lossy().to_string())
-                }
-                _ => return,
-            }
-        };
+    fn accept_suggestion(&mut self) {
+        if let Some(path) = self.suggestions.get(self.suggestion_index).cloned() {
+            self.input.set(&format!("{path}/"));
+            self.suggestions.clear();
+            self.suggestion_index = 0;
+            self.update_suggestions();
+        }
+    }
+
+    /// Whether the dispatch loop may start another worker right now.
+    pub fn can_dispatch(&self) -> bool {
+        !self.paused
+            && !self.draining
+            && self.active_workers < self.max_workers
+            && self.last_spawn.is_none_or(|t| t.elapsed() >= self.spawn_interval)
+    }
+
+    /// Take a worker slot for the prompt about to be spawned. With
+    /// `spawn_interval` set, the rest of a burst waits for later ticks.
+    pub fn start_dispatch(&mut self, index: usize) {
+        self.mark_running(index);
+        self.active_workers += 1;
+        self.last_spawn = Some(Instant::now());
+        if let Some(prompt) = self.prompts.get(index) {
+            self.emit_event(events::Event::Started { id: prompt.id });
+        }
+    }
+
+    /// Stop dispatching for good; running workers finish normally. Unlike
+    /// pause there is no way back. `quit` asks for a shutdown once drained.
+    pub fn start_drain(&mut self, quit: bool) {
+        self.draining = true;
+        self.quit_when_drained |= quit;
+        let msg = format!("Draining: {} worker(s) left", self.active_workers);
+        self.status_message = Some((msg, Instant::now()));
+    }
 
-        let entries = match fs::read_dir(&parent) {
-            Ok(entries) => entries,
-            Err(_) => return,
+    /// Quit once a drain that asked for it has no workers left.
+    pub fn check_drained(&mut self) {
+        if self.draining && self.quit_when_drained && self.active_workers == 0 {
+            self.should_quit = true;
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        let msg = if self.paused {
+            "Queue paused".to_string()
+        } else {
+            "Queue resumed".to_string()
         };
+        self.status_message = Some((msg, Instant::now()));
+        self.save_session_state();
+    }
 
-        let mut matches: Vec<String> = entries
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
-            .filter_map(|e| {
-                let name = e.file_name().to_string_lossy().to_string();
-                if name.starts_with(&prefix) {
-                    Some(e.path().to_string_lossy().to_string())
-                } else {
-                    None
-                }
-            })
+    /// Kill workers whose current turn has exceeded its timeout.
+    /// The prompt is marked Failed when the worker's `Finished` message arrives.
+    pub fn check_timeouts(&mut self) {
+        let timed_out: Vec<(usize, u64)> = self
+            .prompts
+            .iter()
+            .filter(|p| p.status == PromptStatus::Running && p.error.is_none() && p.is_timed_out())
+            .filter_map(|p| p.timeout_secs.map(|t| (p.id, t)))
             .collect();
+        for (id, secs) in timed_out {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.error = Some(format!("Timed out after {secs}s"));
+            }
+            self.signal_kill(id);
+            self.status_message = Some((format!("Prompt #{id} timed out after {secs}s"), Instant::now()));
+        }
+    }
 
-        matches.sort();
-        if matches.len() > 10 {
-            matches.truncate(10);
+    /// Send one reminder per wait for agents that have been waiting on the
+    /// user longer than `input_reminder`. The list flags them either way;
+    /// the notification also needs `notifications` on.
+    pub fn check_input_reminders(&mut self) {
+        let Some(after) = self.input_reminder else {
+            return;
+        };
+        let now = Instant::now();
+        let due: Vec<usize> = self
+            .prompts
+            .iter()
+            .filter(|p| !p.input_reminder_sent && p.is_awaiting_input(after, now))
+            .map(|p| p.id)
+            .collect();
+        for id in due {
+            if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                prompt.input_reminder_sent = true;
+            }
+            if self.should_notify(id) {
+                if let Some(prompt) = self.prompts.iter().find(|p| p.id == id) {
+                    let (title, body) = notify::waiting_message(prompt);
+                    notify::send(title, body);
+                }
+            }
         }
+    }
 
-        self.suggestions = matches;
+    /// Whether `prompt` has been waiting on the user past `input_reminder`.
+    pub fn is_awaiting_input(&self, prompt: &Prompt) -> bool {
+        self.input_reminder.is_some_and(|after| prompt.is_awaiting_input(after, Instant::now()))
     }
 
-    fn accept_suggestion(&mut self) {
-        if let Some(path) = self.suggestions.get(self.suggestion_index).cloned() {
-            self.input.set(&format!("{path}/"));
-            self.suggestions.clear();
-            self.suggestion_index = 0;
-            self.update_suggestions();
-        }
+    /// Whether starting `prompt` would exceed the `tag_limits` cap of any of
+    /// its tags. Idle workers count, since their process is still alive.
+    fn exceeds_tag_limit(&self, prompt: &Prompt) -> bool {
+        prompt.tags.iter().any(|tag| {
+            let Some(&limit) = self.tag_limits.get(tag) else {
+                return false;
+            };
+            let active = self
+                .prompts
+                .iter()
+                .filter(|p| matches!(p.status, PromptStatus::Running | PromptStatus::Idle))
+                .filter(|p| p.tags.contains(tag))
+                .count();
+            active >= limit
+        })
     }
 
+    /// Index of the next prompt to dispatch: highest priority first, ties
+    /// broken by `queue_rank`. Prompts waiting on a dependency or held back
+    /// by a tag limit are skipped.
     pub fn next_pending_prompt_index(&self) -> Option<usize> {
         self.prompts
             .iter()
-            .position(|p| p.status == PromptStatus::Pending)
+            .enumerate()
+            .filter(|(_, p)| {
+                p.status == PromptStatus::Pending
+                    && p.retry_at.is_none_or(|at| at <= Instant::now())
+                    && self.dependency_satisfied(p)
+                    && !self.exceeds_tag_limit(p)
+            })
+            .min_by(|(_, a), (_, b)| {
+                b.priority.cmp(&a.priority).then_with(|| {
+                    a.queue_rank
+                        .partial_cmp(&b.queue_rank)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Set the dispatch priority of a pending prompt.
+    pub fn set_prompt_priority(&mut self, prompt_id: usize, priority: PromptPriority) {
+        let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) else {
+            return;
+        };
+        if prompt.status != PromptStatus::Pending {
+            return;
+        }
+        prompt.priority = priority;
+        self.persist_prompt_by_id(prompt_id);
+    }
+
+    /// Fail a prompt the dispatch loop picked but could not start, as if its
+    /// worker had failed to spawn (so it takes a slot and may be retried).
+    pub fn fail_dispatch(&mut self, index: usize, error: String) {
+        let Some(prompt_id) = self.prompts.get(index).map(|p| p.id) else {
+            return;
+        };
+        self.mark_running(index);
+        self.active_workers += 1;
+        self.apply_message(WorkerMessage::SpawnError { prompt_id, error });
+    }
+
+    /// Check the prompt's cwd before anything is spawned or a worktree is
+    /// created from it. A missing directory fails the prompt right away.
+    pub fn validate_cwd(&mut self, index: usize) -> bool {
+        let Some(cwd) = self.prompts.get(index).and_then(|p| p.cwd.clone()) else {
+            return true;
+        };
+        if Path::new(&cwd).is_dir() {
+            return true;
+        }
+        self.fail_dispatch(index, format!("cwd does not exist: {cwd}"));
+        false
     }
 
     pub fn mark_running(&mut self, index: usize) {
         if let Some(prompt) = self.prompts.get_mut(index) {
             prompt.status = PromptStatus::Running;
             prompt.started_at = Some(Instant::now());
+            prompt.turn_started_at = prompt.started_at;
+            prompt.styled_output = None;
+            prompt.retry_at = None;
+            if let Some(ref dir) = self.inbox_dir {
+                inbox::clear_exit(dir, prompt.id);
+            }
         }
         if let Some(prompt) = self.prompts.get(index) {
             self.persist_prompt(prompt);
         }
+        self.resort();
+    }
+
+    /// Apply `first` and whatever is already queued behind it (up to
+    /// `MAX_MESSAGES_PER_FRAME`). A burst of output then costs one redraw
+    /// rather than one per chunk, so a `Finished` stuck behind it frees its
+    /// worker slot promptly.
+    pub fn apply_queued_messages(
+        &mut self,
+        first: WorkerMessage,
+        rx: &mut mpsc::UnboundedReceiver<WorkerMessage>,
+    ) {
+        self.apply_message(first);
+        for _ in 1..MAX_MESSAGES_PER_FRAME {
+            let Ok(msg) = rx.try_recv() else {
+                return;
+            };
+            self.apply_message(msg);
+        }
     }
 
     pub fn apply_message(&mut self, msg: WorkerMessage) {
         match msg {
-            WorkerMessage::OutputChunk { prompt_id, text } => {
+            WorkerMessage::OutputChunk { prompt_id, stream, text } => {
                 if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
                     // If we get output after being idle, we're running again
                     if prompt.status == PromptStatus::Idle {
                         prompt.status = PromptStatus::Running;
+                        prompt.turn_started_at = Some(Instant::now());
                     }
-                    match &mut prompt.output {
-                        Some(existing) => existing.push_str(&text),
-                        None => prompt.output = Some(text),
+                    if let Some(ref dir) = self.inbox_dir {
+                        self.output_mirror.append(dir, prompt_id, &text, self.max_output_bytes);
+                    }
+                    match stream {
+                        OutputStream::Stdout => prompt.append_output(&text, self.max_output_bytes),
+                        OutputStream::Stderr => prompt.append_stderr(&text, self.max_output_bytes),
                     }
                 }
             }
@@ -385,22 +1347,110 @@ impl App {
                             output.push('\n');
                         }
                         prompt.status = PromptStatus::Idle;
+                        prompt.turn_started_at = Some(Instant::now());
+                        prompt.activity = None;
+                        prompt.mark_awaiting_input();
                         save = true;
                     }
                 }
                 if save {
                     self.persist_prompt_by_id(prompt_id);
+                    self.flush_queued_input(prompt_id);
+                    self.resort();
                 }
             }
-            WorkerMessage::PtyUpdate { .. } => {
-                // No-op: redraw happens on next loop iteration
-            }
-            WorkerMessage::SessionId { prompt_id, session_id } => {
+            WorkerMessage::Progress { prompt_id, bytes, lines } => {
                 if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
-                    prompt.session_id = Some(session_id);
+                    prompt.progress = Some((bytes, lines));
+                }
+            }
+            WorkerMessage::PtyUpdate { prompt_id } => {
+                // Redraw happens on next loop iteration; a PTY agent that
+                // stops updating may be waiting on the user
+                if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+                    prompt.mark_awaiting_input();
+                }
+            }
+            WorkerMessage::SessionId { prompt_id, session_id } => {
+                if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+                    prompt.session_id = Some(session_id);
                 }
                 self.persist_prompt_by_id(prompt_id);
             }
+            WorkerMessage::Activity { prompt_id, activity } => {
+                if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+                    prompt.activity = activity;
+                }
+            }
+            WorkerMessage::Command { prompt_id, command } => {
+                if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+                    prompt.command = Some(command);
+                }
+            }
+            WorkerMessage::WorktreeCommit { prompt_id, result } => {
+                let kept = self
+                    .prompts
+                    .iter()
+                    .find(|p| p.id == prompt_id)
+                    .and_then(|p| p.worktree_path.clone())
+                    .filter(|_| self.worktree_cleanup == WorktreeCleanup::Auto);
+                let msg = match result {
+                    Ok(Some(hash)) => match kept {
+                        Some(path) => format!("#{prompt_id}: committed worktree changes as {hash}; kept {path} to apply"),
+                        None => format!("#{prompt_id}: committed worktree changes as {hash}"),
+                    },
+                    Ok(None) => format!("#{prompt_id}: nothing to commit in worktree"),
+                    Err(e) => format!("#{prompt_id}: worktree commit failed: {}", e.trim()),
+                };
+                self.status_message = Some((msg, Instant::now()));
+            }
+            WorkerMessage::Spawned { prompt_id, pid } => {
+                self.record_worker_pid(prompt_id, pid);
+            }
+            WorkerMessage::WorktreeDiff { prompt_id, result } => {
+                // Dropped if the view was closed or moved on meanwhile
+                if let Some(view) = self.worktree_diff.as_mut().filter(|v| v.prompt_id == prompt_id) {
+                    match result {
+                        Ok(diff) if diff.is_empty() => {
+                            view.diff = Some("No changes in this worktree".to_string());
+                        }
+                        Ok(diff) => view.diff = Some(diff),
+                        Err(e) => {
+                            self.worktree_diff = None;
+                            self.status_message = Some((format!("#{prompt_id}: diff failed: {e}"), Instant::now()));
+                        }
+                    }
+                }
+            }
+            WorkerMessage::WorktreeApplied { prompt_id, result } => {
+                self.applying_worktrees.remove(&prompt_id);
+                let msg = match result {
+                    Ok(Some(hash)) => format!("#{prompt_id}: worktree merged ({hash})"),
+                    Ok(None) => format!("#{prompt_id}: nothing to merge"),
+                    Err(e) => format!("#{prompt_id}: apply failed: {e}"),
+                };
+                self.status_message = Some((msg, Instant::now()));
+            }
+            WorkerMessage::WorktreeDiscarded { prompt_id, result } => {
+                let msg = match result {
+                    Ok(()) => {
+                        if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+                            prompt.worktree_path = None;
+                        }
+                        self.persist_prompt_by_id(prompt_id);
+                        if self.worktree_diff.as_ref().is_some_and(|v| v.prompt_id == prompt_id) {
+                            self.worktree_diff = None;
+                        }
+                        format!("#{prompt_id}: worktree discarded")
+                    }
+                    Err(e) => format!("#{prompt_id}: discard failed: {e}"),
+                };
+                self.status_message = Some((msg, Instant::now()));
+            }
+            WorkerMessage::WebhookFailed { prompt_id, error } => {
+                let msg = format!("#{prompt_id}: completion webhook failed: {error}");
+                self.status_message = Some((msg, Instant::now()));
+            }
             WorkerMessage::Finished {
                 prompt_id,
                 exit_code,
@@ -408,11 +1458,23 @@ impl App {
                 if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
                     // For PTY workers: extract text from terminal grid before clearing state
                     if prompt.pty_state.is_some() {
-                        let text = pty_worker::extract_text_from_term(
-                            prompt.pty_state.as_ref().unwrap(),
-                        );
+                        let state = prompt.pty_state.as_ref().unwrap();
+                        let text = pty_worker::extract_transcript(state);
                         if !text.is_empty() {
-                            prompt.output = Some(text);
+                            if let Some(ref dir) = self.inbox_dir {
+                                self.output_mirror.append(dir, prompt_id, &text, self.max_output_bytes);
+                            }
+                            let mut styled = pty_worker::extract_styled_transcript(state);
+                            // A resumed prompt keeps its earlier transcript in front
+                            let text = match prompt.output.take() {
+                                Some(prior) => {
+                                    styled.splice(0..0, pty_worker::plain_styled_lines(&prior));
+                                    format!("{prior}{text}")
+                                }
+                                None => text,
+                            };
+                            prompt.styled_output = Some(styled);
+                            prompt.set_output(Some(text));
                         }
                         prompt.pty_state = None;
                     } else if let Some(output) = &mut prompt.output {
@@ -420,20 +1482,47 @@ impl App {
                     }
 
                     prompt.finished_at = Some(Instant::now());
-                    if exit_code == Some(0) || exit_code.is_none() {
-                        prompt.status = PromptStatus::Completed;
-                    } else {
-                        prompt.status = PromptStatus::Failed;
-                        if prompt.error.is_none() {
-                            prompt.error = Some(format!("Exit code: {}", exit_code.unwrap()));
+                    prompt.queued_inputs.clear();
+                    prompt.activity = None;
+                    match exit_code {
+                        // PTY workers report 0 even when killed
+                        _ if prompt.killed => {
+                            prompt.status = PromptStatus::Failed;
+                            prompt.error.get_or_insert_with(|| "Killed".to_string());
+                        }
+                        // A timed-out worker is killed and may exit cleanly
+                        Some(0) | None if prompt.error.is_none() => {
+                            prompt.status = PromptStatus::Completed
+                        }
+                        Some(0) | None => prompt.status = PromptStatus::Failed,
+                        Some(code) => {
+                            prompt.status = PromptStatus::Failed;
+                            if prompt.error.is_none() {
+                                prompt.error = Some(format!("Exit code: {code}"));
+                            }
                         }
                     }
                 }
+                let retrying = self.schedule_retry(prompt_id);
                 self.persist_prompt_by_id(prompt_id);
-                self.maybe_cleanup_worktree(prompt_id);
+                if !retrying {
+                    self.mirror_exit(prompt_id, exit_code);
+                    self.fail_blocked_dependents();
+                    self.maybe_notify(prompt_id, exit_code);
+                    self.post_completion_webhook(prompt_id, exit_code);
+                    self.emit_finished(prompt_id, exit_code);
+                }
+                let completed = self
+                    .prompts
+                    .iter()
+                    .any(|p| p.id == prompt_id && p.status == PromptStatus::Completed);
+                self.finish_worktree(prompt_id, completed);
+                self.output_mirror.close(prompt_id);
                 self.pty_handles.remove(&prompt_id);
+                self.pty_sizes.remove(&prompt_id);
                 self.worker_inputs.remove(&prompt_id);
                 self.active_workers = self.active_workers.saturating_sub(1);
+                self.resort();
 
                 // If we're in PtyInteract for this prompt, go back to ViewOutput
                 if self.mode == AppMode::PtyInteract {
@@ -450,24 +1539,244 @@ impl App {
                     prompt.finished_at = Some(Instant::now());
                     prompt.error = Some(error);
                     prompt.pty_state = None;
+                    prompt.queued_inputs.clear();
+                    prompt.activity = None;
                 }
+                let retrying = self.schedule_retry(prompt_id);
                 self.persist_prompt_by_id(prompt_id);
-                self.maybe_cleanup_worktree(prompt_id);
+                if !retrying {
+                    self.mirror_exit(prompt_id, Some(1));
+                    self.fail_blocked_dependents();
+                    self.maybe_notify(prompt_id, None);
+                    self.post_completion_webhook(prompt_id, None);
+                    self.emit_finished(prompt_id, None);
+                }
+                self.finish_worktree(prompt_id, false);
+                self.output_mirror.close(prompt_id);
                 self.pty_handles.remove(&prompt_id);
+                self.pty_sizes.remove(&prompt_id);
                 self.worker_inputs.remove(&prompt_id);
                 self.active_workers = self.active_workers.saturating_sub(1);
+                self.resort();
             }
         }
     }
 
+    /// Requeue a just-failed prompt for another attempt, unless it was killed
+    /// by the user or has used up `max_attempts`. The retry waits
+    /// `retry_delay`, doubled for each earlier retry. Returns whether the
+    /// prompt was requeued.
+    fn schedule_retry(&mut self, prompt_id: usize) -> bool {
+        let base_delay = self.retry_delay;
+        let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) else {
+            return false;
+        };
+        if prompt.status != PromptStatus::Failed || prompt.killed || prompt.attempt >= prompt.max_attempts {
+            return false;
+        }
+        let delay = base_delay.saturating_mul(1 << (prompt.attempt - 1).min(16));
+        let reason = match prompt.error.take() {
+            Some(e) => format!("failed ({e})"),
+            None => "failed".to_string(),
+        };
+        prompt.attempt += 1;
+        prompt.status = PromptStatus::Pending;
+        prompt.started_at = None;
+        prompt.finished_at = None;
+        prompt.turn_started_at = None;
+        prompt.set_output(None);
+        prompt.stderr = None;
+        prompt.styled_output = None;
+        prompt.progress = None;
+        if !prompt.resume {
+            prompt.session_id = None;
+        }
+        prompt.retry_at = (!delay.is_zero()).then(|| Instant::now() + delay);
+        let msg = format!(
+            "#{prompt_id} {reason} — retrying (attempt {}/{})",
+            prompt.attempt, prompt.max_attempts
+        );
+        self.status_message = Some((msg, Instant::now()));
+        true
+    }
+
+    /// Start the next turn with the oldest queued follow-up, if any.
+    fn flush_queued_input(&mut self, prompt_id: usize) {
+        let Some(idx) = self.prompts.iter().position(|p| p.id == prompt_id) else {
+            return;
+        };
+        if let Some(text) = self.prompts[idx].queued_inputs.pop_front() {
+            self.write_stream_input(idx, text);
+        }
+    }
+
+    /// Kill a worker at the user's request. The resulting failure is final,
+    /// so it is never auto-retried.
+    fn kill_worker(&mut self, prompt_id: usize) {
+        if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+            prompt.killed = true;
+        }
+        self.signal_kill(prompt_id);
+    }
+
+    /// Ask a worker to stop: SIGTERM, escalating to SIGKILL after
+    /// `kill_grace`. The PTY handle is kept until `Finished` arrives, since
+    /// dropping it would hang up the terminal before the agent can clean up.
+    /// One-shot workers take no input, so their recorded pid is signalled.
+    fn signal_kill(&mut self, prompt_id: usize) {
+        if let Some(sender) = self.worker_inputs.get(&prompt_id) {
+            let _ = sender.send(WorkerInput::Kill);
+        } else if let Some(pid) = self
+            .prompts
+            .iter()
+            .find(|p| p.id == prompt_id)
+            .and_then(|p| p.worker_pid)
+        {
+            process::terminate(pid, self.kill_grace);
+        }
+    }
+
+    /// Send an allowlisted signal (`process::SENDABLE_SIGNALS`) to a PTY
+    /// worker's process group, reaching tools the agent launched without
+    /// ending the session. Returns the canonical signal name.
+    pub fn send_signal(&mut self, prompt_id: usize, name: &str) -> Result<&'static str, String> {
+        let sig = process::sendable_signal(name)?;
+        let handle = self
+            .pty_handles
+            .get(&prompt_id)
+            .ok_or_else(|| "no running interactive (PTY) worker".to_string())?;
+        // portable-pty starts the child in its own session, so its pid is
+        // also its process group id
+        let pgid = handle.child.process_id().ok_or_else(|| "worker has no pid".to_string())?;
+        if process::signal_group(pgid, sig) {
+            Ok(sig)
+        } else {
+            Err(format!("could not deliver SIG{sig}"))
+        }
+    }
+
+    /// Kill every running or idle worker, leaving queued prompts for the
+    /// dispatcher. Each `Finished` releases its worker slot as usual.
+    pub fn kill_all_workers(&mut self) {
+        let ids: Vec<usize> = self
+            .prompts
+            .iter()
+            .filter(|p| p.status == PromptStatus::Running || p.status == PromptStatus::Idle)
+            .map(|p| p.id)
+            .collect();
+        let count = ids.len();
+        for id in ids {
+            self.kill_worker(id);
+        }
+        let msg = format!("Killed {count} worker{}", if count == 1 { "" } else { "s" });
+        self.status_message = Some((msg, Instant::now()));
+    }
+
+    /// Stop every worker on shutdown, waiting up to `kill_grace` for them
+    /// to exit before SIGKILLing the rest. Blocks the caller.
+    pub fn terminate_all_workers(&mut self) {
+        self.worker_inputs.clear();
+        self.pty_sizes.clear();
+        let mut handles: Vec<PtyHandle> = self.pty_handles.drain().map(|(_, h)| h).collect();
+        let mut pids: Vec<u32> = handles.iter().filter_map(|h| h.child.process_id()).collect();
+        handles.retain(|h| h.child.process_id().is_some());
+        // Workers without a terminal are only known by the pid they reported
+        pids.extend(
+            self.prompts
+                .iter()
+                .filter(|p| {
+                    matches!(p.status, PromptStatus::Running | PromptStatus::Idle)
+                        && p.pty_state.is_none()
+                })
+                .filter_map(|p| p.worker_pid),
+        );
+        process::terminate_all(&pids, self.kill_grace, |i| match handles.get_mut(i) {
+            Some(handle) => matches!(handle.child.try_wait(), Ok(Some(_))),
+            None => !process::is_alive(pids[i]),
+        });
+    }
+
+    /// Whether a finished prompt should raise a desktop notification.
+    fn should_notify(&self, prompt_id: usize) -> bool {
+        self.notifications && self.wants_alert(prompt_id)
+    }
+
+    /// Whether a prompt is worth alerting about (notification or bell).
+    /// The selected prompt is skipped since the user is already looking at it.
+    /// Killed prompts are skipped too: the user stopped them on purpose.
+    fn wants_alert(&self, prompt_id: usize) -> bool {
+        self.selected_prompt().map(|p| p.id) != Some(prompt_id)
+            && !self.prompts.iter().any(|p| p.id == prompt_id && p.is_killed())
+    }
+
+    fn maybe_notify(&mut self, prompt_id: usize, exit_code: Option<i32>) {
+        if self.bell_on_complete && self.wants_alert(prompt_id) {
+            self.pending_bell = true;
+        }
+        if !self.should_notify(prompt_id) {
+            return;
+        }
+        if let Some(prompt) = self.prompts.iter().find(|p| p.id == prompt_id) {
+            let (title, body) = notify::finished_message(prompt, exit_code);
+            notify::send(title, body);
+        }
+    }
+
+    /// Remember the agent's pid on the prompt file, so a later instance can
+    /// reap it if this one crashes.
+    pub fn record_worker_pid(&mut self, prompt_id: usize, pid: u32) {
+        if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) {
+            prompt.worker_pid = Some(pid);
+        }
+        self.persist_prompt_by_id(prompt_id);
+    }
+
+    /// POST the final outcome to `completion_webhook_url`, if set. Retried
+    /// attempts don't fire it; only the run that ends the prompt does.
+    fn post_completion_webhook(&self, prompt_id: usize, exit_code: Option<i32>) {
+        let Some(ref url) = self.completion_webhook_url else {
+            return;
+        };
+        if let Some(prompt) = self.prompts.iter().find(|p| p.id == prompt_id) {
+            let payload = webhook::CompletionPayload::from_prompt(prompt, exit_code);
+            webhook::post(url.clone(), &payload, self.message_tx.clone());
+        }
+    }
+
+    fn emit_finished(&self, prompt_id: usize, exit_code: Option<i32>) {
+        if let Some(prompt) = self.prompts.iter().find(|p| p.id == prompt_id) {
+            self.emit_event(events::Event::finished(prompt, exit_code));
+        }
+    }
+
+    /// Record the exit status for `clhorde tail`. Failures without a
+    /// non-zero exit code (timeouts, spawn errors) report 1.
+    fn mirror_exit(&self, prompt_id: usize, exit_code: Option<i32>) {
+        let Some(ref dir) = self.inbox_dir else {
+            return;
+        };
+        let Some(prompt) = self.prompts.iter().find(|p| p.id == prompt_id) else {
+            return;
+        };
+        let code = match prompt.status {
+            PromptStatus::Failed => exit_code.filter(|c| *c != 0).unwrap_or(1),
+            _ => 0,
+        };
+        inbox::write_exit(dir, prompt_id, code);
+    }
+
     pub fn selected_prompt(&self) -> Option<&Prompt> {
         self.list_state
             .selected()
             .and_then(|i| self.prompts.get(i))
     }
 
-    /// Mark the currently selected prompt as seen if it's finished.
+    /// Mark the currently selected prompt as seen if it's finished and not
+    /// hidden in a folded group.
     fn mark_selected_seen(&mut self) {
+        if self.selection_is_folded() {
+            return;
+        }
         if let Some(idx) = self.list_state.selected() {
             if let Some(prompt) = self.prompts.get_mut(idx) {
                 if prompt.status == PromptStatus::Completed || prompt.status == PromptStatus::Failed
@@ -478,6 +1787,14 @@ impl App {
         }
     }
 
+    /// Show a save failure once; the footer keeps flagging it afterwards.
+    pub fn report_persistence_error(&mut self) {
+        if let Some(error) = self.persistence_error.take() {
+            let msg = format!("Persistence disabled, {error}; changes are kept in memory only");
+            self.status_message = Some((msg, Instant::now()));
+        }
+    }
+
     /// Clear expired status messages (older than 3 seconds) and stale move highlights.
     pub fn clear_expired_status(&mut self) {
         if let Some((_, created)) = &self.status_message {
@@ -519,6 +1836,23 @@ impl App {
             return;
         }
 
+        // Kill-all confirmation intercepts all keys
+        if self.confirm_kill_all {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                self.kill_all_workers();
+            }
+            self.confirm_kill_all = false;
+            return;
+        }
+
+        // Discard-worktree confirmation intercepts all keys
+        if let Some(prompt_id) = self.confirm_discard_worktree.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                self.discard_worktree(prompt_id);
+            }
+            return;
+        }
+
         // Batch delete confirmation intercepts all keys
         if self.confirm_batch_delete {
             match key.code {
@@ -541,16 +1875,68 @@ impl App {
         }
     }
 
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        let pos = Position::new(event.column, event.row);
+        let in_list = self
+            .list_area
+            .is_some_and(|area| !self.list_collapsed && area.contains(pos));
+        match event.kind {
+            MouseEventKind::Down(_) if in_list && self.mode == AppMode::Normal => {
+                let area = self.list_area.unwrap_or_default();
+                // Row 0 is the top border
+                let Some(row) = event.row.checked_sub(area.y + 1) else {
+                    return;
+                };
+                let clicked = match self.list_rows().get(self.list_offset + row as usize) {
+                    Some(ListRow::Prompt(idx)) => Some(*idx),
+                    Some(ListRow::Group { first, .. }) => Some(*first),
+                    None => None,
+                };
+                if let Some(idx) = clicked {
+                    self.list_state.select(Some(idx));
+                    self.mark_selected_seen();
+                }
+            }
+            MouseEventKind::ScrollDown => match self.mode {
+                AppMode::ViewOutput => self.scroll_output(true, 3),
+                AppMode::Normal => {
+                    self.select_next();
+                    self.mark_selected_seen();
+                }
+                _ => {}
+            },
+            MouseEventKind::ScrollUp => match self.mode {
+                AppMode::ViewOutput => self.scroll_output(false, 3),
+                AppMode::Normal => {
+                    self.select_prev();
+                    self.mark_selected_seen();
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
     fn handle_normal_key(&mut self, key: KeyEvent) {
-        // Handle gg sequence: second g completes go-to-top
-        if self.pending_g {
-            self.pending_g = false;
-            if key.code == KeyCode::Char('g') && key.modifiers == KeyModifiers::NONE {
-                self.select_first();
-                self.mark_selected_seen();
+        if self.tag_input.is_some() {
+            self.handle_tag_input_key(key);
+            return;
+        }
+
+        // Continue a multi-key sequence (gg, za, …)
+        if !self.pending_sequence.is_empty() {
+            let mut keys = std::mem::take(&mut self.pending_sequence);
+            self.pending_sequence_at = None;
+            if key.code == KeyCode::Esc {
                 return;
             }
-            // Not a second g — fall through to normal handling
+            keys.push(KeyCombo::from(&key));
+            match self.keymap.resolve_sequence(&keys) {
+                SequenceMatch::Action(action) => return self.run_normal_action(action),
+                SequenceMatch::Prefix if self.sequence_applies(&keys) => return self.start_sequence(keys),
+                // Not part of a sequence — fall through to normal handling
+                _ => {}
+            }
         }
 
         // Ctrl+D → half page down
@@ -582,23 +1968,67 @@ impl App {
             return;
         }
 
-        // g starts the gg sequence
-        if key.code == KeyCode::Char('g') && key.modifiers == KeyModifiers::NONE
-            && !self.keymap.normal.contains_key(&KeyCode::Char('g'))
-        {
-            self.pending_g = true;
+        let combo = KeyCombo::from(&key);
+        let Some(&action) = self.keymap.normal.get(&combo) else {
+            if self.keymap.resolve_sequence(&[combo]) == SequenceMatch::Prefix && self.sequence_applies(&[combo]) {
+                return self.start_sequence(vec![combo]);
+            }
+            // Fallback: quick prompts go to the whole batch selection
+            if !self.selected_ids.is_empty() {
+                if let Some(message) = self.keymap.quick_prompts.get(&combo).cloned() {
+                    self.batch_quick_prompt(&message);
+                }
+            }
             return;
+        };
+        self.run_normal_action(action);
+    }
+
+    /// Whether a longer sequence after `keys` can do anything here. The fold
+    /// sequences (`za`, `zM`, `zR`) only mean something in the grouped view,
+    /// so outside it their prefix is an ordinary unbound key.
+    fn sequence_applies(&self, keys: &[KeyCombo]) -> bool {
+        self.keymap.sequences.iter().any(|(sequence, action)| {
+            sequence.len() > keys.len()
+                && sequence.starts_with(keys)
+                && (self.grouped
+                    || !matches!(
+                        action,
+                        NormalAction::ToggleGroupFold | NormalAction::FoldAllGroups | NormalAction::UnfoldAllGroups
+                    ))
+        })
+    }
+
+    /// Wait for the next key of a sequence, showing the which-key overlay.
+    fn start_sequence(&mut self, keys: Vec<KeyCombo>) {
+        self.pending_sequence = keys;
+        self.pending_sequence_at = Some(Instant::now());
+    }
+
+    /// Drop a half-typed sequence once `SEQUENCE_TIMEOUT` passes without
+    /// another key. Called on every tick.
+    pub fn expire_pending_sequence(&mut self) {
+        if self.pending_sequence_at.is_some_and(|at| at.elapsed() >= SEQUENCE_TIMEOUT) {
+            self.pending_sequence.clear();
+            self.pending_sequence_at = None;
         }
+    }
 
-        let Some(action) = self.keymap.normal.get(&key.code) else {
-            return;
-        };
+    fn run_normal_action(&mut self, action: NormalAction) {
         match action {
+            // A folded group's header stands in for its first prompt, which
+            // isn't on screen; acting on it would surprise the user
+            NormalAction::ViewOutput
+            | NormalAction::Retry
+            | NormalAction::Resume
+            | NormalAction::DeleteSelected
+            | NormalAction::KillSelected
+                if self.selected_ids.is_empty() && self.selection_is_folded() =>
+            {
+                self.status_message = Some(("Group is folded — unfold it first".to_string(), Instant::now()));
+            }
             NormalAction::Quit => {
-                let has_active = self.prompts.iter().any(|p| {
-                    p.status == PromptStatus::Running || p.status == PromptStatus::Idle
-                });
-                if has_active {
+                if self.live_worker_count() > 0 {
                     self.confirm_quit = true;
                 } else {
                     self.should_quit = true;
@@ -613,6 +2043,27 @@ impl App {
                 self.template_suggestions.clear();
                 self.template_suggestion_index = 0;
                 self.worktree_pending = false;
+                self.duplicate_source = None;
+                self.continue_session = None;
+            }
+            NormalAction::SelectNext if self.focused_pane == FocusedPane::Output => {
+                self.scroll_output(true, 1);
+            }
+            NormalAction::SelectPrev if self.focused_pane == FocusedPane::Output => {
+                self.scroll_output(false, 1);
+            }
+            NormalAction::NextUnseen => {
+                if self.select_next_unseen() {
+                    self.mark_selected_seen();
+                } else {
+                    self.status_message = Some(("No unseen prompts".to_string(), Instant::now()));
+                }
+            }
+            NormalAction::FocusToggle => {
+                self.focused_pane = match self.focused_pane {
+                    FocusedPane::List => FocusedPane::Output,
+                    FocusedPane::Output => FocusedPane::List,
+                };
             }
             NormalAction::SelectNext => {
                 self.select_next();
@@ -640,44 +2091,54 @@ impl App {
                 if let Some(idx) = self.list_state.selected() {
                     if idx < self.prompts.len() {
                         self.prompts[idx].seen = true;
-                        self.scroll_offset = 0;
+                        self.reset_output_scroll();
                         self.mode = AppMode::ViewOutput;
                         self.list_collapsed = true;
                     }
                 }
             }
             NormalAction::Interact => {
-                let target_mode = self.selected_prompt().and_then(|p| {
-                    if p.status == PromptStatus::Running || p.status == PromptStatus::Idle {
-                        if p.pty_state.is_some() {
-                            Some(AppMode::PtyInteract)
-                        } else {
-                            Some(AppMode::Interact)
-                        }
-                    } else {
-                        None
-                    }
-                });
-                if let Some(mode) = target_mode {
-                    self.scroll_offset = 0;
-                    if mode == AppMode::Interact {
-                        self.interact_input.clear();
-                    }
-                    self.mode = mode;
-                    self.list_collapsed = true;
+                if self.enter_interact(None) {
+                    self.reset_output_scroll();
+                }
+            }
+            NormalAction::ForceInteract => {
+                if self.enter_interact(Some(AppMode::Interact)) {
+                    self.reset_output_scroll();
+                }
+            }
+            NormalAction::ForcePtyInteract => {
+                if self.enter_interact(Some(AppMode::PtyInteract)) {
+                    self.reset_output_scroll();
                 }
             }
             NormalAction::IncreaseWorkers => {
                 self.max_workers = (self.max_workers + 1).min(20);
+                self.save_session_state();
             }
             NormalAction::DecreaseWorkers => {
                 self.max_workers = self.max_workers.saturating_sub(1).max(1);
+                self.save_session_state();
+            }
+            NormalAction::ToggleGroupFold if self.grouped => self.toggle_group_fold(),
+            NormalAction::FoldAllGroups if self.grouped => self.fold_all_groups(true),
+            NormalAction::UnfoldAllGroups if self.grouped => self.fold_all_groups(false),
+            NormalAction::ToggleGroupFold | NormalAction::FoldAllGroups | NormalAction::UnfoldAllGroups => {}
+            NormalAction::ToggleGrouped => {
+                self.toggle_grouped();
+            }
+            NormalAction::CycleSort => {
+                self.cycle_sort();
+            }
+            NormalAction::TogglePause => {
+                self.toggle_pause();
             }
             NormalAction::ToggleMode => {
                 if !self.selected_ids.is_empty() {
                     self.batch_toggle_mode();
                 } else {
                     self.default_mode = self.default_mode.toggle();
+                    self.save_session_state();
                 }
             }
             NormalAction::Retry => {
@@ -690,6 +2151,36 @@ impl App {
             NormalAction::Resume => {
                 self.resume_selected();
             }
+            NormalAction::ContinueSession => {
+                self.start_continue_selected();
+            }
+            NormalAction::Duplicate => {
+                self.start_duplicate_selected();
+            }
+            NormalAction::Export => {
+                self.export_selected_output();
+            }
+            NormalAction::ExportJson => {
+                self.export_selected_json();
+            }
+            NormalAction::EditTags => {
+                if self.selected_prompt().is_some() || !self.selected_ids.is_empty() {
+                    self.tag_input = Some(String::new());
+                }
+            }
+            NormalAction::CyclePriority => {
+                if !self.selected_ids.is_empty() {
+                    self.batch_cycle_priority();
+                } else {
+                    self.cycle_selected_priority();
+                }
+            }
+            NormalAction::MoveUp if self.sort_mode == SortMode::Manual => {
+                self.move_in_display_order(false);
+            }
+            NormalAction::MoveDown if self.sort_mode == SortMode::Manual => {
+                self.move_in_display_order(true);
+            }
             NormalAction::MoveUp => {
                 self.move_selected_up();
             }
@@ -806,25 +2297,84 @@ impl App {
                         }
                     });
                     if let Some(id) = kill_id {
-                        if let Some(sender) = self.worker_inputs.get(&id) {
-                            let _ = sender.send(WorkerInput::Kill);
-                        }
-                        if let Some(mut handle) = self.pty_handles.remove(&id) {
-                            let _ = handle.child.kill();
-                        }
+                        self.kill_worker(id);
                     }
                 }
             }
+            NormalAction::KillAll => {
+                if self.live_worker_count() > 0 {
+                    self.confirm_kill_all = true;
+                } else {
+                    self.status_message = Some(("No running workers".to_string(), Instant::now()));
+                }
+            }
+            NormalAction::Interrupt => {
+                if let Some(id) = self.selected_prompt().map(|p| p.id) {
+                    let message = match self.send_signal(id, "INT") {
+                        Ok(sig) => format!("Sent SIG{sig} to #{id}"),
+                        Err(e) => format!("Cannot interrupt #{id}: {e}"),
+                    };
+                    self.status_message = Some((message, Instant::now()));
+                }
+            }
         }
     }
 
     fn handle_insert_key(&mut self, key: KeyEvent) {
-        // Ctrl+W toggles worktree mode for the current prompt
-        if key.code == KeyCode::Char('w') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.history_search.is_some() || (ctrl && key.code == KeyCode::Char('r')) {
+            self.handle_history_search_key(key);
+            return;
+        }
+
+        // Ctrl+T toggles worktree mode for the current prompt
+        if key.code == KeyCode::Char('t') && key.modifiers.contains(KeyModifiers::CONTROL) {
             self.worktree_pending = !self.worktree_pending;
             return;
         }
 
+        // Readline-style word and line editing, plus undo/redo
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('w') if ctrl => {
+                self.input.delete_word_backward();
+                self.history_index = None;
+                self.update_suggestions();
+                self.update_template_suggestions();
+                return;
+            }
+            KeyCode::Char('k') if ctrl => {
+                self.input.delete_to_end_of_line();
+                self.history_index = None;
+                self.update_suggestions();
+                self.update_template_suggestions();
+                return;
+            }
+            KeyCode::Char('z') if ctrl => {
+                self.input.undo();
+                self.history_index = None;
+                self.update_suggestions();
+                self.update_template_suggestions();
+                return;
+            }
+            KeyCode::Char('y') if ctrl => {
+                self.input.redo();
+                self.history_index = None;
+                self.update_suggestions();
+                self.update_template_suggestions();
+                return;
+            }
+            KeyCode::Char('b') if alt => {
+                self.input.move_word_left();
+                return;
+            }
+            KeyCode::Char('f') if alt => {
+                self.input.move_word_right();
+                return;
+            }
+            _ => {}
+        }
+
         // Shift+Enter or Alt+Enter → insert newline
         if key.code == KeyCode::Enter
             && (key.modifiers.contains(KeyModifiers::SHIFT)
@@ -861,7 +2411,7 @@ impl App {
             return;
         }
 
-        if let Some(action) = self.keymap.insert.get(&key.code) {
+        if let Some(action) = self.keymap.insert.get(&KeyCombo::from(&key)) {
             match action {
                 InsertAction::Cancel => {
                     self.mode = AppMode::Normal;
@@ -873,15 +2423,18 @@ impl App {
                     self.template_suggestions.clear();
                     self.template_suggestion_index = 0;
                     self.worktree_pending = false;
+                    self.duplicate_source = None;
+                    self.continue_session = None;
                 }
                 InsertAction::Submit => {
                     let text = self.input.trimmed();
                     if !text.is_empty() {
                         let (cwd, prompt_text) = Self::parse_cwd_prefix(&text);
                         if !prompt_text.is_empty() {
+                            let (mode, prompt_text) = crate::prompt::parse_mode_token(&prompt_text);
                             let (tags, clean_text) = crate::prompt::parse_tags(&prompt_text);
                             if !clean_text.is_empty() {
-                                self.add_prompt(clean_text, cwd, self.worktree_pending, tags);
+                                self.submit_input_prompt(clean_text, cwd, tags, mode);
                             }
                             self.append_history(&text);
                         }
@@ -894,6 +2447,8 @@ impl App {
                     self.template_suggestions.clear();
                     self.template_suggestion_index = 0;
                     self.worktree_pending = false;
+                    self.duplicate_source = None;
+                    self.continue_session = None;
                     self.mode = AppMode::Normal;
                 }
                 InsertAction::AcceptSuggestion => {
@@ -901,6 +2456,8 @@ impl App {
                         self.accept_suggestion();
                     } else if !self.template_suggestions.is_empty() {
                         self.accept_template_suggestion();
+                    } else {
+                        self.input.next_placeholder();
                     }
                 }
                 InsertAction::NextSuggestion => {
@@ -971,61 +2528,89 @@ impl App {
         // Ctrl+P toggles quick prompts popup
         if key.code == KeyCode::Char('p') && key.modifiers.contains(KeyModifiers::CONTROL) {
             self.show_quick_prompts_popup = !self.show_quick_prompts_popup;
+            self.quick_prompt_index = 0;
+            self.quick_prompt_filter.clear();
             return;
         }
 
-        // When popup is visible, any other key closes it
+        // While the popup is open it owns the keyboard
         if self.show_quick_prompts_popup {
-            self.show_quick_prompts_popup = false;
-            // If it's Esc, consume it (don't also leave view mode)
-            if key.code == KeyCode::Esc {
-                return;
+            self.handle_quick_prompts_key(key);
+            return;
+        }
+
+        // Typing a search query captures all keys until Enter/Esc
+        if let Some(ref mut query) = self.output_search_input {
+            match key.code {
+                KeyCode::Enter => {
+                    let query = query.trim().to_string();
+                    self.output_search_input = None;
+                    if query.is_empty() {
+                        self.clear_output_search();
+                    } else {
+                        self.output_search = Some(query);
+                        self.output_search_line = None;
+                        self.jump_to_output_match(true);
+                    }
+                }
+                KeyCode::Esc => self.output_search_input = None,
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Char(c) => query.push(c),
+                _ => {}
             }
-            // Otherwise fall through to normal view mode handling (including quick prompt dispatch)
+            return;
         }
 
         // View actions take priority
-        let Some(action) = self.keymap.view.get(&key.code) else {
+        let Some(action) = self.keymap.view.get(&KeyCombo::from(&key)) else {
             // Fallback: check quick prompts
             self.try_quick_prompt(&key);
             return;
         };
         match action {
             ViewAction::Back => {
+                // First Esc closes the diff or clears an active search,
+                // second leaves the view
+                if self.worktree_diff.is_some() {
+                    self.reset_output_scroll();
+                    return;
+                }
+                if self.output_search.is_some() {
+                    self.clear_output_search();
+                    return;
+                }
                 self.show_quick_prompts_popup = false;
                 self.mode = AppMode::Normal;
-                self.scroll_offset = 0;
+                self.reset_output_scroll();
                 self.list_collapsed = false;
             }
-            ViewAction::ScrollDown => {
-                self.scroll_offset = self.scroll_offset.saturating_add(1);
-            }
-            ViewAction::ScrollUp => {
-                self.scroll_offset = self.scroll_offset.saturating_sub(1);
-            }
+            ViewAction::ScrollDown => self.scroll_output(true, 1),
+            ViewAction::ScrollUp => self.scroll_output(false, 1),
             ViewAction::Interact => {
-                let target_mode = self.selected_prompt().and_then(|p| {
-                    if p.status == PromptStatus::Running || p.status == PromptStatus::Idle {
-                        if p.pty_state.is_some() {
-                            Some(AppMode::PtyInteract)
-                        } else {
-                            Some(AppMode::Interact)
-                        }
-                    } else {
-                        None
-                    }
-                });
-                if let Some(mode) = target_mode {
+                if self.enter_interact(None) {
+                    self.show_quick_prompts_popup = false;
+                }
+            }
+            ViewAction::ForceInteract => {
+                if self.enter_interact(Some(AppMode::Interact)) {
+                    self.show_quick_prompts_popup = false;
+                }
+            }
+            ViewAction::ForcePtyInteract => {
+                if self.enter_interact(Some(AppMode::PtyInteract)) {
                     self.show_quick_prompts_popup = false;
-                    if mode == AppMode::Interact {
-                        self.interact_input.clear();
-                    }
-                    self.mode = mode;
-                    self.list_collapsed = true;
                 }
             }
             ViewAction::ToggleAutoscroll => {
-                self.auto_scroll = !self.auto_scroll;
+                if self.auto_scroll && !self.output_at_bottom {
+                    // Following but scrolled up: jump back to the bottom
+                    self.output_at_bottom = true;
+                } else {
+                    self.auto_scroll = !self.auto_scroll;
+                    self.output_at_bottom |= self.auto_scroll;
+                }
             }
             ViewAction::KillWorker => {
                 let kill_id = self.selected_prompt().and_then(|p| {
@@ -1036,26 +2621,81 @@ impl App {
                     }
                 });
                 if let Some(id) = kill_id {
-                    if let Some(sender) = self.worker_inputs.get(&id) {
-                        let _ = sender.send(WorkerInput::Kill);
-                    }
-                    // Kill the child process and drop the PTY handle
-                    if let Some(mut handle) = self.pty_handles.remove(&id) {
-                        let _ = handle.child.kill();
-                    }
+                    self.kill_worker(id);
                 }
             }
             ViewAction::Export => {
                 self.export_selected_output();
             }
+            ViewAction::ExportJson => {
+                self.export_selected_json();
+            }
             ViewAction::ToggleSplit => {
                 self.list_collapsed = !self.list_collapsed;
             }
+            ViewAction::CopyToClipboard => {
+                self.copy_selected_output();
+            }
+            ViewAction::Search => {
+                self.output_search_input = Some(String::new());
+            }
+            ViewAction::SearchNext => {
+                self.jump_to_output_match(true);
+            }
+            ViewAction::SearchPrev => {
+                self.jump_to_output_match(false);
+            }
+            ViewAction::WorktreeDiff => {
+                self.toggle_worktree_diff();
+            }
+            ViewAction::ApplyWorktree => {
+                self.apply_selected_worktree();
+            }
+            ViewAction::DiscardWorktree => {
+                if let Some((prompt_id, _)) = self.finished_worktree_of_selected() {
+                    if self.applying_worktrees.contains(&prompt_id) {
+                        let msg = format!("#{prompt_id}: worktree merge in progress; discard it once that finishes");
+                        self.status_message = Some((msg, Instant::now()));
+                    } else {
+                        self.confirm_discard_worktree = Some(prompt_id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Switch to an interact mode for the selected running prompt: raw-key
+    /// `PtyInteract` for PTY workers and line-based `Interact` otherwise,
+    /// unless `force`d. Line input works on any live worker; raw keys need
+    /// a PTY. Returns whether the mode changed.
+    fn enter_interact(&mut self, force: Option<AppMode>) -> bool {
+        let Some(prompt) = self.selected_prompt() else {
+            return false;
+        };
+        if prompt.status != PromptStatus::Running && prompt.status != PromptStatus::Idle {
+            return false;
+        }
+        let has_pty = prompt.pty_state.is_some();
+        let mode = match force {
+            Some(AppMode::PtyInteract) if !has_pty => {
+                let msg = format!("#{} has no terminal to attach to", prompt.id);
+                self.status_message = Some((msg, Instant::now()));
+                return false;
+            }
+            Some(mode) => mode,
+            None if has_pty => AppMode::PtyInteract,
+            None => AppMode::Interact,
+        };
+        if mode == AppMode::Interact {
+            self.interact_input.clear();
         }
+        self.mode = mode;
+        self.list_collapsed = true;
+        true
     }
 
     fn handle_interact_key(&mut self, key: KeyEvent) {
-        if let Some(action) = self.keymap.interact.get(&key.code) {
+        if let Some(action) = self.keymap.interact.get(&KeyCombo::from(&key)) {
             match action {
                 InteractAction::Back => {
                     self.mode = AppMode::Normal;
@@ -1064,19 +2704,11 @@ impl App {
                 }
                 InteractAction::Send => {
                     if let Some(idx) = self.list_state.selected() {
-                        if let Some(prompt) = self.prompts.get_mut(idx) {
-                            let id = prompt.id;
-                            if let Some(sender) = self.worker_inputs.get(&id) {
-                                let text = self.interact_input.clone();
-                                let echo = format!("\n\n> {text}\n\n");
-                                match &mut prompt.output {
-                                    Some(existing) => existing.push_str(&echo),
-                                    None => prompt.output = Some(echo),
-                                }
-                                let mut send_text = text;
-                                send_text.push('\n');
-                                let _ = sender.send(WorkerInput::SendInput(send_text));
-                            }
+                        let text = self.interact_input.clone();
+                        if self.prompts.get(idx).is_some_and(|p| p.pty_state.is_some()) {
+                            self.send_pty_line(idx, text);
+                        } else {
+                            self.send_stream_input(idx, text);
                         }
                     }
                     self.interact_input.clear();
@@ -1097,16 +2729,11 @@ impl App {
     }
 
     fn handle_filter_key(&mut self, key: KeyEvent) {
-        if let Some(action) = self.keymap.filter.get(&key.code) {
+        if let Some(action) = self.keymap.filter.get(&KeyCombo::from(&key)) {
             match action {
                 FilterAction::Confirm => {
-                    let text = self.filter_input.trim().to_string();
-                    if text.is_empty() {
-                        self.filter_text = None;
-                    } else {
-                        self.filter_text = Some(text);
-                    }
-                    self.rebuild_filter();
+                    self.save_named_filter();
+                    self.apply_filter_input();
                     self.mode = AppMode::Normal;
                     // Adjust selection to be valid within filtered view
                     self.clamp_selection_to_filter();
@@ -1124,22 +2751,94 @@ impl App {
             KeyCode::Backspace => {
                 self.filter_input.pop();
                 // Live filter as user types
-                let text = self.filter_input.trim().to_string();
-                self.filter_text = if text.is_empty() { None } else { Some(text) };
-                self.rebuild_filter();
+                self.apply_filter_input();
                 self.clamp_selection_to_filter();
             }
             KeyCode::Char(c) => {
                 self.filter_input.push(c);
-                let text = self.filter_input.trim().to_string();
-                self.filter_text = if text.is_empty() { None } else { Some(text) };
-                self.rebuild_filter();
+                self.apply_filter_input();
                 self.clamp_selection_to_filter();
             }
             _ => {}
         }
     }
 
+    /// Set `filter_text` from the filter input, expanding `!name` tokens
+    /// and ignoring a trailing `>name` save token.
+    fn apply_filter_input(&mut self) {
+        let (filter, _) = split_save_token(&self.filter_input);
+        let text = filters::expand(filter, &self.saved_filters);
+        self.filter_text = if text.is_empty() { None } else { Some(text) };
+        self.rebuild_filter();
+    }
+
+    /// If the filter input ends with `>name`, save the rest of it (as
+    /// typed, so `!name` references stay references) under `name`.
+    fn save_named_filter(&mut self) {
+        let (filter, Some(name)) = split_save_token(&self.filter_input) else {
+            return;
+        };
+        let msg = if !filters::is_valid_name(name) {
+            format!("Invalid filter name '{name}'")
+        } else if filter.is_empty() {
+            "Nothing to save".to_string()
+        } else {
+            let name = name.to_string();
+            self.saved_filters.insert(name.clone(), filter.to_string());
+            match filters::save_filters(&self.saved_filters) {
+                Ok(()) => format!("Saved filter !{name}"),
+                Err(e) => format!("Failed to save filter: {e}"),
+            }
+        };
+        self.status_message = Some((msg, Instant::now()));
+        self.filter_input = filter.to_string();
+    }
+
+    /// Bracketed paste from the terminal. Insert mode takes the text as
+    /// typed, PTY Interact forwards it to the agent as one framed paste, and
+    /// Interact appends it to the single-line input with line breaks as
+    /// spaces.
+    pub fn handle_paste(&mut self, text: &str) {
+        match self.mode {
+            AppMode::Insert => {
+                for c in text.chars() {
+                    if c == '\n' {
+                        self.input.insert_newline();
+                    } else if c != '\r' {
+                        self.input.insert_char(c);
+                    }
+                }
+            }
+            AppMode::PtyInteract => {
+                let Some(prompt) = self
+                    .selected_prompt()
+                    .filter(|p| p.status == PromptStatus::Running || p.status == PromptStatus::Idle)
+                else {
+                    return;
+                };
+                // Only frame the paste when the agent asked for it; otherwise
+                // the markers would arrive as literal junk
+                let bracketed = prompt
+                    .pty_state
+                    .as_ref()
+                    .is_some_and(pty_worker::bracketed_paste_enabled);
+                let bytes = if bracketed {
+                    pty_worker::bracketed_paste(text)
+                } else {
+                    text.as_bytes().to_vec()
+                };
+                if let Some(sender) = self.worker_inputs.get(&prompt.id) {
+                    let _ = sender.send(WorkerInput::SendBytes(bytes));
+                }
+            }
+            AppMode::Interact => {
+                let line = text.replace("\r\n", " ").replace(['\r', '\n'], " ");
+                self.interact_input.push_str(&line);
+            }
+            _ => {}
+        }
+    }
+
     fn handle_pty_interact_key(&mut self, key: KeyEvent) {
         // Esc exits PTY interact mode back to view
         if key.code == KeyCode::Esc && key.modifiers == KeyModifiers::NONE {
@@ -1168,58 +2867,164 @@ impl App {
         }
     }
 
-    fn try_quick_prompt(&mut self, key: &KeyEvent) {
-        let Some(message) = self.keymap.quick_prompts.get(&key.code) else {
+    /// Keys for the open quick prompts popup: j/k or arrows move the
+    /// highlight, Enter sends it, other characters narrow the list. Once a
+    /// filter is typed, j/k are text too.
+    fn handle_quick_prompts_key(&mut self, key: KeyEvent) {
+        let typing = !self.quick_prompt_filter.is_empty();
+        if key.code == KeyCode::Down || (!typing && key.code == KeyCode::Char('j')) {
+            let last = self.quick_prompt_matches().len().saturating_sub(1);
+            self.quick_prompt_index = (self.quick_prompt_index + 1).min(last);
             return;
-        };
-        let Some(idx) = self.list_state.selected() else {
+        }
+        if key.code == KeyCode::Up || (!typing && key.code == KeyCode::Char('k')) {
+            self.quick_prompt_index = self.quick_prompt_index.saturating_sub(1);
             return;
-        };
-        let Some(prompt) = self.prompts.get_mut(idx) else {
+        }
+        match key.code {
+            KeyCode::Esc => self.show_quick_prompts_popup = false,
+            KeyCode::Enter => {
+                let selected = self.quick_prompt_matches().into_iter().nth(self.quick_prompt_index);
+                if let Some((_, message)) = selected {
+                    self.show_quick_prompts_popup = false;
+                    self.send_quick_prompt(message);
+                }
+            }
+            KeyCode::Backspace => {
+                self.quick_prompt_filter.pop();
+                self.quick_prompt_index = 0;
+            }
+            KeyCode::Char(c) => {
+                self.quick_prompt_filter.push(c);
+                self.quick_prompt_index = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Quick prompts as (key, message), narrowed by `quick_prompt_filter`
+    /// (case-insensitive, on either the key or the message).
+    pub fn quick_prompt_matches(&self) -> Vec<(String, String)> {
+        let filter = self.quick_prompt_filter.to_lowercase();
+        self.keymap
+            .quick_prompt_help()
+            .into_iter()
+            .filter(|(key, msg)| key.to_lowercase().contains(&filter) || msg.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    fn try_quick_prompt(&mut self, key: &KeyEvent) {
+        let Some(message) = self.keymap.quick_prompts.get(&KeyCombo::from(key)) else {
             return;
         };
+        self.send_quick_prompt(message.clone());
+    }
+
+    /// Send a quick prompt's message to the selected prompt's live worker.
+    fn send_quick_prompt(&mut self, message: String) {
+        if let Some(idx) = self.list_state.selected() {
+            self.send_quick_prompt_to(idx, message);
+        }
+    }
+
+    /// Send a quick prompt's message to the worker of `prompts[idx]`.
+    /// Returns false if the prompt has no live worker to take it.
+    fn send_quick_prompt_to(&mut self, idx: usize, message: String) -> bool {
+        let Some(prompt) = self.prompts.get(idx) else {
+            return false;
+        };
         if prompt.status != PromptStatus::Running && prompt.status != PromptStatus::Idle {
-            return;
+            return false;
         }
-        let id = prompt.id;
-        let Some(sender) = self.worker_inputs.get(&id) else {
-            return;
+        let Some(sender) = self.worker_inputs.get(&prompt.id) else {
+            return false;
         };
-
         if prompt.pty_state.is_some() {
             // PTY worker: send message as typed text + Enter (no echo needed,
             // the PTY terminal will show it)
-            let mut bytes = message.as_bytes().to_vec();
+            let mut bytes = message.into_bytes();
             bytes.push(b'\r');
             let _ = sender.send(WorkerInput::SendBytes(bytes));
         } else {
-            // Stream-json worker: echo and send as structured input
-            let echo = format!("\n\n> {message}\n\n");
-            match &mut prompt.output {
-                Some(existing) => existing.push_str(&echo),
-                None => prompt.output = Some(echo),
+            self.send_stream_input(idx, message);
+        }
+        true
+    }
+
+    /// Fan a quick prompt out to every batch-selected prompt with a live
+    /// worker; the others are skipped.
+    fn batch_quick_prompt(&mut self, message: &str) {
+        let indices: Vec<usize> = (0..self.prompts.len())
+            .filter(|&i| self.selected_ids.contains(&self.prompts[i].id))
+            .collect();
+        let mut count = 0;
+        for idx in indices {
+            if self.send_quick_prompt_to(idx, message.to_string()) {
+                count += 1;
             }
-            let mut send_text = message.clone();
-            send_text.push('\n');
-            let _ = sender.send(WorkerInput::SendInput(send_text));
         }
+        self.clear_selection();
+        let noun = if count == 1 { "agent" } else { "agents" };
+        self.status_message = Some((format!("Sent to {count} {noun}"), Instant::now()));
+    }
+
+    /// Send a follow-up to a stream-json worker. While a turn is running the
+    /// message is queued and flushed on the next `TurnComplete`, since the
+    /// agent would otherwise receive it mid-turn.
+    fn send_stream_input(&mut self, idx: usize, text: String) {
+        let Some(prompt) = self.prompts.get_mut(idx) else {
+            return;
+        };
+        if !self.worker_inputs.contains_key(&prompt.id) {
+            return;
+        }
+        if prompt.status == PromptStatus::Running {
+            prompt.queued_inputs.push_back(text);
+            return;
+        }
+        self.write_stream_input(idx, text);
+    }
+
+    /// Type a whole line into a PTY worker, as if entered and confirmed with
+    /// Enter. The terminal echoes it, so nothing is added to the output.
+    fn send_pty_line(&self, idx: usize, text: String) {
+        let Some(sender) = self.prompts.get(idx).and_then(|p| self.worker_inputs.get(&p.id)) else {
+            return;
+        };
+        let mut line = text;
+        line.push('\r');
+        let _ = sender.send(WorkerInput::SendInput(line));
+    }
+
+    /// Echo `text` into the output and write it to the worker, starting a turn.
+    fn write_stream_input(&mut self, idx: usize, text: String) {
+        let prompt = &mut self.prompts[idx];
+        let Some(sender) = self.worker_inputs.get(&prompt.id) else {
+            return;
+        };
+        prompt.append_output(&format!("\n\n> {text}\n\n"), self.max_output_bytes);
+        prompt.status = PromptStatus::Running;
+        prompt.turn_started_at = Some(Instant::now());
+        let mut send_text = text;
+        send_text.push('\n');
+        let _ = sender.send(WorkerInput::SendInput(send_text));
     }
 
     fn select_next(&mut self) {
         if self.prompts.is_empty() {
             return;
         }
-        if self.filter_text.is_some() && !self.filtered_indices.is_empty() {
-            // Navigate within filtered list
+        if self.view_is_reordered() {
+            // Navigate within the filtered/grouped list
+            let order = self.navigable_indices();
             let current = self.list_state.selected().unwrap_or(0);
-            let current_filter_pos = self
-                .filtered_indices
+            let current_filter_pos = order
                 .iter()
                 .position(|&i| i == current)
                 .unwrap_or(0);
-            let next_pos = (current_filter_pos + 1).min(self.filtered_indices.len() - 1);
+            let next_pos = (current_filter_pos + 1).min(order.len() - 1);
             self.list_state
-                .select(Some(self.filtered_indices[next_pos]));
+                .select(Some(order[next_pos]));
         } else {
             let i = match self.list_state.selected() {
                 Some(i) => (i + 1).min(self.prompts.len() - 1),
@@ -1233,16 +3038,16 @@ impl App {
         if self.prompts.is_empty() {
             return;
         }
-        if self.filter_text.is_some() && !self.filtered_indices.is_empty() {
+        if self.view_is_reordered() {
+            let order = self.navigable_indices();
             let current = self.list_state.selected().unwrap_or(0);
-            let current_filter_pos = self
-                .filtered_indices
+            let current_filter_pos = order
                 .iter()
                 .position(|&i| i == current)
                 .unwrap_or(0);
             let prev_pos = current_filter_pos.saturating_sub(1);
             self.list_state
-                .select(Some(self.filtered_indices[prev_pos]));
+                .select(Some(order[prev_pos]));
         } else {
             let i = match self.list_state.selected() {
                 Some(i) => i.saturating_sub(1),
@@ -1252,6 +3057,28 @@ impl App {
         }
     }
 
+    /// Select the next finished prompt not yet looked at, in list order and
+    /// wrapping past the end. Returns false if there is none.
+    fn select_next_unseen(&mut self) -> bool {
+        let order = if self.view_is_reordered() {
+            self.navigable_indices()
+        } else {
+            (0..self.prompts.len()).collect()
+        };
+        let start = self
+            .list_state
+            .selected()
+            .and_then(|current| order.iter().position(|&i| i == current))
+            .map_or(0, |pos| pos + 1);
+        let next = (0..order.len())
+            .map(|offset| order[(start + offset) % order.len()])
+            .find(|&i| self.prompts[i].is_unseen());
+        if let Some(idx) = next {
+            self.list_state.select(Some(idx));
+        }
+        next.is_some()
+    }
+
     fn half_page_size(&self) -> usize {
         let h = if self.list_height > 2 { self.list_height - 2 } else { 10 };
         (h as usize / 2).max(1)
@@ -1262,16 +3089,16 @@ impl App {
             return;
         }
         let step = self.half_page_size();
-        if self.filter_text.is_some() && !self.filtered_indices.is_empty() {
+        if self.view_is_reordered() {
+            let order = self.navigable_indices();
             let current = self.list_state.selected().unwrap_or(0);
-            let current_filter_pos = self
-                .filtered_indices
+            let current_filter_pos = order
                 .iter()
                 .position(|&i| i == current)
                 .unwrap_or(0);
-            let next_pos = (current_filter_pos + step).min(self.filtered_indices.len() - 1);
+            let next_pos = (current_filter_pos + step).min(order.len() - 1);
             self.list_state
-                .select(Some(self.filtered_indices[next_pos]));
+                .select(Some(order[next_pos]));
         } else {
             let i = match self.list_state.selected() {
                 Some(i) => (i + step).min(self.prompts.len() - 1),
@@ -1286,16 +3113,16 @@ impl App {
             return;
         }
         let step = self.half_page_size();
-        if self.filter_text.is_some() && !self.filtered_indices.is_empty() {
+        if self.view_is_reordered() {
+            let order = self.navigable_indices();
             let current = self.list_state.selected().unwrap_or(0);
-            let current_filter_pos = self
-                .filtered_indices
+            let current_filter_pos = order
                 .iter()
                 .position(|&i| i == current)
                 .unwrap_or(0);
             let prev_pos = current_filter_pos.saturating_sub(step);
             self.list_state
-                .select(Some(self.filtered_indices[prev_pos]));
+                .select(Some(order[prev_pos]));
         } else {
             let i = match self.list_state.selected() {
                 Some(i) => i.saturating_sub(step),
@@ -1309,9 +3136,10 @@ impl App {
         if self.prompts.is_empty() {
             return;
         }
-        if self.filter_text.is_some() && !self.filtered_indices.is_empty() {
+        if self.view_is_reordered() {
+            let order = self.navigable_indices();
             self.list_state
-                .select(Some(self.filtered_indices[0]));
+                .select(Some(order[0]));
         } else {
             self.list_state.select(Some(0));
         }
@@ -1321,67 +3149,226 @@ impl App {
         if self.prompts.is_empty() {
             return;
         }
-        if self.filter_text.is_some() && !self.filtered_indices.is_empty() {
+        if self.view_is_reordered() {
+            let order = self.navigable_indices();
             self.list_state
-                .select(Some(*self.filtered_indices.last().unwrap()));
+                .select(Some(*order.last().unwrap()));
         } else {
             self.list_state.select(Some(self.prompts.len() - 1));
         }
     }
 
+    // ── Output scrolling ──
+
+    /// Scroll the output view by `rows`. Like `less +F`, follow mode only
+    /// tracks new output while the view is at the bottom: scrolling up
+    /// pauses it and scrolling back down to the end resumes it.
+    fn scroll_output(&mut self, down: bool, rows: u16) {
+        self.scroll_offset = if down {
+            self.scroll_offset.saturating_add(rows)
+        } else {
+            self.scroll_offset.saturating_sub(rows)
+        };
+        self.output_at_bottom = self.scroll_offset >= self.output_scroll_max;
+    }
+
+    /// Start the output view from the top, following a running prompt.
+    fn reset_output_scroll(&mut self) {
+        self.scroll_offset = 0;
+        self.output_at_bottom = true;
+        self.worktree_diff = None;
+    }
+
     // ── PTY resize ──
 
-    pub fn resize_pty_workers(&mut self, cols: u16, rows: u16) {
-        for handle in self.pty_handles.values() {
-            pty_worker::resize_pty(handle, cols, rows);
+    /// Size a PTY should have: the output panel's while that prompt is the
+    /// one being viewed, `DEFAULT_PTY_SIZE` otherwise, so background agents
+    /// don't reflow every time the panel changes.
+    pub fn pty_size_for(&self, prompt_id: usize) -> (u16, u16) {
+        match self.output_panel_size {
+            Some((cols, rows)) if self.viewed_pty == Some(prompt_id) && cols > 0 && rows > 0 => (cols, rows),
+            _ => pty_worker::DEFAULT_PTY_SIZE,
+        }
+    }
+
+    /// PTYs whose size differs from `pty_size_for`, with the size to send.
+    fn pending_pty_resizes(&self) -> Vec<(usize, (u16, u16))> {
+        self.pty_sizes
+            .iter()
+            .map(|(&id, &size)| (id, size, self.pty_size_for(id)))
+            .filter(|(_, size, target)| size != target)
+            .map(|(id, _, target)| (id, target))
+            .collect()
+    }
+
+    /// Resize the PTYs whose target size changed since the last call.
+    pub fn sync_pty_sizes(&mut self) {
+        for (id, (cols, rows)) in self.pending_pty_resizes() {
+            if let Some(handle) = self.pty_handles.get(&id) {
+                pty_worker::resize_pty(handle, cols, rows);
+            }
+            self.pty_sizes.insert(id, (cols, rows));
         }
-        self.last_pty_size = Some((cols, rows));
     }
 
-    // ── Worktree cleanup ──
+    // ── Worktree autocommit & cleanup ──
 
-    fn maybe_cleanup_worktree(&mut self, prompt_id: usize) {
-        if self.worktree_cleanup != WorktreeCleanup::Auto {
+    /// Commit (`worktree_autocommit`, successful prompts only) and/or remove
+    /// (`worktree_cleanup = "auto"`) a finished prompt's worktree. Both run
+    /// in one background thread so the commit lands before removal, which
+    /// is skipped when the commit made one.
+    fn finish_worktree(&mut self, prompt_id: usize, completed: bool) {
+        let commit = self.worktree_autocommit && completed;
+        let cleanup = self.worktree_cleanup == WorktreeCleanup::Auto;
+        if !commit && !cleanup {
             return;
         }
         let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == prompt_id) else {
             return;
         };
-        let Some(wt_path) = prompt.worktree_path.take() else {
+        let Some(wt_path) = prompt.worktree_path.clone() else {
             return;
         };
-        // Persist the cleared worktree_path
-        if let Some(ref dir) = self.prompts_dir {
-            if let Some(prompt) = self.prompts.iter().find(|p| p.id == prompt_id) {
-                persistence::save_prompt(dir, &prompt.uuid, &persistence::PromptFile::from_prompt(prompt));
-            }
+        let message = prompt.text.clone();
+        // With autocommit, removal waits for the commit outcome
+        if cleanup && !commit {
+            prompt.worktree_path = None;
+            // Persist the cleared worktree_path
+            self.persist_prompt_by_id(prompt_id);
         }
-        // Spawn a background thread for cleanup to avoid blocking
+        let tx = self.message_tx.clone().filter(|_| commit);
         let wt_path = PathBuf::from(&wt_path);
         std::thread::spawn(move || {
-            // Try to find repo root from the worktree path's parent
-            if let Some(parent) = wt_path.parent() {
-                // Look for the main repo among siblings
-                if let Ok(entries) = std::fs::read_dir(parent) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.is_dir() && path != wt_path
-                            && worktree::is_git_repo(&path)
-                        {
-                            if let Some(root) = worktree::repo_root(&path) {
-                                let _ = worktree::remove_worktree(&root, &wt_path);
-                                return;
-                            }
-                        }
-                    }
+            if !commit {
+                remove_sibling_worktree(&wt_path);
+                return;
+            }
+            let result = worktree::commit_all(&wt_path, &message);
+            // A new commit lives only in the worktree, so it stays for apply
+            let remove = cleanup && matches!(result, Ok(None));
+            if let Some(ref tx) = tx {
+                let _ = tx.send(WorkerMessage::WorktreeCommit { prompt_id, result });
+            }
+            if remove {
+                let result = match worktree::owning_repo(&wt_path) {
+                    Some(root) => worktree::remove_worktree(&root, &wt_path),
+                    None => Err(format!("Cannot find the repo {} belongs to", wt_path.display())),
+                };
+                if let Some(tx) = tx {
+                    let _ = tx.send(WorkerMessage::WorktreeDiscarded { prompt_id, result });
                 }
             }
         });
     }
 
+    /// Show the selected prompt's worktree diff in the output panel, or
+    /// go back to its output. `git diff` runs on a background thread and
+    /// reports back with `WorkerMessage::WorktreeDiff`.
+    fn toggle_worktree_diff(&mut self) {
+        if self.worktree_diff.is_some() {
+            self.reset_output_scroll();
+            return;
+        }
+        let Some(prompt) = self.selected_prompt() else {
+            return;
+        };
+        let prompt_id = prompt.id;
+        let Some(wt_path) = prompt.worktree_path.clone() else {
+            self.status_message = Some((format!("#{prompt_id} has no worktree"), Instant::now()));
+            return;
+        };
+        self.reset_output_scroll();
+        self.worktree_diff = Some(WorktreeDiffView { prompt_id, diff: None });
+        let Some(tx) = self.message_tx.clone() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            let result = worktree::diff(Path::new(&wt_path));
+            let _ = tx.send(WorkerMessage::WorktreeDiff { prompt_id, result });
+        });
+    }
+
+    /// Id and worktree of the selected prompt, if it has one its worker is
+    /// done with; otherwise says why not in the status bar.
+    fn finished_worktree_of_selected(&mut self) -> Option<(usize, String)> {
+        let prompt = self.selected_prompt()?;
+        let prompt_id = prompt.id;
+        let problem = match (&prompt.worktree_path, &prompt.status) {
+            (None, _) => "has no worktree",
+            (Some(path), PromptStatus::Completed | PromptStatus::Failed) => return Some((prompt_id, path.clone())),
+            (Some(_), _) => "hasn't finished yet",
+        };
+        self.status_message = Some((format!("#{prompt_id} {problem}"), Instant::now()));
+        None
+    }
+
+    /// Merge the selected finished prompt's worktree into its repo on a
+    /// background thread; the outcome comes back as `WorktreeApplied`.
+    fn apply_selected_worktree(&mut self) {
+        let Some((prompt_id, wt_path)) = self.finished_worktree_of_selected() else {
+            return;
+        };
+        // A second merge in the same checkout would trip over the first
+        if self.applying_worktrees.contains(&prompt_id) {
+            let msg = format!("#{prompt_id}: worktree merge already in progress");
+            self.status_message = Some((msg, Instant::now()));
+            return;
+        }
+        let text = self.prompts.iter().find(|p| p.id == prompt_id).map(|p| p.text.clone()).unwrap_or_default();
+        self.status_message = Some((format!("Merging worktree of #{prompt_id}..."), Instant::now()));
+        let Some(tx) = self.message_tx.clone() else {
+            return;
+        };
+        self.applying_worktrees.insert(prompt_id);
+        std::thread::spawn(move || {
+            let result = worktree::apply(Path::new(&wt_path), prompt_id, &text);
+            let _ = tx.send(WorkerMessage::WorktreeApplied { prompt_id, result });
+        });
+    }
+
+    /// Remove a finished prompt's worktree on a background thread; the
+    /// prompt forgets it once `WorktreeDiscarded` reports success.
+    fn discard_worktree(&mut self, prompt_id: usize) {
+        let Some(prompt) = self.prompts.iter().find(|p| p.id == prompt_id) else {
+            return;
+        };
+        // The prompt may have been resumed while the confirmation was open
+        if !matches!(prompt.status, PromptStatus::Completed | PromptStatus::Failed) {
+            return;
+        }
+        if self.applying_worktrees.contains(&prompt_id) {
+            let msg = format!("#{prompt_id}: worktree merge in progress; discard it once that finishes");
+            self.status_message = Some((msg, Instant::now()));
+            return;
+        }
+        let Some(wt_path) = prompt.worktree_path.clone().map(PathBuf::from) else {
+            return;
+        };
+        let Some(tx) = self.message_tx.clone() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            let result = match worktree::owning_repo(&wt_path) {
+                Some(root) => worktree::remove_worktree(&root, &wt_path),
+                None => Err(format!("Cannot find the repo {} belongs to", wt_path.display())),
+            };
+            let _ = tx.send(WorkerMessage::WorktreeDiscarded { prompt_id, result });
+        });
+    }
+
+    /// The diff to show instead of the selected prompt's output, if open.
+    pub fn selected_worktree_diff(&self) -> Option<&WorktreeDiffView> {
+        let selected = self.selected_prompt()?.id;
+        self.worktree_diff.as_ref().filter(|view| view.prompt_id == selected)
+    }
+
     // ── Feature 1: Export ──
 
     fn export_selected_output(&mut self) {
+        if !self.selected_ids.is_empty() {
+            self.export_batch_output();
+            return;
+        }
         let Some(prompt) = self.selected_prompt() else {
             self.status_message = Some(("No prompt selected".to_string(), Instant::now()));
             return;
@@ -1393,9 +3380,7 @@ impl App {
         }
 
         let id = prompt.id;
-        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
-        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        let filename = home.join(format!("clhorde-output-{id}-{timestamp}.md"));
+        let filename = export_path(&format!("{id}"), "md");
 
         let header = format!("# clhorde output #{id}\n\nPrompt: {}\n\n---\n\n", prompt.text);
         let content = format!("{header}{output}");
@@ -1414,62 +3399,461 @@ impl App {
         }
     }
 
-    // ── Feature 2: Retry ──
+    /// Export the batch selection (or the cursor prompt) as a JSON array of
+    /// [`persistence::PromptExport`] records, for piping into other tools.
+    fn export_selected_json(&mut self) {
+        let records: Vec<persistence::PromptExport> = if self.selected_ids.is_empty() {
+            self.selected_prompt()
+                .map(persistence::PromptExport::from_prompt)
+                .into_iter()
+                .collect()
+        } else {
+            self.selected_for_export()
+                .into_iter()
+                .map(persistence::PromptExport::from_prompt)
+                .collect()
+        };
+        let name = match records.as_slice() {
+            [] => {
+                self.status_message = Some(("No prompt selected".to_string(), Instant::now()));
+                return;
+            }
+            [single] => single.id.to_string(),
+            _ => "batch".to_string(),
+        };
 
-    fn retry_selected(&mut self) {
-        let Some(prompt) = self.selected_prompt() else {
-            return;
+        let filename = export_path(&name, "json");
+        let msg = match serde_json::to_string_pretty(&records)
+            .map_err(std::io::Error::other)
+            .and_then(|content| fs::write(&filename, content))
+        {
+            Ok(_) => format!("Exported {} prompts to {}", records.len(), filename.display()),
+            Err(e) => format!("Export failed: {e}"),
         };
-        if prompt.status != PromptStatus::Completed && prompt.status != PromptStatus::Failed {
+        self.status_message = Some((msg, Instant::now()));
+    }
+
+    /// Export all batch-selected prompts, in list order, to one markdown file.
+    fn export_batch_output(&mut self) {
+        let selected = self.selected_for_export();
+        let (content, written) = batch_export_markdown(&selected);
+        if written == 0 {
+            self.status_message = Some(("No output to export".to_string(), Instant::now()));
             return;
         }
-        let text = prompt.text.clone();
-        let cwd = prompt.cwd.clone();
-        let mode = prompt.mode;
-        let wt = prompt.worktree;
-        let tags = prompt.tags.clone();
-        let mut new_prompt = Prompt::new(self.next_id, text, cwd, mode);
-        new_prompt.worktree = wt;
-        new_prompt.tags = tags;
-        let max_rank = self.prompts.iter().map(|p| p.queue_rank).fold(0.0_f64, f64::max);
-        new_prompt.queue_rank = max_rank + 1.0;
-        self.next_id += 1;
-        self.persist_prompt(&new_prompt);
-        self.prompts.push(new_prompt);
-        self.rebuild_filter();
+
+        let filename = export_path("batch", "md");
+        let msg = match fs::write(&filename, &content) {
+            Ok(_) => format!("Exported {written} prompts to {}", filename.display()),
+            Err(e) => format!("Export failed: {e}"),
+        };
+        self.status_message = Some((msg, Instant::now()));
     }
 
-    fn resume_selected(&mut self) {
-        let Some(idx) = self.list_state.selected() else {
+    // ── Output search ──
+
+    fn clear_output_search(&mut self) {
+        self.output_search = None;
+        self.output_search_line = None;
+    }
+
+    /// Move to the next (or previous) line matching the active search,
+    /// wrapping around at the ends, and scroll it into view.
+    fn jump_to_output_match(&mut self, forward: bool) {
+        let Some(query) = self.output_search.clone() else {
             return;
         };
-        let Some(prompt) = self.prompts.get_mut(idx) else {
+        let Some(content) = self.selected_prompt().map(|p| self.output_view_content(p)) else {
             return;
         };
-        if prompt.status != PromptStatus::Completed && prompt.status != PromptStatus::Failed {
+        let lines = output_match_lines(&content, &query);
+        if lines.is_empty() {
+            self.output_search_line = None;
+            self.status_message = Some((format!("Pattern not found: {query}"), Instant::now()));
             return;
         }
-        // Reset the same prompt to Pending with resume flag
-        prompt.status = PromptStatus::Pending;
-        prompt.resume = true;
-        prompt.output = None;
-        prompt.error = None;
-        prompt.started_at = None;
-        prompt.finished_at = None;
-        prompt.seen = false;
-        prompt.pty_state = None;
-        if let Some(ref dir) = self.prompts_dir {
-            persistence::save_prompt(dir, &self.prompts[idx].uuid, &persistence::PromptFile::from_prompt(&self.prompts[idx]));
-        }
-    }
-
-    // ── Feature 4: Reorder ──
-
-    fn move_selected_up(&mut self) {
-        let Some(idx) = self.list_state.selected() else {
-            return;
+        // Search from the current match, or from the top of the view for a new query
+        let next = match (self.output_search_line, forward) {
+            (Some(cur), true) => lines.iter().find(|&&l| l > cur).or(lines.first()),
+            (Some(cur), false) => lines.iter().rev().find(|&&l| l < cur).or(lines.last()),
+            (None, _) => {
+                let top = self.scroll_offset as usize;
+                lines.iter().find(|&&l| l >= top).or(lines.first())
+            }
         };
-        if idx == 0 {
+        let Some(&line) = next else {
+            return;
+        };
+        let pos = lines.iter().position(|&l| l == line).unwrap_or(0) + 1;
+        self.output_search_line = Some(line);
+        self.auto_scroll = false;
+        self.scroll_offset = wrapped_row(&content, line, self.output_width);
+        self.status_message = Some((format!("/{query} [{pos}/{}]", lines.len()), Instant::now()));
+    }
+
+    /// Text shown in the output viewer for a (non-PTY) prompt.
+    pub fn output_view_content(&self, prompt: &Prompt) -> String {
+        match &prompt.status {
+            PromptStatus::Pending => match prompt.depends_on {
+                Some(dep) => format!("(pending — waiting for #{dep})"),
+                None => "(pending)".to_string(),
+            },
+            PromptStatus::Running => {
+                let elapsed = prompt.elapsed_display().unwrap_or_else(|| "0.0s".into());
+                match &prompt.output {
+                    Some(output) => {
+                        format!("Running... ({elapsed})\n\n{output}")
+                    }
+                    None => format!("Running... ({elapsed})"),
+                }
+            }
+            PromptStatus::Idle => {
+                let elapsed = prompt.elapsed_display().unwrap_or_else(|| "0.0s".into());
+                let hint = if prompt.mode == PromptMode::Interactive {
+                    let key = self.keymap.view_key_hint(ViewAction::Interact);
+                    format!(" — press '{key}' to interact")
+                } else {
+                    String::new()
+                };
+                match &prompt.output {
+                    Some(output) => {
+                        format!("{output}\n\n— Idle ({elapsed}){hint}")
+                    }
+                    None => format!("Idle ({elapsed}){hint}"),
+                }
+            }
+            PromptStatus::Completed => {
+                prompt.output.clone().unwrap_or_else(|| "(no output)".to_string())
+            }
+            PromptStatus::Failed => {
+                let mut text = String::from("FAILED");
+                if let Some(err) = &prompt.error {
+                    text.push_str(&format!(":\n{err}"));
+                }
+                if let Some(output) = &prompt.output {
+                    if !output.is_empty() {
+                        text.push_str(&format!("\n\nOutput:\n{output}"));
+                    }
+                }
+                text
+            }
+        }
+    }
+
+    /// Text shown for the selected prompt: the live PTY screen for running
+    /// interactive workers, otherwise the accumulated output.
+    fn selected_output_text(&self) -> Option<String> {
+        let prompt = self.selected_prompt()?;
+        let text = match prompt.pty_state {
+            Some(ref state) => pty_worker::extract_text_from_term(state),
+            None => prompt.output.clone().unwrap_or_default(),
+        };
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+
+    fn copy_selected_output(&mut self) {
+        let Some(text) = self.selected_output_text() else {
+            self.status_message = Some(("Nothing to copy".to_string(), Instant::now()));
+            return;
+        };
+        let msg = match clipboard::copy(&text) {
+            Ok(()) => format!("Copied {} chars", text.chars().count()),
+            Err(e) => format!("Copy failed: {e}"),
+        };
+        self.status_message = Some((msg, Instant::now()));
+    }
+
+    // ── Feature 2: Retry ──
+
+    fn retry_selected(&mut self) {
+        let Some(prompt) = self.selected_prompt() else {
+            return;
+        };
+        if prompt.status != PromptStatus::Completed && prompt.status != PromptStatus::Failed {
+            return;
+        }
+        if prompt.is_killed() {
+            let msg = format!("#{} was killed — duplicate it to run it again", prompt.id);
+            self.status_message = Some((msg, Instant::now()));
+            return;
+        }
+        let text = prompt.text.clone();
+        let cwd = prompt.cwd.clone();
+        let mode = prompt.mode;
+        let wt = prompt.worktree;
+        let tags = prompt.tags.clone();
+        let priority = prompt.priority;
+        let timeout_secs = prompt.timeout_secs;
+        let max_attempts = prompt.max_attempts;
+        let env = prompt.env.clone();
+        let resume_session_id = prompt.resume_session_id.clone();
+        let mut new_prompt = Prompt::new(self.next_id, text, cwd, mode);
+        new_prompt.worktree = wt;
+        new_prompt.tags = tags;
+        new_prompt.priority = priority;
+        new_prompt.timeout_secs = timeout_secs;
+        new_prompt.max_attempts = max_attempts;
+        new_prompt.env = env;
+        new_prompt.resume_session_id = resume_session_id;
+        let max_rank = self.prompts.iter().map(|p| p.queue_rank).fold(0.0_f64, f64::max);
+        new_prompt.queue_rank = max_rank + 1.0;
+        new_prompt.display_rank = self.next_display_rank();
+        self.next_id += 1;
+        self.persist_prompt(&new_prompt);
+        self.emit_event(events::Event::added(&new_prompt));
+        self.prompts.push(new_prompt);
+        self.rebuild_filter();
+    }
+
+    // ── Duplicate ──
+
+    /// Enter Insert mode pre-filled with the selected prompt's text. On
+    /// submit the edited text is queued with the source's settings.
+    fn start_duplicate_selected(&mut self) {
+        let Some(prompt) = self.selected_prompt() else {
+            return;
+        };
+        let (id, text, worktree) = (prompt.id, prompt.text.clone(), prompt.worktree);
+        self.mode = AppMode::Insert;
+        self.input.set(&text);
+        self.open_external_editor = false;
+        self.history_index = None;
+        self.history_stash.clear();
+        self.suggestions.clear();
+        self.template_suggestions.clear();
+        self.template_suggestion_index = 0;
+        self.worktree_pending = worktree;
+        self.duplicate_source = Some(id);
+    }
+
+    /// Enter Insert mode to type a new prompt that continues the selected
+    /// finished prompt's agent session, in the same cwd.
+    fn start_continue_selected(&mut self) {
+        let Some(prompt) = self.selected_prompt() else {
+            return;
+        };
+        if !matches!(prompt.status, PromptStatus::Completed | PromptStatus::Failed) {
+            return;
+        }
+        let id = prompt.id;
+        let Some(session_id) = prompt.session_id.clone().filter(|s| !s.trim().is_empty()) else {
+            self.status_message = Some((format!("#{id} has no session to continue"), Instant::now()));
+            return;
+        };
+        self.mode = AppMode::Insert;
+        self.input.clear();
+        self.open_external_editor = false;
+        self.history_index = None;
+        self.history_stash.clear();
+        self.suggestions.clear();
+        self.template_suggestions.clear();
+        self.template_suggestion_index = 0;
+        // A worktree is a new directory, where the agent can't find the session
+        self.worktree_pending = false;
+        self.continue_session = Some((id, session_id));
+    }
+
+    /// Queue text submitted from Insert mode, as a duplicate if one is being
+    /// edited or continuing a session if one was picked. A `cwd:` prefix
+    /// overrides the source's cwd; tags are merged.
+    fn submit_input_prompt(&mut self, text: String, cwd: Option<String>, tags: Vec<String>, mode: Option<PromptMode>) {
+        let source = self
+            .duplicate_source
+            .or(self.continue_session.as_ref().map(|(id, _)| *id));
+        let duplicate = source.and_then(|src| self.duplicate_of(src, text.clone()));
+        let Some(mut prompt) = duplicate else {
+            let id = self.add_prompt(text, cwd, self.worktree_pending, tags);
+            if let Some(mode) = mode {
+                if let Some(prompt) = self.prompts.iter_mut().find(|p| p.id == id) {
+                    prompt.mode = mode;
+                }
+                self.persist_prompt_by_id(id);
+            }
+            return;
+        };
+        if let Some(mode) = mode {
+            prompt.mode = mode;
+        }
+        if cwd.is_some() {
+            prompt.cwd = cwd;
+        }
+        for tag in tags {
+            if !prompt.tags.contains(&tag) {
+                prompt.tags.push(tag);
+            }
+        }
+        prompt.worktree = self.worktree_pending;
+        prompt.resume_session_id = self.continue_session.as_ref().map(|(_, s)| s.clone());
+        self.queue_prompt(prompt);
+    }
+
+    // ── Tags ──
+
+    /// Add a tag to a prompt. Returns whether it was newly added.
+    pub fn add_tag(&mut self, prompt_id: usize, tag: &str) -> Result<bool, String> {
+        let tag = crate::prompt::normalize_tag(tag).ok_or_else(|| format!("Invalid tag '{tag}'"))?;
+        let prompt = self
+            .prompts
+            .iter_mut()
+            .find(|p| p.id == prompt_id)
+            .ok_or_else(|| format!("Unknown prompt #{prompt_id}"))?;
+        let added = prompt.add_tag(tag);
+        if added {
+            self.persist_prompt_by_id(prompt_id);
+            self.rebuild_filter();
+        }
+        Ok(added)
+    }
+
+    /// Remove a tag from a prompt. Returns whether it was present.
+    pub fn remove_tag(&mut self, prompt_id: usize, tag: &str) -> Result<bool, String> {
+        let tag = crate::prompt::normalize_tag(tag).ok_or_else(|| format!("Invalid tag '{tag}'"))?;
+        let prompt = self
+            .prompts
+            .iter_mut()
+            .find(|p| p.id == prompt_id)
+            .ok_or_else(|| format!("Unknown prompt #{prompt_id}"))?;
+        let removed = prompt.remove_tag(&tag);
+        if removed {
+            self.persist_prompt_by_id(prompt_id);
+            self.rebuild_filter();
+        }
+        Ok(removed)
+    }
+
+    fn handle_tag_input_key(&mut self, key: KeyEvent) {
+        let Some(ref mut input) = self.tag_input else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let input = std::mem::take(input);
+                self.tag_input = None;
+                self.apply_tag_edit(&input);
+            }
+            KeyCode::Esc => self.tag_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Apply a tag edit to the batch selection, or the cursor prompt if
+    /// nothing is selected. Words prefixed with `-` remove a tag; others
+    /// (optionally `+` or `@` prefixed) add one. Nothing is applied if any
+    /// tag is invalid.
+    fn apply_tag_edit(&mut self, input: &str) {
+        let mut edits = Vec::new();
+        for word in input.split_whitespace() {
+            let (remove, tag) = match word.strip_prefix('-') {
+                Some(tag) => (true, tag),
+                None => (false, word.strip_prefix('+').unwrap_or(word)),
+            };
+            if crate::prompt::normalize_tag(tag).is_none() {
+                self.status_message = Some((format!("Invalid tag '{tag}'"), Instant::now()));
+                return;
+            }
+            edits.push((remove, tag));
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        let targets: Vec<usize> = if self.selected_ids.is_empty() {
+            self.selected_prompt().map(|p| p.id).into_iter().collect()
+        } else {
+            let mut ids: Vec<usize> = self.selected_ids.iter().copied().collect();
+            ids.sort_unstable();
+            ids
+        };
+        let mut changed = 0;
+        for &id in &targets {
+            let mut prompt_changed = false;
+            for &(remove, tag) in &edits {
+                let result = if remove { self.remove_tag(id, tag) } else { self.add_tag(id, tag) };
+                prompt_changed |= result.unwrap_or(false);
+            }
+            if prompt_changed {
+                changed += 1;
+            }
+        }
+        let noun = if changed == 1 { "prompt" } else { "prompts" };
+        self.status_message = Some((format!("Updated tags on {changed} {noun}"), Instant::now()));
+    }
+
+    fn resume_selected(&mut self) {
+        let Some(idx) = self.list_state.selected() else {
+            return;
+        };
+        let Some(prompt) = self.prompts.get_mut(idx) else {
+            return;
+        };
+        if prompt.status != PromptStatus::Completed && prompt.status != PromptStatus::Failed {
+            return;
+        }
+        // Without a captured session the agent would start over (or ask
+        // which session to open), so refuse instead
+        if prompt.session_id.is_none() {
+            let message = format!("#{} has no session to resume", prompt.id);
+            self.status_message = Some((message, Instant::now()));
+            return;
+        }
+        // Reset the same prompt to Pending with resume flag, keeping the
+        // output so far in front of what the resumed run adds
+        prompt.status = PromptStatus::Pending;
+        prompt.resume = true;
+        if let Some(output) = &mut prompt.output {
+            if !output.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(RESUME_SEPARATOR);
+        }
+        if let Some(stderr) = &mut prompt.stderr {
+            if !stderr.ends_with('\n') {
+                stderr.push('\n');
+            }
+            stderr.push_str(RESUME_SEPARATOR);
+        }
+        prompt.error = None;
+        prompt.started_at = None;
+        prompt.finished_at = None;
+        prompt.seen = false;
+        prompt.pty_state = None;
+        prompt.progress = None;
+        self.persist_prompt(&self.prompts[idx]);
+    }
+
+    // ── Priority ──
+
+    fn cycle_selected_priority(&mut self) {
+        let Some(prompt) = self.selected_prompt() else {
+            return;
+        };
+        if prompt.status != PromptStatus::Pending {
+            return;
+        }
+        let id = prompt.id;
+        let priority = prompt.priority.cycle();
+        self.set_prompt_priority(id, priority);
+        self.status_message = Some((
+            format!("Priority #{id}: {}", priority.label()),
+            Instant::now(),
+        ));
+    }
+
+    // ── Feature 4: Reorder ──
+
+    fn move_selected_up(&mut self) {
+        let Some(idx) = self.list_state.selected() else {
+            return;
+        };
+        if idx == 0 {
             return;
         }
         // Only move pending prompts
@@ -1483,10 +3867,8 @@ impl App {
         self.prompts[idx - 1].queue_rank = rank_a;
         self.prompts.swap(idx, idx - 1);
         // Save both to disk
-        if let Some(ref dir) = self.prompts_dir {
-            persistence::save_prompt(dir, &self.prompts[idx].uuid, &persistence::PromptFile::from_prompt(&self.prompts[idx]));
-            persistence::save_prompt(dir, &self.prompts[idx - 1].uuid, &persistence::PromptFile::from_prompt(&self.prompts[idx - 1]));
-        }
+        self.persist_prompt(&self.prompts[idx]);
+        self.persist_prompt(&self.prompts[idx - 1]);
         self.list_state.select(Some(idx - 1));
         self.recently_moved = Some((self.prompts[idx - 1].id, Instant::now()));
         self.status_message = Some((format!("Moved #{} up", self.prompts[idx - 1].id), Instant::now()));
@@ -1510,35 +3892,98 @@ impl App {
         self.prompts[idx + 1].queue_rank = rank_a;
         self.prompts.swap(idx, idx + 1);
         // Save both to disk
-        if let Some(ref dir) = self.prompts_dir {
-            persistence::save_prompt(dir, &self.prompts[idx].uuid, &persistence::PromptFile::from_prompt(&self.prompts[idx]));
-            persistence::save_prompt(dir, &self.prompts[idx + 1].uuid, &persistence::PromptFile::from_prompt(&self.prompts[idx + 1]));
-        }
+        self.persist_prompt(&self.prompts[idx]);
+        self.persist_prompt(&self.prompts[idx + 1]);
         self.list_state.select(Some(idx + 1));
         self.recently_moved = Some((self.prompts[idx + 1].id, Instant::now()));
         self.status_message = Some((format!("Moved #{} down", self.prompts[idx + 1].id), Instant::now()));
         self.rebuild_filter();
     }
 
+    /// Swap the cursor prompt with its neighbour in the manual sort view.
+    /// Works for any status; only `display_rank` changes, not dispatch order.
+    fn move_in_display_order(&mut self, down: bool) {
+        let Some(idx) = self.list_state.selected() else {
+            return;
+        };
+        let Some(pos) = self.filtered_indices.iter().position(|&i| i == idx) else {
+            return;
+        };
+        let neighbour = if down { pos.checked_add(1) } else { pos.checked_sub(1) };
+        let Some(&other) = neighbour.and_then(|n| self.filtered_indices.get(n)) else {
+            return;
+        };
+        let rank = self.prompts[idx].display_rank;
+        self.prompts[idx].display_rank = self.prompts[other].display_rank;
+        self.prompts[other].display_rank = rank;
+        self.persist_prompt(&self.prompts[idx]);
+        self.persist_prompt(&self.prompts[other]);
+        let id = self.prompts[idx].id;
+        self.recently_moved = Some((id, Instant::now()));
+        let dir = if down { "down" } else { "up" };
+        self.status_message = Some((format!("Moved #{id} {dir}"), Instant::now()));
+        self.rebuild_filter();
+    }
+
+    /// Batch-selected prompts in export order: the manual sort view's
+    /// order when it is active, queue order otherwise.
+    fn selected_for_export(&self) -> Vec<&Prompt> {
+        let mut selected: Vec<&Prompt> = self
+            .prompts
+            .iter()
+            .filter(|p| self.selected_ids.contains(&p.id))
+            .collect();
+        if self.sort_mode == SortMode::Manual {
+            selected.sort_by(|a, b| {
+                a.display_rank
+                    .partial_cmp(&b.display_rank)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        selected
+    }
+
     // ── Feature 5: Filter ──
 
     fn rebuild_filter(&mut self) {
+        let mut regex_invalid = false;
         self.filtered_indices = match &self.filter_text {
             Some(filter) => {
-                // Split filter into @tag tokens and text tokens
+                // Split filter into @tag, status:/is: and text tokens
                 let mut tag_filters = Vec::new();
+                let mut status_filters = Vec::new();
                 let mut text_parts = Vec::new();
                 for word in filter.split_whitespace() {
-                    if let Some(tag) = word.strip_prefix('@') {
+                    let status = word
+                        .strip_prefix("status:")
+                        .or_else(|| word.strip_prefix("is:"))
+                        .map(str::to_lowercase)
+                        .filter(|s| s == "killed" || PromptStatus::from_label(s).is_some());
+                    if let Some(status) = status {
+                        status_filters.push(status);
+                    } else if let Some(tag) = word.strip_prefix('@') {
                         if !tag.is_empty() {
                             tag_filters.push(tag.to_lowercase());
                         }
                     } else {
-                        text_parts.push(word.to_lowercase());
+                        text_parts.push(word);
                     }
                 }
                 let text_filter = text_parts.join(" ");
 
+                // `/pattern/` is a case-insensitive regex; if it doesn't
+                // compile, fall back to a substring match on the pattern
+                let pattern = text_filter
+                    .strip_prefix('/')
+                    .and_then(|t| t.strip_suffix('/'))
+                    .filter(|t| !t.is_empty());
+                let regex = pattern.and_then(|pat| {
+                    let compiled = RegexBuilder::new(pat).case_insensitive(true).build();
+                    regex_invalid = compiled.is_err();
+                    compiled.ok()
+                });
+                let substring = pattern.unwrap_or(&text_filter).to_lowercase();
+
                 self.prompts
                     .iter()
                     .enumerate()
@@ -1547,16 +3992,199 @@ impl App {
                         let tags_match = tag_filters.iter().all(|tf| {
                             p.tags.iter().any(|t| t.to_lowercase() == *tf)
                         });
+                        // Any status filter may match
+                        let status_match = status_filters.is_empty()
+                            || status_filters.iter().any(|s| s == p.state_label());
                         // Text filter must match prompt text (if present)
-                        let text_match = text_filter.is_empty()
-                            || p.text.to_lowercase().contains(&text_filter);
-                        tags_match && text_match
+                        let text_match = match &regex {
+                            Some(re) => re.is_match(&p.text),
+                            None => substring.is_empty() || p.text.to_lowercase().contains(&substring),
+                        };
+                        tags_match && status_match && text_match
                     })
                     .map(|(i, _)| i)
                     .collect()
             }
             None => (0..self.prompts.len()).collect(),
         };
+        self.filter_regex_invalid = regex_invalid;
+
+        // Stable sorts keep queue order among equal keys
+        let prompts = &self.prompts;
+        match self.sort_mode {
+            SortMode::Queue => {}
+            SortMode::Status => self
+                .filtered_indices
+                .sort_by_key(|&i| status_rank(&prompts[i].status)),
+            SortMode::Elapsed => self.filtered_indices.sort_by(|&a, &b| {
+                let (a, b) = (prompts[a].elapsed_secs(), prompts[b].elapsed_secs());
+                b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal)
+            }),
+            SortMode::Id => self
+                .filtered_indices
+                .sort_by_key(|&i| std::cmp::Reverse(prompts[i].id)),
+            SortMode::Manual => self.filtered_indices.sort_by(|&a, &b| {
+                prompts[a]
+                    .display_rank
+                    .partial_cmp(&prompts[b].display_rank)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }),
+        }
+
+        // Cluster by group in order of first appearance, keeping the sort
+        // order within each group
+        if self.grouped {
+            let mut groups: Vec<&str> = Vec::new();
+            for &i in &self.filtered_indices {
+                let tag = group_tag(&prompts[i]);
+                if !groups.contains(&tag) {
+                    groups.push(tag);
+                }
+            }
+            self.filtered_indices
+                .sort_by_key(|&i| groups.iter().position(|&t| t == group_tag(&prompts[i])));
+            self.snap_selection_to_group();
+        }
+    }
+
+    fn cycle_sort(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        // Selection is a prompt index, so it stays on the same prompt
+        self.rebuild_filter();
+        self.status_message = Some((
+            format!("Sort: {}", self.sort_mode.label()),
+            Instant::now(),
+        ));
+    }
+
+    /// Re-sort after a status change when the list is not in queue order.
+    fn resort(&mut self) {
+        if self.sort_mode != SortMode::Queue {
+            self.rebuild_filter();
+        }
+    }
+
+    /// Whether navigation must follow `navigable_indices` rather than raw
+    /// prompt order.
+    fn view_is_reordered(&self) -> bool {
+        (self.filter_text.is_some() || self.sort_mode != SortMode::Queue || self.grouped)
+            && !self.filtered_indices.is_empty()
+    }
+
+    // ── Tag groups ──
+
+    fn toggle_grouped(&mut self) {
+        self.grouped = !self.grouped;
+        self.rebuild_filter();
+        let msg = if self.grouped { "Grouped by tag (za to fold)" } else { "Ungrouped" };
+        self.status_message = Some((msg.to_string(), Instant::now()));
+    }
+
+    /// Fold or unfold the group of the selected prompt.
+    fn toggle_group_fold(&mut self) {
+        let Some(tag) = self
+            .list_state
+            .selected()
+            .and_then(|i| self.prompts.get(i))
+            .map(|p| group_tag(p).to_string())
+        else {
+            return;
+        };
+        if !self.collapsed_tags.remove(&tag) {
+            self.collapsed_tags.insert(tag);
+        }
+        self.snap_selection_to_group();
+    }
+
+    fn fold_all_groups(&mut self, collapsed: bool) {
+        if collapsed {
+            let tags: Vec<String> = self
+                .filtered_indices
+                .iter()
+                .map(|&i| group_tag(&self.prompts[i]).to_string())
+                .collect();
+            self.collapsed_tags.extend(tags);
+        } else {
+            self.collapsed_tags.clear();
+        }
+        self.snap_selection_to_group();
+    }
+
+    /// Whether the cursor is on a folded group's header row.
+    fn selection_is_folded(&self) -> bool {
+        self.grouped
+            && self
+                .selected_prompt()
+                .is_some_and(|p| self.collapsed_tags.contains(group_tag(p)))
+    }
+
+    /// Move a selection hidden inside a folded group onto the group's
+    /// stand-in (its first prompt), which renders as the header row.
+    fn snap_selection_to_group(&mut self) {
+        if !self.grouped {
+            return;
+        }
+        let Some(tag) = self
+            .list_state
+            .selected()
+            .and_then(|i| self.prompts.get(i))
+            .map(group_tag)
+        else {
+            return;
+        };
+        if !self.collapsed_tags.contains(tag) {
+            return;
+        }
+        let first = self
+            .filtered_indices
+            .iter()
+            .copied()
+            .find(|&i| group_tag(&self.prompts[i]) == tag);
+        if let Some(first) = first {
+            self.list_state.select(Some(first));
+        }
+    }
+
+    /// Rows of the prompt list as rendered: prompts in view order, with tag
+    /// headers (and folded groups hidden) in the grouped view.
+    pub fn list_rows(&self) -> Vec<ListRow> {
+        let visible = &self.filtered_indices;
+        if !self.grouped {
+            return visible.iter().map(|&i| ListRow::Prompt(i)).collect();
+        }
+        let mut rows = Vec::new();
+        let mut start = 0;
+        while start < visible.len() {
+            let tag = group_tag(&self.prompts[visible[start]]);
+            let count = visible[start..]
+                .iter()
+                .take_while(|&&i| group_tag(&self.prompts[i]) == tag)
+                .count();
+            let collapsed = self.collapsed_tags.contains(tag);
+            rows.push(ListRow::Group {
+                tag: tag.to_string(),
+                count,
+                collapsed,
+                first: visible[start],
+            });
+            if !collapsed {
+                rows.extend(visible[start..start + count].iter().map(|&i| ListRow::Prompt(i)));
+            }
+            start += count;
+        }
+        rows
+    }
+
+    /// Prompt indices j/k move through: folded groups count as one stop.
+    fn navigable_indices(&self) -> Vec<usize> {
+        self.list_rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                ListRow::Prompt(i) => Some(i),
+                ListRow::Group { collapsed: true, first, .. } => Some(first),
+                ListRow::Group { .. } => None,
+            })
+            .collect()
     }
 
     fn clamp_selection_to_filter(&mut self) {
@@ -1608,27 +4236,37 @@ impl App {
     // ── Batch operations ──
 
     fn batch_retry(&mut self) {
-        let to_retry: Vec<(String, Option<String>, PromptMode, bool)> = self
+        let to_retry: Vec<usize> = self
             .prompts
             .iter()
-            .filter(|p| {
+            .enumerate()
+            .filter(|(_, p)| {
                 self.selected_ids.contains(&p.id)
                     && (p.status == PromptStatus::Completed || p.status == PromptStatus::Failed)
+                    && !p.is_killed()
             })
-            .map(|p| (p.text.clone(), p.cwd.clone(), p.mode, p.worktree))
+            .map(|(i, _)| i)
             .collect();
         let count = to_retry.len();
-        for (text, cwd, mode, wt) in to_retry {
-            let mut new_prompt = Prompt::new(self.next_id, text, cwd, mode);
-            new_prompt.worktree = wt;
+        for idx in to_retry {
+            let src = &self.prompts[idx];
+            let mut new_prompt = Prompt::new(self.next_id, src.text.clone(), src.cwd.clone(), src.mode);
+            new_prompt.worktree = src.worktree;
+            new_prompt.priority = src.priority;
+            new_prompt.timeout_secs = src.timeout_secs;
+            new_prompt.max_attempts = src.max_attempts;
+            new_prompt.env = src.env.clone();
+            new_prompt.resume_session_id = src.resume_session_id.clone();
             let max_rank = self
                 .prompts
                 .iter()
                 .map(|p| p.queue_rank)
                 .fold(0.0_f64, f64::max);
             new_prompt.queue_rank = max_rank + 1.0;
+            new_prompt.display_rank = self.next_display_rank();
             self.next_id += 1;
             self.persist_prompt(&new_prompt);
+            self.emit_event(events::Event::added(&new_prompt));
             self.prompts.push(new_prompt);
         }
         self.clear_selection();
@@ -1650,12 +4288,7 @@ impl App {
             .collect();
         let count = ids.len();
         for id in ids {
-            if let Some(sender) = self.worker_inputs.get(&id) {
-                let _ = sender.send(WorkerInput::Kill);
-            }
-            if let Some(mut handle) = self.pty_handles.remove(&id) {
-                let _ = handle.child.kill();
-            }
+            self.kill_worker(id);
         }
         self.clear_selection();
         if count > 0 {
@@ -1667,17 +4300,11 @@ impl App {
         let ids: Vec<usize> = self.selected_ids.iter().copied().collect();
         let mut count = 0;
         for id in ids {
-            // Kill running/idle workers first
+            // Kill running/idle workers first; their `Finished` message
+            // releases the worker slot once the process has exited
             if let Some(prompt) = self.prompts.iter().find(|p| p.id == id) {
                 if prompt.status == PromptStatus::Running || prompt.status == PromptStatus::Idle {
-                    if let Some(sender) = self.worker_inputs.get(&id) {
-                        let _ = sender.send(WorkerInput::Kill);
-                    }
-                    if let Some(mut handle) = self.pty_handles.remove(&id) {
-                        let _ = handle.child.kill();
-                    }
-                    self.worker_inputs.remove(&id);
-                    self.active_workers = self.active_workers.saturating_sub(1);
+                    self.kill_worker(id);
                 }
             }
             // Delete persistence file
@@ -1686,6 +4313,9 @@ impl App {
                     persistence::delete_prompt_file(dir, &prompt.uuid);
                 }
             }
+            if let Some(ref dir) = self.inbox_dir {
+                self.output_mirror.remove(dir, id);
+            }
             // Remove from prompts list
             if let Some(pos) = self.prompts.iter().position(|p| p.id == id) {
                 self.prompts.remove(pos);
@@ -1723,13 +4353,31 @@ impl App {
         }
     }
 
-    // ── Feature 6: History ──
-
-    fn data_dir() -> Option<PathBuf> {
-        dirs::data_dir().map(|d| d.join("clhorde"))
-    }
-
-    fn history_path() -> Option<PathBuf> {
+    fn batch_cycle_priority(&mut self) {
+        let ids: Vec<(usize, PromptPriority)> = self
+            .prompts
+            .iter()
+            .filter(|p| self.selected_ids.contains(&p.id) && p.status == PromptStatus::Pending)
+            .map(|p| (p.id, p.priority.cycle()))
+            .collect();
+        let count = ids.len();
+        for (id, priority) in ids {
+            self.set_prompt_priority(id, priority);
+        }
+        self.clear_selection();
+        if count > 0 {
+            self.status_message =
+                Some((format!("Changed priority on {count} prompts"), Instant::now()));
+        }
+    }
+
+    // ── Feature 6: History ──
+
+    fn data_dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("clhorde"))
+    }
+
+    fn history_path() -> Option<PathBuf> {
         Self::data_dir().map(|d| d.join("history"))
     }
 
@@ -1770,6 +4418,65 @@ impl App {
         }
     }
 
+    /// Ctrl+R reverse-i-search: typing narrows to the newest history entry
+    /// containing the query (case-insensitive), Ctrl+R again steps to older
+    /// matches, Enter keeps the match for editing and Esc restores the input.
+    fn handle_history_search_key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let Some(mut search) = self.history_search.take() else {
+            self.history_search = Some(HistorySearch {
+                query: String::new(),
+                matched: None,
+                stash: self.input.to_string(),
+            });
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.input.set(&search.stash);
+                return;
+            }
+            KeyCode::Enter => {
+                self.history_index = None;
+                self.update_suggestions();
+                self.update_template_suggestions();
+                return;
+            }
+            KeyCode::Char('r') if ctrl => {
+                let before = search.matched.unwrap_or(self.history.len());
+                if let Some(idx) = self.find_in_history(&search.query, before) {
+                    search.matched = Some(idx);
+                }
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.matched = self.find_in_history(&search.query, self.history.len());
+            }
+            KeyCode::Char(c) if !ctrl => {
+                search.query.push(c);
+                search.matched = self.find_in_history(&search.query, self.history.len());
+            }
+            _ => {}
+        }
+        match search.matched {
+            Some(idx) => self.input.set(&self.history[idx].clone()),
+            None if search.query.is_empty() => self.input.set(&search.stash),
+            None => {}
+        }
+        self.history_search = Some(search);
+    }
+
+    /// Newest history entry before index `before` that contains `query`.
+    fn find_in_history(&self, query: &str, before: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+        let query = query.to_lowercase();
+        self.history[..before.min(self.history.len())]
+            .iter()
+            .rposition(|entry| entry.to_lowercase().contains(&query))
+    }
+
     fn history_prev(&mut self) {
         if self.history.is_empty() {
             return;
@@ -1882,7 +4589,13 @@ impl App {
     fn accept_template_suggestion(&mut self) {
         if let Some(name) = self.template_suggestions.get(self.template_suggestion_index).cloned() {
             if let Some(template_text) = self.templates.get(&name).cloned() {
-                self.input.set(&format!("{template_text} "));
+                let cwd = std::env::current_dir().unwrap_or_default();
+                let vars = [
+                    ("cwd", Some(cwd.to_string_lossy().to_string())),
+                    ("date", Some(chrono::Local::now().format("%Y-%m-%d").to_string())),
+                    ("branch", worktree::current_branch(&cwd)),
+                ];
+                self.input.set_template(&expand_template_vars(&template_text, &vars));
                 self.template_suggestions.clear();
                 self.template_suggestion_index = 0;
             }
@@ -1890,13 +4603,269 @@ impl App {
     }
 }
 
+/// A prompt file left `running` by an instance that crashed.
+#[derive(Debug, PartialEq)]
+enum Orphan {
+    /// Its agent outlived the crash and should be reaped.
+    Alive(u32),
+    /// The agent is gone, its pid wasn't recorded, or the pid now belongs
+    /// to an unrelated process.
+    Gone,
+}
+
+/// Classify a restored prompt file. Only `running` files from a crashed
+/// instance are orphans; a live pid is only trusted if the process still
+/// runs the program from the prompt's recorded command line, since pids
+/// get reused.
+fn orphan_of(
+    pf: &persistence::PromptFile,
+    crashed: bool,
+    is_alive: impl Fn(u32) -> bool,
+    command_name: impl Fn(u32) -> Option<String>,
+) -> Option<Orphan> {
+    if !crashed || pf.state != "running" {
+        return None;
+    }
+    let Some(pid) = pf.worker_pid.filter(|&pid| is_alive(pid)) else {
+        return Some(Orphan::Gone);
+    };
+    let program = pf
+        .command
+        .as_deref()
+        .and_then(|c| c.split_whitespace().next())
+        .map(|p| p.trim_matches('\'').rsplit('/').next().unwrap_or_default().to_string());
+    match (program, command_name(pid)) {
+        (Some(program), Some(name)) if !name.is_empty() && program.starts_with(&name) => Some(Orphan::Alive(pid)),
+        _ => Some(Orphan::Gone),
+    }
+}
+
+/// Directory completions for a typed path: everything up to the last `/`
+/// names the parent (absolute, relative or `~`-based), the rest is fuzzy
+/// matched against its subdirectories. Suggestions keep the typed parent,
+/// so `~/Dow` offers `~/Downloads`. At most 10, none if the parent is missing.
+fn dir_suggestions(input: &str, home: Option<&Path>) -> Vec<String> {
+    let (base, partial) = match input.rfind('/') {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None if input == "~" => ("~/", ""),
+        None => ("", input),
+    };
+    let parent = if base.is_empty() { PathBuf::from(".") } else { persistence::expand_tilde(base, home) };
+    let Ok(entries) = fs::read_dir(&parent) else {
+        return Vec::new();
+    };
+    let dirs: Vec<(String, String)> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+        .map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let path = format!("{base}{name}");
+            (name, path)
+        })
+        .collect();
+    let mut matches = rank_fuzzy(dirs, partial);
+    matches.truncate(10);
+    matches
+}
+
+/// Sort key for `name` against a typed `query`, lower is better: exact
+/// prefix matches first, then case-insensitive prefixes, then fuzzy
+/// subsequences (`dwn` → `Downloads`) by how late and spread out the
+/// matched characters are. `None` if `name` doesn't contain the query's
+/// characters in order.
+fn fuzzy_rank(name: &str, query: &str) -> Option<(u8, usize)> {
+    if name.starts_with(query) {
+        return Some((0, 0));
+    }
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name.starts_with(&query) {
+        return Some((1, 0));
+    }
+    let mut chars = name.chars().enumerate();
+    let mut first = None;
+    let mut last = 0;
+    let mut gaps = 0;
+    for q in query.chars() {
+        let (i, _) = chars.find(|&(_, c)| c == q)?;
+        if first.is_some() {
+            gaps += i - last - 1;
+        }
+        first.get_or_insert(i);
+        last = i;
+    }
+    Some((2, first.unwrap_or(0) + gaps))
+}
+
+/// Values of `(name, value)` pairs whose name matches `query`, best
+/// `fuzzy_rank` first; ties go to the shorter, then alphabetical, name.
+fn rank_fuzzy(candidates: Vec<(String, String)>, query: &str) -> Vec<String> {
+    let mut ranked: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|(name, value)| fuzzy_rank(&name, query).map(|rank| (rank, name, value)))
+        .collect();
+    ranked.sort_by(|(ra, na, _), (rb, nb, _)| ra.cmp(rb).then(na.len().cmp(&nb.len())).then(na.cmp(nb)));
+    ranked.into_iter().map(|(_, _, value)| value).collect()
+}
+
+/// Substitute built-in `{name}` variables in template text. Variables
+/// without a value, and unknown ones, stay literal: they become fields,
+/// but keep their text unless something is typed into them.
+fn expand_template_vars(text: &str, vars: &[(&str, Option<String>)]) -> String {
+    vars.iter().fold(text.to_string(), |text, (name, value)| match value {
+        Some(value) => text.replace(&format!("{{{name}}}"), value),
+        None => text,
+    })
+}
+
+/// Split a trailing `>name` token off filter input: (filter, name).
+fn split_save_token(input: &str) -> (&str, Option<&str>) {
+    let input = input.trim();
+    match input.rsplit_once(char::is_whitespace).unwrap_or(("", input)) {
+        (filter, word) if word.starts_with('>') => (filter.trim_end(), Some(&word[1..])),
+        _ => (input, None),
+    }
+}
+
+/// Timestamped export file in the home directory, e.g.
+/// `~/clhorde-output-3-20250101-120000.md`.
+fn export_path(name: &str, ext: &str) -> PathBuf {
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(format!("clhorde-output-{name}-{timestamp}.{ext}"))
+}
+
+/// Concatenate prompts into one markdown document, each with an `#id`
+/// header, its text and output, separated by `---`. Prompts without output
+/// are skipped. Returns the document and the number of prompts written.
+pub fn batch_export_markdown(prompts: &[&Prompt]) -> (String, usize) {
+    let sections: Vec<String> = prompts
+        .iter()
+        .filter_map(|p| {
+            let output = p.output.as_deref().filter(|o| !o.is_empty())?;
+            Some(format!("# clhorde output #{}\n\nPrompt: {}\n\n{output}\n", p.id, p.text))
+        })
+        .collect();
+    (sections.join("\n---\n\n"), sections.len())
+}
+
+/// Byte ranges of case-insensitive matches of `query` within `line`.
+pub fn find_matches(line: &str, query: &str) -> Vec<(usize, usize)> {
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let hay: Vec<(usize, char)> = line.char_indices().map(|(i, c)| (i, fold(c))).collect();
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + needle.len() <= hay.len() {
+        if hay[i..i + needle.len()].iter().map(|(_, c)| *c).eq(needle.iter().copied()) {
+            let start = hay[i].0;
+            let end = hay.get(i + needle.len()).map(|(b, _)| *b).unwrap_or(line.len());
+            matches.push((start, end));
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    matches
+}
+
+/// Indices of lines in `content` containing a case-insensitive match.
+pub fn output_match_lines(content: &str, query: &str) -> Vec<usize> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !find_matches(line, query).is_empty())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// What a cached output-view height was computed from: the prompt shown,
+/// whether it was a worktree diff, the text's length and line count, and
+/// the view width.
+pub type OutputRowsKey = (Option<usize>, bool, usize, usize, u16);
+
+/// Screen row where logical `line` of `content` starts once wrapped to
+/// `width` columns (the line index itself when the width isn't known yet).
+fn wrapped_row(content: &str, line: usize, width: u16) -> u16 {
+    let row = if width == 0 {
+        line
+    } else {
+        content.lines().take(line).map(|l| wrapped_rows(l.chars(), width)).sum()
+    };
+    row.min(u16::MAX as usize) as u16
+}
+
+/// Screen rows one line takes in a `Paragraph` wrapped to `width` columns
+/// with `Wrap { trim: false }`. Follows ratatui's word wrapper: lines break
+/// before the word that overflows, words wider than the view are split, and
+/// whitespace that fits at the end of a row is swallowed by the break.
+pub fn wrapped_rows(chars: impl IntoIterator<Item = char>, width: u16) -> usize {
+    let max = width as usize;
+    if max == 0 {
+        return 0;
+    }
+    let mut rows = 0;
+    let mut buf = [0; 4];
+    let (mut line_width, mut line_empty) = (0, true);
+    let (mut word_width, mut word_empty) = (0, true);
+    let mut whitespace = std::collections::VecDeque::new();
+    let mut whitespace_width = 0;
+    let mut after_word = false;
+    for c in chars {
+        let is_whitespace = c.is_whitespace();
+        let w = UnicodeWidthStr::width(&*c.encode_utf8(&mut buf));
+        // Ratatui drops control characters and symbols wider than the line
+        if c.is_control() || w > max {
+            continue;
+        }
+        // A finished word, or a first word that can't share the row, joins the row
+        if (after_word && is_whitespace) || (line_empty && word_width + whitespace_width + w > max) {
+            line_width += whitespace_width + word_width;
+            line_empty &= whitespace.is_empty() && word_empty;
+            whitespace.clear();
+            (whitespace_width, word_width, word_empty) = (0, 0, true);
+        }
+        if line_width >= max || (w > 0 && line_width + whitespace_width + word_width >= max) {
+            let mut remaining = max.saturating_sub(line_width);
+            rows += 1;
+            (line_width, line_empty) = (0, true);
+            while let Some(&front) = whitespace.front() {
+                if front > remaining {
+                    break;
+                }
+                whitespace_width -= front;
+                remaining -= front;
+                whitespace.pop_front();
+            }
+            if is_whitespace && whitespace.is_empty() {
+                continue;
+            }
+        }
+        if is_whitespace {
+            whitespace_width += w;
+            whitespace.push_back(w);
+        } else {
+            word_width += w;
+            word_empty = false;
+        }
+        after_word = !is_whitespace;
+    }
+    if !line_empty || !whitespace.is_empty() || !word_empty {
+        rows += 1;
+    }
+    rows.max(1)
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::worker::WorkerMessage;
 
     /// Test-only constructor that skips persistence loading.
-    fn new_test_app() -> App {
+    pub(crate) fn new_test_app() -> App {
         let mut list_state = ListState::default();
         list_state.select(None);
         App {
@@ -1904,6 +4873,29 @@ mod tests {
             next_id: 1,
             max_workers: 3,
             active_workers: 0,
+            paused: false,
+            draining: false,
+            quit_when_drained: false,
+            default_timeout_secs: None,
+            default_max_attempts: 1,
+            retry_delay: Duration::ZERO,
+            agent: AgentCommand::default(),
+            notifications: false,
+            mouse: false,
+            terminal_title: false,
+            title_summary: None,
+            bell_on_complete: false,
+            pending_bell: false,
+            tag_limits: HashMap::new(),
+            kill_grace: Duration::from_millis(process::DEFAULT_KILL_GRACE_MS),
+            input_reminder: Some(Duration::from_secs(DEFAULT_INPUT_REMINDER_SECS)),
+            spawn_interval: Duration::ZERO,
+            last_spawn: None,
+            completion_webhook_url: None,
+            event_socket: None,
+            persistence_healthy: Cell::new(true),
+            persistence_error: RefCell::new(None),
+            pty_scrollback_bytes: pty_worker::DEFAULT_SCROLLBACK_BYTES,
             mode: AppMode::Normal,
             list_state,
             input: TextBuffer::new(),
@@ -1913,6 +4905,10 @@ mod tests {
             worker_inputs: HashMap::new(),
             interact_input: String::new(),
             auto_scroll: true,
+            output_at_bottom: true,
+            output_scroll_max: 0,
+            output_width: 0,
+            output_rows: None,
             suggestions: Vec::new(),
             suggestion_index: 0,
             tick: 0,
@@ -1922,25 +4918,47 @@ mod tests {
             confirm_quit: false,
             filter_text: None,
             filter_input: String::new(),
+            output_search_input: None,
+            worktree_diff: None,
+            output_search: None,
+            output_search_line: None,
             filtered_indices: Vec::new(),
+            filter_regex_invalid: false,
+            saved_filters: HashMap::new(),
             history: Vec::new(),
             history_index: None,
             history_stash: String::new(),
+            history_search: None,
             templates: HashMap::new(),
             template_suggestions: Vec::new(),
             template_suggestion_index: 0,
             show_quick_prompts_popup: false,
+            quick_prompt_index: 0,
+            quick_prompt_filter: String::new(),
             pty_handles: HashMap::new(),
             output_panel_size: None,
-            last_pty_size: None,
+            viewed_pty: None,
+            pty_sizes: HashMap::new(),
             max_saved_prompts: 100,
+            max_saved_output_bytes: persistence::DEFAULT_MAX_SAVED_OUTPUT_BYTES,
+            max_output_bytes: crate::prompt::DEFAULT_MAX_OUTPUT_BYTES,
             prompts_dir: None,
+            state_path: None,
+            inbox_dir: None,
+            output_mirror: inbox::OutputMirror::default(),
             worktree_pending: false,
             worktree_cleanup: WorktreeCleanup::Manual,
+            worktree_branch_template: None,
+            worktree_autocommit: false,
+            message_tx: None,
             list_height: 0,
-            pending_g: false,
+            list_area: None,
+            list_offset: 0,
+            pending_sequence: Vec::new(),
+            pending_sequence_at: None,
             list_ratio: 40,
             list_collapsed: false,
+            focused_pane: FocusedPane::List,
             session_start: Instant::now(),
             show_help_overlay: false,
             help_scroll: 0,
@@ -1948,6 +4966,18 @@ mod tests {
             selected_ids: HashSet::new(),
             visual_select_active: false,
             confirm_batch_delete: false,
+            confirm_kill_all: false,
+            confirm_discard_worktree: None,
+            applying_worktrees: HashSet::new(),
+            duplicate_source: None,
+            continue_session: None,
+            tag_input: None,
+            theme: Theme::default(),
+            sort_mode: SortMode::Queue,
+            grouped: false,
+            collapsed_tags: HashSet::new(),
+            version_warning: None,
+            published_status: None,
         }
     }
 
@@ -1986,301 +5016,2576 @@ mod tests {
     }
 
     #[test]
-    fn add_prompt_selects_first() {
+    fn add_prompt_returns_assigned_id() {
         let mut app = new_test_app();
-        assert!(app.list_state.selected().is_none());
-        app.add_prompt("test".to_string(), None, false, Vec::new());
-        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.add_prompt("a".to_string(), None, false, Vec::new()), 1);
+        assert_eq!(app.add_prompt("b".to_string(), None, false, Vec::new()), 2);
+        assert_eq!(app.prompts[1].id, 2);
     }
 
     #[test]
-    fn pending_and_completed_counts() {
+    fn accept_submission_applies_options() {
         let mut app = new_test_app();
-        app.add_prompt("a".to_string(), None, false, Vec::new());
-        app.add_prompt("b".to_string(), None, false, Vec::new());
-        app.add_prompt("c".to_string(), None, false, Vec::new());
-
-        assert_eq!(app.pending_count(), 3);
-        assert_eq!(app.completed_count(), 0);
-
-        app.prompts[0].status = PromptStatus::Completed;
-        app.prompts[1].status = PromptStatus::Failed;
+        let id = app.accept_submission(SubmitRequest {
+            protocol_version: inbox::PROTOCOL_VERSION,
+            prompt: "@ci run the tests".to_string(),
+            options: persistence::PromptOptions {
+                mode: "one_shot".to_string(),
+                context: Some("/tmp".to_string()),
+                worktree: Some(true),
+                priority: None,
+                timeout_secs: Some(600),
+                max_attempts: None,
+                env: [("RUST_LOG".to_string(), "debug".to_string())].into(),
+            },
+            depends_on: None,
+            resume_session_id: None,
+        })
+        .unwrap();
+        let p = &app.prompts[0];
+        assert_eq!(p.id, id);
+        assert_eq!(p.text, "run the tests");
+        assert_eq!(p.tags, vec!["ci".to_string()]);
+        assert_eq!(p.mode, PromptMode::OneShot);
+        assert_eq!(p.cwd.as_deref(), Some("/tmp"));
+        assert!(p.worktree);
+        assert_eq!(p.env.get("RUST_LOG").map(String::as_str), Some("debug"));
+        assert_eq!(p.timeout_secs, Some(600));
+    }
 
-        assert_eq!(app.pending_count(), 1);
-        assert_eq!(app.completed_count(), 2);
+    #[test]
+    fn accept_submission_unknown_mode_uses_default() {
+        let mut app = new_test_app();
+        app.default_mode = PromptMode::Interactive;
+        app.accept_submission(SubmitRequest {
+            protocol_version: inbox::PROTOCOL_VERSION,
+            prompt: "hi".to_string(),
+            options: persistence::PromptOptions {
+                mode: String::new(),
+                context: None,
+                worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: Default::default(),
+            },
+            depends_on: None,
+            resume_session_id: None,
+        })
+        .unwrap();
+        assert_eq!(app.prompts[0].mode, PromptMode::Interactive);
     }
 
-    // ── select_next / select_prev ──
+    fn versioned_request(protocol_version: u32) -> SubmitRequest {
+        SubmitRequest {
+            protocol_version,
+            ..dependent_request(0)
+        }
+    }
 
     #[test]
-    fn select_next_empty_list() {
+    fn submission_from_newer_protocol_is_refused_with_banner() {
         let mut app = new_test_app();
-        app.select_next(); // should not panic
-        assert!(app.list_state.selected().is_none());
+        let req = SubmitRequest { depends_on: None, ..versioned_request(inbox::PROTOCOL_VERSION + 1) };
+        let err = app.accept_submission(req).unwrap_err();
+        assert!(err.contains("newer than the running TUI"));
+        assert!(app.prompts.is_empty());
+        assert!(app.version_warning.as_ref().unwrap().contains("restart clhorde"));
     }
 
     #[test]
-    fn select_prev_empty_list() {
+    fn submission_from_older_protocol_is_accepted_with_banner() {
         let mut app = new_test_app();
-        app.select_prev(); // should not panic
-        assert!(app.list_state.selected().is_none());
+        let req = SubmitRequest { depends_on: None, ..versioned_request(0) };
+        assert!(app.accept_submission(req).is_ok());
+        assert!(app.version_warning.is_some());
     }
 
     #[test]
-    fn select_next_clamps_to_end() {
-        let mut app = new_test_app();
-        app.add_prompt("a".to_string(), None, false, Vec::new());
-        app.add_prompt("b".to_string(), None, false, Vec::new());
-        app.list_state.select(Some(1));
+    fn poll_inbox_publishes_status_and_honors_stop() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.inbox_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.active_workers = 1;
 
-        app.select_next();
-        assert_eq!(app.list_state.selected(), Some(1)); // stays at end
+        app.poll_inbox();
+        let status = inbox::read_status(tmp.path()).unwrap();
+        assert_eq!(status.active_workers, 1);
+        assert_eq!(status.max_workers, app.max_workers);
+        assert!(status.counts.contains(&("running".to_string(), 1)));
+        assert!(status.counts.contains(&("pending".to_string(), 1)));
+        assert!(!app.should_quit);
+
+        inbox::request_stop(tmp.path()).unwrap();
+        app.poll_inbox();
+        assert!(app.should_quit);
     }
 
     #[test]
-    fn select_prev_clamps_to_start() {
-        let mut app = new_test_app();
-        app.add_prompt("a".to_string(), None, false, Vec::new());
-        app.add_prompt("b".to_string(), None, false, Vec::new());
-        app.list_state.select(Some(0));
+    fn signal_requests_are_checked_against_the_allowlist() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.inbox_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+
+        inbox::request_signal(tmp.path(), 1, "KILL").unwrap();
+        app.poll_inbox();
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.starts_with("Rejected signal for #1: signal 'KILL' not allowed"), "{msg}");
+
+        // Allowed, but there is no terminal to signal
+        assert_eq!(
+            app.send_signal(1, "INT"),
+            Err("no running interactive (PTY) worker".to_string())
+        );
+    }
 
-        app.select_prev();
-        assert_eq!(app.list_state.selected(), Some(0)); // stays at start
+    #[test]
+    fn ctrl_c_interrupt_reports_missing_pty() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert_eq!(msg, "Cannot interrupt #1: no running interactive (PTY) worker");
+        assert_eq!(app.prompts[0].status, PromptStatus::Running);
     }
 
     #[test]
-    fn select_next_advances() {
+    fn queue_stats_over_mixed_prompts() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d", "e", "f", "g"]);
+        let start = Instant::now();
+        let finish = |p: &mut Prompt, status, secs| {
+            p.status = status;
+            p.started_at = Some(start);
+            p.finished_at = Some(start + Duration::from_secs(secs));
+        };
+        finish(&mut app.prompts[0], PromptStatus::Completed, 10);
+        finish(&mut app.prompts[1], PromptStatus::Failed, 20);
+        finish(&mut app.prompts[2], PromptStatus::Failed, 30);
+        app.prompts[2].killed = true;
+        // Restored from disk: finished, but with no recorded duration
+        app.prompts[3].status = PromptStatus::Completed;
+        app.mark_running(4);
+        app.prompts[5].status = PromptStatus::Idle;
+
+        let stats = app.queue_stats();
+        assert_eq!(
+            (stats.completed, stats.failed, stats.killed, stats.pending, stats.running),
+            (2, 1, 1, 1, 2)
+        );
+        assert_eq!(stats.avg_elapsed_secs, Some(20.0));
+        assert_eq!(app.instance_status().stats, stats);
+        // The restored prompt has no output to tail
+        assert_eq!(app.instance_status().prompt_ids, vec![1, 2, 3, 5, 6, 7]);
+    }
+
+    #[test]
+    fn queue_stats_without_finished_prompts_has_no_average() {
+        let app = app_with_prompts(&["a"]);
+        assert_eq!(app.queue_stats().avg_elapsed_secs, None);
+    }
+
+    #[test]
+    fn matching_protocol_sets_no_banner() {
         let mut app = new_test_app();
-        app.add_prompt("a".to_string(), None, false, Vec::new());
-        app.add_prompt("b".to_string(), None, false, Vec::new());
-        app.add_prompt("c".to_string(), None, false, Vec::new());
-        app.list_state.select(Some(0));
+        let req = SubmitRequest { depends_on: None, ..versioned_request(inbox::PROTOCOL_VERSION) };
+        assert!(app.accept_submission(req).is_ok());
+        assert!(app.version_warning.is_none());
+    }
 
-        app.select_next();
-        assert_eq!(app.list_state.selected(), Some(1));
-        app.select_next();
-        assert_eq!(app.list_state.selected(), Some(2));
+    // ── dependencies ──
+
+    fn dependent_request(dep: usize) -> SubmitRequest {
+        SubmitRequest {
+            protocol_version: inbox::PROTOCOL_VERSION,
+            prompt: "step 2".to_string(),
+            options: persistence::PromptOptions {
+                mode: String::new(),
+                context: None,
+                worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: Default::default(),
+            },
+            depends_on: Some(dep),
+            resume_session_id: None,
+        }
     }
 
     #[test]
-    fn select_prev_goes_back() {
+    fn dependent_waits_for_completion() {
+        let mut app = app_with_prompts(&["step 1"]);
+        let id = app.accept_submission(dependent_request(1)).unwrap();
+        assert_eq!(app.prompts[1].depends_on, Some(1));
+
+        app.mark_running(0);
+        assert_eq!(app.next_pending_prompt_index(), None);
+
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert_eq!(app.next_pending_prompt_index(), Some(1));
+        assert_eq!(app.prompts[1].id, id);
+    }
+
+    #[test]
+    fn dependent_does_not_block_others() {
+        let mut app = app_with_prompts(&["step 1"]);
+        app.accept_submission(dependent_request(1)).unwrap();
+        app.add_prompt("unrelated".to_string(), None, false, Vec::new());
+        app.mark_running(0);
+        assert_eq!(app.next_pending_prompt_index(), Some(2));
+    }
+
+    #[test]
+    fn failed_dependency_fails_chain() {
+        let mut app = app_with_prompts(&["step 1"]);
+        app.accept_submission(dependent_request(1)).unwrap();
+        app.accept_submission(dependent_request(2)).unwrap();
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(1) });
+
+        assert_eq!(app.prompts[1].status, PromptStatus::Failed);
+        assert_eq!(app.prompts[1].error.as_deref(), Some("Dependency #1 failed"));
+        assert_eq!(app.prompts[2].status, PromptStatus::Failed);
+        assert_eq!(app.prompts[2].error.as_deref(), Some("Dependency #2 failed"));
+    }
+
+    #[test]
+    fn restored_dependents_of_a_failed_prompt_fail_on_startup() {
+        let tmp = tempfile::tempdir().unwrap();
+        // As `App::new` restores them: #1 failed last session, #2 and #3
+        // queue again behind it
+        let mut app = app_with_prompts(&["step 1", "step 2", "step 3"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.prompts[0].status = PromptStatus::Failed;
+        app.prompts[1].depends_on = Some(1);
+        app.prompts[2].depends_on = Some(2);
+
+        app.fail_blocked_dependents();
+        assert_eq!(app.prompts[1].status, PromptStatus::Failed);
+        assert_eq!(app.prompts[2].error.as_deref(), Some("Dependency #2 failed"));
+        assert_eq!(app.next_pending_prompt_index(), None);
+        let saved = persistence::load_all_prompts(tmp.path());
+        assert_eq!(saved.iter().filter(|(_, pf)| pf.state == "failed").count(), 2);
+    }
+
+    #[test]
+    fn submission_with_unknown_dependency_rejected() {
         let mut app = new_test_app();
-        app.add_prompt("a".to_string(), None, false, Vec::new());
-        app.add_prompt("b".to_string(), None, false, Vec::new());
-        app.list_state.select(Some(1));
+        assert!(app.accept_submission(dependent_request(7)).is_err());
+        assert!(app.prompts.is_empty());
+    }
 
-        app.select_prev();
-        assert_eq!(app.list_state.selected(), Some(0));
+    #[test]
+    fn dependency_cycle_rejected() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.prompts[0].depends_on = Some(2);
+        assert!(app.check_dependency(2, 1).is_err());
+        assert!(app.check_dependency(1, 1).is_err());
+        assert!(app.check_dependency(3, 1).is_ok());
     }
 
-    // ── move_selected_up / move_selected_down ──
+    // ── tag limits ──
 
-    fn app_with_prompts(texts: &[&str]) -> App {
+    /// Mirror of the dispatch loop in `main.rs`; returns the ids started.
+    fn dispatch_all(app: &mut App) -> Vec<usize> {
+        let mut started = Vec::new();
+        while app.can_dispatch() {
+            let Some(idx) = app.next_pending_prompt_index() else {
+                break;
+            };
+            app.mark_running(idx);
+            app.active_workers += 1;
+            started.push(app.prompts[idx].id);
+        }
+        started
+    }
+
+    #[test]
+    fn tag_limit_caps_concurrent_workers() {
         let mut app = new_test_app();
-        for t in texts {
-            app.add_prompt(t.to_string(), None, false, Vec::new());
+        app.max_workers = 5;
+        app.tag_limits.insert("repoA".to_string(), 1);
+        for text in ["a", "b", "c"] {
+            app.add_prompt(text.to_string(), None, false, vec!["repoA".to_string()]);
         }
-        app
+
+        assert_eq!(dispatch_all(&mut app), vec![1]);
+        assert_eq!(dispatch_all(&mut app), Vec::<usize>::new());
+
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert_eq!(dispatch_all(&mut app), vec![2]);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 2, exit_code: Some(0) });
+        assert_eq!(dispatch_all(&mut app), vec![3]);
     }
 
     #[test]
-    fn move_down_swaps_pending() {
-        let mut app = app_with_prompts(&["a", "b", "c"]);
-        app.list_state.select(Some(0));
+    fn tag_limit_leaves_other_prompts_dispatchable() {
+        let mut app = new_test_app();
+        app.tag_limits.insert("repoA".to_string(), 1);
+        app.add_prompt("a".to_string(), None, false, vec!["repoA".to_string()]);
+        app.add_prompt("b".to_string(), None, false, vec!["repoA".to_string()]);
+        app.add_prompt("c".to_string(), None, false, vec!["repoB".to_string()]);
+        app.add_prompt("d".to_string(), None, false, Vec::new());
 
-        app.move_selected_down();
-        assert_eq!(app.prompts[0].text, "b");
-        assert_eq!(app.prompts[1].text, "a");
-        assert_eq!(app.list_state.selected(), Some(1));
+        assert_eq!(dispatch_all(&mut app), vec![1, 3, 4]);
     }
 
     #[test]
-    fn move_up_swaps_pending() {
-        let mut app = app_with_prompts(&["a", "b", "c"]);
-        app.list_state.select(Some(2));
+    fn output_burst_is_applied_in_few_frames() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.active_workers = 1;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for _ in 0..5000 {
+            let text = "x".repeat(64);
+            tx.send(WorkerMessage::OutputChunk { prompt_id: 1, stream: OutputStream::Stdout, text }).unwrap();
+        }
+        tx.send(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) }).unwrap();
 
-        app.move_selected_up();
-        assert_eq!(app.prompts[1].text, "c");
-        assert_eq!(app.prompts[2].text, "b");
-        assert_eq!(app.list_state.selected(), Some(1));
+        let mut frames = 0;
+        while let Ok(first) = rx.try_recv() {
+            app.apply_queued_messages(first, &mut rx);
+            frames += 1;
+        }
+        assert_eq!(frames, 5001usize.div_ceil(MAX_MESSAGES_PER_FRAME));
+        assert_eq!(app.active_workers, 0);
+        assert_eq!(app.prompts[0].status, PromptStatus::Completed);
     }
 
     #[test]
-    fn move_down_at_end_is_noop() {
+    fn finish_survives_output_burst() {
         let mut app = app_with_prompts(&["a", "b"]);
-        app.list_state.select(Some(1));
+        app.mark_running(0);
+        app.mark_running(1);
+        app.active_workers = 2;
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let senders: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|prompt_id| {
+                let tx = tx.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20_000 {
+                        let text = "x".repeat(64);
+                        tx.send(WorkerMessage::OutputChunk { prompt_id, stream: OutputStream::Stdout, text }).unwrap();
+                    }
+                    tx.send(WorkerMessage::Finished { prompt_id, exit_code: Some(0) }).unwrap();
+                })
+            })
+            .collect();
+        drop(tx);
+        for handle in senders {
+            handle.join().unwrap();
+        }
+        while let Ok(msg) = rx.try_recv() {
+            app.apply_message(msg);
+        }
+        assert_eq!(app.active_workers, 0);
+        assert!(app.prompts.iter().all(|p| p.status == PromptStatus::Completed));
+        assert_eq!(app.prompts[0].output.as_ref().map(|o| o.len()), Some(20_000 * 64 + 1));
+    }
 
-        app.move_selected_down();
-        assert_eq!(app.prompts[0].text, "a");
-        assert_eq!(app.prompts[1].text, "b");
-        assert_eq!(app.list_state.selected(), Some(1));
+    #[test]
+    fn oversized_output_is_capped_in_memory() {
+        let mut app = app_with_prompts(&["a"]);
+        app.max_output_bytes = 64 * 1024;
+        app.mark_running(0);
+        for _ in 0..64 {
+            let text = "y".repeat(16 * 1024);
+            app.apply_message(WorkerMessage::OutputChunk { prompt_id: 1, stream: OutputStream::Stdout, text });
+        }
+        let prompt = &app.prompts[0];
+        let marker_len = persistence::truncation_marker(prompt.output_truncated).len();
+        assert!(prompt.output.as_ref().unwrap().len() <= 64 * 1024 + marker_len);
+        assert!(prompt.output_truncated > 0);
+    }
+
+    #[test]
+    fn progress_message_updates_prompt() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::Progress { prompt_id: 1, bytes: 2048, lines: 12 });
+        assert_eq!(app.prompts[0].progress, Some((2048, 12)));
+    }
+
+    #[test]
+    fn command_message_is_persisted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::Command { prompt_id: 1, command: "claude 'a'".to_string() });
+        assert_eq!(app.prompts[0].command.as_deref(), Some("claude 'a'"));
+        app.apply_message(WorkerMessage::SpawnError { prompt_id: 1, error: "boom".to_string() });
+
+        let loaded = persistence::load_all_prompts(tmp.path());
+        assert_eq!(loaded[0].1.command.as_deref(), Some("claude 'a'"));
+    }
+
+    fn running_file(pid: Option<u32>) -> persistence::PromptFile {
+        let mut prompt = Prompt::new(1, "a".to_string(), None, PromptMode::OneShot);
+        prompt.status = PromptStatus::Running;
+        prompt.worker_pid = pid;
+        prompt.command = Some("/usr/local/bin/claude -p a".to_string());
+        persistence::PromptFile::from_prompt(&prompt)
+    }
+
+    #[test]
+    fn orphans_only_after_a_crash() {
+        let pf = running_file(Some(42));
+        assert_eq!(orphan_of(&pf, false, |_| true, |_| Some("claude".into())), None);
+
+        let mut done = running_file(Some(42));
+        done.state = "completed".to_string();
+        assert_eq!(orphan_of(&done, true, |_| true, |_| Some("claude".into())), None);
+    }
+
+    #[test]
+    fn live_orphan_is_reaped_only_if_pid_still_runs_the_agent() {
+        let pf = running_file(Some(42));
+        assert_eq!(orphan_of(&pf, true, |_| true, |_| Some("claude".into())), Some(Orphan::Alive(42)));
+        // Pid reused by something else
+        assert_eq!(orphan_of(&pf, true, |_| true, |_| Some("bash".into())), Some(Orphan::Gone));
+        // Worker died with the instance
+        assert_eq!(orphan_of(&pf, true, |_| false, |_| Some("claude".into())), Some(Orphan::Gone));
+        // No pid recorded (e.g. crashed before the spawn was reported)
+        assert_eq!(orphan_of(&running_file(None), true, |_| true, |_| Some("claude".into())), Some(Orphan::Gone));
+    }
+
+    #[test]
+    fn truncated_process_name_still_matches() {
+        let mut pf = running_file(Some(7));
+        pf.command = Some("'/opt/agents/my-long-agent-binary' --flag".to_string());
+        assert_eq!(
+            orphan_of(&pf, true, |_| true, |_| Some("my-long-agent-b".into())),
+            Some(Orphan::Alive(7))
+        );
+    }
+
+    #[test]
+    fn worker_pid_is_persisted_only_while_running() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::Spawned { prompt_id: 1, pid: 4242 });
+        assert_eq!(persistence::load_all_prompts(tmp.path())[0].1.worker_pid, Some(4242));
+
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert_eq!(persistence::load_all_prompts(tmp.path())[0].1.worker_pid, None);
+    }
+
+    fn running_with_input(pty: bool) -> (App, mpsc::UnboundedReceiver<WorkerInput>) {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        if pty {
+            app.prompts[0].pty_state = Some(crate::pty_worker::test_state(20, 5, 1024));
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.keymap.normal.insert(KeyCode::Char('S').into(), NormalAction::ForceInteract);
+        app.keymap.normal.insert(KeyCode::Char('I').into(), NormalAction::ForcePtyInteract);
+        (app, rx)
+    }
+
+    #[test]
+    fn paste_goes_to_the_pty_as_one_bracketed_paste() {
+        let (mut app, mut rx) = running_with_input(true);
+        {
+            let state = app.prompts[0].pty_state.as_ref().unwrap();
+            let mut pty = state.lock().unwrap();
+            let crate::pty_worker::PtyState { term, processor, .. } = &mut *pty;
+            processor.advance(term, b"\x1b[?2004h");
+        }
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.mode, AppMode::PtyInteract);
+        app.handle_paste("fn main() {}\n");
+        let expected = b"\x1b[200~fn main() {}\n\x1b[201~".to_vec();
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendBytes(b)) if b == expected));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn paste_is_sent_raw_without_bracketed_paste_mode() {
+        let (mut app, mut rx) = running_with_input(true);
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.mode, AppMode::PtyInteract);
+        app.handle_paste("ls\n");
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendBytes(b)) if b == b"ls\n".to_vec()));
+    }
+
+    #[test]
+    fn paste_in_interact_appends_one_line() {
+        let (mut app, _rx) = running_with_input(false);
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.mode, AppMode::Interact);
+        press(&mut app, KeyCode::Char('>'));
+        app.handle_paste("a\r\nb\nc");
+        assert_eq!(app.interact_input, ">a b c");
+    }
+
+    #[test]
+    fn interact_picks_mode_from_worker() {
+        let (mut app, _rx) = running_with_input(true);
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.mode, AppMode::PtyInteract);
+
+        let (mut app, _rx) = running_with_input(false);
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.mode, AppMode::Interact);
+    }
+
+    #[test]
+    fn force_interact_sends_a_line_to_pty_worker() {
+        let (mut app, mut rx) = running_with_input(true);
+        press(&mut app, KeyCode::Char('S'));
+        assert_eq!(app.mode, AppMode::Interact);
+        for c in "yes".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        // Written straight away with a carriage return, not queued for a turn end
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "yes\r"));
+        assert!(app.prompts[0].queued_inputs.is_empty());
+    }
+
+    #[test]
+    fn force_pty_interact_needs_a_pty() {
+        let (mut app, _rx) = running_with_input(false);
+        press(&mut app, KeyCode::Char('I'));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.status_message.as_ref().is_some_and(|(m, _)| m.contains("no terminal")));
+
+        let (mut app, _rx) = running_with_input(true);
+        press(&mut app, KeyCode::Char('I'));
+        assert_eq!(app.mode, AppMode::PtyInteract);
+    }
+
+    #[test]
+    fn ctrl_binding_does_not_fire_on_the_plain_key() {
+        let mut app = app_with_prompts(&["a"]);
+        app.keymap.normal.insert(keymap::parse_key("C-o").unwrap(), NormalAction::ToggleGrouped);
+        app.handle_key(KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL));
+        assert!(app.grouped);
+        // Plain o keeps its own binding
+        press(&mut app, KeyCode::Char('o'));
+        assert!(app.grouped);
+        assert_eq!(app.sort_mode, SortMode::Status);
+    }
+
+    #[test]
+    fn tab_moves_j_k_to_the_output_panel() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.focused_pane, FocusedPane::Output);
+        assert_eq!(app.mode, AppMode::Normal);
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.scroll_offset, 1);
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.focused_pane, FocusedPane::List);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn output_focus_keeps_enter_and_esc() {
+        let mut app = app_with_prompts(&["a"]);
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, AppMode::ViewOutput);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn save_failure_switches_to_memory_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        // A file where the prompts directory should be: every save fails
+        let blocker = tmp.path().join("prompts");
+        fs::write(&blocker, "").unwrap();
+        let mut app = new_test_app();
+        app.prompts_dir = Some(blocker.clone());
+
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        assert!(!app.persistence_healthy.get());
+        app.report_persistence_error();
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.starts_with("Persistence disabled"), "{msg}");
+
+        // Reported once; later saves are skipped and the queue keeps working
+        app.status_message = None;
+        app.add_prompt("b".to_string(), None, false, Vec::new());
+        app.report_persistence_error();
+        assert!(app.status_message.is_none());
+        assert_eq!(app.prompts.len(), 2);
+        assert!(blocker.is_file());
+    }
+
+    #[test]
+    fn finished_output_is_persisted_with_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.max_saved_output_bytes = 6;
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::OutputChunk { prompt_id: 1, stream: OutputStream::Stdout, text: "0123456789".to_string() });
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+
+        let loaded = persistence::load_all_prompts(tmp.path());
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].1.state, "completed");
+        assert_eq!(loaded[0].1.output.as_deref(), Some("[… 5 bytes truncated …]\n56789\n"));
+    }
+
+    #[test]
+    fn activity_is_cleared_when_turn_ends() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::Activity { prompt_id: 1, activity: Some("Bash".to_string()) });
+        assert_eq!(app.prompts[0].activity.as_deref(), Some("Bash"));
+        app.apply_message(WorkerMessage::Activity { prompt_id: 1, activity: None });
+        assert_eq!(app.prompts[0].activity, None);
+
+        app.apply_message(WorkerMessage::Activity { prompt_id: 1, activity: Some("Read".to_string()) });
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        assert_eq!(app.prompts[0].activity, None);
+        app.apply_message(WorkerMessage::Activity { prompt_id: 1, activity: Some("Read".to_string()) });
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert_eq!(app.prompts[0].activity, None);
+    }
+
+    #[test]
+    fn missing_cwd_fails_without_spawning() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.prompts[0].cwd = Some("/nonexistent_xyz_123".to_string());
+        app.prompts[1].cwd = Some("/tmp".to_string());
+
+        assert!(!app.validate_cwd(0));
+        let prompt = app.prompts.iter().find(|p| p.id == 1).unwrap();
+        assert_eq!(prompt.status, PromptStatus::Failed);
+        assert_eq!(prompt.error.as_deref(), Some("cwd does not exist: /nonexistent_xyz_123"));
+        assert!(prompt.command.is_none());
+        assert_eq!(app.active_workers, 0);
+
+        let idx = app.prompts.iter().position(|p| p.id == 2).unwrap();
+        assert!(app.validate_cwd(idx));
+        assert_eq!(app.prompts[idx].status, PromptStatus::Pending);
+    }
+
+    #[test]
+    fn stderr_is_interleaved_and_persisted_apart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::OutputChunk { prompt_id: 1, stream: OutputStream::Stdout, text: "hi".to_string() });
+        app.apply_message(WorkerMessage::OutputChunk { prompt_id: 1, stream: OutputStream::Stderr, text: "oops\n".to_string() });
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(1) });
+        assert!(app.prompts[0].output.as_deref().unwrap().starts_with("hi\noops\n"));
+
+        let loaded = persistence::load_all_prompts(tmp.path());
+        assert_eq!(loaded[0].1.stderr.as_deref(), Some("oops\n"));
+    }
+
+    #[test]
+    fn output_and_exit_are_mirrored_for_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.inbox_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::OutputChunk { prompt_id: 1, stream: OutputStream::Stdout, text: "hi".to_string() });
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert_eq!(inbox::read_output_from(tmp.path(), 1, 0), b"hi");
+        assert_eq!(inbox::read_exit(tmp.path(), 1), Some(0));
+
+        app.mark_running(1);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 2, exit_code: Some(3) });
+        assert_eq!(inbox::read_exit(tmp.path(), 2), Some(3));
+
+        // Deleting a prompt removes its mirror
+        app.selected_ids.insert(1);
+        app.execute_batch_delete();
+        assert_eq!(inbox::output_len(tmp.path(), 1), 0);
+        assert_eq!(inbox::read_exit(tmp.path(), 1), None);
+    }
+
+    #[test]
+    fn notifications_skip_selected_prompt() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.list_state.select(Some(0));
+        assert!(!app.should_notify(2));
+        app.notifications = true;
+        assert!(!app.should_notify(1));
+        assert!(app.should_notify(2));
+    }
+
+    #[test]
+    fn bell_rings_for_prompts_not_being_viewed() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(0));
+        app.mark_running(0);
+        app.mark_running(1);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 2, exit_code: Some(0) });
+        assert!(!app.pending_bell, "off by default");
+
+        app.bell_on_complete = true;
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert!(!app.pending_bell, "selected prompt is skipped");
+
+        app.mark_running(2);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 3, exit_code: Some(1) });
+        assert!(app.pending_bell);
+    }
+
+    #[test]
+    fn copy_without_output_reports_nothing() {
+        let mut app = app_with_prompts(&["a"]);
+        app.list_state.select(Some(0));
+        assert!(app.selected_output_text().is_none());
+        app.copy_selected_output();
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Nothing to copy");
+
+        app.prompts[0].output = Some("answer".to_string());
+        assert_eq!(app.selected_output_text().as_deref(), Some("answer"));
+    }
+
+    #[test]
+    fn add_prompt_selects_first() {
+        let mut app = new_test_app();
+        assert!(app.list_state.selected().is_none());
+        app.add_prompt("test".to_string(), None, false, Vec::new());
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn pending_and_completed_counts() {
+        let mut app = new_test_app();
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        app.add_prompt("b".to_string(), None, false, Vec::new());
+        app.add_prompt("c".to_string(), None, false, Vec::new());
+
+        assert_eq!(app.pending_count(), 3);
+        assert_eq!(app.completed_count(), 0);
+
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[1].status = PromptStatus::Failed;
+
+        assert_eq!(app.pending_count(), 1);
+        assert_eq!(app.completed_count(), 2);
+    }
+
+    #[test]
+    fn session_completed_count_skips_restored() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        // Restored prompts have no finish time from this session
+        app.prompts[0].status = PromptStatus::Completed;
+        app.mark_running(1);
+        app.active_workers = 1;
+        app.apply_message(WorkerMessage::Finished { prompt_id: 2, exit_code: Some(0) });
+        app.prompts[2].status = PromptStatus::Failed;
+        app.prompts[2].finished_at = Some(Instant::now());
+
+        assert_eq!(app.session_completed_count(), 1);
+    }
+
+    // ── select_next / select_prev ──
+
+    #[test]
+    fn select_next_empty_list() {
+        let mut app = new_test_app();
+        app.select_next(); // should not panic
+        assert!(app.list_state.selected().is_none());
+    }
+
+    #[test]
+    fn select_prev_empty_list() {
+        let mut app = new_test_app();
+        app.select_prev(); // should not panic
+        assert!(app.list_state.selected().is_none());
+    }
+
+    #[test]
+    fn select_next_clamps_to_end() {
+        let mut app = new_test_app();
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        app.add_prompt("b".to_string(), None, false, Vec::new());
+        app.list_state.select(Some(1));
+
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(1)); // stays at end
+    }
+
+    #[test]
+    fn select_prev_clamps_to_start() {
+        let mut app = new_test_app();
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        app.add_prompt("b".to_string(), None, false, Vec::new());
+        app.list_state.select(Some(0));
+
+        app.select_prev();
+        assert_eq!(app.list_state.selected(), Some(0)); // stays at start
+    }
+
+    #[test]
+    fn select_next_advances() {
+        let mut app = new_test_app();
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        app.add_prompt("b".to_string(), None, false, Vec::new());
+        app.add_prompt("c".to_string(), None, false, Vec::new());
+        app.list_state.select(Some(0));
+
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(1));
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn select_prev_goes_back() {
+        let mut app = new_test_app();
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        app.add_prompt("b".to_string(), None, false, Vec::new());
+        app.list_state.select(Some(1));
+
+        app.select_prev();
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    // ── move_selected_up / move_selected_down ──
+
+    fn app_with_prompts(texts: &[&str]) -> App {
+        let mut app = new_test_app();
+        for t in texts {
+            app.add_prompt(t.to_string(), None, false, Vec::new());
+        }
+        app
+    }
+
+    #[test]
+    fn move_down_swaps_pending() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(0));
+
+        app.move_selected_down();
+        assert_eq!(app.prompts[0].text, "b");
+        assert_eq!(app.prompts[1].text, "a");
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn move_up_swaps_pending() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(2));
+
+        app.move_selected_up();
+        assert_eq!(app.prompts[1].text, "c");
+        assert_eq!(app.prompts[2].text, "b");
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn move_down_at_end_is_noop() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.list_state.select(Some(1));
+
+        app.move_selected_down();
+        assert_eq!(app.prompts[0].text, "a");
+        assert_eq!(app.prompts[1].text, "b");
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn move_up_at_start_is_noop() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.list_state.select(Some(0));
+
+        app.move_selected_up();
+        assert_eq!(app.prompts[0].text, "a");
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn move_non_pending_is_noop() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.prompts[0].status = PromptStatus::Running;
+        app.list_state.select(Some(0));
+
+        app.move_selected_down();
+        assert_eq!(app.prompts[0].text, "a");
+        assert_eq!(app.prompts[1].text, "b");
+    }
+
+    #[test]
+    fn move_no_selection_is_noop() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.list_state.select(None);
+        app.move_selected_up(); // should not panic
+        app.move_selected_down(); // should not panic
+    }
+
+    // ── priority dispatch ──
+
+    #[test]
+    fn next_pending_follows_queue_rank_by_default() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.prompts[0].status = PromptStatus::Running;
+        assert_eq!(app.next_pending_prompt_index(), Some(1));
+    }
+
+    #[test]
+    fn next_pending_prefers_high_priority() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.set_prompt_priority(3, PromptPriority::High);
+        assert_eq!(app.next_pending_prompt_index(), Some(2));
+    }
+
+    #[test]
+    fn next_pending_low_priority_goes_last() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.set_prompt_priority(1, PromptPriority::Low);
+        assert_eq!(app.next_pending_prompt_index(), Some(1));
+    }
+
+    #[test]
+    fn next_pending_ties_broken_by_queue_rank() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.set_prompt_priority(2, PromptPriority::High);
+        app.set_prompt_priority(3, PromptPriority::High);
+        assert_eq!(app.next_pending_prompt_index(), Some(1));
+    }
+
+    #[test]
+    fn set_priority_ignores_non_pending() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].status = PromptStatus::Running;
+        app.set_prompt_priority(1, PromptPriority::High);
+        assert_eq!(app.prompts[0].priority, PromptPriority::Normal);
+    }
+
+    #[test]
+    fn retry_keeps_priority() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].priority = PromptPriority::High;
+        app.prompts[0].status = PromptStatus::Completed;
+        app.list_state.select(Some(0));
+        app.retry_selected();
+        assert_eq!(app.prompts[1].priority, PromptPriority::High);
+    }
+
+    // ── auto-retry ──
+
+    fn fail_run(app: &mut App) {
+        app.mark_running(0);
+        app.active_workers += 1;
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(2) });
+    }
+
+    #[test]
+    fn failure_is_retried_until_max_attempts() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].max_attempts = 3;
+        fail_run(&mut app);
+        assert_eq!(app.prompts[0].status, PromptStatus::Pending);
+        assert_eq!(app.prompts[0].attempt, 2);
+        assert!(app.prompts[0].error.is_none());
+        assert_eq!(app.next_pending_prompt_index(), Some(0));
+
+        fail_run(&mut app);
+        assert_eq!(app.prompts[0].attempt, 3);
+        assert_eq!(app.prompts[0].status, PromptStatus::Pending);
+
+        fail_run(&mut app);
+        assert_eq!(app.prompts[0].attempt, 3);
+        assert_eq!(app.prompts[0].status, PromptStatus::Failed);
+        assert_eq!(app.prompts[0].error.as_deref(), Some("Exit code: 2"));
+        assert_eq!(app.active_workers, 0);
+    }
+
+    #[test]
+    fn retry_starts_with_empty_stderr() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].max_attempts = 2;
+        app.mark_running(0);
+        app.active_workers += 1;
+        app.apply_message(WorkerMessage::OutputChunk {
+            prompt_id: 1,
+            stream: OutputStream::Stderr,
+            text: "boom\n".to_string(),
+        });
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(2) });
+        assert_eq!(app.prompts[0].status, PromptStatus::Pending);
+        assert!(app.prompts[0].stderr.is_none());
+        assert!(app.prompts[0].output.is_none());
+    }
+
+    #[test]
+    fn killed_prompt_is_not_retried() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].max_attempts = 3;
+        app.mark_running(0);
+        app.kill_worker(1);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(143) });
+        assert_eq!(app.prompts[0].status, PromptStatus::Failed);
+        assert_eq!(app.prompts[0].attempt, 1);
+    }
+
+    #[test]
+    fn killing_a_stream_worker_without_input_signals_its_pid() {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.kill_grace = Duration::from_secs(5);
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::Spawned { prompt_id: 1, pid: child.id() });
+        assert!(!app.worker_inputs.contains_key(&1));
+
+        let start = Instant::now();
+        app.kill_worker(1);
+        let status = child.wait().unwrap();
+        assert!(!status.success());
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(app.prompts[0].killed);
+    }
+
+    #[test]
+    fn killed_pty_prompt_is_not_completed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.kill_worker(1);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert!(app.prompts[0].is_killed());
+        assert_eq!(app.prompts[0].state_label(), "killed");
+        assert_eq!(app.prompts[0].error.as_deref(), Some("Killed"));
+        let loaded = persistence::load_all_prompts(tmp.path());
+        assert_eq!(loaded[0].1.state, "killed");
+    }
+
+    #[test]
+    fn kill_all_drains_workers_and_keeps_queue() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d", "e"]);
+        let mut receivers = Vec::new();
+        for idx in 0..3 {
+            let (tx, rx) = mpsc::unbounded_channel();
+            app.worker_inputs.insert(idx + 1, tx);
+            receivers.push(rx);
+            app.mark_running(idx);
+            app.active_workers += 1;
+        }
+
+        press(&mut app, KeyCode::Char('X'));
+        assert!(app.confirm_kill_all);
+        press(&mut app, KeyCode::Char('y'));
+        assert!(!app.confirm_kill_all);
+        for rx in &mut receivers {
+            assert!(matches!(rx.try_recv(), Ok(WorkerInput::Kill)));
+        }
+        for id in 1..=3 {
+            app.apply_message(WorkerMessage::Finished { prompt_id: id, exit_code: Some(143) });
+        }
+
+        assert_eq!(app.active_workers, 0);
+        assert!(app.prompts[..3].iter().all(|p| p.is_killed()));
+        assert!(app.prompts[3..].iter().all(|p| p.status == PromptStatus::Pending));
+        assert_eq!(app.status_message.as_ref().map(|(m, _)| m.as_str()), Some("Killed 3 workers"));
+    }
+
+    #[test]
+    fn kill_all_needs_confirmation_and_active_workers() {
+        let mut app = app_with_prompts(&["a"]);
+        press(&mut app, KeyCode::Char('X'));
+        assert!(!app.confirm_kill_all);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.mark_running(0);
+        press(&mut app, KeyCode::Char('X'));
+        press(&mut app, KeyCode::Char('n'));
+        assert!(!app.confirm_kill_all);
+        assert!(rx.try_recv().is_err());
+        assert!(!app.prompts[0].killed);
+    }
+
+    #[test]
+    fn killed_prompt_is_not_manually_retried() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].status = PromptStatus::Failed;
+        app.prompts[0].killed = true;
+        app.list_state.select(Some(0));
+        app.retry_selected();
+        assert_eq!(app.prompts.len(), 1);
+    }
+
+    #[test]
+    fn status_filter_separates_killed_from_failed() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.prompts[0].status = PromptStatus::Failed;
+        app.prompts[1].status = PromptStatus::Failed;
+        app.prompts[1].killed = true;
+        app.filter_text = Some("status:killed".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![1]);
+        app.filter_text = Some("is:failed".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0]);
+    }
+
+    #[test]
+    fn retry_waits_for_backoff_delay() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].max_attempts = 2;
+        app.retry_delay = Duration::from_secs(60);
+        fail_run(&mut app);
+        assert_eq!(app.prompts[0].status, PromptStatus::Pending);
+        assert_eq!(app.next_pending_prompt_index(), None);
+        app.prompts[0].retry_at = Some(Instant::now());
+        assert_eq!(app.next_pending_prompt_index(), Some(0));
+    }
+
+    #[test]
+    fn spawn_error_is_retried() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].max_attempts = 2;
+        app.mark_running(0);
+        app.active_workers += 1;
+        app.apply_message(WorkerMessage::SpawnError { prompt_id: 1, error: "not found".to_string() });
+        assert_eq!(app.prompts[0].status, PromptStatus::Pending);
+        assert_eq!(app.prompts[0].attempt, 2);
+    }
+
+    // ── timeouts ──
+
+    #[test]
+    fn check_timeouts_flags_expired_turn() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.prompts[0].timeout_secs = Some(5);
+        app.prompts[0].turn_started_at = Some(Instant::now() - std::time::Duration::from_secs(10));
+        app.check_timeouts();
+        assert_eq!(app.prompts[0].error.as_deref(), Some("Timed out after 5s"));
+
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: None });
+        assert_eq!(app.prompts[0].status, PromptStatus::Failed);
+    }
+
+    #[test]
+    fn check_timeouts_ignores_within_limit() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.prompts[0].timeout_secs = Some(60);
+        app.check_timeouts();
+        assert!(app.prompts[0].error.is_none());
+    }
+
+    #[test]
+    fn check_timeouts_ignores_idle() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.prompts[0].timeout_secs = Some(5);
+        app.prompts[0].status = PromptStatus::Idle;
+        app.prompts[0].turn_started_at = Some(Instant::now() - std::time::Duration::from_secs(10));
+        app.check_timeouts();
+        assert!(app.prompts[0].error.is_none());
+    }
+
+    #[test]
+    fn waiting_flag_escalates_after_the_reminder_threshold() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        let since = app.prompts[0].awaiting_input_since.unwrap();
+        let after = Duration::from_secs(60);
+        assert!(!app.prompts[0].is_awaiting_input(after, since + Duration::from_secs(59)));
+        assert!(app.prompts[0].is_awaiting_input(after, since + after));
+
+        // Output resumes the turn, so the prompt is no longer waiting
+        app.apply_message(WorkerMessage::OutputChunk {
+            prompt_id: 1,
+            stream: OutputStream::Stdout,
+            text: "more".to_string(),
+        });
+        assert!(!app.prompts[0].is_awaiting_input(after, since + after));
+
+        // A running stream worker with no turn ended is never waiting
+        app.mark_running(1);
+        app.prompts[1].awaiting_input_since = Some(since);
+        assert!(!app.prompts[1].is_awaiting_input(after, since + after));
+    }
+
+    #[test]
+    fn input_reminder_fires_once_per_wait() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        app.check_input_reminders();
+        assert!(!app.prompts[0].input_reminder_sent);
+
+        app.prompts[0].awaiting_input_since = Some(Instant::now() - Duration::from_secs(61));
+        assert!(app.is_awaiting_input(&app.prompts[0]));
+        app.check_input_reminders();
+        assert!(app.prompts[0].input_reminder_sent);
+
+        // The next turn ending re-arms it
+        app.prompts[0].status = PromptStatus::Running;
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        assert!(!app.prompts[0].input_reminder_sent);
+
+        app.input_reminder = None;
+        app.prompts[0].awaiting_input_since = Some(Instant::now() - Duration::from_secs(61));
+        assert!(!app.is_awaiting_input(&app.prompts[0]));
+        app.check_input_reminders();
+        assert!(!app.prompts[0].input_reminder_sent);
+    }
+
+    #[test]
+    fn turn_complete_resets_turn_timer() {
+        let mut app = app_with_prompts(&["a"]);
+        app.mark_running(0);
+        let old = Instant::now() - std::time::Duration::from_secs(10);
+        app.prompts[0].turn_started_at = Some(old);
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        assert!(app.prompts[0].turn_started_at.unwrap() > old);
+    }
+
+    #[test]
+    fn add_prompt_applies_default_timeout() {
+        let mut app = new_test_app();
+        app.default_timeout_secs = Some(120);
+        app.add_prompt("a".to_string(), None, false, Vec::new());
+        assert_eq!(app.prompts[0].timeout_secs, Some(120));
+    }
+
+    // ── output search ──
+
+    #[test]
+    fn find_matches_case_insensitive() {
+        assert_eq!(find_matches("Foo foo FOO", "foo"), vec![(0, 3), (4, 7), (8, 11)]);
+        assert_eq!(find_matches("héllo HÉLLO", "héllo"), vec![(0, 6), (7, 13)]);
+        assert!(find_matches("abc", "").is_empty());
+        assert!(find_matches("abc", "abcd").is_empty());
+    }
+
+    #[test]
+    fn output_match_lines_finds_lines() {
+        let content = "one\nError here\nthree\nanother error";
+        assert_eq!(output_match_lines(content, "error"), vec![1, 3]);
+    }
+
+    fn viewing_app(output: &str) -> App {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[0].output = Some(output.to_string());
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_search(app: &mut App, query: &str) {
+        press(app, KeyCode::Char('/'));
+        for c in query.chars() {
+            press(app, KeyCode::Char(c));
+        }
+        press(app, KeyCode::Enter);
+    }
+
+    #[test]
+    fn output_search_jumps_and_wraps() {
+        let mut app = viewing_app("x\nmatch\ny\nMATCH\nz");
+        type_search(&mut app, "match");
+        assert_eq!(app.output_search.as_deref(), Some("match"));
+        assert_eq!(app.scroll_offset, 1);
+        assert!(!app.auto_scroll);
+
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.scroll_offset, 3);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.scroll_offset, 1); // wrapped
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(app.scroll_offset, 3); // wrapped backwards
+    }
+
+    #[test]
+    fn output_search_scrolls_to_the_wrapped_row() {
+        let mut app = viewing_app(&format!("{}\nmatch", "a".repeat(25)));
+        app.output_width = 10;
+        type_search(&mut app, "match");
+        // The 25-column line above the match wraps onto 3 rows
+        assert_eq!(app.scroll_offset, 3);
+    }
+
+    #[test]
+    fn output_search_typing_does_not_trigger_view_actions() {
+        let mut app = viewing_app("quit\nnothing");
+        type_search(&mut app, "qn");
+        assert_eq!(app.mode, AppMode::ViewOutput);
+        assert_eq!(app.output_search.as_deref(), Some("qn"));
+        assert!(app.status_message.as_ref().unwrap().0.contains("not found"));
+    }
+
+    #[test]
+    fn output_search_esc_clears_before_leaving_view() {
+        let mut app = viewing_app("match");
+        type_search(&mut app, "match");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.output_search.is_none());
+        assert_eq!(app.mode, AppMode::ViewOutput);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn output_search_esc_while_typing_cancels() {
+        let mut app = viewing_app("match");
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Char('m'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.output_search_input.is_none());
+        assert!(app.output_search.is_none());
+        assert_eq!(app.mode, AppMode::ViewOutput);
+    }
+
+    // ── queue pause ──
+
+    #[test]
+    fn paused_blocks_dispatch() {
+        let mut app = new_test_app();
+        assert!(app.can_dispatch());
+        app.toggle_pause();
+        assert!(app.paused);
+        assert!(!app.can_dispatch());
+    }
+
+    #[test]
+    fn unpause_allows_dispatch() {
+        let mut app = new_test_app();
+        app.toggle_pause();
+        app.toggle_pause();
+        assert!(!app.paused);
+        assert!(app.can_dispatch());
+    }
+
+    #[test]
+    fn drain_stops_dispatch_and_quits_when_done() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a"]);
+        app.inbox_dir = Some(tmp.path().to_path_buf());
+        app.mark_running(0);
+        app.active_workers = 1;
+
+        inbox::request_drain(tmp.path(), true).unwrap();
+        app.poll_inbox();
+        assert!(app.draining);
+        assert!(!app.can_dispatch());
+        assert!(inbox::read_status(tmp.path()).unwrap().draining);
+
+        // Submissions are still accepted, but only queued
+        let req = SubmitRequest { depends_on: None, ..versioned_request(inbox::PROTOCOL_VERSION) };
+        let id = app.accept_submission(req).unwrap();
+        assert_eq!(app.prompts.iter().find(|p| p.id == id).unwrap().status, PromptStatus::Pending);
+        assert!(!app.can_dispatch());
+
+        app.check_drained();
+        assert!(!app.should_quit);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        app.check_drained();
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn drain_without_quit_keeps_running() {
+        let mut app = new_test_app();
+        app.start_drain(false);
+        app.check_drained();
+        assert!(app.draining);
+        assert!(!app.should_quit);
+        // Toggling pause doesn't undo a drain
+        app.toggle_pause();
+        app.toggle_pause();
+        assert!(!app.can_dispatch());
+    }
+
+    #[test]
+    fn spawn_interval_defers_second_spawn() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.spawn_interval = Duration::from_secs(60);
+        let mut spawned = Vec::new();
+        while app.can_dispatch() {
+            let Some(idx) = app.next_pending_prompt_index() else { break };
+            app.start_dispatch(idx);
+            spawned.push(idx);
+        }
+        assert_eq!(spawned, vec![0]);
+        assert_eq!(app.prompts[1].status, PromptStatus::Pending);
+
+        // Once the interval has passed the deferred prompt goes out
+        app.last_spawn = Instant::now().checked_sub(Duration::from_secs(61));
+        assert!(app.can_dispatch());
+        assert_eq!(app.next_pending_prompt_index(), Some(1));
+    }
+
+    #[test]
+    fn zero_spawn_interval_dispatches_burst() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.start_dispatch(0);
+        assert!(app.can_dispatch());
+    }
+
+    #[test]
+    fn event_socket_streams_prompt_lifecycle() {
+        use std::io::BufRead;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("events.sock");
+        let mut app = new_test_app();
+        app.open_event_socket(&path);
+        let stream = std::os::unix::net::UnixStream::connect(&path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut reader = std::io::BufReader::new(stream);
+
+        // Events aren't buffered for readers the accept thread hasn't picked up yet
+        while app.event_socket.as_ref().unwrap().reader_count() == 0 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let id = app.add_prompt("ship it".to_string(), None, false, Vec::new());
+        app.start_dispatch(0);
+        app.apply_message(WorkerMessage::Finished { prompt_id: id, exit_code: Some(0) });
+
+        let mut next = || {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            serde_json::from_str::<serde_json::Value>(&line).unwrap()
+        };
+        let added = next();
+        assert_eq!(added["event"], "added");
+        assert_eq!(added["text"], "ship it");
+        assert_eq!(next(), serde_json::json!({ "event": "started", "id": id }));
+        assert_eq!(
+            next(),
+            serde_json::json!({ "event": "finished", "id": id, "status": "completed", "exit_code": 0 })
+        );
+    }
+
+    #[test]
+    fn session_state_survives_restart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("state.json");
+        let mut app = new_test_app();
+        app.state_path = Some(path.clone());
+        press(&mut app, KeyCode::Char('+'));
+        press(&mut app, KeyCode::Char('+'));
+        press(&mut app, KeyCode::Char('m'));
+        press(&mut app, KeyCode::Char('P'));
+
+        let mut restarted = new_test_app();
+        restarted.state_path = Some(path);
+        restarted.restore_session_state();
+        assert_eq!(restarted.max_workers, 5);
+        assert_eq!(restarted.default_mode, PromptMode::OneShot);
+        assert!(restarted.paused);
+    }
+
+    #[test]
+    fn can_dispatch_respects_max_workers() {
+        let mut app = new_test_app();
+        app.active_workers = app.max_workers;
+        assert!(!app.can_dispatch());
+    }
+
+    // ── retry_selected ──
+
+    #[test]
+    fn retry_completed_creates_new_prompt() {
+        let mut app = app_with_prompts(&["hello world"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[0].cwd = Some("/tmp".to_string());
+        app.list_state.select(Some(0));
+
+        app.retry_selected();
+
+        assert_eq!(app.prompts.len(), 2);
+        let retried = &app.prompts[1];
+        assert_eq!(retried.text, "hello world");
+        assert_eq!(retried.cwd, Some("/tmp".to_string()));
+        assert_eq!(retried.status, PromptStatus::Pending);
+        assert!(retried.id > app.prompts[0].id);
+    }
+
+    #[test]
+    fn retry_failed_creates_new_prompt() {
+        let mut app = app_with_prompts(&["fail"]);
+        app.prompts[0].status = PromptStatus::Failed;
+        app.list_state.select(Some(0));
+
+        app.retry_selected();
+        assert_eq!(app.prompts.len(), 2);
+        assert_eq!(app.prompts[1].status, PromptStatus::Pending);
+    }
+
+    #[test]
+    fn retry_running_is_noop() {
+        let mut app = app_with_prompts(&["running"]);
+        app.prompts[0].status = PromptStatus::Running;
+        app.list_state.select(Some(0));
+
+        app.retry_selected();
+        assert_eq!(app.prompts.len(), 1);
+    }
+
+    #[test]
+    fn retry_pending_is_noop() {
+        let mut app = app_with_prompts(&["pending"]);
+        app.list_state.select(Some(0));
+
+        app.retry_selected();
+        assert_eq!(app.prompts.len(), 1);
+    }
+
+    #[test]
+    fn retry_no_selection_is_noop() {
+        let mut app = app_with_prompts(&["test"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.list_state.select(None);
+
+        app.retry_selected();
+        assert_eq!(app.prompts.len(), 1);
+    }
+
+    // ── worktree diff ──
+
+    #[test]
+    fn worktree_diff_opens_fills_and_closes() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.prompts[0].worktree_path = Some("/wt".to_string());
+        app.list_state.select(Some(1));
+        app.mode = AppMode::ViewOutput;
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.worktree_diff.is_none());
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#2 has no worktree");
+
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.selected_worktree_diff(), Some(&WorktreeDiffView { prompt_id: 1, diff: None }));
+
+        app.apply_message(WorkerMessage::WorktreeDiff { prompt_id: 1, result: Ok(String::new()) });
+        assert_eq!(app.worktree_diff.as_ref().unwrap().diff.as_deref(), Some("No changes in this worktree"));
+
+        // Esc closes the diff but stays in the view
+        press(&mut app, KeyCode::Esc);
+        assert!(app.worktree_diff.is_none());
+        assert_eq!(app.mode, AppMode::ViewOutput);
+
+        // A late result for a closed view is dropped; errors close it
+        app.apply_message(WorkerMessage::WorktreeDiff { prompt_id: 1, result: Ok("+x".to_string()) });
+        assert!(app.worktree_diff.is_none());
+        press(&mut app, KeyCode::Char('d'));
+        app.apply_message(WorkerMessage::WorktreeDiff { prompt_id: 1, result: Err("not a git worktree".to_string()) });
+        assert!(app.worktree_diff.is_none());
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#1: diff failed: not a git worktree");
+    }
+
+    #[test]
+    fn apply_and_discard_are_refused_while_the_prompt_runs() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].worktree_path = Some("/wt".to_string());
+        app.mark_running(0);
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#1 hasn't finished yet");
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.confirm_discard_worktree, None);
+    }
+
+    #[test]
+    fn discard_needs_confirmation_and_forgets_the_worktree_once_removed() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].worktree_path = Some("/wt".to_string());
+        app.prompts[0].status = PromptStatus::Completed;
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.confirm_discard_worktree, Some(1));
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.confirm_discard_worktree, None);
+        assert_eq!(app.mode, AppMode::ViewOutput);
+
+        app.worktree_diff = Some(WorktreeDiffView { prompt_id: 1, diff: None });
+        app.apply_message(WorkerMessage::WorktreeDiscarded { prompt_id: 1, result: Err("locked".to_string()) });
+        assert_eq!(app.prompts[0].worktree_path.as_deref(), Some("/wt"));
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#1: discard failed: locked");
+
+        app.apply_message(WorkerMessage::WorktreeDiscarded { prompt_id: 1, result: Ok(()) });
+        assert_eq!(app.prompts[0].worktree_path, None);
+        assert!(app.worktree_diff.is_none());
+    }
+
+    #[test]
+    fn apply_outcome_is_reported() {
+        let mut app = app_with_prompts(&["a"]);
+        app.apply_message(WorkerMessage::WorktreeApplied { prompt_id: 1, result: Ok(Some("abc1234".to_string())) });
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#1: worktree merged (abc1234)");
+        app.apply_message(WorkerMessage::WorktreeApplied {
+            prompt_id: 1,
+            result: Err("merge conflicts in a.rs; nothing was merged".to_string()),
+        });
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#1: apply failed: merge conflicts in a.rs; nothing was merged");
+    }
+
+    #[test]
+    fn apply_in_flight_blocks_repeat_and_discard() {
+        let mut app = app_with_prompts(&["a"]);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.message_tx = Some(tx);
+        app.prompts[0].worktree_path = Some("/definitely/not/a/worktree".to_string());
+        app.prompts[0].status = PromptStatus::Completed;
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.applying_worktrees.contains(&1));
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.status_message.as_ref().unwrap().0, "#1: worktree merge already in progress");
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.confirm_discard_worktree, None);
+
+        app.apply_message(WorkerMessage::WorktreeApplied { prompt_id: 1, result: Ok(None) });
+        assert!(app.applying_worktrees.is_empty());
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.confirm_discard_worktree, Some(1));
+    }
+
+    // ── resume ──
+
+    #[test]
+    fn resume_reattaches_to_the_captured_session() {
+        let mut app = app_with_prompts(&["fix it"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[0].session_id = Some("sess-1".to_string());
+        app.prompts[0].output = Some("first run".to_string());
+        app.prompts[0].stderr = Some("warning".to_string());
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Char('R'));
+        let prompt = &app.prompts[0];
+        assert_eq!(prompt.status, PromptStatus::Pending);
+        assert_eq!(prompt.output.as_deref(), Some("first run\n── resumed ──\n"));
+        assert_eq!(prompt.stderr.as_deref(), Some("warning\n── resumed ──\n"));
+
+        let session = crate::worker::SessionMode::for_prompt(prompt);
+        assert_eq!(session, crate::worker::SessionMode::Resume("sess-1".to_string()));
+        let args = AgentCommand::default().oneshot_args(&prompt.text, "/repo", &session);
+        assert!(args.windows(2).any(|w| w == ["--resume", "sess-1"]));
+
+        // The resumed run's output follows the earlier one
+        app.mark_running(0);
+        app.apply_message(WorkerMessage::OutputChunk {
+            prompt_id: 1,
+            stream: OutputStream::Stdout,
+            text: "second run".to_string(),
+        });
+        assert_eq!(app.prompts[0].output.as_deref(), Some("first run\n── resumed ──\nsecond run"));
+    }
+
+    #[test]
+    fn resume_without_a_session_is_refused() {
+        let mut app = app_with_prompts(&["fix it"]);
+        app.prompts[0].status = PromptStatus::Failed;
+        app.prompts[0].output = Some("partial".to_string());
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.prompts[0].status, PromptStatus::Failed);
+        assert!(!app.prompts[0].resume);
+        assert_eq!(app.prompts[0].output.as_deref(), Some("partial"));
+        let (msg, _) = app.status_message.as_ref().unwrap();
+        assert_eq!(msg, "#1 has no session to resume");
+    }
+
+    // ── duplicate ──
+
+    #[test]
+    fn duplicate_prefills_insert_and_copies_settings() {
+        let mut app = app_with_prompts(&["refactor parser"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[0].cwd = Some("/tmp".to_string());
+        app.prompts[0].mode = PromptMode::OneShot;
+        app.prompts[0].worktree = true;
+        app.prompts[0].tags = vec!["api".to_string()];
+        app.prompts[0].session_id = Some("sess-1".to_string());
+        app.prompts[0].resume = true;
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.mode, AppMode::Insert);
+        assert_eq!(app.input.to_string(), "refactor parser");
+        // Nothing is queued until the edit is submitted
+        assert_eq!(app.prompts.len(), 1);
+
+        press(&mut app, KeyCode::Char('!'));
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.prompts.len(), 2);
+        let dup = &app.prompts[1];
+        assert_eq!(dup.text, "refactor parser!");
+        assert_eq!(dup.status, PromptStatus::Pending);
+        assert_eq!(dup.cwd, Some("/tmp".to_string()));
+        assert_eq!(dup.mode, PromptMode::OneShot);
+        assert!(dup.worktree);
+        assert_eq!(dup.tags, vec!["api".to_string()]);
+        assert!(dup.session_id.is_none());
+        assert!(!dup.resume);
+        assert_ne!(dup.uuid, app.prompts[0].uuid);
+        assert!(dup.queue_rank > app.prompts[0].queue_rank);
+        assert!(app.duplicate_source.is_none());
+    }
+
+    #[test]
+    fn template_placeholders_are_filled_with_tab() {
+        let mut app = new_test_app();
+        app.templates.insert("review".to_string(), "Review {file} for {issue}".to_string());
+        press(&mut app, KeyCode::Char('i'));
+        for c in ":rev".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.placeholder(), Some("file"));
+        for c in "a.rs".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Tab);
+        for c in "bugs".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.placeholder(), None);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.prompts[0].text, "Review a.rs for bugs");
+    }
+
+    #[test]
+    fn unknown_template_var_survives_tab() {
+        let mut app = new_test_app();
+        app.templates.insert("fix".to_string(), "Use {foo} to fix {file}".to_string());
+        press(&mut app, KeyCode::Char('i'));
+        for c in ":fix".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.placeholder(), Some("foo"));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input.placeholder(), Some("file"));
+        for c in "a.rs".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.prompts[0].text, "Use {foo} to fix a.rs");
+    }
+
+    #[test]
+    fn mode_token_overrides_default_for_one_prompt() {
+        let mut app = new_test_app();
+        app.default_mode = PromptMode::Interactive;
+        for line in ["@ui !oneshot fix it", "plain"] {
+            press(&mut app, KeyCode::Char('i'));
+            for c in line.chars() {
+                press(&mut app, KeyCode::Char(c));
+            }
+            press(&mut app, KeyCode::Enter);
+        }
+
+        assert_eq!(app.prompts[0].text, "fix it");
+        assert_eq!(app.prompts[0].tags, vec!["ui".to_string()]);
+        assert_eq!(app.prompts[0].mode, PromptMode::OneShot);
+        assert_eq!(app.prompts[1].mode, PromptMode::Interactive);
+    }
+
+    #[test]
+    fn continue_session_queues_new_prompt_in_that_session() {
+        let mut app = app_with_prompts(&["write the parser"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[0].cwd = Some("/tmp".to_string());
+        app.prompts[0].worktree = true;
+        app.prompts[0].session_id = Some("sess-1".to_string());
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(app.mode, AppMode::Insert);
+        assert!(app.input.to_string().is_empty());
+        for c in "now test it".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.prompts.len(), 2);
+        let next = &app.prompts[1];
+        assert_eq!(next.text, "now test it");
+        assert_eq!(next.cwd, Some("/tmp".to_string()));
+        assert_eq!(next.resume_session_id.as_deref(), Some("sess-1"));
+        assert!(!next.worktree);
+        assert!(!next.resume);
+        assert!(app.continue_session.is_none());
+    }
+
+    #[test]
+    fn continue_session_needs_a_session() {
+        let mut app = app_with_prompts(&["a"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.status_message.as_ref().unwrap().0.contains("no session"));
+    }
+
+    #[test]
+    fn submission_with_resume_session() {
+        let mut app = new_test_app();
+        let req = SubmitRequest { resume_session_id: Some(" sess-9 ".to_string()), ..dependent_request(0) };
+        let id = app.accept_submission(SubmitRequest { depends_on: None, ..req }).unwrap();
+        let prompt = app.prompts.iter().find(|p| p.id == id).unwrap();
+        assert_eq!(prompt.resume_session_id.as_deref(), Some("sess-9"));
+
+        let req = SubmitRequest { resume_session_id: Some("  ".to_string()), ..dependent_request(0) };
+        assert!(app.accept_submission(SubmitRequest { depends_on: None, ..req }).is_err());
+        assert_eq!(app.prompts.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_cancel_queues_nothing() {
+        let mut app = app_with_prompts(&["running"]);
+        app.prompts[0].status = PromptStatus::Running;
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Char('D'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.prompts.len(), 1);
+        assert!(app.duplicate_source.is_none());
+
+        // A plain insert afterwards is not treated as a duplicate
+        app.prompts[0].mode = PromptMode::OneShot;
+        press(&mut app, KeyCode::Char('i'));
+        press(&mut app, KeyCode::Char('x'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.prompts[1].mode, app.default_mode);
+    }
+
+    // ── mouse ──
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }
+    }
+
+    #[test]
+    fn mouse_click_selects_visible_row() {
+        let mut app = app_with_many_prompts(10);
+        app.list_area = Some(Rect::new(0, 2, 30, 8));
+        app.list_offset = 2;
+        app.handle_mouse(mouse(MouseEventKind::Down(crossterm::event::MouseButton::Left), 5, 4));
+        // Border at y=2, so y=4 is the second row: offset 2 + 1
+        assert_eq!(app.list_state.selected(), Some(3));
+
+        // Clicks outside the list are ignored
+        app.handle_mouse(mouse(MouseEventKind::Down(crossterm::event::MouseButton::Left), 40, 4));
+        assert_eq!(app.list_state.selected(), Some(3));
+    }
+
+    #[test]
+    fn mouse_scroll_moves_selection_or_output() {
+        let mut app = app_with_many_prompts(5);
+        app.list_state.select(Some(0));
+        app.handle_mouse(mouse(MouseEventKind::ScrollDown, 0, 0));
+        assert_eq!(app.list_state.selected(), Some(1));
+
+        app.mode = AppMode::ViewOutput;
+        app.handle_mouse(mouse(MouseEventKind::ScrollDown, 0, 0));
+        assert_eq!(app.scroll_offset, 3);
+        app.handle_mouse(mouse(MouseEventKind::ScrollUp, 0, 0));
+        assert_eq!(app.scroll_offset, 0);
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    // ── batch export ──
+
+    #[test]
+    fn batch_export_skips_empty_and_separates() {
+        let mut app = app_with_prompts(&["first", "empty", "third"]);
+        app.prompts[0].output = Some("out one".to_string());
+        app.prompts[2].output = Some("out three".to_string());
+        let prompts: Vec<&Prompt> = app.prompts.iter().collect();
+
+        let (content, written) = batch_export_markdown(&prompts);
+        assert_eq!(written, 2);
+        assert!(content.starts_with("# clhorde output #1\n\nPrompt: first\n\nout one\n"));
+        assert!(content.contains("\n---\n\n# clhorde output #3\n\nPrompt: third\n\nout three\n"));
+        assert!(!content.contains("#2"));
+    }
+
+    #[test]
+    fn batch_export_nothing_to_write() {
+        let mut app = app_with_prompts(&["a"]);
+        app.selected_ids.insert(1);
+        app.export_selected_output();
+        assert_eq!(app.status_message.as_ref().unwrap().0, "No output to export");
+    }
+
+    // ── tags ──
+
+    #[test]
+    fn add_tag_normalizes_and_rejects_empty() {
+        let mut app = app_with_prompts(&["a"]);
+        assert_eq!(app.add_tag(1, " API "), Ok(true));
+        assert_eq!(app.add_tag(1, "@api"), Ok(false));
+        assert_eq!(app.prompts[0].tags, vec!["api"]);
+        assert!(app.add_tag(1, "  ").is_err());
+        assert!(app.add_tag(99, "x").is_err());
+        assert_eq!(app.remove_tag(1, "Api"), Ok(true));
+        assert!(app.prompts[0].tags.is_empty());
+    }
+
+    #[test]
+    fn tag_edit_applies_to_batch_selection() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.prompts[0].tags = vec!["old".to_string()];
+        app.selected_ids.insert(1);
+        app.selected_ids.insert(2);
+
+        press(&mut app, KeyCode::Char('t'));
+        assert!(app.tag_input.is_some());
+        for c in "+New -old".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+
+        assert!(app.tag_input.is_none());
+        assert_eq!(app.prompts[0].tags, vec!["new"]);
+        assert_eq!(app.prompts[1].tags, vec!["new"]);
+        assert!(app.prompts[2].tags.is_empty());
+    }
+
+    #[test]
+    fn tag_edit_invalid_applies_nothing() {
+        let mut app = app_with_prompts(&["a"]);
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('t'));
+        for c in "ok -b@d".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert!(app.prompts[0].tags.is_empty());
+        assert!(app.status_message.as_ref().unwrap().0.contains("Invalid tag"));
+    }
+
+    #[test]
+    fn tag_edit_typing_does_not_trigger_actions() {
+        let mut app = app_with_prompts(&["a"]);
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('t'));
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.should_quit && !app.confirm_quit);
+        assert!(app.tag_input.is_none());
+        assert!(app.prompts[0].tags.is_empty());
+    }
+
+    // ── rebuild_filter ──
+
+    #[test]
+    fn filter_no_text_includes_all() {
+        let mut app = app_with_prompts(&["foo", "bar", "baz"]);
+        app.filter_text = None;
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn filter_matches_case_insensitive() {
+        let mut app = app_with_prompts(&["Hello World", "goodbye", "HELLO again"]);
+        app.filter_text = Some("hello".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 2]);
+    }
+
+    #[test]
+    fn filter_no_matches() {
+        let mut app = app_with_prompts(&["foo", "bar"]);
+        app.filter_text = Some("xyz".to_string());
+        app.rebuild_filter();
+        assert!(app.filtered_indices.is_empty());
+    }
+
+    #[test]
+    fn filter_partial_match() {
+        let mut app = app_with_prompts(&["refactor auth", "fix auth bug", "add tests"]);
+        app.filter_text = Some("auth".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn clamp_selection_when_filtered_out() {
+        let mut app = app_with_prompts(&["foo", "bar", "baz"]);
+        app.list_state.select(Some(1)); // "bar" selected
+        app.filter_text = Some("foo".to_string());
+        app.rebuild_filter();
+        app.clamp_selection_to_filter();
+        // "bar" is filtered out, selection should snap to first match
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn clamp_selection_empty_filter_result() {
+        let mut app = app_with_prompts(&["foo"]);
+        app.list_state.select(Some(0));
+        app.filter_text = Some("xyz".to_string());
+        app.rebuild_filter();
+        app.clamp_selection_to_filter();
+        assert!(app.list_state.selected().is_none());
+    }
+
+    // ── sort mode ──
+
+    #[test]
+    fn sort_by_status_reorders_view_only() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d"]);
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[1].status = PromptStatus::Running;
+        app.prompts[3].status = PromptStatus::Failed;
+        app.sort_mode = SortMode::Status;
+        app.rebuild_filter();
+
+        assert_eq!(app.filtered_indices, vec![1, 2, 3, 0]);
+        let ids: Vec<usize> = app.prompts.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn sort_by_id_combines_with_filter() {
+        let mut app = app_with_prompts(&["fix a", "docs", "fix b"]);
+        app.sort_mode = SortMode::Id;
+        app.filter_text = Some("fix".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![2, 0]);
+    }
+
+    #[test]
+    fn sort_by_elapsed_puts_never_run_last() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        let now = Instant::now();
+        app.prompts[1].started_at = Some(now - std::time::Duration::from_secs(5));
+        app.prompts[1].finished_at = Some(now);
+        app.prompts[2].started_at = Some(now - std::time::Duration::from_secs(60));
+        app.prompts[2].finished_at = Some(now);
+        app.sort_mode = SortMode::Elapsed;
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn cycle_sort_keeps_selected_prompt_and_navigates_in_view_order() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(1));
+        app.cycle_sort(); // status: all pending, queue order
+        app.cycle_sort(); // elapsed
+        app.cycle_sort(); // newest first
+        assert_eq!(app.sort_mode, SortMode::Id);
+        assert_eq!(app.selected_prompt().unwrap().id, 2);
+
+        app.select_next();
+        assert_eq!(app.selected_prompt().unwrap().id, 1);
+        app.select_first();
+        assert_eq!(app.selected_prompt().unwrap().id, 3);
+
+        app.cycle_sort();
+        assert_eq!(app.sort_mode, SortMode::Manual);
+        app.cycle_sort();
+        assert_eq!(app.sort_mode, SortMode::Queue);
+        assert_eq!(app.selected_prompt().unwrap().id, 3);
+    }
+
+    #[test]
+    fn manual_sort_moves_any_prompt_without_touching_queue() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.prompts_dir = Some(tmp.path().to_path_buf());
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[1].status = PromptStatus::Failed;
+        app.sort_mode = SortMode::Manual;
+        app.rebuild_filter();
+        let queue: Vec<f64> = app.prompts.iter().map(|p| p.queue_rank).collect();
+
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('J'));
+        press(&mut app, KeyCode::Char('J'));
+        assert_eq!(app.filtered_indices, vec![1, 2, 0]);
+        assert_eq!(app.selected_prompt().unwrap().id, 1);
+        // Bottom of the view: nothing to swap with
+        press(&mut app, KeyCode::Char('J'));
+        assert_eq!(app.filtered_indices, vec![1, 2, 0]);
+
+        assert_eq!(app.prompts.iter().map(|p| p.queue_rank).collect::<Vec<_>>(), queue);
+        assert_eq!(app.next_pending_prompt_index(), Some(2));
+        let saved = persistence::load_all_prompts(tmp.path());
+        let first = saved.iter().find(|(uuid, _)| *uuid == app.prompts[0].uuid).unwrap();
+        assert_eq!(first.1.display_rank, Some(app.prompts[0].display_rank));
+    }
+
+    #[test]
+    fn manual_sort_orders_batch_export() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.prompts[2].display_rank = 0.5;
+        app.selected_ids.extend([1, 3]);
+        let ids = |app: &App| app.selected_for_export().iter().map(|p| p.id).collect::<Vec<_>>();
+        assert_eq!(ids(&app), vec![1, 3]);
+        app.sort_mode = SortMode::Manual;
+        assert_eq!(ids(&app), vec![3, 1]);
+    }
+
+    #[test]
+    fn worktree_commit_result_sets_status() {
+        let mut app = app_with_prompts(&["a"]);
+        app.apply_message(WorkerMessage::WorktreeCommit {
+            prompt_id: 1,
+            result: Ok(Some("abc1234".to_string())),
+        });
+        assert!(app.status_message.as_ref().unwrap().0.contains("abc1234"));
+        app.apply_message(WorkerMessage::WorktreeCommit { prompt_id: 1, result: Ok(None) });
+        assert!(app.status_message.as_ref().unwrap().0.contains("nothing to commit"));
+        app.apply_message(WorkerMessage::WorktreeCommit {
+            prompt_id: 1,
+            result: Err("not a git worktree\n".to_string()),
+        });
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "#1: worktree commit failed: not a git worktree"
+        );
+    }
+
+    #[test]
+    fn autocommit_keeps_worktree_from_auto_cleanup() {
+        let mut app = app_with_prompts(&["a"]);
+        app.worktree_cleanup = WorktreeCleanup::Auto;
+        app.worktree_autocommit = true;
+        app.prompts[0].worktree_path = Some("/wt".to_string());
+        app.mark_running(0);
+        app.active_workers = 1;
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+        assert_eq!(app.prompts[0].worktree_path.as_deref(), Some("/wt"));
+
+        app.apply_message(WorkerMessage::WorktreeCommit {
+            prompt_id: 1,
+            result: Ok(Some("abc1234".to_string())),
+        });
+        assert_eq!(
+            app.status_message.as_ref().unwrap().0,
+            "#1: committed worktree changes as abc1234; kept /wt to apply"
+        );
+    }
+
+    #[test]
+    fn failed_prompt_keeps_worktree_without_autocommit() {
+        let mut app = app_with_prompts(&["a"]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.message_tx = Some(tx);
+        app.worktree_autocommit = true;
+        app.prompts[0].worktree_path = Some("/nonexistent/wt".to_string());
+        app.mark_running(0);
+        app.active_workers = 1;
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(2) });
+        // No commit thread is started for a failed prompt
+        assert!(rx.try_recv().is_err());
+        assert_eq!(app.prompts[0].worktree_path.as_deref(), Some("/nonexistent/wt"));
+    }
+
+    #[test]
+    fn status_sort_follows_worker_finish() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.sort_mode = SortMode::Status;
+        app.mark_running(1);
+        app.active_workers = 1;
+        assert_eq!(app.filtered_indices, vec![1, 0]);
+        app.apply_message(WorkerMessage::Finished { prompt_id: 2, exit_code: Some(0) });
+        assert_eq!(app.filtered_indices, vec![0, 1]);
+    }
+
+    fn app_with_tagged(tags: &[&[&str]]) -> App {
+        let mut app = new_test_app();
+        for (i, t) in tags.iter().enumerate() {
+            let tags = t.iter().map(|s| s.to_string()).collect();
+            app.add_prompt(format!("p{i}"), None, false, tags);
+        }
+        app
+    }
+
+    #[test]
+    fn grouped_view_clusters_by_first_tag() {
+        let mut app = app_with_tagged(&[&["fe"], &["be", "fe"], &["fe"], &[]]);
+        app.toggle_grouped();
+        assert_eq!(app.filtered_indices, vec![0, 2, 1, 3]);
+        let headers: Vec<(String, usize)> = app
+            .list_rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                ListRow::Group { tag, count, .. } => Some((tag, count)),
+                ListRow::Prompt(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            headers,
+            vec![("fe".to_string(), 2), ("be".to_string(), 1), (String::new(), 1)]
+        );
+
+        app.toggle_grouped();
+        assert_eq!(app.filtered_indices, vec![0, 1, 2, 3]);
+        assert!(app.list_rows().iter().all(|r| matches!(r, ListRow::Prompt(_))));
+    }
+
+    #[test]
+    fn folded_group_is_one_navigation_stop() {
+        let mut app = app_with_tagged(&[&["fe"], &["be"], &["fe"], &["be"]]);
+        press(&mut app, KeyCode::Char('T'));
+        app.list_state.select(Some(2));
+        press(&mut app, KeyCode::Char('z'));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.collapsed_tags.contains("fe"));
+        // Selection moves to the folded group's stand-in
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(
+            app.list_rows()[0],
+            ListRow::Group { tag: "fe".to_string(), count: 2, collapsed: true, first: 0 }
+        );
+
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(1));
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(3));
+        app.select_prev();
+        app.select_prev();
+        assert_eq!(app.list_state.selected(), Some(0));
+
+        press(&mut app, KeyCode::Char('z'));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.collapsed_tags.is_empty());
+        app.select_next();
+        assert_eq!(app.list_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn folded_group_header_does_not_act_on_hidden_prompt() {
+        let mut app = app_with_tagged(&[&["fe"], &["be"]]);
+        press(&mut app, KeyCode::Char('T'));
+        app.list_state.select(Some(0));
+        app.prompts[0].status = PromptStatus::Completed;
+        press(&mut app, KeyCode::Char('z'));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.selection_is_folded());
+        app.mark_selected_seen();
+        assert!(!app.prompts[0].seen);
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, AppMode::Normal);
+        press(&mut app, KeyCode::Char('d'));
+        assert!(!app.confirm_batch_delete);
+        assert!(app.selected_ids.is_empty());
+        let (msg, _) = app.status_message.clone().unwrap();
+        assert!(msg.contains("folded"));
+
+        press(&mut app, KeyCode::Char('z'));
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.confirm_batch_delete);
+    }
+
+    #[test]
+    fn fold_all_and_unfold_all() {
+        let mut app = app_with_tagged(&[&["fe"], &["be"], &[]]);
+        app.toggle_grouped();
+        app.list_state.select(Some(1));
+        app.fold_all_groups(true);
+        assert_eq!(app.list_rows().len(), 3);
+        assert_eq!(app.navigable_indices(), vec![0, 1, 2]);
+        app.fold_all_groups(false);
+        assert_eq!(app.list_rows().len(), 6);
+    }
+
+    #[test]
+    fn gg_sequence_goes_to_top() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(2));
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.pending_sequence, vec![KeyCombo::from(KeyCode::Char('g'))]);
+        assert_eq!(app.keymap.sequence_completions(&app.pending_sequence), vec![("g".to_string(), "top")]);
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert!(app.pending_sequence.is_empty());
+    }
+
+    #[test]
+    fn broken_sequence_handles_the_key_on_its_own() {
+        let mut app = app_with_prompts(&["a", "b", "c"]);
+        app.list_state.select(Some(2));
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('k'));
+        assert!(app.pending_sequence.is_empty());
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn esc_cancels_a_pending_sequence_only() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.list_state.select(Some(1));
+        app.selected_ids.insert(app.prompts[0].id);
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.pending_sequence.is_empty());
+        assert_eq!(app.selected_ids.len(), 1);
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn pending_sequence_times_out() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.list_state.select(Some(1));
+        press(&mut app, KeyCode::Char('g'));
+        app.expire_pending_sequence();
+        assert!(!app.pending_sequence.is_empty());
+
+        app.pending_sequence_at = Some(Instant::now() - SEQUENCE_TIMEOUT);
+        app.expire_pending_sequence();
+        assert!(app.pending_sequence.is_empty());
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn single_key_binding_shadows_sequence_prefix() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        app.keymap.normal.insert(KeyCode::Char('g').into(), NormalAction::GoToBottom);
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Char('g'));
+        assert!(app.pending_sequence.is_empty());
+        assert_eq!(app.list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn z_prefix_ignored_when_not_grouped() {
+        let mut app = app_with_tagged(&[&["fe"], &["fe"]]);
+        app.list_state.select(Some(1));
+        press(&mut app, KeyCode::Char('z'));
+        assert!(app.pending_sequence.is_empty());
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.collapsed_tags.is_empty());
+    }
+
+    // ── tag filtering ──
+
+    #[test]
+    fn filter_by_tag() {
+        let mut app = new_test_app();
+        app.add_prompt("Fix navbar".to_string(), None, false, vec!["frontend".to_string()]);
+        app.add_prompt("Fix API".to_string(), None, false, vec!["backend".to_string()]);
+        app.add_prompt("Fix styles".to_string(), None, false, vec!["frontend".to_string()]);
+        app.filter_text = Some("@frontend".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 2]);
+    }
+
+    #[test]
+    fn filter_by_tag_and_text() {
+        let mut app = new_test_app();
+        app.add_prompt("Fix navbar".to_string(), None, false, vec!["frontend".to_string()]);
+        app.add_prompt("Fix styles".to_string(), None, false, vec!["frontend".to_string()]);
+        app.add_prompt("Fix API".to_string(), None, false, vec!["backend".to_string()]);
+        app.filter_text = Some("@frontend navbar".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0]);
     }
 
     #[test]
-    fn move_up_at_start_is_noop() {
-        let mut app = app_with_prompts(&["a", "b"]);
-        app.list_state.select(Some(0));
-
-        app.move_selected_up();
-        assert_eq!(app.prompts[0].text, "a");
-        assert_eq!(app.list_state.selected(), Some(0));
+    fn filter_regex_matches() {
+        let mut app = app_with_prompts(&["fix login bug", "fix the logout", "add Login page"]);
+        app.filter_text = Some("/^(fix|add) login/".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 2]);
+        assert!(!app.filter_regex_invalid);
     }
 
     #[test]
-    fn move_non_pending_is_noop() {
-        let mut app = app_with_prompts(&["a", "b"]);
-        app.prompts[0].status = PromptStatus::Running;
-        app.list_state.select(Some(0));
+    fn filter_invalid_regex_falls_back_to_substring() {
+        let mut app = app_with_prompts(&["match (a", "other"]);
+        app.filter_text = Some("/(a/".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0]);
+        assert!(app.filter_regex_invalid);
 
-        app.move_selected_down();
-        assert_eq!(app.prompts[0].text, "a");
-        assert_eq!(app.prompts[1].text, "b");
+        app.filter_text = Some("other".to_string());
+        app.rebuild_filter();
+        assert!(!app.filter_regex_invalid);
     }
 
     #[test]
-    fn move_no_selection_is_noop() {
-        let mut app = app_with_prompts(&["a", "b"]);
-        app.list_state.select(None);
-        app.move_selected_up(); // should not panic
-        app.move_selected_down(); // should not panic
+    fn filter_regex_with_tag() {
+        let mut app = app_with_prompts(&["fix api 1", "fix api 2", "fix ui 3"]);
+        app.prompts[0].tags = vec!["backend".to_string()];
+        app.prompts[2].tags = vec!["backend".to_string()];
+        app.filter_text = Some("@backend /fix (api|ui) \\d/".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 2]);
     }
 
-    // ── retry_selected ──
-
     #[test]
-    fn retry_completed_creates_new_prompt() {
-        let mut app = app_with_prompts(&["hello world"]);
+    fn filter_by_status_and_tag() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d"]);
         app.prompts[0].status = PromptStatus::Completed;
-        app.prompts[0].cwd = Some("/tmp".to_string());
-        app.list_state.select(Some(0));
-
-        app.retry_selected();
-
-        assert_eq!(app.prompts.len(), 2);
-        let retried = &app.prompts[1];
-        assert_eq!(retried.text, "hello world");
-        assert_eq!(retried.cwd, Some("/tmp".to_string()));
-        assert_eq!(retried.status, PromptStatus::Pending);
-        assert!(retried.id > app.prompts[0].id);
+        app.prompts[0].tags = vec!["frontend".to_string()];
+        app.prompts[1].status = PromptStatus::Completed;
+        app.prompts[2].tags = vec!["frontend".to_string()];
+        app.filter_text = Some("status:completed @frontend".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0]);
     }
 
     #[test]
-    fn retry_failed_creates_new_prompt() {
-        let mut app = app_with_prompts(&["fail"]);
+    fn filter_status_tokens_or_together() {
+        let mut app = app_with_prompts(&["fix a", "fix b", "fix c"]);
         app.prompts[0].status = PromptStatus::Failed;
-        app.list_state.select(Some(0));
-
-        app.retry_selected();
-        assert_eq!(app.prompts.len(), 2);
-        assert_eq!(app.prompts[1].status, PromptStatus::Pending);
+        app.prompts[1].status = PromptStatus::Running;
+        app.filter_text = Some("is:failed status:Running fix".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0, 1]);
     }
 
     #[test]
-    fn retry_running_is_noop() {
-        let mut app = app_with_prompts(&["running"]);
-        app.prompts[0].status = PromptStatus::Running;
-        app.list_state.select(Some(0));
-
-        app.retry_selected();
-        assert_eq!(app.prompts.len(), 1);
+    fn filter_unknown_status_is_text() {
+        let mut app = app_with_prompts(&["status:done", "other"]);
+        app.filter_text = Some("status:done".to_string());
+        app.rebuild_filter();
+        assert_eq!(app.filtered_indices, vec![0]);
     }
 
     #[test]
-    fn retry_pending_is_noop() {
-        let mut app = app_with_prompts(&["pending"]);
-        app.list_state.select(Some(0));
+    fn filter_expands_saved_name() {
+        let mut app = app_with_prompts(&["fix a", "fix b"]);
+        app.prompts[1].status = PromptStatus::Failed;
+        app.saved_filters.insert("bad".to_string(), "status:failed".to_string());
+        press(&mut app, KeyCode::Char('/'));
+        for c in "!bad fix".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.filter_text.as_deref(), Some("status:failed fix"));
+        assert_eq!(app.filtered_indices, vec![1]);
+    }
 
-        app.retry_selected();
-        assert_eq!(app.prompts.len(), 1);
+    fn dir_tree(dirs: &[&str]) -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        for dir in dirs {
+            fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        }
+        fs::write(tmp.path().join("src/config.rs"), "").unwrap();
+        tmp
     }
 
     #[test]
-    fn retry_no_selection_is_noop() {
-        let mut app = app_with_prompts(&["test"]);
-        app.prompts[0].status = PromptStatus::Completed;
-        app.list_state.select(None);
-
-        app.retry_selected();
-        assert_eq!(app.prompts.len(), 1);
+    fn dir_suggestions_expand_tilde() {
+        let home = dir_tree(&["Downloads", "Documents", "src"]);
+        assert_eq!(dir_suggestions("~/Dow", Some(home.path())), vec!["~/Downloads"]);
+        assert_eq!(dir_suggestions("~", Some(home.path())), vec!["~/src", "~/Documents", "~/Downloads"]);
     }
 
-    // ── rebuild_filter ──
-
     #[test]
-    fn filter_no_text_includes_all() {
-        let mut app = app_with_prompts(&["foo", "bar", "baz"]);
-        app.filter_text = None;
-        app.rebuild_filter();
-        assert_eq!(app.filtered_indices, vec![0, 1, 2]);
+    fn dir_suggestions_complete_nested_path() {
+        let tmp = dir_tree(&["src/commands", "src/core/deep"]);
+        let base = tmp.path().to_string_lossy().to_string();
+        // Files are never suggested
+        assert_eq!(
+            dir_suggestions(&format!("{base}/src/co"), None),
+            vec![format!("{base}/src/core"), format!("{base}/src/commands")]
+        );
+        assert_eq!(dir_suggestions(&format!("{base}/src/core/"), None), vec![format!("{base}/src/core/deep")]);
     }
 
     #[test]
-    fn filter_matches_case_insensitive() {
-        let mut app = app_with_prompts(&["Hello World", "goodbye", "HELLO again"]);
-        app.filter_text = Some("hello".to_string());
-        app.rebuild_filter();
-        assert_eq!(app.filtered_indices, vec![0, 2]);
+    fn dir_suggestions_missing_parent_is_empty() {
+        let tmp = dir_tree(&["src"]);
+        let base = tmp.path().to_string_lossy().to_string();
+        assert!(dir_suggestions(&format!("{base}/nope/sr"), None).is_empty());
+        assert!(dir_suggestions("~/src", None).is_empty());
+    }
+
+    fn ranked(names: &[&str], query: &str) -> Vec<String> {
+        let candidates = names.iter().map(|n| (n.to_string(), n.to_string())).collect();
+        rank_fuzzy(candidates, query)
     }
 
+    const DIRS: &[&str] = &["Downloads", "Documents", "dev", "node_modules", "down", "Desktop", "src"];
+
     #[test]
-    fn filter_no_matches() {
-        let mut app = app_with_prompts(&["foo", "bar"]);
-        app.filter_text = Some("xyz".to_string());
-        app.rebuild_filter();
-        assert!(app.filtered_indices.is_empty());
+    fn fuzzy_rank_finds_subsequences() {
+        assert_eq!(ranked(DIRS, "dwn"), vec!["down", "Downloads"]);
+        assert_eq!(fuzzy_rank("Downloads", "dwn"), Some((2, 1)));
+        assert_eq!(fuzzy_rank("src", "dwn"), None);
     }
 
     #[test]
-    fn filter_partial_match() {
-        let mut app = app_with_prompts(&["refactor auth", "fix auth bug", "add tests"]);
-        app.filter_text = Some("auth".to_string());
-        app.rebuild_filter();
-        assert_eq!(app.filtered_indices, vec![0, 1]);
+    fn fuzzy_rank_puts_prefixes_first() {
+        assert_eq!(
+            ranked(DIRS, "do"),
+            vec!["down", "Documents", "Downloads", "Desktop", "node_modules"]
+        );
     }
 
     #[test]
-    fn clamp_selection_when_filtered_out() {
-        let mut app = app_with_prompts(&["foo", "bar", "baz"]);
-        app.list_state.select(Some(1)); // "bar" selected
-        app.filter_text = Some("foo".to_string());
-        app.rebuild_filter();
-        app.clamp_selection_to_filter();
-        // "bar" is filtered out, selection should snap to first match
-        assert_eq!(app.list_state.selected(), Some(0));
+    fn fuzzy_rank_prefers_tight_early_matches() {
+        assert!(fuzzy_rank("my-docs", "md").unwrap() < fuzzy_rank("my-old-docs", "md").unwrap());
+        assert!(fuzzy_rank("xdocs", "dc").unwrap() < fuzzy_rank("xxdocs", "dc").unwrap());
     }
 
     #[test]
-    fn clamp_selection_empty_filter_result() {
-        let mut app = app_with_prompts(&["foo"]);
-        app.list_state.select(Some(0));
-        app.filter_text = Some("xyz".to_string());
-        app.rebuild_filter();
-        app.clamp_selection_to_filter();
-        assert!(app.list_state.selected().is_none());
+    fn fuzzy_rank_empty_query_matches_all() {
+        assert_eq!(ranked(&["b", "a"], ""), vec!["a", "b"]);
     }
 
-    // ── tag filtering ──
+    #[test]
+    fn template_vars_expand_cwd_date_and_branch() {
+        let vars = [
+            ("cwd", Some("/work/app".to_string())),
+            ("date", Some("2026-10-16".to_string())),
+            ("branch", Some("main".to_string())),
+        ];
+        assert_eq!(expand_template_vars("In {cwd}", &vars), "In /work/app");
+        assert_eq!(expand_template_vars("Log for {date}", &vars), "Log for 2026-10-16");
+        assert_eq!(expand_template_vars("on {branch}, {branch}", &vars), "on main, main");
+    }
 
     #[test]
-    fn filter_by_tag() {
-        let mut app = new_test_app();
-        app.add_prompt("Fix navbar".to_string(), None, false, vec!["frontend".to_string()]);
-        app.add_prompt("Fix API".to_string(), None, false, vec!["backend".to_string()]);
-        app.add_prompt("Fix styles".to_string(), None, false, vec!["frontend".to_string()]);
-        app.filter_text = Some("@frontend".to_string());
-        app.rebuild_filter();
-        assert_eq!(app.filtered_indices, vec![0, 2]);
+    fn template_vars_leave_unknown_and_missing_literal() {
+        let vars = [("cwd", Some("/w".to_string())), ("branch", None)];
+        assert_eq!(
+            expand_template_vars("{cwd} {branch} {file} {CWD}", &vars),
+            "/w {branch} {file} {CWD}"
+        );
     }
 
     #[test]
-    fn filter_by_tag_and_text() {
-        let mut app = new_test_app();
-        app.add_prompt("Fix navbar".to_string(), None, false, vec!["frontend".to_string()]);
-        app.add_prompt("Fix styles".to_string(), None, false, vec!["frontend".to_string()]);
-        app.add_prompt("Fix API".to_string(), None, false, vec!["backend".to_string()]);
-        app.filter_text = Some("@frontend navbar".to_string());
-        app.rebuild_filter();
-        assert_eq!(app.filtered_indices, vec![0]);
+    fn split_save_token_parses_trailing_name() {
+        assert_eq!(split_save_token("@a status:failed >bf"), ("@a status:failed", Some("bf")));
+        assert_eq!(split_save_token(">bf"), ("", Some("bf")));
+        assert_eq!(split_save_token("fix a>b"), ("fix a>b", None));
     }
 
     #[test]
@@ -2376,6 +7681,69 @@ mod tests {
         assert!(app.history_index.is_none());
     }
 
+    fn ctrl_r(app: &mut App) {
+        app.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    }
+
+    fn history_search_app() -> App {
+        let mut app = new_test_app();
+        app.history = vec![
+            "fix the login bug".to_string(),
+            "write tests".to_string(),
+            "Fix the navbar".to_string(),
+        ];
+        press(&mut app, KeyCode::Char('i'));
+        app.input.set("draft");
+        ctrl_r(&mut app);
+        app
+    }
+
+    #[test]
+    fn history_search_shows_newest_match() {
+        let mut app = history_search_app();
+        assert!(app.history_search.is_some());
+        for c in "fix".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.input, "Fix the navbar");
+        press(&mut app, KeyCode::Char('z'));
+        // No match for "fixz": the last match stays visible
+        assert_eq!(app.history_search.as_ref().unwrap().matched, None);
+        assert_eq!(app.input, "Fix the navbar");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.history_search.as_ref().unwrap().matched, Some(2));
+    }
+
+    #[test]
+    fn history_search_ctrl_r_cycles_older_matches() {
+        let mut app = history_search_app();
+        for c in "fix".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        ctrl_r(&mut app);
+        assert_eq!(app.input, "fix the login bug");
+        // Oldest match reached: stays put
+        ctrl_r(&mut app);
+        assert_eq!(app.input, "fix the login bug");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.history_search.is_none());
+        assert_eq!(app.mode, AppMode::Insert);
+        assert_eq!(app.input, "fix the login bug");
+    }
+
+    #[test]
+    fn history_search_esc_restores_input() {
+        let mut app = history_search_app();
+        for c in "tests".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.input, "write tests");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.history_search.is_none());
+        assert_eq!(app.mode, AppMode::Insert);
+        assert_eq!(app.input, "draft");
+    }
+
     // ── parse_cwd_prefix ──
 
     #[test]
@@ -2416,10 +7784,12 @@ mod tests {
 
         app.apply_message(WorkerMessage::OutputChunk {
             prompt_id: 1,
+            stream: OutputStream::Stdout,
             text: "hello ".to_string(),
         });
         app.apply_message(WorkerMessage::OutputChunk {
             prompt_id: 1,
+            stream: OutputStream::Stdout,
             text: "world".to_string(),
         });
 
@@ -2491,12 +7861,166 @@ mod tests {
 
         app.apply_message(WorkerMessage::OutputChunk {
             prompt_id: 1,
+            stream: OutputStream::Stdout,
             text: "more".to_string(),
         });
 
         assert_eq!(app.prompts[0].status, PromptStatus::Running);
     }
 
+    fn quick_prompt_app() -> (App, mpsc::UnboundedReceiver<WorkerInput>) {
+        let mut app = app_with_prompts(&["test"]);
+        let (tx, rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.prompts[0].status = PromptStatus::Idle;
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+        app.keymap.quick_prompts.insert(KeyCode::Char('c').into(), "continue".to_string());
+        app.keymap.quick_prompts.insert(KeyCode::Char('r').into(), "review the diff".to_string());
+        app.keymap.quick_prompts.insert(KeyCode::Char('t').into(), "run the tests".to_string());
+        app.handle_key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        (app, rx)
+    }
+
+    #[test]
+    fn quick_prompt_popup_navigates_and_sends() {
+        let (mut app, mut rx) = quick_prompt_app();
+        assert!(app.show_quick_prompts_popup);
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.quick_prompt_index, 2);
+        press(&mut app, KeyCode::Char('k'));
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.show_quick_prompts_popup);
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "review the diff\n"));
+    }
+
+    #[test]
+    fn quick_prompt_popup_filters_by_typing() {
+        let (mut app, mut rx) = quick_prompt_app();
+        for c in "TES".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.quick_prompt_matches(), vec![("t".to_string(), "run the tests".to_string())]);
+        // With a filter typed, j is text rather than movement
+        press(&mut app, KeyCode::Char('j'));
+        assert!(app.quick_prompt_matches().is_empty());
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "run the tests\n"));
+    }
+
+    #[test]
+    fn quick_prompt_popup_esc_closes_without_leaving_view() {
+        let (mut app, mut rx) = quick_prompt_app();
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.show_quick_prompts_popup);
+        assert_eq!(app.mode, AppMode::ViewOutput);
+        // Closed again, single-key shortcuts still fire
+        press(&mut app, KeyCode::Char('c'));
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "continue\n"));
+    }
+
+    #[test]
+    fn quick_prompt_fans_out_to_batch_selection() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d"]);
+        let (tx1, mut rx1) = mpsc::unbounded_channel();
+        let (tx2, mut rx2) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx1);
+        app.worker_inputs.insert(2, tx2);
+        app.prompts[0].status = PromptStatus::Idle;
+        app.prompts[1].status = PromptStatus::Idle;
+        // #3 finished, #4 running without an input channel: both skipped
+        app.prompts[2].status = PromptStatus::Completed;
+        app.prompts[3].status = PromptStatus::Running;
+        app.keymap.quick_prompts.insert(KeyCode::F(5).into(), "continue".to_string());
+        app.selected_ids.extend([1, 2, 3, 4]);
+
+        press(&mut app, KeyCode::F(5));
+        assert!(matches!(rx1.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "continue\n"));
+        assert!(matches!(rx2.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "continue\n"));
+        assert!(app.selected_ids.is_empty());
+        assert_eq!(app.status_message.as_ref().map(|(m, _)| m.as_str()), Some("Sent to 2 agents"));
+    }
+
+    #[test]
+    fn quick_prompt_key_is_ignored_without_selection() {
+        let mut app = app_with_prompts(&["a"]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.prompts[0].status = PromptStatus::Idle;
+        app.keymap.quick_prompts.insert(KeyCode::F(5).into(), "continue".to_string());
+        press(&mut app, KeyCode::F(5));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn stream_input_queued_while_running() {
+        let mut app = app_with_prompts(&["test"]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.prompts[0].status = PromptStatus::Running;
+
+        app.send_stream_input(0, "first".to_string());
+        app.send_stream_input(0, "second".to_string());
+
+        assert_eq!(app.prompts[0].queued_inputs.len(), 2);
+        assert!(rx.try_recv().is_err());
+        assert!(app.prompts[0].output.is_none());
+    }
+
+    #[test]
+    fn queued_input_flushed_one_per_turn() {
+        let mut app = app_with_prompts(&["test"]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.prompts[0].status = PromptStatus::Running;
+        app.send_stream_input(0, "first".to_string());
+        app.send_stream_input(0, "second".to_string());
+
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "first\n"));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(app.prompts[0].status, PromptStatus::Running);
+        assert_eq!(app.prompts[0].queued_inputs.len(), 1);
+        assert!(app.prompts[0].output.as_deref().unwrap().contains("> first"));
+
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "second\n"));
+        app.apply_message(WorkerMessage::TurnComplete { prompt_id: 1 });
+        assert!(rx.try_recv().is_err());
+        assert_eq!(app.prompts[0].status, PromptStatus::Idle);
+    }
+
+    #[test]
+    fn stream_input_sent_immediately_when_idle() {
+        let mut app = app_with_prompts(&["test"]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.prompts[0].status = PromptStatus::Idle;
+
+        app.send_stream_input(0, "go".to_string());
+
+        assert!(matches!(rx.try_recv(), Ok(WorkerInput::SendInput(t)) if t == "go\n"));
+        assert!(app.prompts[0].queued_inputs.is_empty());
+        assert_eq!(app.prompts[0].status, PromptStatus::Running);
+    }
+
+    #[test]
+    fn queued_input_dropped_on_finish() {
+        let mut app = app_with_prompts(&["test"]);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        app.worker_inputs.insert(1, tx);
+        app.prompts[0].status = PromptStatus::Running;
+        app.active_workers = 1;
+        app.send_stream_input(0, "later".to_string());
+
+        app.apply_message(WorkerMessage::Finished { prompt_id: 1, exit_code: Some(0) });
+
+        assert!(app.prompts[0].queued_inputs.is_empty());
+    }
+
     // ── select_first / select_last ──
 
     #[test]
@@ -2600,8 +8124,82 @@ mod tests {
         assert_eq!(app.list_state.selected(), Some(5));
     }
 
+    #[test]
+    fn attention_summary_counts_prompts_needing_the_user() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d", "e", "f", "g"]);
+        app.prompts[0].status = PromptStatus::Running;
+        app.prompts[1].status = PromptStatus::Idle;
+        app.prompts[2].status = PromptStatus::Idle;
+        app.prompts[3].status = PromptStatus::Completed;
+        app.prompts[4].status = PromptStatus::Failed;
+        app.prompts[5].status = PromptStatus::Failed;
+        app.prompts[5].seen = true;
+
+        let summary = app.attention_summary();
+        assert_eq!(summary, AttentionSummary { running: 1, idle: 2, unseen: 2, failed_unseen: 1 });
+        assert_eq!(summary.label(), "1 running · 2 idle · 2 unseen (1 failed)");
+        assert_eq!(AttentionSummary::default().label(), "");
+    }
+
+    #[test]
+    fn title_is_only_set_when_the_counts_change() {
+        let mut app = app_with_prompts(&["a", "b"]);
+        assert_eq!(app.take_title_update(), None, "off by default");
+
+        app.terminal_title = true;
+        assert_eq!(app.take_title_update().as_deref(), Some("clhorde"));
+        assert_eq!(app.take_title_update(), None);
+
+        app.prompts[0].status = PromptStatus::Running;
+        assert_eq!(app.take_title_update().as_deref(), Some("clhorde · 1 running"));
+        assert_eq!(app.take_title_update(), None);
+    }
+
     // ── filtered navigation for new methods ──
 
+    #[test]
+    fn n_jumps_to_the_next_unseen_prompt_and_wraps() {
+        let mut app = app_with_prompts(&["a", "b", "c", "d", "e"]);
+        for i in [0, 1, 3] {
+            app.prompts[i].status = PromptStatus::Completed;
+        }
+        app.prompts[1].seen = true;
+        app.prompts[4].status = PromptStatus::Failed;
+        app.list_state.select(Some(1));
+        assert_eq!(app.attention_summary().unseen, 3);
+
+        // Skips the seen #2 and the pending #3
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.list_state.selected(), Some(3));
+        assert!(app.prompts[3].seen);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.list_state.selected(), Some(4));
+        // Wraps past the end
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.attention_summary().unseen, 0);
+
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.status_message.as_ref().unwrap().0, "No unseen prompts");
+    }
+
+    #[test]
+    fn next_unseen_follows_the_filtered_list() {
+        let mut app = app_with_prompts(&["foo", "bar", "foo2", "foo3"]);
+        for prompt in &mut app.prompts {
+            prompt.status = PromptStatus::Completed;
+        }
+        app.filter_text = Some("foo".to_string());
+        app.rebuild_filter();
+        app.list_state.select(Some(2));
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.list_state.selected(), Some(3));
+        // "bar" is filtered out, so the wrap lands on "foo"
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.list_state.selected(), Some(0));
+    }
+
     #[test]
     fn select_first_with_filter() {
         let mut app = app_with_prompts(&["foo", "bar", "foo2", "baz", "foo3"]);
@@ -2639,4 +8237,39 @@ mod tests {
         // jump 10 positions in filtered list: pos 10 → index 20
         assert_eq!(app.list_state.selected(), Some(20));
     }
+
+    // ── PTY sizing ──
+
+    #[test]
+    fn only_the_viewed_pty_follows_the_panel_size() {
+        let mut app = new_test_app();
+        let default = pty_worker::DEFAULT_PTY_SIZE;
+        app.pty_sizes.insert(1, default);
+        app.pty_sizes.insert(2, default);
+        app.output_panel_size = Some((100, 30));
+        app.viewed_pty = Some(1);
+
+        assert_eq!(app.pending_pty_resizes(), vec![(1, (100, 30))]);
+        app.sync_pty_sizes();
+        assert_eq!(app.pty_sizes[&1], (100, 30));
+        assert_eq!(app.pty_sizes[&2], default);
+
+        // Viewing another prompt hands the panel size over
+        app.viewed_pty = Some(2);
+        app.sync_pty_sizes();
+        assert_eq!(app.pty_sizes[&1], default);
+        assert_eq!(app.pty_sizes[&2], (100, 30));
+        assert!(app.pending_pty_resizes().is_empty());
+    }
+
+    #[test]
+    fn unviewed_pty_spawns_at_the_default_size() {
+        let mut app = new_test_app();
+        app.output_panel_size = Some((100, 30));
+        assert_eq!(app.pty_size_for(3), pty_worker::DEFAULT_PTY_SIZE);
+        app.viewed_pty = Some(3);
+        assert_eq!(app.pty_size_for(3), (100, 30));
+        app.output_panel_size = Some((0, 0));
+        assert_eq!(app.pty_size_for(3), pty_worker::DEFAULT_PTY_SIZE);
+    }
 }
@@ -1,13 +1,17 @@
-use std::collections::HashMap;
-
-use crossterm::event::KeyCode;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
 
 use crate::keymap::{
-    self, FilterAction, InsertAction, InteractAction, Keymap, NormalAction, TomlConfig,
-    TomlFilterBindings, TomlInsertBindings, TomlInteractBindings, TomlNormalBindings,
-    TomlViewBindings, ViewAction,
+    self, FilterAction, InsertAction, InteractAction, KeyCombo, Keymap, NormalAction,
+    QuickPromptConflict, TomlConfig, TomlFilterBindings, TomlInsertBindings, TomlInteractBindings,
+    TomlNormalBindings, TomlViewBindings, ViewAction,
 };
-use crate::persistence;
+use crate::filters;
+use crate::inbox::{self, SubmitRequest};
+use crate::persistence::{self, PromptFile, PromptOptions};
+use crate::process;
+use crate::prompt::{format_duration, normalize_tag, PromptPriority};
+use crate::theme;
 use crate::worktree;
 
 pub struct LaunchOptions {
@@ -28,10 +32,14 @@ pub fn run(args: &[String]) -> CliAction {
     match cmd {
         "help" | "--help" | "-h" => CliAction::Exit(cmd_help()),
         "qp" => CliAction::Exit(cmd_qp(&args[2..])),
+        "filters" => CliAction::Exit(cmd_filters(&args[2..])),
         "keys" => CliAction::Exit(cmd_keys(&args[2..])),
         "config" => CliAction::Exit(cmd_config(&args[2..])),
         "store" => CliAction::Exit(cmd_store(&args[2..])),
         "prompt-from-files" => cmd_prompt_from_files(&args[2..]),
+        "submit" => CliAction::Exit(cmd_submit(&args[2..])),
+        "tail" => CliAction::Exit(cmd_tail(&args[2..])),
+        "tui" => CliAction::Exit(cmd_tui(&args[2..])),
         _ => CliAction::LaunchTui(LaunchOptions { prompts: vec![], worktree: false, run_path: None }),
     }
 }
@@ -48,13 +56,24 @@ fn cmd_help() -> i32 {
     println!("    list              List all stored prompts");
     println!("    count             Show prompt counts by state");
     println!("    path              Print storage directory path");
-    println!("    drop <filter>     Delete stored prompts");
+    println!("    drop <filter>     Delete stored prompts (--id UUID, --tag NAME narrow it)");
     println!("    keep <filter>     Keep only matching, delete rest");
-    println!("    clean-worktrees   Remove lingering git worktrees");
+    println!("    worktrees         List recorded worktree paths and whether they exist");
+    println!("    clean-worktrees [--dry-run]");
+    println!("                      Remove lingering git worktrees (--dry-run: only list them)");
+    println!("    export <file> [--format md|json]");
+    println!("                      Write all stored prompts to a file");
+    println!("    import <file.json>  Add prompts from a JSON array as pending");
+    println!("    archive <list|path> Browse prompts pruned into archive_dir");
     println!("  qp                  Manage quick prompts");
     println!("    list              List all quick prompts");
     println!("    add <key> <msg>   Add a quick prompt");
     println!("    remove <key>      Remove a quick prompt");
+    println!("  filters             Manage saved filters (recalled with !name)");
+    println!("    list              List saved filters");
+    println!("    add <name> <filter...>");
+    println!("                      Save a filter");
+    println!("    remove <name>     Remove a saved filter");
     println!("  keys                Manage keybindings");
     println!("    list [mode]       List keybindings (all or by mode)");
     println!("    set <mode> <action> <key1...>");
@@ -65,6 +84,21 @@ fn cmd_help() -> i32 {
     println!("    path              Print config file path");
     println!("    edit              Open config in $EDITOR");
     println!("    init [--force]    Create config with defaults");
+    println!("  submit [--cwd <dir>] [--worktree] [--mode one-shot|interactive] [--depends-on <id>] [--max-attempts <n>] [--timeout <secs>] [--resume-session <id>] [--] <text>");
+    println!("                      Queue a prompt in the running TUI and print its id");
+    println!("  submit --batch <file|->  Queue a JSON array of {{text, cwd, mode, worktree, tags, env}} specs,");
+    println!("                      printing one id per entry in input order");
+    println!("  tail <id> [--follow] [--since-byte <n>]");
+    println!("                        Stream a prompt's output from the running TUI");
+    println!("                      Exits with the prompt's exit code (--follow keeps going)");
+    println!("  tui                 Inspect the running TUI (exit 3 if none is running)");
+    println!("    status            Show pid, workers and prompt counts");
+    println!("    stats             Show completed/failed/killed totals and average run time");
+    println!("    stop              Quit it, terminating its workers");
+    println!("    kill-all          Kill its running workers, keeping queued prompts");
+    println!("    drain [--quit]    Stop dispatching; with --quit, exit once workers finish");
+    println!("    signal <id> [INT|TSTP|QUIT]");
+    println!("                      Signal a PTY worker's process group (default: INT)");
     println!("  prompt-from-files [--run-path <path>] <files...>");
     println!("                      Load prompts from files and launch TUI");
     println!("                      Each prompt runs in its own git worktree");
@@ -73,16 +107,25 @@ fn cmd_help() -> i32 {
     println!("Modes: normal, insert, view, interact, filter");
     println!();
     println!("Filters for drop/keep: all, completed, failed, pending");
+    println!("drop also takes --id UUID and --tag NAME (repeatable; a unique UUID prefix is enough)");
     println!();
     println!("Examples:");
     println!("  clhorde store list");
     println!("  clhorde store drop all");
     println!("  clhorde store drop failed");
-    println!("  clhorde store keep completed");
-    println!("  clhorde qp add g \"let's go\"");
+    println!("  clhorde store drop --id 0192f3a1 --id 0192f3b7");
+    println!("  clhorde store keep completed --yes");
+    println!("  clhorde store export session.md --format md");
+    println!("  clhorde store import tasks.json");
+    println!("  clhorde qp add L \"let's go\"");
+    println!("  clhorde filters add bf @backend status:failed");
     println!("  clhorde keys set normal quit Q");
     println!("  clhorde keys list normal");
     println!("  clhorde config init");
+    println!("  clhorde submit --mode one-shot \"run the test suite\"");
+    println!("  clhorde submit --batch - < prompts.json");
+    println!("  clhorde tail 3");
+    println!("  clhorde tui status");
     println!("  clhorde prompt-from-files tasks/*.md");
     println!("  clhorde prompt-from-files --run-path /tmp/myproject tasks/*.md");
     println!("  clhorde prompt-from-files a.txt,b.txt c.txt");
@@ -98,9 +141,9 @@ fn cmd_qp(args: &[String]) -> i32 {
         Some("remove") => qp_remove(&args[1..]),
         _ => {
             eprintln!("Usage: clhorde qp <list|add|remove>");
-            eprintln!("  list              List all quick prompts");
-            eprintln!("  add <key> <msg>   Add a quick prompt");
-            eprintln!("  remove <key>      Remove a quick prompt");
+            eprintln!("  list                      List all quick prompts");
+            eprintln!("  add [--yes] <key> <msg>   Add a quick prompt; --yes skips the key conflict check");
+            eprintln!("  remove <key>              Remove a quick prompt");
             1
         }
     }
@@ -110,10 +153,28 @@ fn qp_list() -> i32 {
     let config = keymap::load_toml_config();
     match config.quick_prompts {
         Some(ref qp) if !qp.is_empty() => {
+            let km = Keymap::load();
             let mut entries: Vec<_> = qp.iter().collect();
-            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            entries.sort_by_key(|(a, _)| *a);
+            let conflicts = km.quick_prompt_conflicts();
             for (key, message) in entries {
-                println!("{key} = \"{message}\"");
+                let kc = keymap::parse_key(key);
+                let shadows: Vec<String> = conflicts
+                    .iter()
+                    .filter(|(c, _)| Some(*c) == kc)
+                    .map(|(_, conflict)| match conflict {
+                        QuickPromptConflict::View(action) => format!("view action {}", view_action_name(*action)),
+                        QuickPromptConflict::Normal(action) => {
+                            format!("normal action {}", keymap::normal_label(*action))
+                        }
+                        QuickPromptConflict::Sequence => "a normal-mode sequence".to_string(),
+                    })
+                    .collect();
+                if shadows.is_empty() {
+                    println!("{key} = \"{message}\"");
+                } else {
+                    println!("{key} = \"{message}\"  # conflict: shadowed by {}", shadows.join(", "));
+                }
             }
         }
         _ => println!("No quick prompts configured."),
@@ -122,18 +183,39 @@ fn qp_list() -> i32 {
 }
 
 fn qp_add(args: &[String]) -> i32 {
+    let (yes, args) = match args.first().map(|s| s.as_str()) {
+        Some("--yes" | "-y") => (true, &args[1..]),
+        _ => (false, args),
+    };
     if args.len() < 2 {
-        eprintln!("Usage: clhorde qp add <key> <message...>");
+        eprintln!("Usage: clhorde qp add [--yes] <key> <message...>");
         return 1;
     }
     let key_str = &args[0];
-    if keymap::parse_key(key_str).is_none() {
+    let Some(kc) = keymap::parse_key(key_str) else {
         eprintln!("Invalid key: {key_str}");
-        eprintln!("Valid keys: single characters (a-z, A-Z, 0-9, symbols) or Enter, Esc, Tab, Space, Up, Down, Left, Right, Backspace");
+        eprintln!("Valid keys: single characters (a-z, A-Z, 0-9, symbols) or Enter, Esc, Tab, Space, Up, Down, Left, Right, Backspace, with optional C-/A-/S- prefixes (C-x, A-Enter)");
         return 1;
-    }
+    };
     let message = args[1..].join(" ");
 
+    // Other bindings win, so the quick prompt would never fire there
+    let mut km = Keymap::load();
+    km.quick_prompts.insert(kc, message.clone());
+    let conflicts: Vec<QuickPromptConflict> = km
+        .quick_prompt_conflicts()
+        .into_iter()
+        .filter(|(c, _)| *c == kc)
+        .map(|(_, conflict)| conflict)
+        .collect();
+    for conflict in &conflicts {
+        eprintln!("Warning: {key_str} is also {}.", conflict_description(*conflict));
+    }
+    if !conflicts.is_empty() && !yes && !confirm("Add it anyway?", &mut std::io::stdin().lock()) {
+        eprintln!("Aborted; quick prompt not added.");
+        return 1;
+    }
+
     let mut config = keymap::load_toml_config();
     let qp = config.quick_prompts.get_or_insert_with(HashMap::new);
     qp.insert(key_str.clone(), message.clone());
@@ -243,28 +325,713 @@ fn cmd_prompt_from_files(args: &[String]) -> CliAction {
     CliAction::LaunchTui(LaunchOptions { prompts, worktree: true, run_path })
 }
 
+// ── submit ──
+
+const SUBMIT_USAGE: &str =
+    "Usage: clhorde submit [--cwd <dir>] [--worktree] [--mode one-shot|interactive] [--depends-on <id>] [--max-attempts <n>] [--timeout <secs>] [--resume-session <id>] [--] <text>\n       clhorde submit --batch <file|->";
+
+fn cmd_submit(args: &[String]) -> i32 {
+    if args.first().map(|s| s.as_str()) == Some("--batch") {
+        return submit_batch(args.get(1).map(|s| s.as_str()));
+    }
+    let req = match parse_submit_args(args) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            eprintln!("{SUBMIT_USAGE}");
+            return 1;
+        }
+    };
+    let Some(dir) = inbox::default_inbox_dir() else {
+        eprintln!("Error: could not determine data directory.");
+        return 1;
+    };
+    if !inbox::is_running(&dir) {
+        eprintln!("Error: clhorde is not running. Start the TUI first.");
+        return 1;
+    }
+
+    let token = uuid::Uuid::now_v7().to_string();
+    if let Err(e) = inbox::write_request(&dir, &token, &req) {
+        eprintln!("Error: failed to submit prompt: {e}");
+        return 1;
+    }
+    match inbox::wait_for_accept(&dir, &token, std::time::Duration::from_secs(5)) {
+        Some(Ok(id)) => {
+            println!("{id}");
+            0
+        }
+        Some(Err(reason)) => {
+            eprintln!("Error: clhorde rejected the prompt: {reason}");
+            1
+        }
+        None => {
+            inbox::remove_request(&dir, &token);
+            eprintln!("Error: clhorde did not accept the prompt (timed out).");
+            1
+        }
+    }
+}
+
+fn parse_submit_args(args: &[String]) -> Result<SubmitRequest, String> {
+    let mut cwd: Option<String> = None;
+    let mut worktree = false;
+    let mut mode = String::new();
+    let mut depends_on: Option<usize> = None;
+    let mut max_attempts: Option<u32> = None;
+    let mut timeout_secs: Option<u64> = None;
+    let mut resume_session_id: Option<String> = None;
+    let mut words: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--cwd" => {
+                let Some(path) = args.get(i + 1) else {
+                    return Err("--cwd requires a path argument".to_string());
+                };
+                cwd = Some(path.clone());
+                i += 2;
+            }
+            "--worktree" => {
+                worktree = true;
+                i += 1;
+            }
+            "--mode" => {
+                mode = match args.get(i + 1).map(|s| s.as_str()) {
+                    Some("one-shot") => "one_shot".to_string(),
+                    Some("interactive") => "interactive".to_string(),
+                    Some(other) => return Err(format!("unknown mode '{other}'")),
+                    None => return Err("--mode requires one-shot or interactive".to_string()),
+                };
+                i += 2;
+            }
+            "--depends-on" => {
+                let id = args
+                    .get(i + 1)
+                    .and_then(|s| s.trim_start_matches('#').parse::<usize>().ok());
+                let Some(id) = id else {
+                    return Err("--depends-on requires a prompt id".to_string());
+                };
+                depends_on = Some(id);
+                i += 2;
+            }
+            "--max-attempts" => {
+                let n = args.get(i + 1).and_then(|s| s.parse::<u32>().ok()).filter(|n| *n >= 1);
+                let Some(n) = n else {
+                    return Err("--max-attempts requires a number of at least 1".to_string());
+                };
+                max_attempts = Some(n);
+                i += 2;
+            }
+            "--timeout" => {
+                let secs = args.get(i + 1).and_then(|s| s.parse::<u64>().ok()).filter(|n| *n >= 1);
+                let Some(secs) = secs else {
+                    return Err("--timeout requires a number of seconds of at least 1".to_string());
+                };
+                timeout_secs = Some(secs);
+                i += 2;
+            }
+            "--resume-session" => {
+                let id = args.get(i + 1).map(|s| s.trim()).filter(|s| !s.is_empty());
+                let Some(id) = id else {
+                    return Err("--resume-session requires a session id".to_string());
+                };
+                resume_session_id = Some(id.to_string());
+                i += 2;
+            }
+            // Everything after `--` is prompt text, even if it looks like a flag
+            "--" => {
+                words.extend(args[i + 1..].iter().map(|s| s.as_str()));
+                break;
+            }
+            other if other.starts_with("--") => {
+                return Err(format!("unknown option '{other}' (use -- before text that starts with --)"));
+            }
+            other => {
+                words.push(other);
+                i += 1;
+            }
+        }
+    }
+
+    let text = words.join(" ").trim().to_string();
+    if text.is_empty() {
+        return Err("no prompt text given".to_string());
+    }
+
+    Ok(SubmitRequest {
+        protocol_version: inbox::PROTOCOL_VERSION,
+        prompt: text,
+        options: PromptOptions {
+            mode,
+            context: Some(resolve_submit_cwd(cwd)?),
+            worktree: if worktree { Some(true) } else { None },
+            priority: None,
+            timeout_secs,
+            max_attempts,
+            env: Default::default(),
+        },
+        depends_on,
+        resume_session_id,
+    })
+}
+
+/// Resolve against the caller's directory so the TUI runs the prompt in
+/// the right place.
+fn resolve_submit_cwd(cwd: Option<String>) -> Result<String, String> {
+    let cwd_path = match cwd {
+        Some(p) => std::path::PathBuf::from(p),
+        None => std::env::current_dir().map_err(|e| e.to_string())?,
+    };
+    if !cwd_path.is_dir() {
+        return Err(format!("not a directory: {}", cwd_path.display()));
+    }
+    let cwd = std::fs::canonicalize(&cwd_path).unwrap_or(cwd_path);
+    Ok(cwd.to_string_lossy().to_string())
+}
+
+/// One prompt spec of `clhorde submit --batch`.
+#[derive(serde::Deserialize)]
+struct BatchEntry {
+    text: String,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    worktree: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+}
+
+/// One parsed entry of a batch, with the input line it starts on.
+struct BatchItem {
+    line: usize,
+    request: Result<SubmitRequest, String>,
+}
+
+/// Parse a batch. Malformed JSON fails the whole batch; otherwise each
+/// entry is validated on its own, so every bad entry can be reported.
+fn parse_batch(input: &str) -> Result<Vec<BatchItem>, String> {
+    let entries: Vec<&serde_json::value::RawValue> =
+        serde_json::from_str(input).map_err(|e| format!("invalid batch JSON: {e}"))?;
+    Ok(entries
+        .into_iter()
+        .map(|raw| {
+            // Raw values borrow from `input`, so their offset gives the line
+            let offset = raw.get().as_ptr() as usize - input.as_ptr() as usize;
+            let line = input[..offset].matches('\n').count() + 1;
+            let request = serde_json::from_str::<BatchEntry>(raw.get())
+                .map_err(|e| e.to_string())
+                .and_then(batch_request);
+            BatchItem { line, request }
+        })
+        .collect())
+}
+
+fn batch_request(entry: BatchEntry) -> Result<SubmitRequest, String> {
+    let text = entry.text.trim();
+    if text.is_empty() {
+        return Err("text: empty".to_string());
+    }
+    let mode = match entry.mode.as_deref() {
+        None => String::new(),
+        Some("one-shot" | "one_shot") => "one_shot".to_string(),
+        Some("interactive") => "interactive".to_string(),
+        Some(other) => return Err(format!("mode: unknown mode '{other}'")),
+    };
+    inbox::validate_env(&entry.env).map_err(|e| format!("env: {e}"))?;
+    // Tags travel as `@tag` prefixes, which the TUI parses from the text
+    let mut prompt = String::new();
+    for tag in &entry.tags {
+        let tag = normalize_tag(tag).ok_or_else(|| format!("tags: invalid tag '{tag}'"))?;
+        prompt.push_str(&format!("@{tag} "));
+    }
+    prompt.push_str(text);
+    Ok(SubmitRequest {
+        protocol_version: inbox::PROTOCOL_VERSION,
+        prompt,
+        options: PromptOptions {
+            mode,
+            context: Some(resolve_submit_cwd(entry.cwd).map_err(|e| format!("cwd: {e}"))?),
+            worktree: entry.worktree.then_some(true),
+            priority: None,
+            timeout_secs: None,
+            max_attempts: None,
+            env: entry.env,
+        },
+        depends_on: None,
+        resume_session_id: None,
+    })
+}
+
+/// Queue every entry of a batch and print the assigned ids in input order
+/// (`-` for entries that failed). Nothing is sent if any entry is invalid.
+fn submit_batch(source: Option<&str>) -> i32 {
+    let input = match source {
+        Some("-") => std::io::read_to_string(std::io::stdin()),
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            eprintln!("Error: --batch requires a file or - for stdin");
+            eprintln!("{SUBMIT_USAGE}");
+            return 1;
+        }
+    };
+    let input = match input {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: could not read batch: {e}");
+            return 1;
+        }
+    };
+    let parsed = match parse_batch(&input) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+    let mut requests = Vec::new();
+    let mut invalid = 0;
+    for item in parsed {
+        match item.request {
+            Ok(req) => requests.push((item.line, req)),
+            Err(e) => {
+                eprintln!("Error: line {}: {e}", item.line);
+                invalid += 1;
+            }
+        }
+    }
+    if invalid > 0 {
+        eprintln!("Error: {invalid} invalid entr{}; nothing submitted.", if invalid == 1 { "y" } else { "ies" });
+        return 1;
+    }
+
+    let Some(dir) = inbox::default_inbox_dir() else {
+        eprintln!("Error: could not determine data directory.");
+        return 1;
+    };
+    if !inbox::is_running(&dir) {
+        eprintln!("Error: clhorde is not running. Start the TUI first.");
+        return 1;
+    }
+
+    // The TUI takes requests in token order, so a shared prefix plus the
+    // entry index queues them in input order
+    let batch = uuid::Uuid::now_v7();
+    let tokens: Vec<(usize, Option<String>)> = requests
+        .iter()
+        .enumerate()
+        .map(|(i, (line, req))| {
+            let token = format!("{batch}-{i:06}");
+            match inbox::write_request(&dir, &token, req) {
+                Ok(()) => (*line, Some(token)),
+                Err(e) => {
+                    eprintln!("Error: line {line}: failed to submit: {e}");
+                    (*line, None)
+                }
+            }
+        })
+        .collect();
+
+    let mut failed = 0;
+    for (line, token) in &tokens {
+        let Some(token) = token else {
+            println!("-");
+            failed += 1;
+            continue;
+        };
+        match inbox::wait_for_accept(&dir, token, std::time::Duration::from_secs(5)) {
+            Some(Ok(id)) => println!("{id}"),
+            Some(Err(reason)) => {
+                println!("-");
+                eprintln!("Error: line {line}: rejected: {reason}");
+                failed += 1;
+            }
+            None => {
+                inbox::remove_request(&dir, token);
+                println!("-");
+                eprintln!("Error: line {line}: not accepted (timed out)");
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 { 1 } else { 0 }
+}
+
+// ── tui ──
+
+/// Exit code of `clhorde tui` commands when no TUI is running.
+const EXIT_NOT_RUNNING: i32 = 3;
+
+fn cmd_tui(args: &[String]) -> i32 {
+    let sub = args.first().map(|s| s.as_str());
+    if !matches!(sub, Some("status" | "stats" | "stop" | "kill-all" | "drain" | "signal")) {
+        eprintln!("Usage: clhorde tui <status|stats|stop|kill-all|drain [--quit]|signal <id> [signal]>");
+        eprintln!("  status            Show pid, workers and prompt counts");
+        eprintln!("  stats             Show completed/failed/killed totals and average run time");
+        eprintln!("  stop              Quit the running TUI, terminating its workers");
+        eprintln!("  kill-all          Kill all running workers, keeping queued prompts");
+        eprintln!("  drain [--quit]    Stop dispatching new prompts; --quit exits once workers finish");
+        eprintln!("  signal <id> [sig] Send INT (default), TSTP or QUIT to a PTY worker's process group");
+        return 1;
+    }
+    let signal = match sub {
+        Some("signal") => match parse_signal_args(&args[1..]) {
+            Ok(signal) => Some(signal),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                eprintln!("{SIGNAL_USAGE}");
+                return 1;
+            }
+        },
+        _ => None,
+    };
+    let Some(dir) = inbox::default_inbox_dir() else {
+        eprintln!("Error: could not determine data directory.");
+        return 1;
+    };
+    if !inbox::is_running(&dir) {
+        println!("clhorde is not running.");
+        return EXIT_NOT_RUNNING;
+    }
+    match sub {
+        Some("stats") => tui_stats(&dir),
+        Some("stop") => tui_stop(&dir),
+        Some("kill-all") => tui_kill_all(&dir),
+        Some("drain") => tui_drain(&dir, args[1..].iter().any(|a| a == "--quit")),
+        Some("signal") => {
+            let (prompt_id, sig) = signal.expect("parsed above");
+            tui_signal(&dir, prompt_id, sig)
+        }
+        _ => tui_status(&dir),
+    }
+}
+
+const SIGNAL_USAGE: &str = "Usage: clhorde tui signal <prompt_id> [INT|TSTP|QUIT]";
+
+/// Parse `<prompt_id> [signal]`, checking the signal against the same
+/// allowlist the TUI enforces.
+fn parse_signal_args(args: &[String]) -> Result<(usize, &'static str), String> {
+    let (id, rest) = args.split_first().ok_or("no prompt id given")?;
+    let prompt_id = id
+        .trim_start_matches('#')
+        .parse()
+        .map_err(|_| format!("invalid prompt id '{id}'"))?;
+    let sig = match rest {
+        [] => "INT",
+        [name] => process::sendable_signal(name)?,
+        _ => return Err("too many arguments".to_string()),
+    };
+    Ok((prompt_id, sig))
+}
+
+fn tui_signal(dir: &std::path::Path, prompt_id: usize, sig: &str) -> i32 {
+    if let Err(e) = inbox::request_signal(dir, prompt_id, sig) {
+        eprintln!("Error: could not write signal request: {e}");
+        return 1;
+    }
+    println!("SIG{sig} requested for #{prompt_id}.");
+    0
+}
+
+fn tui_status(dir: &std::path::Path) -> i32 {
+    let pid = inbox::read_pid(dir).unwrap_or_default();
+    println!("clhorde is running (pid {pid})");
+    println!("inbox: {}", dir.display());
+    match inbox::read_status(dir) {
+        Some(status) => {
+            let paused = match (status.paused, status.draining) {
+                (_, true) => " (draining)",
+                (true, false) => " (paused)",
+                (false, false) => "",
+            };
+            println!("workers: {}/{} active{paused}", status.active_workers, status.max_workers);
+            let counts: Vec<String> = status
+                .counts
+                .iter()
+                .map(|(label, n)| format!("{label} {n}"))
+                .collect();
+            println!("prompts: {}", counts.join(", "));
+        }
+        None => println!("workers: unknown (status not published yet)"),
+    }
+    0
+}
+
+fn tui_stats(dir: &std::path::Path) -> i32 {
+    let Some(status) = inbox::read_status(dir) else {
+        println!("stats: unknown (status not published yet)");
+        return 0;
+    };
+    let stats = status.stats;
+    println!("completed: {}", stats.completed);
+    println!("failed: {}", stats.failed);
+    println!("killed: {}", stats.killed);
+    println!("pending: {}", stats.pending);
+    println!("running: {}", stats.running);
+    match stats.avg_elapsed_secs {
+        Some(secs) => println!("avg elapsed: {}", format_duration(secs)),
+        None => println!("avg elapsed: -"),
+    }
+    0
+}
+
+fn tui_stop(dir: &std::path::Path) -> i32 {
+    if let Err(e) = inbox::request_stop(dir) {
+        eprintln!("Error: could not write stop request: {e}");
+        return 1;
+    }
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while inbox::is_running(dir) {
+        if std::time::Instant::now() >= deadline {
+            eprintln!("Error: clhorde did not stop within 10s.");
+            return 1;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    println!("Stopped.");
+    0
+}
+
+fn tui_kill_all(dir: &std::path::Path) -> i32 {
+    if let Err(e) = inbox::request_kill_all(dir) {
+        eprintln!("Error: could not write kill-all request: {e}");
+        return 1;
+    }
+    println!("Kill-all requested.");
+    0
+}
+
+fn tui_drain(dir: &std::path::Path, quit: bool) -> i32 {
+    if let Err(e) = inbox::request_drain(dir, quit) {
+        eprintln!("Error: could not write drain request: {e}");
+        return 1;
+    }
+    if quit {
+        println!("Drain requested; clhorde will quit once its workers finish.");
+    } else {
+        println!("Drain requested; no new prompts will start.");
+    }
+    0
+}
+
+// ── tail ──
+
+const TAIL_USAGE: &str = "Usage: clhorde tail <prompt_id> [--follow] [--since-byte <n>]";
+
+struct TailArgs {
+    prompt_id: usize,
+    follow: bool,
+    /// Output bytes the caller already has; streaming starts after them.
+    since_byte: u64,
+}
+
+fn parse_tail_args(args: &[String]) -> Result<TailArgs, String> {
+    let mut prompt_id = None;
+    let mut follow = false;
+    let mut since_byte = 0;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--follow" | "-f" => follow = true,
+            "--since-byte" => {
+                i += 1;
+                since_byte = args
+                    .get(i)
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .ok_or("--since-byte requires a byte offset")?;
+            }
+            arg if prompt_id.is_none() && !arg.starts_with('-') => {
+                let id = arg.trim_start_matches('#').parse::<usize>();
+                prompt_id = Some(id.map_err(|_| format!("invalid prompt id '{arg}'"))?);
+            }
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+        i += 1;
+    }
+    let prompt_id = prompt_id.ok_or("no prompt id given")?;
+    Ok(TailArgs { prompt_id, follow, since_byte })
+}
+
+/// Polls (200ms apart) `tail` waits for an unknown id to appear.
+const TAIL_LOOKUP_POLLS: u32 = 10;
+
+/// Whether the running TUI has `prompt_id` with output to stream: listed in
+/// its published status, or already mirrored.
+fn tail_target_known(dir: &std::path::Path, prompt_id: usize) -> bool {
+    inbox::read_status(dir).is_some_and(|s| s.prompt_ids.contains(&prompt_id))
+        || inbox::read_exit(dir, prompt_id).is_some()
+        || !inbox::read_output_from(dir, prompt_id, 0).is_empty()
+}
+
+fn cmd_tail(args: &[String]) -> i32 {
+    let TailArgs { prompt_id, follow, since_byte } = match parse_tail_args(args) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            eprintln!("{TAIL_USAGE}");
+            return 1;
+        }
+    };
+    let Some(dir) = inbox::default_inbox_dir() else {
+        eprintln!("Error: could not determine data directory.");
+        return 1;
+    };
+    if !inbox::is_running(&dir) {
+        eprintln!("Error: clhorde is not running. Start the TUI first.");
+        return 1;
+    }
+
+    use std::io::Write;
+    let mut stdout = std::io::stdout();
+    let mut offset = since_byte;
+    let mut mirror_len = 0;
+    let mut polls: u32 = 0;
+    // A just-submitted prompt shows up in the status on the TUI's next tick
+    let mut known = false;
+    loop {
+        if !known {
+            known = tail_target_known(&dir, prompt_id);
+            if !known && polls >= TAIL_LOOKUP_POLLS {
+                eprintln!("Error: no such prompt #{prompt_id} in the running session.");
+                return 1;
+            }
+        }
+        // Read the exit status first so output written before it is never missed
+        let exit = inbox::read_exit(&dir, prompt_id);
+        // The TUI restarts a mirror that reached its size cap
+        let len = inbox::output_len(&dir, prompt_id);
+        if len < mirror_len {
+            offset = 0;
+        }
+        mirror_len = len;
+        let chunk = inbox::read_output_from(&dir, prompt_id, offset);
+        if !chunk.is_empty() {
+            offset += chunk.len() as u64;
+            let _ = stdout.write_all(&chunk);
+            let _ = stdout.flush();
+        }
+        if let Some(code) = exit {
+            if !follow {
+                eprintln!("[#{prompt_id} exited with code {code}]");
+                return code;
+            }
+        }
+        polls = polls.wrapping_add(1);
+        if polls.is_multiple_of(10) && !inbox::is_running(&dir) {
+            eprintln!("Error: clhorde exited while tailing #{prompt_id}.");
+            return 1;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+}
+
+// ── filters subcommands ──
+
+fn cmd_filters(args: &[String]) -> i32 {
+    match args.first().map(|s| s.as_str()) {
+        Some("list") => filters_list(),
+        Some("add") => filters_add(&args[1..]),
+        Some("remove") => filters_remove(&args[1..]),
+        _ => {
+            eprintln!("Usage: clhorde filters <list|add|remove>");
+            eprintln!("  list                    List saved filters");
+            eprintln!("  add <name> <filter...>  Save a filter");
+            eprintln!("  remove <name>           Remove a saved filter");
+            1
+        }
+    }
+}
+
+fn filters_list() -> i32 {
+    let saved = filters::load_filters();
+    if saved.is_empty() {
+        println!("No saved filters.");
+        return 0;
+    }
+    let mut entries: Vec<_> = saved.iter().collect();
+    entries.sort_by_key(|(a, _)| *a);
+    for (name, filter) in entries {
+        println!("{name} = \"{filter}\"");
+    }
+    0
+}
+
+fn filters_add(args: &[String]) -> i32 {
+    if args.len() < 2 {
+        eprintln!("Usage: clhorde filters add <name> <filter...>");
+        return 1;
+    }
+    let name = &args[0];
+    if !filters::is_valid_name(name) {
+        eprintln!("Invalid filter name: {name} (use letters, digits, '-' and '_')");
+        return 1;
+    }
+    let filter = args[1..].join(" ");
+
+    let mut saved = filters::load_filters();
+    saved.insert(name.clone(), filter.clone());
+    if let Err(e) = filters::save_filters(&saved) {
+        eprintln!("Failed to save filters: {e}");
+        return 1;
+    }
+    println!("Added filter: {name} = \"{filter}\"");
+    0
+}
+
+fn filters_remove(args: &[String]) -> i32 {
+    let Some(name) = args.first() else {
+        eprintln!("Usage: clhorde filters remove <name>");
+        return 1;
+    };
+    let mut saved = filters::load_filters();
+    if saved.remove(name).is_none() {
+        eprintln!("Filter '{name}' not found.");
+        return 1;
+    }
+    if let Err(e) = filters::save_filters(&saved) {
+        eprintln!("Failed to save filters: {e}");
+        return 1;
+    }
+    println!("Removed filter: {name}");
+    0
+}
+
 // ── store subcommands ──
 
-const VALID_STATES: &[&str] = &["completed", "failed", "pending", "running"];
+const VALID_STATES: &[&str] = &["completed", "failed", "killed", "pending", "running"];
 
 fn cmd_store(args: &[String]) -> i32 {
     match args.first().map(|s| s.as_str()) {
         Some("list") => store_list(),
         Some("count") => store_count(),
         Some("path") => store_path(),
-        Some("drop") => store_drop(args.get(1).map(|s| s.as_str())),
-        Some("keep") => store_keep(args.get(1).map(|s| s.as_str())),
-        Some("clean-worktrees") => store_clean_worktrees(),
+        Some("drop") => store_drop(&args[1..]),
+        Some("keep") => store_keep(&args[1..]),
+        Some("worktrees") => store_worktrees(),
+        Some("clean-worktrees") => store_clean_worktrees(&args[1..]),
+        Some("export") => store_export(&args[1..]),
+        Some("import") => store_import(args.get(1).map(|s| s.as_str())),
+        Some("archive") => store_archive(&args[1..]),
         _ => {
-            eprintln!("Usage: clhorde store <list|count|path|drop|keep|clean-worktrees>");
+            eprintln!("Usage: clhorde store <list|count|path|drop|keep|worktrees|clean-worktrees|export|import|archive>");
             eprintln!("  list              List all stored prompts");
             eprintln!("  count             Show prompt counts by state");
             eprintln!("  path              Print storage directory path");
-            eprintln!("  drop <filter>     Delete stored prompts");
+            eprintln!("  drop <filter>     Delete stored prompts (--id UUID, --tag NAME narrow it)");
             eprintln!("  keep <filter>     Keep only matching, delete rest");
-            eprintln!("  clean-worktrees   Remove lingering git worktrees");
+            eprintln!("  worktrees         List recorded worktree paths and whether they exist");
+            eprintln!("  clean-worktrees [--dry-run]");
+            eprintln!("                    Remove lingering git worktrees (--dry-run: only list them)");
+            eprintln!("  export <file> [--format md|json]");
+            eprintln!("                    Write all stored prompts to a file");
+            eprintln!("  import <file.json>  Add prompts from a JSON array as pending");
+            eprintln!("  archive <list|path> Browse prompts moved out by pruning (archive_dir setting)");
             eprintln!();
-            eprintln!("Filters: all, completed, failed, pending, running");
+            eprintln!("Filters: all, completed, failed, killed, pending, running");
             1
         }
     }
@@ -300,12 +1067,19 @@ fn store_list() -> i32 {
         println!("No stored prompts.");
         return 0;
     }
+    print_prompt_table(&prompts);
+    0
+}
+
+/// `ID UUID STATE MODE PROMPT` table shared by `store list` and
+/// `store archive list`, followed by the total.
+fn print_prompt_table(prompts: &[(String, PromptFile)]) {
     println!(
-        "{:<38} {:<11} {:<13} PROMPT",
-        "UUID", "STATE", "MODE"
+        "{:<5} {:<38} {:<11} {:<13} PROMPT",
+        "ID", "UUID", "STATE", "MODE"
     );
-    println!("{}", "-".repeat(78));
-    for (uuid, p) in &prompts {
+    println!("{}", "-".repeat(84));
+    for (pos, (uuid, p)) in prompts.iter().enumerate() {
         let text = if p.prompt.len() > 40 {
             format!("{}...", &p.prompt[..37])
         } else {
@@ -314,12 +1088,41 @@ fn store_list() -> i32 {
         // Replace newlines with spaces for display
         let text = text.replace('\n', " ");
         println!(
-            "{:<38} {:<11} {:<13} {}",
-            uuid, p.state, p.options.mode, text
+            "{:<5} {:<38} {:<11} {:<13} {}",
+            format!("#{}", pos + 1), uuid, p.state, p.options.mode, text
         );
     }
     println!("\n{} prompt(s) total.", prompts.len());
-    0
+}
+
+/// `store archive list|path`: prompts `max_saved_prompts` pruned into the
+/// `archive_dir` setting. Ids are positions in the archive, not in the
+/// live store.
+fn store_archive(args: &[String]) -> i32 {
+    let Some(setting) = keymap::load_settings().archive_dir else {
+        eprintln!("No archive: set archive_dir in [settings] to archive pruned prompts instead of deleting them.");
+        return 1;
+    };
+    let dir = persistence::archive_dir(&setting);
+    match args.first().map(|s| s.as_str()) {
+        Some("list") => {
+            let prompts = persistence::load_all_prompts(&dir);
+            if prompts.is_empty() {
+                println!("No archived prompts.");
+            } else {
+                print_prompt_table(&prompts);
+            }
+            0
+        }
+        Some("path") => {
+            println!("{}", dir.display());
+            0
+        }
+        _ => {
+            eprintln!("Usage: clhorde store archive <list|path>");
+            1
+        }
+    }
 }
 
 fn store_count() -> i32 {
@@ -346,61 +1149,179 @@ fn store_count() -> i32 {
     0
 }
 
-fn store_drop(filter: Option<&str>) -> i32 {
-    let filter = match filter {
-        Some(f) => f,
-        None => {
-            eprintln!("Usage: clhorde store drop <filter>");
-            eprintln!("Filters: all, completed, failed, pending, running");
-            return 1;
+/// Which stored prompts `store drop` deletes. All given parts must match:
+/// the state keyword (`None` = all), any of `ids` (UUIDs or unique UUID
+/// prefixes, lowercased), any of `tags`.
+#[derive(Debug, Default, PartialEq)]
+struct DropFilter {
+    state: Option<String>,
+    ids: Vec<String>,
+    tags: Vec<String>,
+}
+
+impl DropFilter {
+    /// Whether the prompt stored as `uuid` matches. `uuids` are the
+    /// prompts `ids` resolved to (see `resolve_ids`).
+    fn matches(&self, uuid: &str, uuids: &[&str], pf: &persistence::PromptFile) -> bool {
+        self.state.as_ref().is_none_or(|state| pf.state == *state)
+            && (self.ids.is_empty() || uuids.contains(&uuid))
+            && (self.tags.is_empty()
+                || pf.tags.iter().any(|t| self.tags.contains(&t.to_lowercase())))
+    }
+
+    /// Resolve each id to the one stored UUID it is a prefix of. Unlike
+    /// list positions, UUIDs don't shift when other prompts are dropped.
+    fn resolve_ids<'a>(&self, prompts: &'a [(String, PromptFile)]) -> Result<Vec<&'a str>, String> {
+        self.ids
+            .iter()
+            .map(|id| {
+                let found: Vec<&str> = prompts
+                    .iter()
+                    .map(|(uuid, _)| uuid.as_str())
+                    .filter(|uuid| uuid.to_lowercase().starts_with(id.as_str()))
+                    .collect();
+                match found[..] {
+                    [uuid] => Ok(uuid),
+                    [] => Err(format!("No stored prompt with id {id}")),
+                    _ => Err(format!("Id {id} matches {} prompts; use more of the UUID", found.len())),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Parse `[all|<state>] [--id UUID]... [--tag NAME]...`.
+fn parse_drop_filter(args: &[String]) -> Result<DropFilter, String> {
+    let mut filter = DropFilter::default();
+    let mut keyword = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--id" => {
+                i += 1;
+                let value = args.get(i).ok_or("--id requires a prompt UUID")?;
+                let id = value.trim().to_lowercase();
+                if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+                    return Err(format!("Invalid prompt id: {value}"));
+                }
+                filter.ids.push(id);
+            }
+            "--tag" => {
+                i += 1;
+                let value = args.get(i).ok_or("--tag requires a tag name")?;
+                let tag = value.trim_start_matches('@').to_lowercase();
+                if tag.is_empty() {
+                    return Err(format!("Invalid tag: {value}"));
+                }
+                filter.tags.push(tag);
+            }
+            word if keyword.is_none() && (word == "all" || VALID_STATES.contains(&word)) => {
+                keyword = Some(word.to_string());
+            }
+            other => return Err(format!("Unknown filter: {other}")),
         }
-    };
+        i += 1;
+    }
+    if keyword.is_none() && filter.ids.is_empty() && filter.tags.is_empty() {
+        return Err("No filter given".to_string());
+    }
+    filter.state = keyword.filter(|k| k != "all");
+    Ok(filter)
+}
 
-    if filter != "all" && !VALID_STATES.contains(&filter) {
-        eprintln!("Unknown filter: {filter}");
-        eprintln!("Valid filters: all, completed, failed, pending, running");
-        return 1;
+/// Delete matching prompts from `dir`, returning the dropped UUIDs. Nothing
+/// is deleted when an id doesn't resolve to exactly one prompt.
+fn drop_matching(dir: &std::path::Path, filter: &DropFilter) -> Result<Vec<String>, String> {
+    let prompts = persistence::load_all_prompts(dir);
+    let uuids = filter.resolve_ids(&prompts)?;
+    let mut dropped = Vec::new();
+    for (uuid, p) in &prompts {
+        if filter.matches(uuid, &uuids, p) {
+            persistence::delete_prompt_file(dir, uuid);
+            dropped.push(uuid.clone());
+        }
     }
+    Ok(dropped)
+}
+
+fn store_drop(args: &[String]) -> i32 {
+    let filter = match parse_drop_filter(args) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("Usage: clhorde store drop [all|<state>] [--id UUID]... [--tag NAME]...");
+            eprintln!("Filters: all, completed, failed, killed, pending, running");
+            return 1;
+        }
+    };
 
     let dir = match store_dir_or_err() {
         Ok(d) => d,
         Err(code) => return code,
     };
 
-    if filter == "all" {
-        let prompts = persistence::load_all_prompts(&dir);
-        let count = prompts.len();
-        for (uuid, _) in &prompts {
-            persistence::delete_prompt_file(&dir, uuid);
+    let dropped = match drop_matching(&dir, &filter) {
+        Ok(dropped) => dropped,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
         }
-        println!("Dropped {count} prompt(s).");
+    };
+    if dropped.is_empty() {
+        println!("Dropped 0 prompt(s).");
     } else {
-        let prompts = persistence::load_all_prompts(&dir);
-        let mut count = 0;
-        for (uuid, p) in &prompts {
-            if p.state == filter {
-                persistence::delete_prompt_file(&dir, uuid);
-                count += 1;
-            }
-        }
-        println!("Dropped {count} {filter} prompt(s).");
+        println!("Dropped {} prompt(s): {}", dropped.len(), dropped.join(", "));
     }
     0
 }
 
-fn store_keep(filter: Option<&str>) -> i32 {
+/// Ask a y/N question on stdout and read the answer from `input`. Anything
+/// but `y`/`yes` (including EOF) declines.
+fn confirm(question: &str, input: &mut impl BufRead) -> bool {
+    print!("{question} [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if input.read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// UUIDs `store keep <filter>` removes: every prompt not in that state.
+fn keep_removals(prompts: &[(String, PromptFile)], filter: &str) -> Vec<String> {
+    prompts
+        .iter()
+        .filter(|(_, p)| p.state != filter)
+        .map(|(uuid, _)| uuid.clone())
+        .collect()
+}
+
+fn store_keep(args: &[String]) -> i32 {
+    let mut filter = None;
+    let mut yes = false;
+    for arg in args {
+        match arg.as_str() {
+            "--yes" | "-y" => yes = true,
+            f if filter.is_none() => filter = Some(f),
+            other => {
+                eprintln!("Unexpected argument: {other}");
+                filter = None;
+                break;
+            }
+        }
+    }
     let filter = match filter {
         Some(f) => f,
         None => {
-            eprintln!("Usage: clhorde store keep <filter>");
-            eprintln!("Filters: completed, failed, pending, running");
+            eprintln!("Usage: clhorde store keep <filter> [--yes]");
+            eprintln!("Filters: completed, failed, killed, pending, running");
             return 1;
         }
     };
 
     if !VALID_STATES.contains(&filter) {
         eprintln!("Unknown filter: {filter}");
-        eprintln!("Valid filters: completed, failed, pending, running");
+        eprintln!("Valid filters: completed, failed, killed, pending, running");
         return 1;
     }
 
@@ -409,21 +1330,73 @@ fn store_keep(filter: Option<&str>) -> i32 {
         Err(code) => return code,
     };
     let prompts = persistence::load_all_prompts(&dir);
-    let mut dropped = 0;
-    let mut kept = 0;
-    for (uuid, p) in &prompts {
-        if p.state != filter {
-            persistence::delete_prompt_file(&dir, uuid);
-            dropped += 1;
-        } else {
-            kept += 1;
-        }
+    let to_remove = keep_removals(&prompts, filter);
+    let kept = prompts.len() - to_remove.len();
+    if to_remove.is_empty() {
+        println!("All {kept} prompt(s) are {filter}; nothing to remove.");
+        return 0;
+    }
+
+    println!(
+        "Keeping {kept} {filter} prompt(s); {} other prompt(s) will be removed.",
+        to_remove.len()
+    );
+    if !yes && !confirm("Remove them?", &mut std::io::stdin().lock()) {
+        eprintln!("Aborted; nothing was removed.");
+        return 1;
+    }
+    for uuid in &to_remove {
+        persistence::delete_prompt_file(&dir, uuid);
+    }
+    println!("Kept {kept} {filter} prompt(s), dropped {}.", to_remove.len());
+    0
+}
+
+/// Persist a prompt with its `worktree_path` cleared.
+fn clear_worktree_path(dir: &std::path::Path, uuid: &str, pf: &persistence::PromptFile) {
+    let updated = persistence::PromptFile {
+        worktree_path: None,
+        ..pf.clone()
+    };
+    if let Err(e) = persistence::save_prompt(dir, uuid, &updated) {
+        eprintln!("Cannot update {uuid}: {e}");
+    }
+}
+
+fn store_worktrees() -> i32 {
+    let dir = match store_dir_or_err() {
+        Ok(d) => d,
+        Err(code) => return code,
+    };
+    let prompts = persistence::load_all_prompts(&dir);
+    let with_worktree: Vec<_> = prompts
+        .iter()
+        .filter_map(|(uuid, p)| p.worktree_path.as_ref().map(|wt| (uuid, p, wt)))
+        .collect();
+    if with_worktree.is_empty() {
+        println!("No worktrees recorded.");
+        return 0;
+    }
+    println!("{:<38} {:<11} {:<8} PATH", "UUID", "STATE", "ON DISK");
+    println!("{}", "-".repeat(78));
+    for (uuid, p, wt) in &with_worktree {
+        let on_disk = if std::path::Path::new(wt).exists() { "yes" } else { "missing" };
+        println!("{:<38} {:<11} {:<8} {}", uuid, p.state, on_disk, wt);
     }
-    println!("Kept {kept} {filter} prompt(s), dropped {dropped}.");
+    println!("\n{} worktree(s) recorded.", with_worktree.len());
     0
 }
 
-fn store_clean_worktrees() -> i32 {
+fn store_clean_worktrees(args: &[String]) -> i32 {
+    let dry_run = match args.first().map(|s| s.as_str()) {
+        None => false,
+        Some("--dry-run" | "-n") => true,
+        Some(other) => {
+            eprintln!("Unknown option: {other}");
+            eprintln!("Usage: clhorde store clean-worktrees [--dry-run]");
+            return 1;
+        }
+    };
     let dir = match store_dir_or_err() {
         Ok(d) => d,
         Err(code) => return code,
@@ -441,81 +1414,248 @@ fn store_clean_worktrees() -> i32 {
         if !wt_path.exists() {
             println!("  skip (already gone): {wt_path_str}");
             skipped += 1;
-            // Clear the worktree_path in the persisted file
-            let updated = persistence::PromptFile {
-                prompt: pf.prompt.clone(),
-                options: persistence::PromptOptions {
-                    mode: pf.options.mode.clone(),
-                    context: pf.options.context.clone(),
-                    worktree: pf.options.worktree,
-                },
-                state: pf.state.clone(),
-                queue_rank: pf.queue_rank,
-                session_id: pf.session_id.clone(),
-                worktree_path: None,
-                tags: pf.tags.clone(),
-            };
-            persistence::save_prompt(&dir, uuid, &updated);
+            if !dry_run {
+                clear_worktree_path(&dir, uuid, pf);
+            }
             continue;
         }
 
-        // Try to find the repo root to run git worktree remove
-        let mut removed = false;
-        if let Some(parent) = wt_path.parent() {
-            if let Ok(entries) = std::fs::read_dir(parent) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_dir() && path != wt_path {
-                        if let Some(root) = worktree::repo_root(&path) {
-                            match worktree::remove_worktree(&root, wt_path) {
-                                Ok(()) => {
-                                    println!("  removed: {wt_path_str}");
-                                    cleaned += 1;
-                                    removed = true;
-                                    // Clear worktree_path in persisted file
-                                    let updated = persistence::PromptFile {
-                                        prompt: pf.prompt.clone(),
-                                        options: persistence::PromptOptions {
-                                            mode: pf.options.mode.clone(),
-                                            context: pf.options.context.clone(),
-                                            worktree: pf.options.worktree,
-                                        },
-                                        state: pf.state.clone(),
-                                        queue_rank: pf.queue_rank,
-                                        session_id: pf.session_id.clone(),
-                                        worktree_path: None,
-                                        tags: pf.tags.clone(),
-                                    };
-                                    persistence::save_prompt(&dir, uuid, &updated);
-                                    break;
-                                }
-                                Err(e) => {
-                                    eprintln!("  error: {wt_path_str}: {e}");
-                                    errors += 1;
-                                    removed = true;
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        if !removed {
+        let Some(root) = worktree::owning_repo(wt_path) else {
             eprintln!("  error: {wt_path_str}: could not find parent git repo");
             errors += 1;
+            continue;
+        };
+        if dry_run {
+            println!("  would remove: {wt_path_str} (repo: {})", root.display());
+            cleaned += 1;
+            continue;
+        }
+        match worktree::remove_worktree(&root, wt_path) {
+            Ok(()) => {
+                println!("  removed: {wt_path_str} (repo: {})", root.display());
+                cleaned += 1;
+                clear_worktree_path(&dir, uuid, pf);
+            }
+            Err(e) => {
+                eprintln!("  error: {wt_path_str}: {e}");
+                errors += 1;
+            }
         }
     }
 
     let total = cleaned + skipped;
     if total == 0 && errors == 0 {
         println!("No worktrees to clean.");
+    } else if dry_run {
+        println!("Would clean {cleaned} worktree(s), {skipped} already gone, {errors} error(s). Nothing was removed.");
     } else {
         println!("Cleaned {cleaned} worktree(s), {skipped} already gone, {errors} error(s).");
     }
     if errors > 0 { 1 } else { 0 }
 }
 
+fn store_export(args: &[String]) -> i32 {
+    let mut file = None;
+    let mut format = "json".to_string();
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--format" {
+            let Some(value) = args.get(i + 1) else {
+                eprintln!("--format requires a value (md or json)");
+                return 1;
+            };
+            format = value.clone();
+            i += 2;
+        } else {
+            file = Some(args[i].clone());
+            i += 1;
+        }
+    }
+    let Some(file) = file else {
+        eprintln!("Usage: clhorde store export <file> [--format md|json]");
+        return 1;
+    };
+
+    let dir = match store_dir_or_err() {
+        Ok(d) => d,
+        Err(code) => return code,
+    };
+    let prompts = persistence::load_all_prompts(&dir);
+    let content = match render_store_export(prompts.iter().map(|(u, p)| (u.as_str(), p)), &format) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+    if let Err(e) = std::fs::write(&file, content) {
+        eprintln!("Failed to write {file}: {e}");
+        return 1;
+    }
+    println!("Exported {} prompt(s) to {file}.", prompts.len());
+    0
+}
+
+/// Render stored prompts as a JSON array of full records, or as a markdown
+/// report grouped by state.
+fn render_store_export<'a>(
+    prompts: impl Iterator<Item = (&'a str, &'a PromptFile)>,
+    format: &str,
+) -> Result<String, String> {
+    match format {
+        "json" => {
+            let records: Vec<persistence::StoredPrompt> = prompts
+                .map(|(uuid, pf)| persistence::StoredPrompt {
+                    uuid: uuid.to_string(),
+                    file: pf.clone(),
+                })
+                .collect();
+            serde_json::to_string_pretty(&records).map_err(|e| e.to_string())
+        }
+        "md" => Ok(store_markdown_report(&prompts.collect::<Vec<_>>())),
+        other => Err(format!("Unknown format: {other} (expected md or json)")),
+    }
+}
+
+fn store_markdown_report(prompts: &[(&str, &PromptFile)]) -> String {
+    let mut out = format!("# clhorde store export\n\n{} prompt(s)\n", prompts.len());
+    let mut states: Vec<&str> = VALID_STATES.to_vec();
+    for (_, p) in prompts {
+        if !states.contains(&p.state.as_str()) {
+            states.push(&p.state);
+        }
+    }
+    for state in states {
+        let group: Vec<_> = prompts.iter().filter(|(_, p)| p.state == state).collect();
+        if group.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {state} ({})\n", group.len()));
+        for (uuid, p) in group {
+            out.push_str(&format!("\n### {uuid}\n\n- mode: {}\n", p.options.mode));
+            if let Some(ref cwd) = p.options.context {
+                out.push_str(&format!("- cwd: {cwd}\n"));
+            }
+            if !p.tags.is_empty() {
+                out.push_str(&format!("- tags: {}\n", p.tags.join(", ")));
+            }
+            out.push_str(&format!("\n{}\n", p.prompt));
+            if let Some(ref output) = p.output {
+                out.push_str(&format!("\n#### Output\n\n{output}\n"));
+            }
+            if let Some(ref error) = p.error {
+                out.push_str(&format!("\n#### Error\n\n{error}\n"));
+            }
+        }
+    }
+    out
+}
+
+fn store_import(file: Option<&str>) -> i32 {
+    let Some(file) = file else {
+        eprintln!("Usage: clhorde store import <file.json>");
+        return 1;
+    };
+    let content = match std::fs::read_to_string(file) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to read {file}: {e}");
+            return 1;
+        }
+    };
+    let dir = match store_dir_or_err() {
+        Ok(d) => d,
+        Err(code) => return code,
+    };
+    match import_prompts(&dir, &content) {
+        Ok(count) => {
+            println!("Imported {count} prompt(s) into {}.", dir.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+/// Parse a JSON array of prompt records and save each as a new pending
+/// prompt (fresh UUID) after the existing ones. Malformed records are
+/// skipped with a warning. Returns how many were imported.
+fn import_prompts(dir: &std::path::Path, content: &str) -> Result<usize, String> {
+    let records: Vec<serde_json::Value> =
+        serde_json::from_str(content).map_err(|e| format!("Expected a JSON array of prompts: {e}"))?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+
+    let mut rank = persistence::load_all_prompts(dir)
+        .iter()
+        .map(|(_, p)| p.queue_rank)
+        .fold(0.0_f64, f64::max);
+    let mut imported = 0;
+    for (i, value) in records.into_iter().enumerate() {
+        match validate_import_record(value) {
+            Ok(pf) => {
+                rank += 1.0;
+                let pf = PromptFile { queue_rank: rank, ..pf };
+                persistence::save_prompt(dir, &uuid::Uuid::now_v7().to_string(), &pf)
+                    .map_err(|e| format!("Cannot write to {}: {e}", dir.display()))?;
+                imported += 1;
+            }
+            Err(e) => eprintln!("Skipping record {}: {e}", i + 1),
+        }
+    }
+    Ok(imported)
+}
+
+fn validate_import_record(value: serde_json::Value) -> Result<PromptFile, String> {
+    let record: persistence::ImportRecord = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    if record.prompt.trim().is_empty() {
+        return Err("empty prompt".to_string());
+    }
+    let options = record.options.unwrap_or(PromptOptions {
+        mode: "interactive".to_string(),
+        context: None,
+        worktree: None,
+        priority: None,
+        timeout_secs: None,
+        max_attempts: None,
+        env: Default::default(),
+    });
+    if options.mode != "interactive" && options.mode != "one_shot" {
+        return Err(format!("unknown mode '{}'", options.mode));
+    }
+    if let Some(ref priority) = options.priority {
+        if PromptPriority::from_label(priority).is_none() {
+            return Err(format!("unknown priority '{priority}'"));
+        }
+    }
+    let mut tags = Vec::new();
+    for tag in &record.tags {
+        let tag = normalize_tag(tag).ok_or_else(|| format!("invalid tag '{tag}'"))?;
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(PromptFile {
+        schema_version: persistence::SCHEMA_VERSION,
+        prompt: record.prompt,
+        options,
+        state: "pending".to_string(),
+        queue_rank: 0.0,
+        display_rank: None,
+        session_id: None,
+        worktree_path: None,
+        tags,
+        depends_on: None,
+        output: None,
+        error: None,
+        command: None,
+        resume_session_id: None,
+        stderr: None,
+        worker_pid: None,
+    })
+}
+
 // ── keys subcommands ──
 
 fn cmd_keys(args: &[String]) -> i32 {
@@ -535,44 +1675,68 @@ fn cmd_keys(args: &[String]) -> i32 {
 
 fn keys_list(mode: Option<&str>) -> i32 {
     let km = Keymap::load();
+    let defaults = Keymap::default();
 
     match mode {
-        Some("normal") => print_mode_bindings("normal", &invert_normal(&km)),
-        Some("insert") => print_mode_bindings("insert", &invert_insert(&km)),
-        Some("view") => print_mode_bindings("view", &invert_view(&km)),
-        Some("interact") => print_mode_bindings("interact", &invert_interact(&km)),
-        Some("filter") => print_mode_bindings("filter", &invert_filter(&km)),
+        Some("normal") => print_mode_bindings("normal", &invert_normal(&km), &invert_normal(&defaults)),
+        Some("insert") => print_mode_bindings("insert", &invert_insert(&km), &invert_insert(&defaults)),
+        Some("view") => {
+            print_mode_bindings("view", &invert_view(&km), &invert_view(&defaults));
+            print_conflicts(&km);
+        }
+        Some("interact") => print_mode_bindings("interact", &invert_interact(&km), &invert_interact(&defaults)),
+        Some("filter") => print_mode_bindings("filter", &invert_filter(&km), &invert_filter(&defaults)),
         Some(m) => {
             eprintln!("Unknown mode: {m}");
             eprintln!("Valid modes: normal, insert, view, interact, filter");
             return 1;
         }
         None => {
-            print_mode_bindings("normal", &invert_normal(&km));
+            print_mode_bindings("normal", &invert_normal(&km), &invert_normal(&defaults));
             println!();
-            print_mode_bindings("insert", &invert_insert(&km));
+            print_mode_bindings("insert", &invert_insert(&km), &invert_insert(&defaults));
             println!();
-            print_mode_bindings("view", &invert_view(&km));
+            print_mode_bindings("view", &invert_view(&km), &invert_view(&defaults));
+            print_conflicts(&km);
             println!();
-            print_mode_bindings("interact", &invert_interact(&km));
+            print_mode_bindings("interact", &invert_interact(&km), &invert_interact(&defaults));
             println!();
-            print_mode_bindings("filter", &invert_filter(&km));
+            print_mode_bindings("filter", &invert_filter(&km), &invert_filter(&defaults));
         }
     }
     0
 }
 
-fn print_mode_bindings(mode: &str, bindings: &[(String, Vec<String>)]) {
+fn print_mode_bindings(mode: &str, bindings: &[(String, Vec<String>)], defaults: &[(String, Vec<String>)]) {
     println!("[{mode}]");
-    for (action, keys) in bindings {
-        let keys_str: Vec<String> = keys.iter().map(|k| format!("\"{k}\"")).collect();
-        println!("{action} = [{}]", keys_str.join(", "));
+    for line in binding_lines(bindings, defaults) {
+        println!("{line}");
+    }
+}
+
+/// One line per action with its effective keys (empty when unbound),
+/// marked `default` or `custom` by comparing with the built-in keymap.
+fn binding_lines(bindings: &[(String, Vec<String>)], defaults: &[(String, Vec<String>)]) -> Vec<String> {
+    bindings
+        .iter()
+        .map(|(action, keys)| {
+            let keys_str: Vec<String> = keys.iter().map(|k| format!("\"{k}\"")).collect();
+            let is_default = defaults.iter().any(|(a, k)| a == action && k == keys);
+            let origin = if is_default { "default" } else { "custom" };
+            format!("{action} = [{}]  # {origin}", keys_str.join(", "))
+        })
+        .collect()
+}
+
+fn print_conflicts(km: &Keymap) {
+    for line in quick_prompt_conflict_lines(km) {
+        println!("# conflict: {line}");
     }
 }
 
-/// Invert a KeyCode->Action hashmap to Action->Vec<key_display_string>, sorted by action name.
+/// Invert a KeyCombo->Action hashmap to Action->Vec<key_display_string>, sorted by action name.
 fn invert_map<A: Eq + Copy>(
-    map: &HashMap<KeyCode, A>,
+    map: &HashMap<KeyCombo, A>,
     action_names: &[(A, &str)],
 ) -> Vec<(String, Vec<String>)> {
     let mut result = Vec::new();
@@ -588,30 +1752,50 @@ fn invert_map<A: Eq + Copy>(
     result
 }
 
+/// Like `invert_map`, but sequences (`g g`) count as keys too.
 fn invert_normal(km: &Keymap) -> Vec<(String, Vec<String>)> {
-    invert_map(
-        &km.normal,
-        &[
-            (NormalAction::Quit, "quit"),
-            (NormalAction::Insert, "insert"),
-            (NormalAction::SelectNext, "select_next"),
-            (NormalAction::SelectPrev, "select_prev"),
-            (NormalAction::ViewOutput, "view_output"),
-            (NormalAction::Interact, "interact"),
-            (NormalAction::IncreaseWorkers, "increase_workers"),
-            (NormalAction::DecreaseWorkers, "decrease_workers"),
-            (NormalAction::ToggleMode, "toggle_mode"),
-            (NormalAction::Retry, "retry"),
-            (NormalAction::Resume, "resume"),
-            (NormalAction::MoveUp, "move_up"),
-            (NormalAction::MoveDown, "move_down"),
-            (NormalAction::Search, "search"),
-            (NormalAction::HalfPageDown, "half_page_down"),
-            (NormalAction::HalfPageUp, "half_page_up"),
-            (NormalAction::GoToTop, "go_to_top"),
-            (NormalAction::GoToBottom, "go_to_bottom"),
-        ],
-    )
+    let names: &[(NormalAction, &str)] = &[
+        (NormalAction::Quit, "quit"),
+        (NormalAction::Insert, "insert"),
+        (NormalAction::SelectNext, "select_next"),
+        (NormalAction::SelectPrev, "select_prev"),
+        (NormalAction::ViewOutput, "view_output"),
+        (NormalAction::Interact, "interact"),
+        (NormalAction::ForceInteract, "force_interact"),
+        (NormalAction::ForcePtyInteract, "force_pty_interact"),
+        (NormalAction::IncreaseWorkers, "increase_workers"),
+        (NormalAction::DecreaseWorkers, "decrease_workers"),
+        (NormalAction::ToggleMode, "toggle_mode"),
+        (NormalAction::Retry, "retry"),
+        (NormalAction::Resume, "resume"),
+        (NormalAction::Duplicate, "duplicate"),
+        (NormalAction::EditTags, "edit_tags"),
+        (NormalAction::Export, "export"),
+        (NormalAction::ExportJson, "export_json"),
+        (NormalAction::MoveUp, "move_up"),
+        (NormalAction::MoveDown, "move_down"),
+        (NormalAction::Search, "search"),
+        (NormalAction::HalfPageDown, "half_page_down"),
+        (NormalAction::HalfPageUp, "half_page_up"),
+        (NormalAction::GoToTop, "go_to_top"),
+        (NormalAction::GoToBottom, "go_to_bottom"),
+        (NormalAction::NextUnseen, "next_unseen"),
+        (NormalAction::CyclePriority, "cycle_priority"),
+        (NormalAction::TogglePause, "toggle_pause"),
+        (NormalAction::CycleSort, "cycle_sort"),
+        (NormalAction::ToggleGrouped, "toggle_grouped"),
+        (NormalAction::ToggleGroupFold, "toggle_group_fold"),
+        (NormalAction::FoldAllGroups, "fold_all_groups"),
+        (NormalAction::UnfoldAllGroups, "unfold_all_groups"),
+        (NormalAction::ContinueSession, "continue_session"),
+        (NormalAction::FocusToggle, "focus_toggle"),
+        (NormalAction::KillAll, "kill_all"),
+        (NormalAction::Interrupt, "interrupt"),
+    ];
+    names
+        .iter()
+        .map(|&(action, name)| (name.to_string(), km.normal_bindings(action, " ")))
+        .collect()
 }
 
 fn invert_insert(km: &Keymap) -> Vec<(String, Vec<String>)> {
@@ -627,19 +1811,62 @@ fn invert_insert(km: &Keymap) -> Vec<(String, Vec<String>)> {
     )
 }
 
+const VIEW_ACTION_NAMES: &[(ViewAction, &str)] = &[
+    (ViewAction::Back, "back"),
+    (ViewAction::ScrollDown, "scroll_down"),
+    (ViewAction::ScrollUp, "scroll_up"),
+    (ViewAction::Interact, "interact"),
+    (ViewAction::ForceInteract, "force_interact"),
+    (ViewAction::ForcePtyInteract, "force_pty_interact"),
+    (ViewAction::ToggleAutoscroll, "toggle_autoscroll"),
+    (ViewAction::KillWorker, "kill_worker"),
+    (ViewAction::Export, "export"),
+    (ViewAction::ExportJson, "export_json"),
+    (ViewAction::CopyToClipboard, "copy"),
+    (ViewAction::Search, "search"),
+    (ViewAction::SearchNext, "search_next"),
+    (ViewAction::SearchPrev, "search_prev"),
+    (ViewAction::WorktreeDiff, "worktree_diff"),
+    (ViewAction::ApplyWorktree, "apply_worktree"),
+    (ViewAction::DiscardWorktree, "discard_worktree"),
+];
+
 fn invert_view(km: &Keymap) -> Vec<(String, Vec<String>)> {
-    invert_map(
-        &km.view,
-        &[
-            (ViewAction::Back, "back"),
-            (ViewAction::ScrollDown, "scroll_down"),
-            (ViewAction::ScrollUp, "scroll_up"),
-            (ViewAction::Interact, "interact"),
-            (ViewAction::ToggleAutoscroll, "toggle_autoscroll"),
-            (ViewAction::KillWorker, "kill_worker"),
-            (ViewAction::Export, "export"),
-        ],
-    )
+    invert_map(&km.view, VIEW_ACTION_NAMES)
+}
+
+fn view_action_name(action: ViewAction) -> &'static str {
+    VIEW_ACTION_NAMES
+        .iter()
+        .find(|(a, _)| *a == action)
+        .map_or("?", |(_, name)| name)
+}
+
+/// What keeps a quick prompt from firing, for warnings.
+fn conflict_description(conflict: QuickPromptConflict) -> String {
+    match conflict {
+        QuickPromptConflict::View(action) => {
+            format!("view action {}; the view action wins", view_action_name(action))
+        }
+        QuickPromptConflict::Normal(action) => format!(
+            "normal action {}; it wins over sending to a batch selection",
+            keymap::normal_label(action)
+        ),
+        QuickPromptConflict::Sequence => {
+            "the start of a normal-mode key sequence; batch selections never get it".to_string()
+        }
+    }
+}
+
+/// One warning line per binding that shadows a quick prompt.
+fn quick_prompt_conflict_lines(km: &Keymap) -> Vec<String> {
+    km.quick_prompt_conflicts()
+        .into_iter()
+        .map(|(kc, conflict)| {
+            let key = keymap::key_display(&kc);
+            format!("\"{key}\" is both a quick prompt and {}", conflict_description(conflict))
+        })
+        .collect()
 }
 
 fn invert_interact(km: &Keymap) -> Vec<(String, Vec<String>)> {
@@ -671,9 +1898,14 @@ fn keys_set(args: &[String]) -> i32 {
     let action = &args[1];
     let keys: Vec<String> = args[2..].to_vec();
 
-    // Validate all keys
+    // Validate all keys; normal mode also takes sequences like "g g"
     for k in &keys {
-        if keymap::parse_key(k).is_none() {
+        let valid = if mode == "normal" {
+            keymap::parse_binding(k).is_some()
+        } else {
+            keymap::parse_key(k).is_some()
+        };
+        if !valid {
             eprintln!("Invalid key: {k}");
             return 1;
         }
@@ -733,6 +1965,9 @@ fn cmd_config(args: &[String]) -> i32 {
             eprintln!("  path          Print config file path");
             eprintln!("  edit          Open config in $EDITOR");
             eprintln!("  init [--force] Create config with defaults");
+            eprintln!();
+            eprintln!("Theme colors ([theme] section): {}", theme::THEME_KEYS.join(", "));
+            eprintln!("  Values: color names (\"red\", \"light-blue\"), hex (\"#ff8800\") or 0-255");
             1
         }
     }
@@ -823,11 +2058,17 @@ fn action_names_for_mode(mode: &str) -> Option<Vec<&'static str>> {
             "select_prev",
             "view_output",
             "interact",
+            "force_interact",
+            "force_pty_interact",
             "increase_workers",
             "decrease_workers",
             "toggle_mode",
             "retry",
             "resume",
+            "duplicate",
+            "edit_tags",
+            "export",
+            "export_json",
             "move_up",
             "move_down",
             "search",
@@ -835,6 +2076,18 @@ fn action_names_for_mode(mode: &str) -> Option<Vec<&'static str>> {
             "half_page_up",
             "go_to_top",
             "go_to_bottom",
+            "next_unseen",
+            "cycle_priority",
+            "toggle_pause",
+            "cycle_sort",
+            "toggle_grouped",
+            "toggle_group_fold",
+            "fold_all_groups",
+            "unfold_all_groups",
+            "continue_session",
+            "focus_toggle",
+            "kill_all",
+            "interrupt",
         ]),
         "insert" => Some(vec![
             "cancel",
@@ -848,9 +2101,19 @@ fn action_names_for_mode(mode: &str) -> Option<Vec<&'static str>> {
             "scroll_down",
             "scroll_up",
             "interact",
+            "force_interact",
+            "force_pty_interact",
             "toggle_autoscroll",
             "kill_worker",
             "export",
+            "export_json",
+            "copy",
+            "search",
+            "search_next",
+            "search_prev",
+            "worktree_diff",
+            "apply_worktree",
+            "discard_worktree",
         ]),
         "interact" => Some(vec!["back", "send"]),
         "filter" => Some(vec!["confirm", "cancel"]),
@@ -885,11 +2148,17 @@ fn set_toml_action(
                 "select_prev" => b.select_prev = keys,
                 "view_output" => b.view_output = keys,
                 "interact" => b.interact = keys,
+                "force_interact" => b.force_interact = keys,
+                "force_pty_interact" => b.force_pty_interact = keys,
                 "increase_workers" => b.increase_workers = keys,
                 "decrease_workers" => b.decrease_workers = keys,
                 "toggle_mode" => b.toggle_mode = keys,
                 "retry" => b.retry = keys,
                 "resume" => b.resume = keys,
+                "duplicate" => b.duplicate = keys,
+                "edit_tags" => b.edit_tags = keys,
+                "export" => b.export = keys,
+                "export_json" => b.export_json = keys,
                 "move_up" => b.move_up = keys,
                 "move_down" => b.move_down = keys,
                 "search" => b.search = keys,
@@ -897,6 +2166,18 @@ fn set_toml_action(
                 "half_page_up" => b.half_page_up = keys,
                 "go_to_top" => b.go_to_top = keys,
                 "go_to_bottom" => b.go_to_bottom = keys,
+                "next_unseen" => b.next_unseen = keys,
+                "cycle_priority" => b.cycle_priority = keys,
+                "toggle_pause" => b.toggle_pause = keys,
+                "cycle_sort" => b.cycle_sort = keys,
+                "toggle_grouped" => b.toggle_grouped = keys,
+                "toggle_group_fold" => b.toggle_group_fold = keys,
+                "fold_all_groups" => b.fold_all_groups = keys,
+                "unfold_all_groups" => b.unfold_all_groups = keys,
+                "continue_session" => b.continue_session = keys,
+                "focus_toggle" => b.focus_toggle = keys,
+                "kill_all" => b.kill_all = keys,
+                "interrupt" => b.interrupt = keys,
                 _ => unreachable!(),
             }
         }
@@ -918,9 +2199,19 @@ fn set_toml_action(
                 "scroll_down" => b.scroll_down = keys,
                 "scroll_up" => b.scroll_up = keys,
                 "interact" => b.interact = keys,
+                "force_interact" => b.force_interact = keys,
+                "force_pty_interact" => b.force_pty_interact = keys,
                 "toggle_autoscroll" => b.toggle_autoscroll = keys,
                 "kill_worker" => b.kill_worker = keys,
                 "export" => b.export = keys,
+                "export_json" => b.export_json = keys,
+                "copy" => b.copy = keys,
+                "search" => b.search = keys,
+                "search_next" => b.search_next = keys,
+                "search_prev" => b.search_prev = keys,
+                "worktree_diff" => b.worktree_diff = keys,
+                "apply_worktree" => b.apply_worktree = keys,
+                "discard_worktree" => b.discard_worktree = keys,
                 _ => unreachable!(),
             }
         }
@@ -979,11 +2270,17 @@ fn reset_toml_action(
                     "select_prev" => b.select_prev = None,
                     "view_output" => b.view_output = None,
                     "interact" => b.interact = None,
+                    "force_interact" => b.force_interact = None,
+                    "force_pty_interact" => b.force_pty_interact = None,
                     "increase_workers" => b.increase_workers = None,
                     "decrease_workers" => b.decrease_workers = None,
                     "toggle_mode" => b.toggle_mode = None,
                     "retry" => b.retry = None,
                     "resume" => b.resume = None,
+                    "duplicate" => b.duplicate = None,
+                    "edit_tags" => b.edit_tags = None,
+                    "export" => b.export = None,
+                    "export_json" => b.export_json = None,
                     "move_up" => b.move_up = None,
                     "move_down" => b.move_down = None,
                     "search" => b.search = None,
@@ -991,6 +2288,18 @@ fn reset_toml_action(
                     "half_page_up" => b.half_page_up = None,
                     "go_to_top" => b.go_to_top = None,
                     "go_to_bottom" => b.go_to_bottom = None,
+                    "next_unseen" => b.next_unseen = None,
+                    "cycle_priority" => b.cycle_priority = None,
+                    "toggle_pause" => b.toggle_pause = None,
+                    "cycle_sort" => b.cycle_sort = None,
+                    "toggle_grouped" => b.toggle_grouped = None,
+                    "toggle_group_fold" => b.toggle_group_fold = None,
+                    "fold_all_groups" => b.fold_all_groups = None,
+                    "unfold_all_groups" => b.unfold_all_groups = None,
+                    "continue_session" => b.continue_session = None,
+                    "focus_toggle" => b.focus_toggle = None,
+                    "kill_all" => b.kill_all = None,
+                    "interrupt" => b.interrupt = None,
                     _ => unreachable!(),
                 }
             }
@@ -1016,9 +2325,19 @@ fn reset_toml_action(
                     "scroll_down" => b.scroll_down = None,
                     "scroll_up" => b.scroll_up = None,
                     "interact" => b.interact = None,
+                    "force_interact" => b.force_interact = None,
+                    "force_pty_interact" => b.force_pty_interact = None,
                     "toggle_autoscroll" => b.toggle_autoscroll = None,
                     "kill_worker" => b.kill_worker = None,
                     "export" => b.export = None,
+                    "export_json" => b.export_json = None,
+                    "copy" => b.copy = None,
+                    "search" => b.search = None,
+                    "search_next" => b.search_next = None,
+                    "search_prev" => b.search_prev = None,
+                    "worktree_diff" => b.worktree_diff = None,
+                    "apply_worktree" => b.apply_worktree = None,
+                    "discard_worktree" => b.discard_worktree = None,
                     _ => unreachable!(),
                 }
             }
@@ -1051,6 +2370,154 @@ fn reset_toml_action(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crossterm::event::KeyCode;
+
+    // ── submit ──
+
+    fn args(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn submit_parses_flags() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let req = parse_submit_args(&args(&[
+            "--cwd", &dir, "--worktree", "--mode", "one-shot", "--depends-on", "#3", "--max-attempts", "3",
+            "--timeout", "600", "--resume-session", "sess-1", "fix", "the", "bug",
+        ]))
+        .unwrap();
+        assert_eq!(req.prompt, "fix the bug");
+        assert_eq!(req.options.mode, "one_shot");
+        assert_eq!(req.options.worktree, Some(true));
+        assert_eq!(req.depends_on, Some(3));
+        assert_eq!(req.options.max_attempts, Some(3));
+        assert_eq!(req.options.timeout_secs, Some(600));
+        assert_eq!(req.resume_session_id.as_deref(), Some("sess-1"));
+        let expected = std::fs::canonicalize(tmp.path()).unwrap();
+        assert_eq!(req.options.context, Some(expected.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn submit_defaults() {
+        let req = parse_submit_args(&args(&["hello"])).unwrap();
+        assert_eq!(req.options.mode, "");
+        assert!(req.options.worktree.is_none());
+        assert!(req.options.context.is_some());
+    }
+
+    #[test]
+    fn submit_rejects_bad_input() {
+        assert!(parse_submit_args(&args(&[])).is_err());
+        assert!(parse_submit_args(&args(&["--mode", "fast", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["--cwd"])).is_err());
+        assert!(parse_submit_args(&args(&["--depends-on", "x", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["--max-attempts", "0", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["--timeout", "0", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["--timeout", "soon", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["hi", "--timeout"])).is_err());
+        assert!(parse_submit_args(&args(&["--resume-session", " ", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["--cwd", "/definitely/not/here", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["--worktre", "hi"])).is_err());
+        assert!(parse_submit_args(&args(&["hi", "--verbose"])).is_err());
+    }
+
+    #[test]
+    fn submit_double_dash_ends_options() {
+        let req = parse_submit_args(&args(&["--worktree", "--", "--explain", "the", "--mode", "flag"])).unwrap();
+        assert_eq!(req.prompt, "--explain the --mode flag");
+        assert_eq!(req.options.worktree, Some(true));
+        assert_eq!(req.options.mode, "");
+    }
+
+    #[test]
+    fn batch_parses_entries_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_string_lossy().to_string();
+        let input = format!(
+            r#"[
+                {{"text": "first", "cwd": "{dir}", "mode": "one-shot", "worktree": true, "tags": ["Backend"]}},
+                {{"text": "second", "env": {{"RUST_LOG": "debug"}}}}
+            ]"#
+        );
+        let reqs: Vec<SubmitRequest> =
+            parse_batch(&input).unwrap().into_iter().map(|item| item.request.unwrap()).collect();
+        assert_eq!(reqs[0].prompt, "@backend first");
+        assert_eq!(reqs[0].options.mode, "one_shot");
+        assert_eq!(reqs[0].options.worktree, Some(true));
+        let expected = std::fs::canonicalize(tmp.path()).unwrap();
+        assert_eq!(reqs[0].options.context, Some(expected.to_string_lossy().to_string()));
+        assert_eq!(reqs[1].prompt, "second");
+        assert_eq!(reqs[1].options.mode, "");
+        assert!(reqs[1].options.worktree.is_none());
+        assert!(reqs[0].options.env.is_empty());
+        assert_eq!(reqs[1].options.env.get("RUST_LOG").map(String::as_str), Some("debug"));
+    }
+
+    #[test]
+    fn batch_reports_each_bad_entry() {
+        let input = r#"[
+            {"text": "ok"},
+            {"text": "  "},
+            {"text": "x", "mode": "fast"},
+            {"text": "x", "cwd": "/definitely/not/here"},
+            {"text": "x", "tags": ["a b"]},
+            {"text": "x", "env": {"A=B": "1"}},
+            {"cwd": "/tmp"}
+        ]"#;
+        let parsed = parse_batch(input).unwrap();
+        assert!(parsed[0].request.is_ok());
+        assert!(parsed[1..].iter().all(|item| item.request.is_err()));
+        let lines: Vec<usize> = parsed.iter().map(|item| item.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5, 6, 7, 8]);
+        assert!(matches!(&parsed[2].request, Err(e) if e.starts_with("mode:")));
+        assert!(matches!(&parsed[5].request, Err(e) if e.starts_with("env:")));
+        assert!(matches!(&parsed[6].request, Err(e) if e.contains("text")));
+        assert!(parse_batch("{\"text\": \"not an array\"}").is_err());
+        assert!(parse_batch("[").is_err());
+    }
+
+    // ── tail ──
+
+    #[test]
+    fn tail_parses_flags() {
+        let a = parse_tail_args(&args(&["--since-byte", "128", "#4", "-f"])).unwrap();
+        assert_eq!((a.prompt_id, a.follow, a.since_byte), (4, true, 128));
+        let a = parse_tail_args(&args(&["2"])).unwrap();
+        assert_eq!((a.prompt_id, a.follow, a.since_byte), (2, false, 0));
+    }
+
+    #[test]
+    fn signal_args_default_to_int_and_reject_other_signals() {
+        assert_eq!(parse_signal_args(&args(&["#3"])), Ok((3, "INT")));
+        assert_eq!(parse_signal_args(&args(&["3", "sigtstp"])), Ok((3, "TSTP")));
+        let err = parse_signal_args(&args(&["3", "KILL"])).unwrap_err();
+        assert!(err.contains("not allowed"), "{err}");
+        assert!(parse_signal_args(&args(&[])).is_err());
+        assert!(parse_signal_args(&args(&["x"])).is_err());
+        assert!(parse_signal_args(&args(&["3", "INT", "QUIT"])).is_err());
+    }
+
+    #[test]
+    fn tail_rejects_bad_input() {
+        assert!(parse_tail_args(&args(&[])).is_err());
+        assert!(parse_tail_args(&args(&["x"])).is_err());
+        assert!(parse_tail_args(&args(&["1", "--since-byte"])).is_err());
+        assert!(parse_tail_args(&args(&["1", "--since-byte", "-5"])).is_err());
+        assert!(parse_tail_args(&args(&["1", "2"])).is_err());
+    }
+
+    #[test]
+    fn tail_target_must_be_in_the_session() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(!tail_target_known(tmp.path(), 1));
+        let status = inbox::InstanceStatus { prompt_ids: vec![2], ..Default::default() };
+        inbox::write_status(tmp.path(), &status);
+        assert!(tail_target_known(tmp.path(), 2));
+        assert!(!tail_target_known(tmp.path(), 1));
+        inbox::OutputMirror::default().append(tmp.path(), 1, "hi", 1024);
+        assert!(tail_target_known(tmp.path(), 1));
+    }
 
     #[test]
     fn action_names_for_all_modes() {
@@ -1181,6 +2648,18 @@ mod tests {
         assert!(matches!(run(&["clhorde".into(), "keys".into()]), CliAction::Exit(_)));
         assert!(matches!(run(&["clhorde".into(), "config".into()]), CliAction::Exit(_)));
         assert!(matches!(run(&["clhorde".into(), "store".into()]), CliAction::Exit(_)));
+        assert!(matches!(run(&["clhorde".into(), "filters".into()]), CliAction::Exit(1)));
+    }
+
+    #[test]
+    fn tui_requires_subcommand() {
+        assert_eq!(cmd_tui(&[]), 1);
+        assert_eq!(cmd_tui(&["restart".into()]), 1);
+    }
+
+    #[test]
+    fn clean_worktrees_rejects_unknown_option() {
+        assert_eq!(store_clean_worktrees(&["--force".into()]), 1);
     }
 
     #[test]
@@ -1338,17 +2817,30 @@ mod tests {
 
     fn make_prompt(state: &str, rank: f64) -> PromptFile {
         PromptFile {
+            schema_version: persistence::SCHEMA_VERSION,
             prompt: format!("test {state}"),
             options: PromptOptions {
                 mode: "interactive".to_string(),
                 context: None,
                 worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: Default::default(),
             },
             state: state.to_string(),
             queue_rank: rank,
+            display_rank: None,
             session_id: None,
             worktree_path: None,
             tags: Vec::new(),
+            depends_on: None,
+            output: None,
+            error: None,
+            command: None,
+            resume_session_id: None,
+            stderr: None,
+            worker_pid: None,
         }
     }
 
@@ -1356,7 +2848,7 @@ mod tests {
         let mut uuids = Vec::new();
         for (i, state) in states.iter().enumerate() {
             let uuid = uuid::Uuid::now_v7().to_string();
-            persistence::save_prompt(dir, &uuid, &make_prompt(state, i as f64));
+            persistence::save_prompt(dir, &uuid, &make_prompt(state, i as f64)).unwrap();
             uuids.push(uuid);
             std::thread::sleep(std::time::Duration::from_millis(1));
         }
@@ -1381,22 +2873,114 @@ mod tests {
 
     #[test]
     fn store_drop_no_filter_returns_error() {
-        assert_eq!(store_drop(None), 1);
+        assert_eq!(store_drop(&[]), 1);
     }
 
     #[test]
     fn store_drop_invalid_filter_returns_error() {
-        assert_eq!(store_drop(Some("bogus")), 1);
+        assert_eq!(store_drop(&["bogus".into()]), 1);
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_drop_filter_keywords() {
+        assert_eq!(parse_drop_filter(&strings(&["all"])).unwrap(), DropFilter::default());
+        assert_eq!(
+            parse_drop_filter(&strings(&["failed"])).unwrap().state.as_deref(),
+            Some("failed")
+        );
+    }
+
+    #[test]
+    fn parse_drop_filter_ids_and_tags() {
+        let f = parse_drop_filter(&strings(&["--id", "0192AB", "--id", "0192-cd", "--tag", "@Backend"])).unwrap();
+        assert_eq!(
+            f,
+            DropFilter {
+                state: None,
+                ids: vec!["0192ab".to_string(), "0192-cd".to_string()],
+                tags: vec!["backend".to_string()],
+            }
+        );
+        let f = parse_drop_filter(&strings(&["completed", "--tag", "api"])).unwrap();
+        assert_eq!(f.state.as_deref(), Some("completed"));
+        assert_eq!(f.tags, vec!["api".to_string()]);
+    }
+
+    #[test]
+    fn parse_drop_filter_errors() {
+        assert!(parse_drop_filter(&[]).is_err());
+        assert!(parse_drop_filter(&strings(&["--id"])).is_err());
+        assert!(parse_drop_filter(&strings(&["--id", ""])).is_err());
+        assert!(parse_drop_filter(&strings(&["--id", "#3"])).is_err());
+        assert!(parse_drop_filter(&strings(&["--id", "x"])).is_err());
+        assert!(parse_drop_filter(&strings(&["--tag", "@"])).is_err());
+        assert!(parse_drop_filter(&strings(&["failed", "completed"])).is_err());
+        assert!(parse_drop_filter(&strings(&["--force"])).is_err());
+    }
+
+    #[test]
+    fn drop_matching_by_id_and_tag() {
+        let dir = temp_store_dir();
+        let uuids = seed_store(&dir, &["completed", "failed", "completed", "pending"]);
+        let mut tagged = make_prompt("completed", 2.0);
+        tagged.tags = vec!["api".to_string()];
+        persistence::save_prompt(&dir, &uuids[2], &tagged).unwrap();
+
+        let filter = parse_drop_filter(&strings(&["--id", &uuids[1], "--id", &uuids[3]])).unwrap();
+        assert_eq!(drop_matching(&dir, &filter).unwrap(), vec![uuids[1].clone(), uuids[3].clone()]);
+        let filter = parse_drop_filter(&strings(&["completed", "--tag", "API"])).unwrap();
+        assert_eq!(drop_matching(&dir, &filter).unwrap(), vec![uuids[2].clone()]);
+        let remaining = persistence::load_all_prompts(&dir);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, uuids[0]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn drop_matching_by_uuid_prefix() {
+        let dir = temp_store_dir();
+        let uuids = ["0192aaaa-0000-7000-8000-000000000001", "0192aabb-0000-7000-8000-000000000002"];
+        for (i, uuid) in uuids.iter().enumerate() {
+            persistence::save_prompt(&dir, uuid, &make_prompt("completed", i as f64)).unwrap();
+        }
+
+        // An ambiguous or unknown prefix drops nothing
+        let filter = parse_drop_filter(&strings(&["--id", "0192aa"])).unwrap();
+        assert!(drop_matching(&dir, &filter).unwrap_err().contains("matches 2 prompts"));
+        let filter = parse_drop_filter(&strings(&["--id", "ffff"])).unwrap();
+        assert!(drop_matching(&dir, &filter).is_err());
+        assert_eq!(persistence::load_all_prompts(&dir).len(), 2);
+
+        let filter = parse_drop_filter(&strings(&["--id", "0192AABB"])).unwrap();
+        assert_eq!(drop_matching(&dir, &filter).unwrap(), vec![uuids[1].to_string()]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn drop_matching_killed_leaves_failed() {
+        let dir = temp_store_dir();
+        seed_store(&dir, &["failed", "killed", "completed"]);
+        let filter = parse_drop_filter(&strings(&["killed"])).unwrap();
+        assert_eq!(drop_matching(&dir, &filter).unwrap().len(), 1);
+        let remaining = persistence::load_all_prompts(&dir);
+        assert!(remaining.iter().all(|(_, p)| p.state != "killed"));
+        assert_eq!(remaining.len(), 2);
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
     fn store_keep_no_filter_returns_error() {
-        assert_eq!(store_keep(None), 1);
+        assert_eq!(store_keep(&[]), 1);
     }
 
     #[test]
     fn store_keep_invalid_filter_returns_error() {
-        assert_eq!(store_keep(Some("bogus")), 1);
+        assert_eq!(store_keep(&["bogus".into()]), 1);
+        assert_eq!(store_keep(&strings(&["failed", "completed"])), 1);
     }
 
     #[test]
@@ -1433,6 +3017,55 @@ mod tests {
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn keep_removals_lists_other_states() {
+        let dir = temp_store_dir();
+        let uuids = seed_store(&dir, &["completed", "failed", "completed", "pending"]);
+        let prompts = persistence::load_all_prompts(&dir);
+        assert_eq!(keep_removals(&prompts, "completed"), vec![uuids[1].clone(), uuids[3].clone()]);
+        assert_eq!(keep_removals(&prompts, "running").len(), 4);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn binding_lines_mark_overridden_actions_custom() {
+        let config: TomlConfig = toml::from_str("[normal]\nquit = [\"Q\"]\n").unwrap();
+        let km = Keymap::from_toml(config);
+        let lines = binding_lines(&invert_normal(&km), &invert_normal(&Keymap::default()));
+
+        // Every action is listed, overridden or not
+        assert_eq!(lines.len(), invert_normal(&Keymap::default()).len());
+        assert!(lines.contains(&"quit = [\"Q\"]  # custom".to_string()));
+        assert!(lines.contains(&"insert = [\"i\"]  # default".to_string()));
+        assert!(lines.contains(&"select_next = [\"Down\", \"j\"]  # default".to_string()));
+        assert_eq!(lines.iter().filter(|l| l.ends_with("# custom")).count(), 1);
+    }
+
+    #[test]
+    fn conflict_lines_name_the_view_action() {
+        let mut km = Keymap::default();
+        km.quick_prompts.insert(KeyCode::Char('L').into(), "let's go".to_string());
+        assert!(quick_prompt_conflict_lines(&km).is_empty());
+
+        km.quick_prompts.insert(KeyCode::Char('q').into(), "quit".to_string());
+        km.quick_prompts.insert(KeyCode::Char('g').into(), "let's go".to_string());
+        assert_eq!(
+            quick_prompt_conflict_lines(&km),
+            vec![
+                "\"g\" is both a quick prompt and the start of a normal-mode key sequence; batch selections never get it",
+                "\"q\" is both a quick prompt and view action back; the view action wins",
+                "\"q\" is both a quick prompt and normal action quit; it wins over sending to a batch selection",
+            ]
+        );
+    }
+
+    #[test]
+    fn confirm_accepts_only_yes() {
+        for (answer, expected) in [("y\n", true), ("YES\n", true), ("n\n", false), ("\n", false), ("", false)] {
+            assert_eq!(confirm("Remove?", &mut std::io::Cursor::new(answer)), expected, "{answer:?}");
+        }
+    }
+
     #[test]
     fn store_keep_by_state_filters_correctly() {
         let dir = temp_store_dir();
@@ -1450,4 +3083,97 @@ mod tests {
         assert!(remaining.iter().all(|(_, p)| p.state == "completed"));
         let _ = fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn store_export_requires_file() {
+        assert_eq!(store_export(&[]), 1);
+        assert_eq!(store_export(&["out.json".into(), "--format".into()]), 1);
+    }
+
+    #[test]
+    fn store_export_json_has_full_records() {
+        let mut pf = make_prompt("completed", 1.0);
+        pf.output = Some("done".to_string());
+        let content = render_store_export([("u-1", &pf)].into_iter(), "json").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(value[0]["uuid"], "u-1");
+        assert_eq!(value[0]["prompt"], "test completed");
+        assert_eq!(value[0]["state"], "completed");
+        assert_eq!(value[0]["options"]["mode"], "interactive");
+        assert_eq!(value[0]["output"], "done");
+    }
+
+    #[test]
+    fn store_export_markdown_groups_by_state() {
+        let a = make_prompt("pending", 1.0);
+        let b = make_prompt("completed", 2.0);
+        let c = make_prompt("pending", 3.0);
+        let prompts = [("u-a", &a), ("u-b", &b), ("u-c", &c)];
+        let content = render_store_export(prompts.into_iter(), "md").unwrap();
+        let completed = content.find("## completed (1)").unwrap();
+        let pending = content.find("## pending (2)").unwrap();
+        assert!(completed < pending);
+        assert!(content[pending..].contains("### u-a"));
+        assert!(content[pending..].contains("### u-c"));
+    }
+
+    #[test]
+    fn store_export_rejects_unknown_format() {
+        assert!(render_store_export(std::iter::empty(), "xml").is_err());
+    }
+
+    #[test]
+    fn store_import_requires_file() {
+        assert_eq!(store_import(None), 1);
+        assert_eq!(store_import(Some("/nonexistent/clhorde-import.json")), 1);
+    }
+
+    #[test]
+    fn import_prompts_creates_pending_and_skips_bad() {
+        let dir = temp_store_dir().join("nested");
+        let content = r#"[
+            {"prompt": "first", "tags": ["API", "api"]},
+            {"prompt": "second", "options": {"mode": "one_shot", "context": "/tmp"}},
+            {"prompt": "   "},
+            {"prompt": "bad mode", "options": {"mode": "sideways", "context": null}},
+            {"text": "missing prompt"}
+        ]"#;
+        assert_eq!(import_prompts(&dir, content), Ok(2));
+
+        let loaded = persistence::load_all_prompts(&dir);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].1.prompt, "first");
+        assert_eq!(loaded[0].1.state, "pending");
+        assert_eq!(loaded[0].1.tags, vec!["api"]);
+        assert_eq!(loaded[1].1.options.mode, "one_shot");
+        assert!(loaded[1].1.queue_rank > loaded[0].1.queue_rank);
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn import_accepts_exported_records() {
+        let src = make_prompt("completed", 1.0);
+        let exported = render_store_export([("u-1", &src)].into_iter(), "json").unwrap();
+        let dir = temp_store_dir();
+        assert_eq!(import_prompts(&dir, &exported), Ok(1));
+        let loaded = persistence::load_all_prompts(&dir);
+        assert_eq!(loaded[0].1.prompt, "test completed");
+        assert_eq!(loaded[0].1.state, "pending");
+        assert_ne!(loaded[0].0, "u-1");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn import_rejects_non_array() {
+        let dir = temp_store_dir();
+        assert!(import_prompts(&dir, "{}").is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filters_add_validates_args() {
+        assert_eq!(filters_add(&["only-name".into()]), 1);
+        assert_eq!(filters_add(&["bad name".into(), "@x".into()]), 1);
+        assert_eq!(filters_remove(&[]), 1);
+    }
 }
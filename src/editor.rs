@@ -1,9 +1,34 @@
+use std::collections::VecDeque;
+
+/// Maximum number of undo states kept per buffer.
+const UNDO_LIMIT: usize = 100;
+
+/// Buffer contents and cursor, as saved on the undo/redo stacks.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+}
+
 /// A multi-line text buffer with cursor tracking for the input bar.
 #[derive(Debug)]
 pub struct TextBuffer {
     lines: Vec<String>,
     row: usize,
     col: usize,
+    undo_stack: VecDeque<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    /// Cursor position after the last single-char insert; typing on from
+    /// there extends the same undo step.
+    coalesce_at: Option<(usize, usize)>,
+    /// Set by `set_template` while `{name}` fields remain to be filled.
+    filling: bool,
+    /// Name of the placeholder the cursor was last moved into.
+    field: Option<String>,
+    /// Where that placeholder's `{name}` still stands; the first character
+    /// typed there replaces it, while Tab leaves it as literal text.
+    field_span: Option<(usize, std::ops::Range<usize>)>,
 }
 
 #[allow(dead_code)]
@@ -13,6 +38,12 @@ impl TextBuffer {
             lines: vec![String::new()],
             row: 0,
             col: 0,
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            coalesce_at: None,
+            filling: false,
+            field: None,
+            field_span: None,
         }
     }
 
@@ -24,10 +55,19 @@ impl TextBuffer {
         };
         let row = lines.len() - 1;
         let col = lines[row].len();
-        Self { lines, row, col }
+        Self {
+            lines,
+            row,
+            col,
+            ..Self::new()
+        }
     }
 
     pub fn set(&mut self, s: &str) {
+        self.push_undo();
+        self.filling = false;
+        self.field = None;
+        self.field_span = None;
         self.lines = if s.is_empty() {
             vec![String::new()]
         } else {
@@ -38,6 +78,10 @@ impl TextBuffer {
     }
 
     pub fn clear(&mut self) {
+        self.push_undo();
+        self.filling = false;
+        self.field = None;
+        self.field_span = None;
         self.lines = vec![String::new()];
         self.row = 0;
         self.col = 0;
@@ -75,11 +119,17 @@ impl TextBuffer {
     // ── Editing ──
 
     pub fn insert_char(&mut self, c: char) {
+        self.overwrite_field();
+        if self.coalesce_at != Some((self.row, self.col)) {
+            self.push_undo();
+        }
         self.lines[self.row].insert(self.col, c);
         self.col += c.len_utf8();
+        self.coalesce_at = Some((self.row, self.col));
     }
 
     pub fn insert_newline(&mut self) {
+        self.push_undo();
         let rest = self.lines[self.row].split_off(self.col);
         self.row += 1;
         self.lines.insert(self.row, rest);
@@ -87,6 +137,7 @@ impl TextBuffer {
     }
 
     pub fn backspace(&mut self) {
+        self.push_undo();
         if self.col > 0 {
             // Find the previous char boundary
             let prev = self.lines[self.row][..self.col]
@@ -106,6 +157,7 @@ impl TextBuffer {
     }
 
     pub fn delete(&mut self) {
+        self.push_undo();
         if self.col < self.lines[self.row].len() {
             self.lines[self.row].remove(self.col);
         } else if self.row + 1 < self.lines.len() {
@@ -115,6 +167,155 @@ impl TextBuffer {
         }
     }
 
+    /// Delete the word before the cursor, plus any whitespace after it. At the
+    /// start of a line this joins with the previous line, like `backspace`.
+    pub fn delete_word_backward(&mut self) {
+        if self.col == 0 {
+            self.backspace();
+            return;
+        }
+        self.push_undo();
+        let start = word_start(&self.lines[self.row][..self.col]);
+        self.lines[self.row].replace_range(start..self.col, "");
+        self.col = start;
+    }
+
+    /// Delete from the cursor to the end of the line. At the end of a line this
+    /// joins the next line instead.
+    pub fn delete_to_end_of_line(&mut self) {
+        if self.col < self.lines[self.row].len() {
+            self.push_undo();
+            self.lines[self.row].truncate(self.col);
+        } else {
+            self.delete();
+        }
+    }
+
+    // ── Template placeholders ──
+
+    /// Load template text. If it has `{name}` placeholders, the cursor goes
+    /// to the first one and `next_placeholder` moves through the rest;
+    /// otherwise the text gets a trailing space to type on after. A field
+    /// keeps its `{name}` until something is typed into it.
+    pub fn set_template(&mut self, text: &str) {
+        if !text.split('\n').any(|line| !placeholder_spans(line).is_empty()) {
+            self.set(&format!("{text} "));
+            return;
+        }
+        self.set(text);
+        self.filling = true;
+        self.row = 0;
+        self.col = 0;
+        self.next_placeholder();
+    }
+
+    /// Move to the next placeholder after the cursor, skipping the one the
+    /// cursor is on if nothing was typed into it, so it stays literal.
+    /// Returns `false`, and ends placeholder navigation, once none are left.
+    pub fn next_placeholder(&mut self) -> bool {
+        if !self.filling {
+            return false;
+        }
+        let from = match self.field_span.take() {
+            Some((row, span)) if (row, span.start) == (self.row, self.col) => span.end,
+            _ => self.col,
+        };
+        let next = (self.row..self.lines.len()).find_map(|row| {
+            let from = if row == self.row { from } else { 0 };
+            placeholder_spans(&self.lines[row])
+                .into_iter()
+                .find(|span| span.start >= from)
+                .map(|span| (row, span))
+        });
+        let Some((row, span)) = next else {
+            self.filling = false;
+            self.field = None;
+            return false;
+        };
+        self.field = Some(self.lines[row][span.start + 1..span.end - 1].to_string());
+        self.row = row;
+        self.col = span.start;
+        self.field_span = Some((row, span));
+        true
+    }
+
+    /// Remove the `{name}` of the field under the cursor before the first
+    /// character typed into it.
+    fn overwrite_field(&mut self) {
+        let Some((row, span)) = self.field_span.take() else {
+            return;
+        };
+        if (row, span.start) != (self.row, self.col) {
+            return;
+        }
+        self.push_undo();
+        self.lines[row].replace_range(span, "");
+        // The typed character joins this undo step
+        self.coalesce_at = Some((self.row, self.col));
+    }
+
+    /// Placeholder being filled in, while template navigation is active.
+    pub fn placeholder(&self) -> Option<&str> {
+        self.field.as_deref().filter(|_| self.filling)
+    }
+
+    // ── Undo ──
+
+    /// Restore the state before the last edit. Returns `false` if there is
+    /// nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let current = self.snapshot();
+        // Skip states identical to the current one (left by no-op edits)
+        while let Some(prev) = self.undo_stack.pop_back() {
+            if prev != current {
+                self.redo_stack.push(current);
+                self.restore(prev);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Re-apply the last undone edit. Returns `false` if there is nothing to
+    /// redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(next) = self.redo_stack.pop() else {
+            return false;
+        };
+        self.undo_stack.push_back(self.snapshot());
+        self.restore(next);
+        true
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            row: self.row,
+            col: self.col,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.row = snapshot.row;
+        self.col = snapshot.col;
+        self.coalesce_at = None;
+    }
+
+    /// Record the current state as an undo point before a mutating edit.
+    fn push_undo(&mut self) {
+        self.coalesce_at = None;
+        let snapshot = self.snapshot();
+        if self.undo_stack.back() == Some(&snapshot) {
+            return;
+        }
+        if self.undo_stack.len() >= UNDO_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(snapshot);
+        self.redo_stack.clear();
+    }
+
     // ── Movement ──
 
     pub fn move_left(&mut self) {
@@ -162,6 +363,40 @@ impl TextBuffer {
         true
     }
 
+    /// Move cursor to the start of the previous word, crossing line breaks.
+    pub fn move_word_left(&mut self) {
+        loop {
+            let before = &self.lines[self.row][..self.col];
+            if !before.trim_end().is_empty() {
+                self.col = word_start(before);
+                return;
+            }
+            if self.row == 0 {
+                self.col = 0;
+                return;
+            }
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+        }
+    }
+
+    /// Move cursor to the end of the next word, crossing line breaks.
+    pub fn move_word_right(&mut self) {
+        loop {
+            let after = &self.lines[self.row][self.col..];
+            if !after.trim_start().is_empty() {
+                self.col += word_end(after);
+                return;
+            }
+            if self.row + 1 >= self.lines.len() {
+                self.col = self.lines[self.row].len();
+                return;
+            }
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
     pub fn move_home(&mut self) {
         self.col = 0;
     }
@@ -177,6 +412,44 @@ impl TextBuffer {
     }
 }
 
+/// Byte ranges of `{name}` placeholders in `line`, in order. A name is
+/// non-empty and has no whitespace or braces, so `{}` and code like
+/// `{ a: 1 }` are left alone.
+pub fn placeholder_spans(line: &str) -> Vec<std::ops::Range<usize>> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(open) = line[from..].find('{').map(|i| from + i) {
+        let name_end = line[open + 1..]
+            .find(|c: char| c.is_whitespace() || c == '{' || c == '}')
+            .map(|i| open + 1 + i);
+        match name_end {
+            Some(close) if close > open + 1 && line[close..].starts_with('}') => {
+                spans.push(open..close + 1);
+                from = close + 1;
+            }
+            _ => from = open + 1,
+        }
+    }
+    spans
+}
+
+/// Byte offset where the last word of `s` starts, ignoring trailing whitespace.
+fn word_start(s: &str) -> usize {
+    s.trim_end()
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0)
+}
+
+/// Byte offset just past the first word of `s`, skipping leading whitespace.
+fn word_end(s: &str) -> usize {
+    let trimmed = s.trim_start();
+    let skipped = s.len() - trimmed.len();
+    skipped + trimmed.find(char::is_whitespace).unwrap_or(trimmed.len())
+}
+
 impl std::fmt::Display for TextBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut first = true;
@@ -467,4 +740,244 @@ mod tests {
         buf.move_left(); // back to '日'
         assert_eq!(buf.col, 3);
     }
+
+    #[test]
+    fn delete_word_backward_within_and_across_lines() {
+        let mut buf = TextBuffer::from_string("fix the\nlogin  bug ");
+        buf.delete_word_backward();
+        assert_eq!(buf.to_string(), "fix the\nlogin  ");
+        buf.delete_word_backward();
+        assert_eq!(buf.to_string(), "fix the\n");
+        assert_eq!(buf.cursor(), (1, 0));
+        buf.delete_word_backward(); // joins with previous line
+        assert_eq!(buf.to_string(), "fix the");
+        assert_eq!(buf.cursor(), (0, 7));
+        buf.delete_word_backward();
+        assert_eq!(buf.to_string(), "fix ");
+    }
+
+    #[test]
+    fn move_word_left_crosses_lines() {
+        let mut buf = TextBuffer::from_string("one two\n  three");
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), (1, 2));
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), (0, 4));
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), (0, 0));
+        buf.move_word_left();
+        assert_eq!(buf.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn move_word_right_crosses_lines() {
+        let mut buf = TextBuffer::from_string("one two\n  three");
+        buf.row = 0;
+        buf.col = 0;
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), (0, 3));
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), (0, 7));
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), (1, 7));
+        buf.move_word_right();
+        assert_eq!(buf.cursor(), (1, 7));
+    }
+
+    #[test]
+    fn word_motion_unicode() {
+        let mut buf = TextBuffer::from_string("héllo wörld");
+        buf.move_word_left();
+        assert_eq!(buf.col, "héllo ".len());
+        buf.delete_word_backward();
+        assert_eq!(buf.to_string(), "wörld");
+    }
+
+    #[test]
+    fn delete_to_end_of_line_truncates_then_joins() {
+        let mut buf = TextBuffer::from_string("hello world\nnext");
+        buf.row = 0;
+        buf.col = 5;
+        buf.delete_to_end_of_line();
+        assert_eq!(buf.to_string(), "hello\nnext");
+        assert_eq!(buf.cursor(), (0, 5));
+        buf.delete_to_end_of_line(); // at end of line: join next
+        assert_eq!(buf.to_string(), "hellonext");
+        buf.move_to_end();
+        buf.delete_to_end_of_line(); // end of buffer: no-op
+        assert_eq!(buf.to_string(), "hellonext");
+    }
+
+    #[test]
+    fn undo_redo_round_trip_typing() {
+        let mut buf = TextBuffer::new();
+        for c in "hello".chars() {
+            buf.insert_char(c);
+        }
+        buf.insert_newline();
+        for c in "world".chars() {
+            buf.insert_char(c);
+        }
+        assert!(buf.undo()); // "world" is one coalesced step
+        assert_eq!(buf.to_string(), "hello\n");
+        assert!(buf.undo());
+        assert_eq!(buf.to_string(), "hello");
+        assert!(buf.undo());
+        assert_eq!(buf.to_string(), "");
+        assert!(!buf.undo());
+        assert!(buf.redo());
+        assert!(buf.redo());
+        assert!(buf.redo());
+        assert_eq!(buf.to_string(), "hello\nworld");
+        assert_eq!(buf.cursor(), (1, 5));
+        assert!(!buf.redo());
+    }
+
+    #[test]
+    fn cursor_move_breaks_coalescing() {
+        let mut buf = TextBuffer::new();
+        buf.insert_char('a');
+        buf.insert_char('c');
+        buf.move_left();
+        buf.insert_char('b');
+        assert_eq!(buf.to_string(), "abc");
+        buf.undo();
+        assert_eq!(buf.to_string(), "ac");
+        assert_eq!(buf.cursor(), (0, 1));
+    }
+
+    #[test]
+    fn set_and_clear_create_undo_points() {
+        let mut buf = TextBuffer::from_string("draft");
+        buf.set("from history");
+        buf.clear();
+        assert!(buf.is_empty());
+        buf.undo();
+        assert_eq!(buf.to_string(), "from history");
+        buf.undo();
+        assert_eq!(buf.to_string(), "draft");
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut buf = TextBuffer::from_string("ab");
+        buf.backspace();
+        buf.undo();
+        buf.insert_newline();
+        assert!(!buf.redo());
+        assert_eq!(buf.to_string(), "ab\n");
+    }
+
+    #[test]
+    fn noop_edits_are_skipped_by_undo() {
+        let mut buf = TextBuffer::from_string("ab");
+        buf.insert_char('c');
+        buf.move_to_end();
+        buf.delete(); // nothing after the cursor
+        buf.undo();
+        assert_eq!(buf.to_string(), "ab");
+    }
+
+    #[test]
+    fn undo_stack_is_bounded() {
+        let mut buf = TextBuffer::new();
+        for _ in 0..(UNDO_LIMIT + 20) {
+            buf.insert_newline();
+        }
+        let mut undone = 0;
+        while buf.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+        assert_eq!(buf.line_count(), 21);
+    }
+
+    // ── Template placeholders ──
+
+    #[test]
+    fn placeholder_spans_in_order() {
+        let line = "Review {file} for {issue}";
+        let spans = placeholder_spans(line);
+        assert_eq!(spans, vec![7..13, 18..25]);
+        assert_eq!(&line[spans[1].clone()], "{issue}");
+    }
+
+    #[test]
+    fn placeholder_spans_skip_non_names() {
+        assert!(placeholder_spans("{} and { a: 1 } and {a b}").is_empty());
+        assert_eq!(placeholder_spans("{{x}}"), vec![1..4]);
+        assert_eq!(placeholder_spans("é{ü}"), vec![2..6]);
+    }
+
+    #[test]
+    fn template_without_placeholders_gets_trailing_space() {
+        let mut buf = TextBuffer::new();
+        buf.set_template("Fix the bug");
+        assert_eq!(buf.to_string(), "Fix the bug ");
+        assert_eq!(buf.placeholder(), None);
+        assert!(!buf.next_placeholder());
+    }
+
+    #[test]
+    fn template_placeholders_are_filled_in_order() {
+        let mut buf = TextBuffer::new();
+        buf.set_template("Review {file} for {issue}");
+        assert_eq!(buf.to_string(), "Review {file} for {issue}");
+        assert_eq!(buf.cursor(), (0, 7));
+        assert_eq!(buf.placeholder(), Some("file"));
+        for c in "main.rs".chars() {
+            buf.insert_char(c);
+        }
+        assert!(buf.next_placeholder());
+        assert_eq!(buf.placeholder(), Some("issue"));
+        for c in "leaks".chars() {
+            buf.insert_char(c);
+        }
+        assert_eq!(buf.to_string(), "Review main.rs for leaks");
+        // All filled: navigation ends and Tab is plain again
+        assert!(!buf.next_placeholder());
+        assert_eq!(buf.placeholder(), None);
+        assert_eq!(buf.cursor(), (0, 24));
+    }
+
+    #[test]
+    fn template_placeholders_span_lines() {
+        let mut buf = TextBuffer::new();
+        buf.set_template("{a}\nthen {b}");
+        assert_eq!(buf.placeholder(), Some("a"));
+        assert!(buf.next_placeholder());
+        assert_eq!(buf.cursor(), (1, 5));
+        assert_eq!(buf.placeholder(), Some("b"));
+        // Navigation only moves forward
+        buf.set_template("{x} {y}");
+        buf.move_to_end();
+        assert!(!buf.next_placeholder());
+        assert_eq!(buf.to_string(), "{x} {y}");
+    }
+
+    #[test]
+    fn skipped_placeholder_stays_literal() {
+        let mut buf = TextBuffer::new();
+        buf.set_template("Fix {foo} in {file}");
+        assert_eq!(buf.placeholder(), Some("foo"));
+        assert!(buf.next_placeholder());
+        assert_eq!(buf.placeholder(), Some("file"));
+        for c in "a.rs".chars() {
+            buf.insert_char(c);
+        }
+        assert_eq!(buf.to_string(), "Fix {foo} in a.rs");
+        assert!(!buf.next_placeholder());
+        // Filling a field is a single undo step
+        assert!(buf.undo());
+        assert_eq!(buf.to_string(), "Fix {foo} in {file}");
+    }
+
+    #[test]
+    fn set_ends_placeholder_navigation() {
+        let mut buf = TextBuffer::new();
+        buf.set_template("{a} {b}");
+        buf.set("plain {c}");
+        assert_eq!(buf.placeholder(), None);
+        assert!(!buf.next_placeholder());
+    }
 }
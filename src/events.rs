@@ -0,0 +1,187 @@
+use std::io::{self, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::prompt::Prompt;
+
+/// One line of the `event_socket` stream. Serialized as a JSON object with
+/// an `event` field naming the variant, e.g.
+/// `{"event":"finished","id":3,"status":"completed","exit_code":0}`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A prompt was queued (typed, submitted, duplicated or retried).
+    Added { id: usize, uuid: String, text: String, mode: String },
+    /// A worker was spawned for the prompt.
+    Started { id: usize },
+    /// The prompt reached its final state; retried attempts don't emit this.
+    Finished { id: usize, status: String, exit_code: Option<i32> },
+    /// Something went wrong outside any one prompt, e.g. saving to disk.
+    Error { message: String },
+}
+
+impl Event {
+    pub fn added(prompt: &Prompt) -> Self {
+        Event::Added {
+            id: prompt.id,
+            uuid: prompt.uuid.clone(),
+            text: prompt.text.clone(),
+            mode: prompt.mode.label().to_string(),
+        }
+    }
+
+    pub fn finished(prompt: &Prompt, exit_code: Option<i32>) -> Self {
+        Event::Finished {
+            id: prompt.id,
+            status: prompt.state_label().to_string(),
+            exit_code,
+        }
+    }
+}
+
+/// Read-only Unix socket that streams newline-delimited JSON events to
+/// every connected reader. Anything a reader writes is ignored.
+pub struct EventSocket {
+    path: PathBuf,
+    readers: Arc<Mutex<Vec<UnixStream>>>,
+}
+
+impl EventSocket {
+    /// Bind `path`, replacing a stale socket left by a crashed instance,
+    /// and accept readers on a background thread.
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(io::ErrorKind::AddrInUse, "another instance is serving this socket"));
+            }
+            std::fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let readers: Arc<Mutex<Vec<UnixStream>>> = Arc::default();
+        let accepted = Arc::clone(&readers);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A reader that stops draining the socket is dropped rather
+                // than allowed to stall the UI
+                if stream.set_nonblocking(true).is_ok() {
+                    if let Ok(mut readers) = accepted.lock() {
+                        readers.push(stream);
+                    }
+                }
+            }
+        });
+        Ok(Self { path: path.to_path_buf(), readers })
+    }
+
+    /// Send the event to every reader, dropping those that disconnected
+    /// or fell behind.
+    pub fn emit(&self, event: &Event) {
+        let Ok(mut line) = serde_json::to_vec(event) else {
+            return;
+        };
+        line.push(b'\n');
+        if let Ok(mut readers) = self.readers.lock() {
+            readers.retain_mut(|stream| stream.write_all(&line).is_ok());
+        }
+    }
+
+    #[cfg(test)]
+    pub fn reader_count(&self) -> usize {
+        self.readers.lock().map(|r| r.len()).unwrap_or(0)
+    }
+}
+
+impl Drop for EventSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{PromptMode, PromptStatus};
+    use std::io::{BufRead, BufReader};
+    use std::time::{Duration, Instant};
+
+    fn connect(path: &Path) -> BufReader<UnixStream> {
+        let stream = UnixStream::connect(path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        BufReader::new(stream)
+    }
+
+    /// Wait for the accept thread to register the reader, then emit `event`
+    /// and return the line the reader got.
+    fn first_line(socket: &EventSocket, reader: &mut BufReader<UnixStream>, event: &Event) -> String {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while socket.reader_count() == 0 {
+            assert!(Instant::now() < deadline, "reader never registered");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        socket.emit(event);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        line
+    }
+
+    #[test]
+    fn streams_events_as_json_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("events.sock");
+        let socket = EventSocket::bind(&path).unwrap();
+        let mut reader = connect(&path);
+
+        let mut prompt = Prompt::new(3, "write tests".to_string(), None, PromptMode::OneShot);
+        let line = first_line(&socket, &mut reader, &Event::added(&prompt));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["event"], "added");
+        assert_eq!(json["id"], 3);
+        assert_eq!(json["text"], "write tests");
+        assert_eq!(json["mode"], "one-shot");
+
+        socket.emit(&Event::Started { id: 3 });
+        prompt.status = PromptStatus::Completed;
+        socket.emit(&Event::finished(&prompt, Some(0)));
+
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "{\"event\":\"started\",\"id\":3}\n");
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "{\"event\":\"finished\",\"id\":3,\"status\":\"completed\",\"exit_code\":0}\n");
+    }
+
+    #[test]
+    fn disconnected_reader_is_dropped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("events.sock");
+        let socket = EventSocket::bind(&path).unwrap();
+        let mut reader = connect(&path);
+        first_line(&socket, &mut reader, &Event::Started { id: 1 });
+        drop(reader);
+
+        // The first write after the close may still succeed; the next fails
+        socket.emit(&Event::Started { id: 2 });
+        socket.emit(&Event::Started { id: 3 });
+        assert_eq!(socket.reader_count(), 0);
+    }
+
+    #[test]
+    fn bind_replaces_stale_socket_and_removes_it_on_drop() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("events.sock");
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let socket = EventSocket::bind(&path).unwrap();
+        assert!(EventSocket::bind(&path).is_err());
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
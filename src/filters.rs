@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Named filter strings, recalled in filter mode with `!name`.
+#[derive(Deserialize, Serialize, Default)]
+struct FiltersConfig {
+    #[serde(default)]
+    filters: BTreeMap<String, String>,
+}
+
+pub fn filters_path() -> Option<PathBuf> {
+    let config_dir = env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .ok()
+        .or_else(|| {
+            env::var("HOME")
+                .ok()
+                .map(|h| PathBuf::from(h).join(".config"))
+        })?;
+    Some(config_dir.join("clhorde").join("filters.toml"))
+}
+
+pub fn load_filters() -> HashMap<String, String> {
+    filters_path().map(|p| load_filters_from(&p)).unwrap_or_default()
+}
+
+/// Read `[filters]` from `path`, falling back to flat key-value pairs
+/// (no section), like `templates.toml`.
+pub fn load_filters_from(path: &Path) -> HashMap<String, String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    match toml::from_str::<FiltersConfig>(&content) {
+        Ok(config) if !config.filters.is_empty() => config.filters.into_iter().collect(),
+        _ => toml::from_str::<HashMap<String, String>>(&content).unwrap_or_default(),
+    }
+}
+
+pub fn save_filters(filters: &HashMap<String, String>) -> io::Result<()> {
+    let path = filters_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot determine config path"))?;
+    save_filters_to(&path, filters)
+}
+
+pub fn save_filters_to(path: &Path, filters: &HashMap<String, String>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let config = FiltersConfig {
+        filters: filters.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+    };
+    let content = toml::to_string_pretty(&config).map_err(io::Error::other)?;
+    fs::write(path, content)
+}
+
+/// Whether `name` can be saved and recalled as a `!name` token.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Replace each `!name` token with its saved filter string. Unknown names
+/// are kept as plain text.
+pub fn expand(input: &str, filters: &HashMap<String, String>) -> String {
+    input
+        .split_whitespace()
+        .map(|word| {
+            word.strip_prefix('!')
+                .and_then(|name| filters.get(name))
+                .map(String::as_str)
+                .unwrap_or(word)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_replaces_known_names() {
+        let mut filters = HashMap::new();
+        filters.insert("bf".to_string(), "@backend status:failed".to_string());
+        assert_eq!(expand("!bf  login", &filters), "@backend status:failed login");
+        assert_eq!(expand("!nope login", &filters), "!nope login");
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("clhorde").join("filters.toml");
+        let mut filters = HashMap::new();
+        filters.insert("bf".to_string(), "@backend status:failed".to_string());
+        save_filters_to(&path, &filters).unwrap();
+        assert_eq!(load_filters_from(&path), filters);
+    }
+
+    #[test]
+    fn load_flat_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("filters.toml");
+        fs::write(&path, "ui = \"@frontend\"\n").unwrap();
+        assert_eq!(load_filters_from(&path).get("ui").map(String::as_str), Some("@frontend"));
+        assert!(load_filters_from(&tmp.path().join("missing.toml")).is_empty());
+    }
+
+    #[test]
+    fn valid_names() {
+        assert!(is_valid_name("my-filter_2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("a b"));
+    }
+}
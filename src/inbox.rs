@@ -0,0 +1,600 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::PromptOptions;
+use crate::process;
+
+/// Version of the inbox request format. Bump it when `SubmitRequest` gains
+/// fields an older TUI would silently ignore.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// A prompt submitted by `clhorde submit`, picked up by the running TUI.
+/// Uses the same `options` shape as persisted prompt files.
+#[derive(Serialize, Deserialize)]
+pub struct SubmitRequest {
+    /// `PROTOCOL_VERSION` of the submitting binary (0 before versioning).
+    #[serde(default)]
+    pub protocol_version: u32,
+    pub prompt: String,
+    pub options: PromptOptions,
+    /// Id of a prompt in the running TUI that must complete first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<usize>,
+    /// Agent session the prompt is sent into, instead of starting a new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_session_id: Option<String>,
+}
+
+/// Check a submitted `env` map: names must be non-empty and free of `=`,
+/// and neither names nor values may contain NUL.
+pub fn validate_env(env: &BTreeMap<String, String>) -> Result<(), String> {
+    for (name, value) in env {
+        if name.is_empty() || name.contains(['=', '\0']) {
+            return Err(format!("invalid env variable name '{name}'"));
+        }
+        if value.contains('\0') {
+            return Err(format!("env variable {name} contains a NUL byte"));
+        }
+    }
+    Ok(())
+}
+
+pub fn default_inbox_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("clhorde").join("inbox"))
+}
+
+fn pid_path(dir: &Path) -> PathBuf {
+    dir.join("clhorde.pid")
+}
+
+/// Record the current process as the TUI owning this inbox.
+pub fn write_pid(dir: &Path) {
+    let _ = fs::create_dir_all(dir);
+    let _ = fs::write(pid_path(dir), std::process::id().to_string());
+}
+
+pub fn remove_pid(dir: &Path) {
+    let _ = fs::remove_file(pid_path(dir));
+}
+
+/// Pid recorded by the TUI owning this inbox, alive or not.
+pub fn read_pid(dir: &Path) -> Option<u32> {
+    fs::read_to_string(pid_path(dir)).ok()?.trim().parse().ok()
+}
+
+/// Whether a live TUI process owns this inbox.
+pub fn is_running(dir: &Path) -> bool {
+    read_pid(dir).is_some_and(|pid| pid == std::process::id() || process::is_alive(pid))
+}
+
+// ── Instance status and control requests (for `clhorde tui`) ──
+
+/// What the running TUI publishes for `clhorde tui status`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct InstanceStatus {
+    pub max_workers: usize,
+    pub active_workers: usize,
+    pub paused: bool,
+    #[serde(default)]
+    pub draining: bool,
+    /// Prompt counts by status label, in lifecycle order.
+    pub counts: Vec<(String, usize)>,
+    #[serde(default)]
+    pub stats: QueueStats,
+    /// Prompts `clhorde tail` can follow: all but the finished ones restored
+    /// from an earlier session, whose output was never mirrored.
+    #[serde(default)]
+    pub prompt_ids: Vec<usize>,
+}
+
+/// Throughput numbers for `clhorde tui stats`. `running` includes idle
+/// workers; `failed` excludes prompts the user killed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct QueueStats {
+    pub completed: usize,
+    pub failed: usize,
+    pub killed: usize,
+    pub pending: usize,
+    pub running: usize,
+    /// Mean run time of prompts finished this session; restored prompts
+    /// have no recorded duration and are left out.
+    pub avg_elapsed_secs: Option<f64>,
+}
+
+// Not `.json`: `take_requests` consumes those as submissions
+fn status_path(dir: &Path) -> PathBuf {
+    dir.join("clhorde.status")
+}
+
+fn stop_path(dir: &Path) -> PathBuf {
+    dir.join("clhorde.stop")
+}
+
+fn kill_all_path(dir: &Path) -> PathBuf {
+    dir.join("clhorde.killall")
+}
+
+fn drain_path(dir: &Path) -> PathBuf {
+    dir.join("clhorde.drain")
+}
+
+/// Publish the TUI's status (temp file + rename, like requests).
+pub fn write_status(dir: &Path, status: &InstanceStatus) {
+    let Ok(content) = serde_json::to_string(status) else {
+        return;
+    };
+    let tmp = dir.join("clhorde.status.tmp");
+    if fs::write(&tmp, content).is_ok() {
+        let _ = fs::rename(&tmp, status_path(dir));
+    }
+}
+
+pub fn read_status(dir: &Path) -> Option<InstanceStatus> {
+    serde_json::from_str(&fs::read_to_string(status_path(dir)).ok()?).ok()
+}
+
+pub fn remove_status(dir: &Path) {
+    let _ = fs::remove_file(status_path(dir));
+}
+
+/// Ask the running TUI to quit (`clhorde tui stop`).
+pub fn request_stop(dir: &Path) -> io::Result<()> {
+    fs::write(stop_path(dir), "")
+}
+
+/// Consume a pending stop request. Returns whether there was one.
+pub fn take_stop_request(dir: &Path) -> bool {
+    fs::remove_file(stop_path(dir)).is_ok()
+}
+
+/// Ask the running TUI to kill its workers (`clhorde tui kill-all`).
+pub fn request_kill_all(dir: &Path) -> io::Result<()> {
+    fs::write(kill_all_path(dir), "")
+}
+
+/// Consume a pending kill-all request. Returns whether there was one.
+pub fn take_kill_all_request(dir: &Path) -> bool {
+    fs::remove_file(kill_all_path(dir)).is_ok()
+}
+
+/// Ask the running TUI to drain (`clhorde tui drain`), quitting once its
+/// workers are done if `quit` is set.
+pub fn request_drain(dir: &Path, quit: bool) -> io::Result<()> {
+    fs::write(drain_path(dir), if quit { "quit" } else { "" })
+}
+
+/// Consume a pending drain request: `Some(quit)` if there was one.
+pub fn take_drain_request(dir: &Path) -> Option<bool> {
+    let content = fs::read_to_string(drain_path(dir)).ok()?;
+    let _ = fs::remove_file(drain_path(dir));
+    Some(content.trim() == "quit")
+}
+
+/// Write a request as `<token>.json`. Written to a temp file and renamed so
+/// the TUI never reads a partial request.
+pub fn write_request(dir: &Path, token: &str, req: &SubmitRequest) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(req).map_err(io::Error::other)?;
+    let tmp = dir.join(format!("{token}.tmp"));
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, dir.join(format!("{token}.json")))
+}
+
+pub fn remove_request(dir: &Path, token: &str) {
+    let _ = fs::remove_file(dir.join(format!("{token}.json")));
+}
+
+/// Read and remove all pending requests, oldest token first.
+/// Unparseable files are discarded.
+pub fn take_requests(dir: &Path) -> Vec<(String, SubmitRequest)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    paths.sort();
+
+    let mut requests = Vec::new();
+    for path in paths {
+        let Some(token) = path.file_stem().and_then(|s| s.to_str()).map(String::from) else {
+            continue;
+        };
+        let parsed = fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<SubmitRequest>(&c).ok());
+        let _ = fs::remove_file(&path);
+        if let Some(req) = parsed {
+            requests.push((token, req));
+        }
+    }
+    requests
+}
+
+/// Reply to a request with the id assigned to the created prompt.
+pub fn write_accept(dir: &Path, token: &str, prompt_id: usize) {
+    let _ = fs::write(dir.join(format!("{token}.accepted")), prompt_id.to_string());
+}
+
+/// Reply to a request that was refused, with the reason.
+pub fn write_reject(dir: &Path, token: &str, reason: &str) {
+    let _ = fs::write(dir.join(format!("{token}.rejected")), reason);
+}
+
+/// Poll for the reply to `token`, consuming it: the assigned id, or the
+/// rejection reason. Returns `None` on timeout.
+pub fn wait_for_accept(dir: &Path, token: &str, timeout: Duration) -> Option<Result<usize, String>> {
+    let path = dir.join(format!("{token}.accepted"));
+    let rejected = dir.join(format!("{token}.rejected"));
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Ok(content) = fs::read_to_string(&path) {
+            if let Ok(id) = content.trim().parse() {
+                let _ = fs::remove_file(&path);
+                return Some(Ok(id));
+            }
+        }
+        if let Ok(reason) = fs::read_to_string(&rejected) {
+            let _ = fs::remove_file(&rejected);
+            return Some(Err(reason));
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// ── Output mirror (for `clhorde tail`) ──
+
+fn output_path(dir: &Path, prompt_id: usize) -> PathBuf {
+    dir.join(format!("{prompt_id}.log"))
+}
+
+fn exit_path(dir: &Path, prompt_id: usize) -> PathBuf {
+    dir.join(format!("{prompt_id}.exit"))
+}
+
+fn signal_path(dir: &Path, prompt_id: usize) -> PathBuf {
+    dir.join(format!("{prompt_id}.signal"))
+}
+
+/// Ask the running TUI to signal a prompt's worker (`clhorde tui signal`).
+/// The TUI checks the name against `process::SENDABLE_SIGNALS`.
+pub fn request_signal(dir: &Path, prompt_id: usize, signal: &str) -> io::Result<()> {
+    fs::write(signal_path(dir, prompt_id), signal)
+}
+
+/// Consume pending signal requests as `(prompt_id, signal name)`.
+pub fn take_signal_requests(dir: &Path) -> Vec<(usize, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut requests = Vec::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("signal") {
+            continue;
+        }
+        let id = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok());
+        let signal = fs::read_to_string(&path).ok();
+        let _ = fs::remove_file(&path);
+        if let (Some(id), Some(signal)) = (id, signal) {
+            requests.push((id, signal.trim().to_string()));
+        }
+    }
+    requests.sort();
+    requests
+}
+
+/// Remove output mirrors left by a previous session (prompt ids restart at 1).
+pub fn clear_outputs(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        let ext = path.extension().and_then(|e| e.to_str());
+        if ext == Some("log") || ext == Some("exit") {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Open `<id>.log` files of running prompts, so an output chunk costs a
+/// single write. A log that would grow past `max_bytes` is truncated and
+/// started over; `clhorde tail` sees the shorter file and reads from 0.
+#[derive(Default)]
+pub struct OutputMirror {
+    files: HashMap<usize, (fs::File, u64)>,
+}
+
+impl OutputMirror {
+    pub fn append(&mut self, dir: &Path, prompt_id: usize, text: &str, max_bytes: usize) {
+        let (file, len) = match self.files.entry(prompt_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let _ = fs::create_dir_all(dir);
+                let Ok(file) = fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(output_path(dir, prompt_id))
+                else {
+                    return;
+                };
+                let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+                entry.insert((file, len))
+            }
+        };
+        let max = max_bytes as u64;
+        let mut bytes = text.as_bytes();
+        if *len + bytes.len() as u64 > max {
+            if file.set_len(0).is_err() {
+                return;
+            }
+            *len = 0;
+            // A single chunk over the cap keeps only its tail
+            bytes = &bytes[bytes.len().saturating_sub(max_bytes)..];
+        }
+        if file.write_all(bytes).is_ok() {
+            *len += bytes.len() as u64;
+        }
+    }
+
+    /// Drop the handle once the prompt's worker has finished.
+    pub fn close(&mut self, prompt_id: usize) {
+        self.files.remove(&prompt_id);
+    }
+
+    /// Forget a deleted prompt: close its log and remove its files.
+    pub fn remove(&mut self, dir: &Path, prompt_id: usize) {
+        self.close(prompt_id);
+        let _ = fs::remove_file(output_path(dir, prompt_id));
+        let _ = fs::remove_file(exit_path(dir, prompt_id));
+    }
+}
+
+pub fn write_exit(dir: &Path, prompt_id: usize, code: i32) {
+    let _ = fs::write(exit_path(dir, prompt_id), code.to_string());
+}
+
+/// Forget the exit status when a prompt runs again (e.g. resume).
+pub fn clear_exit(dir: &Path, prompt_id: usize) {
+    let _ = fs::remove_file(exit_path(dir, prompt_id));
+}
+
+pub fn read_exit(dir: &Path, prompt_id: usize) -> Option<i32> {
+    fs::read_to_string(exit_path(dir, prompt_id))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Size of a prompt's output mirror (0 if it has none).
+pub fn output_len(dir: &Path, prompt_id: usize) -> u64 {
+    fs::metadata(output_path(dir, prompt_id)).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Read mirrored output starting at byte `offset`. Returns the new bytes.
+pub fn read_output_from(dir: &Path, prompt_id: usize, offset: u64) -> Vec<u8> {
+    let Ok(mut f) = fs::File::open(output_path(dir, prompt_id)) else {
+        return Vec::new();
+    };
+    let mut buf = Vec::new();
+    if f.seek(SeekFrom::Start(offset)).is_ok() {
+        let _ = f.read_to_end(&mut buf);
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(text: &str) -> SubmitRequest {
+        SubmitRequest {
+            protocol_version: PROTOCOL_VERSION,
+            prompt: text.to_string(),
+            options: PromptOptions {
+                mode: "one_shot".to_string(),
+                context: Some("/tmp".to_string()),
+                worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: BTreeMap::new(),
+            },
+            depends_on: None,
+            resume_session_id: None,
+        }
+    }
+
+    #[test]
+    fn request_without_version_parses_as_v0() {
+        let json = r#"{"prompt": "hi", "options": {"mode": "one_shot"}}"#;
+        let req: SubmitRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.protocol_version, 0);
+    }
+
+    #[test]
+    fn status_roundtrip_and_stop_request() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        assert!(read_status(dir).is_none());
+        let status = InstanceStatus {
+            max_workers: 3,
+            active_workers: 1,
+            paused: false,
+            draining: true,
+            counts: vec![("pending".to_string(), 2)],
+            stats: QueueStats { pending: 2, avg_elapsed_secs: Some(1.5), ..QueueStats::default() },
+            prompt_ids: vec![1, 2],
+        };
+        write_status(dir, &status);
+        assert_eq!(read_status(dir), Some(status));
+        remove_status(dir);
+        assert!(read_status(dir).is_none());
+
+        assert!(!take_stop_request(dir));
+        request_stop(dir).unwrap();
+        assert!(take_stop_request(dir));
+        assert!(!take_stop_request(dir));
+
+        assert!(!take_kill_all_request(dir));
+        request_kill_all(dir).unwrap();
+        assert!(!take_stop_request(dir));
+        assert!(take_kill_all_request(dir));
+        assert!(!take_kill_all_request(dir));
+
+        assert_eq!(take_drain_request(dir), None);
+        request_drain(dir, false).unwrap();
+        assert_eq!(take_drain_request(dir), Some(false));
+        request_drain(dir, true).unwrap();
+        assert_eq!(take_drain_request(dir), Some(true));
+        assert_eq!(take_drain_request(dir), None);
+
+        assert!(take_signal_requests(dir).is_empty());
+        request_signal(dir, 4, "INT").unwrap();
+        request_signal(dir, 2, "KILL").unwrap();
+        fs::write(dir.join("x.signal"), "INT").unwrap();
+        assert_eq!(
+            take_signal_requests(dir),
+            vec![(2, "KILL".to_string()), (4, "INT".to_string())]
+        );
+        assert!(take_signal_requests(dir).is_empty());
+    }
+
+    #[test]
+    fn request_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_request(tmp.path(), "b", &request("second")).unwrap();
+        write_request(tmp.path(), "a", &request("first")).unwrap();
+
+        let taken = take_requests(tmp.path());
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken[0].0, "a");
+        assert_eq!(taken[0].1.prompt, "first");
+        assert_eq!(taken[1].1.options.context.as_deref(), Some("/tmp"));
+
+        // Requests are consumed
+        assert!(take_requests(tmp.path()).is_empty());
+    }
+
+    #[test]
+    fn take_requests_skips_garbage() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("bad.json"), "not json").unwrap();
+        fs::write(tmp.path().join("clhorde.pid"), "1").unwrap();
+        assert!(take_requests(tmp.path()).is_empty());
+        assert!(!tmp.path().join("bad.json").exists());
+    }
+
+    #[test]
+    fn take_requests_missing_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(take_requests(&tmp.path().join("nope")).is_empty());
+    }
+
+    #[test]
+    fn accept_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_accept(tmp.path(), "tok", 42);
+        assert_eq!(wait_for_accept(tmp.path(), "tok", Duration::from_millis(10)), Some(Ok(42)));
+        assert!(!tmp.path().join("tok.accepted").exists());
+    }
+
+    #[test]
+    fn reject_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        write_reject(tmp.path(), "tok", "Unknown dependency #9");
+        assert_eq!(
+            wait_for_accept(tmp.path(), "tok", Duration::from_millis(10)),
+            Some(Err("Unknown dependency #9".to_string()))
+        );
+        assert!(!tmp.path().join("tok.rejected").exists());
+    }
+
+    #[test]
+    fn wait_for_accept_times_out() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(wait_for_accept(tmp.path(), "tok", Duration::from_millis(10)), None);
+    }
+
+    #[test]
+    fn output_mirror_incremental_read() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut mirror = OutputMirror::default();
+        assert!(read_output_from(tmp.path(), 1, 0).is_empty());
+        mirror.append(tmp.path(), 1, "hello ", 1024);
+        assert_eq!(read_output_from(tmp.path(), 1, 0), b"hello ");
+        mirror.append(tmp.path(), 1, "world", 1024);
+        assert_eq!(read_output_from(tmp.path(), 1, 6), b"world");
+    }
+
+    #[test]
+    fn output_mirror_restarts_at_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut mirror = OutputMirror::default();
+        mirror.append(tmp.path(), 1, "0123456", 8);
+        mirror.append(tmp.path(), 1, "789", 8);
+        assert_eq!(read_output_from(tmp.path(), 1, 0), b"789");
+        mirror.append(tmp.path(), 1, "abcdefghij", 8);
+        assert_eq!(read_output_from(tmp.path(), 1, 0), b"cdefghij");
+        assert_eq!(output_len(tmp.path(), 1), 8);
+
+        // A reopened log counts its existing size toward the cap
+        mirror.close(1);
+        mirror.append(tmp.path(), 1, "k", 8);
+        assert_eq!(read_output_from(tmp.path(), 1, 0), b"k");
+    }
+
+    #[test]
+    fn output_mirror_remove_deletes_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut mirror = OutputMirror::default();
+        mirror.append(tmp.path(), 1, "x", 1024);
+        write_exit(tmp.path(), 1, 0);
+        mirror.remove(tmp.path(), 1);
+        assert_eq!(output_len(tmp.path(), 1), 0);
+        assert_eq!(read_exit(tmp.path(), 1), None);
+    }
+
+    #[test]
+    fn exit_status_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(read_exit(tmp.path(), 3), None);
+        write_exit(tmp.path(), 3, 2);
+        assert_eq!(read_exit(tmp.path(), 3), Some(2));
+        clear_exit(tmp.path(), 3);
+        assert_eq!(read_exit(tmp.path(), 3), None);
+    }
+
+    #[test]
+    fn clear_outputs_keeps_requests() {
+        let tmp = tempfile::tempdir().unwrap();
+        OutputMirror::default().append(tmp.path(), 1, "x", 1024);
+        write_exit(tmp.path(), 1, 0);
+        write_request(tmp.path(), "tok", &request("keep")).unwrap();
+        clear_outputs(tmp.path());
+        assert!(read_output_from(tmp.path(), 1, 0).is_empty());
+        assert_eq!(read_exit(tmp.path(), 1), None);
+        assert_eq!(take_requests(tmp.path()).len(), 1);
+    }
+
+    #[test]
+    fn is_running_checks_pid() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(!is_running(tmp.path()));
+        write_pid(tmp.path());
+        assert!(is_running(tmp.path()));
+        remove_pid(tmp.path());
+        assert!(!is_running(tmp.path()));
+    }
+}
@@ -4,9 +4,49 @@ use std::fs;
 use std::io;
 use std::path::PathBuf;
 
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use serde::{Deserialize, Serialize};
 
+/// A key plus the Ctrl/Alt/Shift modifiers held with it. Shift is folded
+/// into the character for `Char` keys (`J`, not `S-j`), so terminals that
+/// do or don't report it for capitals resolve to the same binding. `BackTab`
+/// is stored as `S-Tab` for the same reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            KeyCode::BackTab => {
+                modifiers.insert(KeyModifiers::SHIFT);
+                KeyCode::Tab
+            }
+            other => other,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyCode> for KeyCombo {
+    fn from(code: KeyCode) -> Self {
+        Self { code, modifiers: KeyModifiers::NONE }
+    }
+}
+
+impl From<&KeyEvent> for KeyCombo {
+    fn from(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NormalAction {
     Quit,
@@ -15,11 +55,19 @@ pub enum NormalAction {
     SelectPrev,
     ViewOutput,
     Interact,
+    /// Line-based Interact mode even for a PTY worker.
+    ForceInteract,
+    /// Raw-key PTY mode, never falling back to Interact.
+    ForcePtyInteract,
     IncreaseWorkers,
     DecreaseWorkers,
     ToggleMode,
     Retry,
     Resume,
+    Duplicate,
+    EditTags,
+    Export,
+    ExportJson,
     MoveUp,
     MoveDown,
     Search,
@@ -27,6 +75,8 @@ pub enum NormalAction {
     HalfPageUp,
     GoToTop,
     GoToBottom,
+    /// Select the next finished prompt whose output hasn't been seen.
+    NextUnseen,
     ShrinkList,
     GrowList,
     ShowHelp,
@@ -35,6 +85,40 @@ pub enum NormalAction {
     VisualSelect,
     DeleteSelected,
     KillSelected,
+    KillAll,
+    /// Send SIGINT to the selected PTY worker's process group.
+    Interrupt,
+    CyclePriority,
+    TogglePause,
+    CycleSort,
+    ToggleGrouped,
+    /// Fold or unfold the selected prompt's group in the grouped view.
+    ToggleGroupFold,
+    FoldAllGroups,
+    UnfoldAllGroups,
+    ContinueSession,
+    FocusToggle,
+}
+
+/// A binding that keeps a quick prompt key from firing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickPromptConflict {
+    /// Bound in the output view, which checks its bindings first.
+    View(ViewAction),
+    /// Bound in Normal mode, so a batch selection never sends it.
+    Normal(NormalAction),
+    /// Starts a Normal-mode sequence, which swallows the key the same way.
+    Sequence,
+}
+
+/// Outcome of `Keymap::resolve_sequence` for the keys typed so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMatch {
+    /// The keys complete a sequence bound to this action.
+    Action(NormalAction),
+    /// A longer sequence starts with these keys; wait for the next one.
+    Prefix,
+    None,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -52,10 +136,20 @@ pub enum ViewAction {
     ScrollDown,
     ScrollUp,
     Interact,
+    ForceInteract,
+    ForcePtyInteract,
     ToggleAutoscroll,
     KillWorker,
     Export,
+    ExportJson,
     ToggleSplit,
+    CopyToClipboard,
+    Search,
+    SearchNext,
+    SearchPrev,
+    WorktreeDiff,
+    ApplyWorktree,
+    DiscardWorktree,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -71,74 +165,106 @@ pub enum FilterAction {
 }
 
 pub struct Keymap {
-    pub normal: HashMap<KeyCode, NormalAction>,
-    pub insert: HashMap<KeyCode, InsertAction>,
-    pub view: HashMap<KeyCode, ViewAction>,
-    pub interact: HashMap<KeyCode, InteractAction>,
-    pub filter: HashMap<KeyCode, FilterAction>,
-    pub quick_prompts: HashMap<KeyCode, String>,
+    pub normal: HashMap<KeyCombo, NormalAction>,
+    /// Multi-key Normal-mode bindings such as `gg`, keyed by the whole
+    /// sequence. A single-key binding shadows sequences that start with it.
+    pub sequences: HashMap<Vec<KeyCombo>, NormalAction>,
+    pub insert: HashMap<KeyCombo, InsertAction>,
+    pub view: HashMap<KeyCombo, ViewAction>,
+    pub interact: HashMap<KeyCombo, InteractAction>,
+    pub filter: HashMap<KeyCombo, FilterAction>,
+    pub quick_prompts: HashMap<KeyCombo, String>,
 }
 
 impl Default for Keymap {
     fn default() -> Self {
         let mut normal = HashMap::new();
-        normal.insert(KeyCode::Char('q'), NormalAction::Quit);
-        normal.insert(KeyCode::Char('i'), NormalAction::Insert);
-        normal.insert(KeyCode::Char('j'), NormalAction::SelectNext);
-        normal.insert(KeyCode::Down, NormalAction::SelectNext);
-        normal.insert(KeyCode::Char('k'), NormalAction::SelectPrev);
-        normal.insert(KeyCode::Up, NormalAction::SelectPrev);
-        normal.insert(KeyCode::Enter, NormalAction::ViewOutput);
-        normal.insert(KeyCode::Char('s'), NormalAction::Interact);
-        normal.insert(KeyCode::Char('+'), NormalAction::IncreaseWorkers);
-        normal.insert(KeyCode::Char('='), NormalAction::IncreaseWorkers);
-        normal.insert(KeyCode::Char('-'), NormalAction::DecreaseWorkers);
-        normal.insert(KeyCode::Char('m'), NormalAction::ToggleMode);
-        normal.insert(KeyCode::Char('r'), NormalAction::Retry);
-        normal.insert(KeyCode::Char('R'), NormalAction::Resume);
-        normal.insert(KeyCode::Char('J'), NormalAction::MoveDown);
-        normal.insert(KeyCode::Char('K'), NormalAction::MoveUp);
-        normal.insert(KeyCode::Char('/'), NormalAction::Search);
-        normal.insert(KeyCode::Char('G'), NormalAction::GoToBottom);
-        normal.insert(KeyCode::Char('h'), NormalAction::ShrinkList);
-        normal.insert(KeyCode::Char('l'), NormalAction::GrowList);
-        normal.insert(KeyCode::Char('?'), NormalAction::ShowHelp);
-        normal.insert(KeyCode::Char(' '), NormalAction::ToggleSelect);
-        normal.insert(KeyCode::Char('V'), NormalAction::SelectAllVisible);
-        normal.insert(KeyCode::Char('v'), NormalAction::VisualSelect);
-        normal.insert(KeyCode::Char('d'), NormalAction::DeleteSelected);
-        normal.insert(KeyCode::Char('x'), NormalAction::KillSelected);
+        normal.insert(KeyCode::Char('q').into(), NormalAction::Quit);
+        normal.insert(KeyCode::Char('i').into(), NormalAction::Insert);
+        normal.insert(KeyCode::Char('j').into(), NormalAction::SelectNext);
+        normal.insert(KeyCode::Down.into(), NormalAction::SelectNext);
+        normal.insert(KeyCode::Char('k').into(), NormalAction::SelectPrev);
+        normal.insert(KeyCode::Up.into(), NormalAction::SelectPrev);
+        normal.insert(KeyCode::Enter.into(), NormalAction::ViewOutput);
+        normal.insert(KeyCode::Char('s').into(), NormalAction::Interact);
+        normal.insert(KeyCode::Char('+').into(), NormalAction::IncreaseWorkers);
+        normal.insert(KeyCode::Char('=').into(), NormalAction::IncreaseWorkers);
+        normal.insert(KeyCode::Char('-').into(), NormalAction::DecreaseWorkers);
+        normal.insert(KeyCode::Char('m').into(), NormalAction::ToggleMode);
+        normal.insert(KeyCode::Char('r').into(), NormalAction::Retry);
+        normal.insert(KeyCode::Char('R').into(), NormalAction::Resume);
+        normal.insert(KeyCode::Char('D').into(), NormalAction::Duplicate);
+        normal.insert(KeyCode::Char('t').into(), NormalAction::EditTags);
+        normal.insert(KeyCode::Char('w').into(), NormalAction::Export);
+        normal.insert(KeyCode::Char('W').into(), NormalAction::ExportJson);
+        normal.insert(KeyCode::Char('J').into(), NormalAction::MoveDown);
+        normal.insert(KeyCode::Char('K').into(), NormalAction::MoveUp);
+        normal.insert(KeyCode::Char('/').into(), NormalAction::Search);
+        normal.insert(KeyCode::Char('G').into(), NormalAction::GoToBottom);
+        normal.insert(KeyCode::Char('n').into(), NormalAction::NextUnseen);
+        normal.insert(KeyCode::Char('h').into(), NormalAction::ShrinkList);
+        normal.insert(KeyCode::Char('l').into(), NormalAction::GrowList);
+        normal.insert(KeyCode::Char('?').into(), NormalAction::ShowHelp);
+        normal.insert(KeyCode::Char(' ').into(), NormalAction::ToggleSelect);
+        normal.insert(KeyCode::Char('V').into(), NormalAction::SelectAllVisible);
+        normal.insert(KeyCode::Char('v').into(), NormalAction::VisualSelect);
+        normal.insert(KeyCode::Char('d').into(), NormalAction::DeleteSelected);
+        normal.insert(KeyCode::Char('x').into(), NormalAction::KillSelected);
+        normal.insert(KeyCode::Char('X').into(), NormalAction::KillAll);
+        normal.insert(KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL), NormalAction::Interrupt);
+        normal.insert(KeyCode::Char('p').into(), NormalAction::CyclePriority);
+        normal.insert(KeyCode::Char('P').into(), NormalAction::TogglePause);
+        normal.insert(KeyCode::Char('o').into(), NormalAction::CycleSort);
+        normal.insert(KeyCode::Char('T').into(), NormalAction::ToggleGrouped);
+        normal.insert(KeyCode::Char('c').into(), NormalAction::ContinueSession);
+        normal.insert(KeyCode::Tab.into(), NormalAction::FocusToggle);
+
+        let key = |c| KeyCombo::from(KeyCode::Char(c));
+        let mut sequences = HashMap::new();
+        sequences.insert(vec![key('g'), key('g')], NormalAction::GoToTop);
+        sequences.insert(vec![key('z'), key('a')], NormalAction::ToggleGroupFold);
+        sequences.insert(vec![key('z'), key('M')], NormalAction::FoldAllGroups);
+        sequences.insert(vec![key('z'), key('R')], NormalAction::UnfoldAllGroups);
 
         let mut insert = HashMap::new();
-        insert.insert(KeyCode::Esc, InsertAction::Cancel);
-        insert.insert(KeyCode::Enter, InsertAction::Submit);
-        insert.insert(KeyCode::Tab, InsertAction::AcceptSuggestion);
-        insert.insert(KeyCode::Down, InsertAction::NextSuggestion);
-        insert.insert(KeyCode::Up, InsertAction::PrevSuggestion);
+        insert.insert(KeyCode::Esc.into(), InsertAction::Cancel);
+        insert.insert(KeyCode::Enter.into(), InsertAction::Submit);
+        insert.insert(KeyCode::Tab.into(), InsertAction::AcceptSuggestion);
+        insert.insert(KeyCode::Down.into(), InsertAction::NextSuggestion);
+        insert.insert(KeyCode::Up.into(), InsertAction::PrevSuggestion);
 
         let mut view = HashMap::new();
-        view.insert(KeyCode::Esc, ViewAction::Back);
-        view.insert(KeyCode::Char('q'), ViewAction::Back);
-        view.insert(KeyCode::Char('j'), ViewAction::ScrollDown);
-        view.insert(KeyCode::Down, ViewAction::ScrollDown);
-        view.insert(KeyCode::Char('k'), ViewAction::ScrollUp);
-        view.insert(KeyCode::Up, ViewAction::ScrollUp);
-        view.insert(KeyCode::Char('s'), ViewAction::Interact);
-        view.insert(KeyCode::Char('f'), ViewAction::ToggleAutoscroll);
-        view.insert(KeyCode::Char('x'), ViewAction::KillWorker);
-        view.insert(KeyCode::Char('w'), ViewAction::Export);
-        view.insert(KeyCode::Char('t'), ViewAction::ToggleSplit);
+        view.insert(KeyCode::Esc.into(), ViewAction::Back);
+        view.insert(KeyCode::Char('q').into(), ViewAction::Back);
+        view.insert(KeyCode::Char('j').into(), ViewAction::ScrollDown);
+        view.insert(KeyCode::Down.into(), ViewAction::ScrollDown);
+        view.insert(KeyCode::Char('k').into(), ViewAction::ScrollUp);
+        view.insert(KeyCode::Up.into(), ViewAction::ScrollUp);
+        view.insert(KeyCode::Char('s').into(), ViewAction::Interact);
+        view.insert(KeyCode::Char('f').into(), ViewAction::ToggleAutoscroll);
+        view.insert(KeyCode::Char('x').into(), ViewAction::KillWorker);
+        view.insert(KeyCode::Char('w').into(), ViewAction::Export);
+        view.insert(KeyCode::Char('W').into(), ViewAction::ExportJson);
+        view.insert(KeyCode::Char('t').into(), ViewAction::ToggleSplit);
+        view.insert(KeyCode::Char('y').into(), ViewAction::CopyToClipboard);
+        view.insert(KeyCode::Char('/').into(), ViewAction::Search);
+        view.insert(KeyCode::Char('n').into(), ViewAction::SearchNext);
+        view.insert(KeyCode::Char('N').into(), ViewAction::SearchPrev);
+        view.insert(KeyCode::Char('d').into(), ViewAction::WorktreeDiff);
+        view.insert(KeyCode::Char('a').into(), ViewAction::ApplyWorktree);
+        view.insert(KeyCode::Char('D').into(), ViewAction::DiscardWorktree);
 
         let mut interact = HashMap::new();
-        interact.insert(KeyCode::Esc, InteractAction::Back);
-        interact.insert(KeyCode::Enter, InteractAction::Send);
+        interact.insert(KeyCode::Esc.into(), InteractAction::Back);
+        interact.insert(KeyCode::Enter.into(), InteractAction::Send);
 
         let mut filter = HashMap::new();
-        filter.insert(KeyCode::Esc, FilterAction::Cancel);
-        filter.insert(KeyCode::Enter, FilterAction::Confirm);
+        filter.insert(KeyCode::Esc.into(), FilterAction::Cancel);
+        filter.insert(KeyCode::Enter.into(), FilterAction::Confirm);
 
         Self {
             normal,
+            sequences,
             insert,
             view,
             interact,
@@ -166,6 +292,29 @@ pub(crate) struct TomlConfig {
     pub(crate) filter: Option<TomlFilterBindings>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) quick_prompts: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) theme: Option<TomlTheme>,
+}
+
+/// `[theme]` colors; see `theme::Theme` for the roles.
+#[derive(Deserialize, Serialize, Default)]
+pub(crate) struct TomlTheme {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) selected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pending: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) running: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) idle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) completed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) failed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) border: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -175,7 +324,51 @@ pub(crate) struct TomlSettings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) worktree_cleanup: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) worktree_branch_template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) worktree_autocommit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) list_ratio: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) default_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_attempts: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) retry_delay_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) agent_command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) agent_args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) worker_wrapper: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) notifications: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) mouse: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) terminal_title: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) bell_on_complete: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) input_reminder_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tag_limits: Option<HashMap<String, usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) kill_grace_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) spawn_interval_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) completion_webhook_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) event_socket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) archive_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_saved_output_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_output_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) pty_scrollback_bytes: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -193,6 +386,10 @@ pub(crate) struct TomlNormalBindings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) interact: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) force_interact: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) force_pty_interact: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) increase_workers: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) decrease_workers: Option<Vec<String>>,
@@ -203,6 +400,14 @@ pub(crate) struct TomlNormalBindings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) resume: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) duplicate: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) edit_tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) export: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) export_json: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) move_up: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) move_down: Option<Vec<String>>,
@@ -217,6 +422,8 @@ pub(crate) struct TomlNormalBindings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) go_to_bottom: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) next_unseen: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) shrink_list: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) grow_list: Option<Vec<String>>,
@@ -232,6 +439,28 @@ pub(crate) struct TomlNormalBindings {
     pub(crate) delete_selected: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) kill_selected: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) kill_all: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) interrupt: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cycle_priority: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) toggle_pause: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) cycle_sort: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) toggle_grouped: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) toggle_group_fold: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) fold_all_groups: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) unfold_all_groups: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) continue_session: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) focus_toggle: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -259,13 +488,33 @@ pub(crate) struct TomlViewBindings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) interact: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) force_interact: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) force_pty_interact: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) toggle_autoscroll: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) kill_worker: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) export: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) export_json: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) toggle_split: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) copy: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) search: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) search_next: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) search_prev: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) worktree_diff: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) apply_worktree: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) discard_worktree: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -284,20 +533,46 @@ pub(crate) struct TomlFilterBindings {
     pub(crate) cancel: Option<Vec<String>>,
 }
 
-pub(crate) fn parse_key(s: &str) -> Option<KeyCode> {
-    match s {
-        "Enter" => Some(KeyCode::Enter),
-        "Esc" => Some(KeyCode::Esc),
-        "Tab" => Some(KeyCode::Tab),
-        "Backspace" => Some(KeyCode::Backspace),
-        "Up" => Some(KeyCode::Up),
-        "Down" => Some(KeyCode::Down),
-        "Left" => Some(KeyCode::Left),
-        "Right" => Some(KeyCode::Right),
-        "Space" => Some(KeyCode::Char(' ')),
-        s if s.len() == 1 => s.chars().next().map(KeyCode::Char),
-        _ => None,
+/// Parse a key like `j`, `Enter`, `C-x` or `A-enter`. `C-`, `A-` and `S-`
+/// prefixes add Ctrl, Alt and Shift; key names are case-insensitive, single
+/// characters are not.
+pub(crate) fn parse_key(s: &str) -> Option<KeyCombo> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = s;
+    while rest.len() > 2 {
+        let modifier = match rest.get(..2) {
+            Some("C-") => KeyModifiers::CONTROL,
+            Some("A-") => KeyModifiers::ALT,
+            Some("S-") => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        modifiers |= modifier;
+        rest = &rest[2..];
     }
+    let code = match rest.to_ascii_lowercase().as_str() {
+        _ if rest.chars().count() == 1 => rest.chars().next().map(KeyCode::Char)?,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "space" => KeyCode::Char(' '),
+        _ => return None,
+    };
+    Some(KeyCombo::new(code, modifiers))
+}
+
+/// Parse a Normal-mode binding: one key, or a sequence of keys separated by
+/// spaces (`"g g"`, `"z M"`).
+pub(crate) fn parse_binding(s: &str) -> Option<Vec<KeyCombo>> {
+    let parts: Vec<&str> = s.split_whitespace().collect();
+    if parts.len() < 2 {
+        return parse_key(s).map(|key| vec![key]);
+    }
+    parts.into_iter().map(parse_key).collect()
 }
 
 pub(crate) fn config_path() -> Option<PathBuf> {
@@ -332,44 +607,62 @@ impl Keymap {
         Self::from_toml(config)
     }
 
-    fn from_toml(config: TomlConfig) -> Self {
+    pub(crate) fn from_toml(config: TomlConfig) -> Self {
         let mut keymap = Self::default();
 
         if let Some(normal) = config.normal {
-            apply_bindings(&mut keymap.normal, NormalAction::Quit, normal.quit);
-            apply_bindings(&mut keymap.normal, NormalAction::Insert, normal.insert);
-            apply_bindings(&mut keymap.normal, NormalAction::SelectNext, normal.select_next);
-            apply_bindings(&mut keymap.normal, NormalAction::SelectPrev, normal.select_prev);
-            apply_bindings(&mut keymap.normal, NormalAction::ViewOutput, normal.view_output);
-            apply_bindings(&mut keymap.normal, NormalAction::Interact, normal.interact);
-            apply_bindings(
-                &mut keymap.normal,
+            apply_normal_bindings(&mut keymap, NormalAction::Quit, normal.quit);
+            apply_normal_bindings(&mut keymap, NormalAction::Insert, normal.insert);
+            apply_normal_bindings(&mut keymap, NormalAction::SelectNext, normal.select_next);
+            apply_normal_bindings(&mut keymap, NormalAction::SelectPrev, normal.select_prev);
+            apply_normal_bindings(&mut keymap, NormalAction::ViewOutput, normal.view_output);
+            apply_normal_bindings(&mut keymap, NormalAction::Interact, normal.interact);
+            apply_normal_bindings(&mut keymap, NormalAction::ForceInteract, normal.force_interact);
+            apply_normal_bindings(&mut keymap, NormalAction::ForcePtyInteract, normal.force_pty_interact);
+            apply_normal_bindings(
+                &mut keymap,
                 NormalAction::IncreaseWorkers,
                 normal.increase_workers,
             );
-            apply_bindings(
-                &mut keymap.normal,
+            apply_normal_bindings(
+                &mut keymap,
                 NormalAction::DecreaseWorkers,
                 normal.decrease_workers,
             );
-            apply_bindings(&mut keymap.normal, NormalAction::ToggleMode, normal.toggle_mode);
-            apply_bindings(&mut keymap.normal, NormalAction::Retry, normal.retry);
-            apply_bindings(&mut keymap.normal, NormalAction::Resume, normal.resume);
-            apply_bindings(&mut keymap.normal, NormalAction::MoveUp, normal.move_up);
-            apply_bindings(&mut keymap.normal, NormalAction::MoveDown, normal.move_down);
-            apply_bindings(&mut keymap.normal, NormalAction::Search, normal.search);
-            apply_bindings(&mut keymap.normal, NormalAction::HalfPageDown, normal.half_page_down);
-            apply_bindings(&mut keymap.normal, NormalAction::HalfPageUp, normal.half_page_up);
-            apply_bindings(&mut keymap.normal, NormalAction::GoToTop, normal.go_to_top);
-            apply_bindings(&mut keymap.normal, NormalAction::GoToBottom, normal.go_to_bottom);
-            apply_bindings(&mut keymap.normal, NormalAction::ShrinkList, normal.shrink_list);
-            apply_bindings(&mut keymap.normal, NormalAction::GrowList, normal.grow_list);
-            apply_bindings(&mut keymap.normal, NormalAction::ShowHelp, normal.show_help);
-            apply_bindings(&mut keymap.normal, NormalAction::ToggleSelect, normal.toggle_select);
-            apply_bindings(&mut keymap.normal, NormalAction::SelectAllVisible, normal.select_all_visible);
-            apply_bindings(&mut keymap.normal, NormalAction::VisualSelect, normal.visual_select);
-            apply_bindings(&mut keymap.normal, NormalAction::DeleteSelected, normal.delete_selected);
-            apply_bindings(&mut keymap.normal, NormalAction::KillSelected, normal.kill_selected);
+            apply_normal_bindings(&mut keymap, NormalAction::ToggleMode, normal.toggle_mode);
+            apply_normal_bindings(&mut keymap, NormalAction::Retry, normal.retry);
+            apply_normal_bindings(&mut keymap, NormalAction::Resume, normal.resume);
+            apply_normal_bindings(&mut keymap, NormalAction::Duplicate, normal.duplicate);
+            apply_normal_bindings(&mut keymap, NormalAction::EditTags, normal.edit_tags);
+            apply_normal_bindings(&mut keymap, NormalAction::Export, normal.export);
+            apply_normal_bindings(&mut keymap, NormalAction::ExportJson, normal.export_json);
+            apply_normal_bindings(&mut keymap, NormalAction::MoveUp, normal.move_up);
+            apply_normal_bindings(&mut keymap, NormalAction::MoveDown, normal.move_down);
+            apply_normal_bindings(&mut keymap, NormalAction::Search, normal.search);
+            apply_normal_bindings(&mut keymap, NormalAction::HalfPageDown, normal.half_page_down);
+            apply_normal_bindings(&mut keymap, NormalAction::HalfPageUp, normal.half_page_up);
+            apply_normal_bindings(&mut keymap, NormalAction::GoToTop, normal.go_to_top);
+            apply_normal_bindings(&mut keymap, NormalAction::GoToBottom, normal.go_to_bottom);
+            apply_normal_bindings(&mut keymap, NormalAction::NextUnseen, normal.next_unseen);
+            apply_normal_bindings(&mut keymap, NormalAction::ShrinkList, normal.shrink_list);
+            apply_normal_bindings(&mut keymap, NormalAction::GrowList, normal.grow_list);
+            apply_normal_bindings(&mut keymap, NormalAction::ShowHelp, normal.show_help);
+            apply_normal_bindings(&mut keymap, NormalAction::ToggleSelect, normal.toggle_select);
+            apply_normal_bindings(&mut keymap, NormalAction::SelectAllVisible, normal.select_all_visible);
+            apply_normal_bindings(&mut keymap, NormalAction::VisualSelect, normal.visual_select);
+            apply_normal_bindings(&mut keymap, NormalAction::DeleteSelected, normal.delete_selected);
+            apply_normal_bindings(&mut keymap, NormalAction::KillSelected, normal.kill_selected);
+            apply_normal_bindings(&mut keymap, NormalAction::KillAll, normal.kill_all);
+            apply_normal_bindings(&mut keymap, NormalAction::Interrupt, normal.interrupt);
+            apply_normal_bindings(&mut keymap, NormalAction::CyclePriority, normal.cycle_priority);
+            apply_normal_bindings(&mut keymap, NormalAction::TogglePause, normal.toggle_pause);
+            apply_normal_bindings(&mut keymap, NormalAction::CycleSort, normal.cycle_sort);
+            apply_normal_bindings(&mut keymap, NormalAction::ToggleGrouped, normal.toggle_grouped);
+            apply_normal_bindings(&mut keymap, NormalAction::ToggleGroupFold, normal.toggle_group_fold);
+            apply_normal_bindings(&mut keymap, NormalAction::FoldAllGroups, normal.fold_all_groups);
+            apply_normal_bindings(&mut keymap, NormalAction::UnfoldAllGroups, normal.unfold_all_groups);
+            apply_normal_bindings(&mut keymap, NormalAction::ContinueSession, normal.continue_session);
+            apply_normal_bindings(&mut keymap, NormalAction::FocusToggle, normal.focus_toggle);
         }
 
         if let Some(insert) = config.insert {
@@ -397,6 +690,8 @@ impl Keymap {
             apply_bindings(&mut keymap.view, ViewAction::ScrollDown, view.scroll_down);
             apply_bindings(&mut keymap.view, ViewAction::ScrollUp, view.scroll_up);
             apply_bindings(&mut keymap.view, ViewAction::Interact, view.interact);
+            apply_bindings(&mut keymap.view, ViewAction::ForceInteract, view.force_interact);
+            apply_bindings(&mut keymap.view, ViewAction::ForcePtyInteract, view.force_pty_interact);
             apply_bindings(
                 &mut keymap.view,
                 ViewAction::ToggleAutoscroll,
@@ -404,7 +699,15 @@ impl Keymap {
             );
             apply_bindings(&mut keymap.view, ViewAction::KillWorker, view.kill_worker);
             apply_bindings(&mut keymap.view, ViewAction::Export, view.export);
+            apply_bindings(&mut keymap.view, ViewAction::ExportJson, view.export_json);
             apply_bindings(&mut keymap.view, ViewAction::ToggleSplit, view.toggle_split);
+            apply_bindings(&mut keymap.view, ViewAction::CopyToClipboard, view.copy);
+            apply_bindings(&mut keymap.view, ViewAction::Search, view.search);
+            apply_bindings(&mut keymap.view, ViewAction::SearchNext, view.search_next);
+            apply_bindings(&mut keymap.view, ViewAction::SearchPrev, view.search_prev);
+            apply_bindings(&mut keymap.view, ViewAction::WorktreeDiff, view.worktree_diff);
+            apply_bindings(&mut keymap.view, ViewAction::ApplyWorktree, view.apply_worktree);
+            apply_bindings(&mut keymap.view, ViewAction::DiscardWorktree, view.discard_worktree);
         }
 
         if let Some(interact) = config.interact {
@@ -435,6 +738,11 @@ pub(crate) fn load_settings() -> TomlSettings {
     config.settings.unwrap_or_default()
 }
 
+/// Load the `[theme]` section from the config file.
+pub(crate) fn load_theme() -> TomlTheme {
+    load_toml_config().theme.unwrap_or_default()
+}
+
 /// Load the raw TOML config (not the resolved Keymap). Returns Default if file missing.
 pub(crate) fn load_toml_config() -> TomlConfig {
     let path = match config_path() {
@@ -464,7 +772,7 @@ pub(crate) fn save_toml_config(config: &TomlConfig) -> io::Result<()> {
 pub(crate) fn default_toml_config() -> TomlConfig {
     let km = Keymap::default();
 
-    fn keys_to_strings<A: PartialEq>(map: &HashMap<KeyCode, A>, action: A) -> Vec<String> {
+    fn keys_to_strings<A: PartialEq>(map: &HashMap<KeyCombo, A>, action: A) -> Vec<String> {
         let mut keys: Vec<_> = map
             .iter()
             .filter(|(_, a)| **a == action)
@@ -477,32 +785,50 @@ pub(crate) fn default_toml_config() -> TomlConfig {
     TomlConfig {
         settings: None,
         normal: Some(TomlNormalBindings {
-            quit: Some(keys_to_strings(&km.normal, NormalAction::Quit)),
-            insert: Some(keys_to_strings(&km.normal, NormalAction::Insert)),
-            select_next: Some(keys_to_strings(&km.normal, NormalAction::SelectNext)),
-            select_prev: Some(keys_to_strings(&km.normal, NormalAction::SelectPrev)),
-            view_output: Some(keys_to_strings(&km.normal, NormalAction::ViewOutput)),
-            interact: Some(keys_to_strings(&km.normal, NormalAction::Interact)),
-            increase_workers: Some(keys_to_strings(&km.normal, NormalAction::IncreaseWorkers)),
-            decrease_workers: Some(keys_to_strings(&km.normal, NormalAction::DecreaseWorkers)),
-            toggle_mode: Some(keys_to_strings(&km.normal, NormalAction::ToggleMode)),
-            retry: Some(keys_to_strings(&km.normal, NormalAction::Retry)),
-            resume: Some(keys_to_strings(&km.normal, NormalAction::Resume)),
-            move_up: Some(keys_to_strings(&km.normal, NormalAction::MoveUp)),
-            move_down: Some(keys_to_strings(&km.normal, NormalAction::MoveDown)),
-            search: Some(keys_to_strings(&km.normal, NormalAction::Search)),
-            half_page_down: Some(keys_to_strings(&km.normal, NormalAction::HalfPageDown)),
-            half_page_up: Some(keys_to_strings(&km.normal, NormalAction::HalfPageUp)),
-            go_to_top: Some(keys_to_strings(&km.normal, NormalAction::GoToTop)),
-            go_to_bottom: Some(keys_to_strings(&km.normal, NormalAction::GoToBottom)),
-            shrink_list: Some(keys_to_strings(&km.normal, NormalAction::ShrinkList)),
-            grow_list: Some(keys_to_strings(&km.normal, NormalAction::GrowList)),
-            show_help: Some(keys_to_strings(&km.normal, NormalAction::ShowHelp)),
-            toggle_select: Some(keys_to_strings(&km.normal, NormalAction::ToggleSelect)),
-            select_all_visible: Some(keys_to_strings(&km.normal, NormalAction::SelectAllVisible)),
-            visual_select: Some(keys_to_strings(&km.normal, NormalAction::VisualSelect)),
-            delete_selected: Some(keys_to_strings(&km.normal, NormalAction::DeleteSelected)),
-            kill_selected: Some(keys_to_strings(&km.normal, NormalAction::KillSelected)),
+            quit: Some(km.normal_bindings(NormalAction::Quit, " ")),
+            insert: Some(km.normal_bindings(NormalAction::Insert, " ")),
+            select_next: Some(km.normal_bindings(NormalAction::SelectNext, " ")),
+            select_prev: Some(km.normal_bindings(NormalAction::SelectPrev, " ")),
+            view_output: Some(km.normal_bindings(NormalAction::ViewOutput, " ")),
+            interact: Some(km.normal_bindings(NormalAction::Interact, " ")),
+            force_interact: Some(km.normal_bindings(NormalAction::ForceInteract, " ")),
+            force_pty_interact: Some(km.normal_bindings(NormalAction::ForcePtyInteract, " ")),
+            increase_workers: Some(km.normal_bindings(NormalAction::IncreaseWorkers, " ")),
+            decrease_workers: Some(km.normal_bindings(NormalAction::DecreaseWorkers, " ")),
+            toggle_mode: Some(km.normal_bindings(NormalAction::ToggleMode, " ")),
+            retry: Some(km.normal_bindings(NormalAction::Retry, " ")),
+            resume: Some(km.normal_bindings(NormalAction::Resume, " ")),
+            duplicate: Some(km.normal_bindings(NormalAction::Duplicate, " ")),
+            edit_tags: Some(km.normal_bindings(NormalAction::EditTags, " ")),
+            export: Some(km.normal_bindings(NormalAction::Export, " ")),
+            export_json: Some(km.normal_bindings(NormalAction::ExportJson, " ")),
+            move_up: Some(km.normal_bindings(NormalAction::MoveUp, " ")),
+            move_down: Some(km.normal_bindings(NormalAction::MoveDown, " ")),
+            search: Some(km.normal_bindings(NormalAction::Search, " ")),
+            half_page_down: Some(km.normal_bindings(NormalAction::HalfPageDown, " ")),
+            half_page_up: Some(km.normal_bindings(NormalAction::HalfPageUp, " ")),
+            go_to_top: Some(km.normal_bindings(NormalAction::GoToTop, " ")),
+            go_to_bottom: Some(km.normal_bindings(NormalAction::GoToBottom, " ")),
+            next_unseen: Some(km.normal_bindings(NormalAction::NextUnseen, " ")),
+            shrink_list: Some(km.normal_bindings(NormalAction::ShrinkList, " ")),
+            grow_list: Some(km.normal_bindings(NormalAction::GrowList, " ")),
+            show_help: Some(km.normal_bindings(NormalAction::ShowHelp, " ")),
+            toggle_select: Some(km.normal_bindings(NormalAction::ToggleSelect, " ")),
+            select_all_visible: Some(km.normal_bindings(NormalAction::SelectAllVisible, " ")),
+            visual_select: Some(km.normal_bindings(NormalAction::VisualSelect, " ")),
+            delete_selected: Some(km.normal_bindings(NormalAction::DeleteSelected, " ")),
+            kill_selected: Some(km.normal_bindings(NormalAction::KillSelected, " ")),
+            kill_all: Some(km.normal_bindings(NormalAction::KillAll, " ")),
+            interrupt: Some(km.normal_bindings(NormalAction::Interrupt, " ")),
+            cycle_priority: Some(km.normal_bindings(NormalAction::CyclePriority, " ")),
+            toggle_pause: Some(km.normal_bindings(NormalAction::TogglePause, " ")),
+            cycle_sort: Some(km.normal_bindings(NormalAction::CycleSort, " ")),
+            toggle_grouped: Some(km.normal_bindings(NormalAction::ToggleGrouped, " ")),
+            toggle_group_fold: Some(km.normal_bindings(NormalAction::ToggleGroupFold, " ")),
+            fold_all_groups: Some(km.normal_bindings(NormalAction::FoldAllGroups, " ")),
+            unfold_all_groups: Some(km.normal_bindings(NormalAction::UnfoldAllGroups, " ")),
+            continue_session: Some(km.normal_bindings(NormalAction::ContinueSession, " ")),
+            focus_toggle: Some(km.normal_bindings(NormalAction::FocusToggle, " ")),
         }),
         insert: Some(TomlInsertBindings {
             cancel: Some(keys_to_strings(&km.insert, InsertAction::Cancel)),
@@ -516,10 +842,20 @@ pub(crate) fn default_toml_config() -> TomlConfig {
             scroll_down: Some(keys_to_strings(&km.view, ViewAction::ScrollDown)),
             scroll_up: Some(keys_to_strings(&km.view, ViewAction::ScrollUp)),
             interact: Some(keys_to_strings(&km.view, ViewAction::Interact)),
+            force_interact: Some(keys_to_strings(&km.view, ViewAction::ForceInteract)),
+            force_pty_interact: Some(keys_to_strings(&km.view, ViewAction::ForcePtyInteract)),
             toggle_autoscroll: Some(keys_to_strings(&km.view, ViewAction::ToggleAutoscroll)),
             kill_worker: Some(keys_to_strings(&km.view, ViewAction::KillWorker)),
             export: Some(keys_to_strings(&km.view, ViewAction::Export)),
+            export_json: Some(keys_to_strings(&km.view, ViewAction::ExportJson)),
             toggle_split: Some(keys_to_strings(&km.view, ViewAction::ToggleSplit)),
+            copy: Some(keys_to_strings(&km.view, ViewAction::CopyToClipboard)),
+            search: Some(keys_to_strings(&km.view, ViewAction::Search)),
+            search_next: Some(keys_to_strings(&km.view, ViewAction::SearchNext)),
+            search_prev: Some(keys_to_strings(&km.view, ViewAction::SearchPrev)),
+            worktree_diff: Some(keys_to_strings(&km.view, ViewAction::WorktreeDiff)),
+            apply_worktree: Some(keys_to_strings(&km.view, ViewAction::ApplyWorktree)),
+            discard_worktree: Some(keys_to_strings(&km.view, ViewAction::DiscardWorktree)),
         }),
         interact: Some(TomlInteractBindings {
             back: Some(keys_to_strings(&km.interact, InteractAction::Back)),
@@ -530,13 +866,35 @@ pub(crate) fn default_toml_config() -> TomlConfig {
             cancel: Some(keys_to_strings(&km.filter, FilterAction::Cancel)),
         }),
         quick_prompts: None,
+        theme: None,
+    }
+}
+
+/// `apply_bindings` for Normal mode, where a binding of several
+/// space-separated keys (`"g g"`) is a sequence.
+fn apply_normal_bindings(keymap: &mut Keymap, action: NormalAction, keys: Option<Vec<String>>) {
+    let Some(keys) = keys else {
+        return;
+    };
+    keymap.normal.retain(|_, a| *a != action);
+    keymap.sequences.retain(|_, a| *a != action);
+    for key_str in &keys {
+        match parse_binding(key_str).as_deref() {
+            Some([key]) => {
+                keymap.normal.insert(*key, action);
+            }
+            Some(sequence) => {
+                keymap.sequences.insert(sequence.to_vec(), action);
+            }
+            None => {}
+        }
     }
 }
 
 /// Remove all existing bindings for `action`, then insert new ones from `keys`.
 /// If `keys` is None, keep defaults.
 fn apply_bindings<A: PartialEq + Copy>(
-    map: &mut HashMap<KeyCode, A>,
+    map: &mut HashMap<KeyCombo, A>,
     action: A,
     keys: Option<Vec<String>>,
 ) {
@@ -558,7 +916,22 @@ fn apply_bindings<A: PartialEq + Copy>(
 
 // Help bar generation
 
-pub(crate) fn key_display(kc: &KeyCode) -> String {
+pub(crate) fn key_display(key: &KeyCombo) -> String {
+    let mut out = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        out.push_str("C-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        out.push_str("A-");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        out.push_str("S-");
+    }
+    out.push_str(&key_code_display(&key.code));
+    out
+}
+
+fn key_code_display(kc: &KeyCode) -> String {
     match kc {
         KeyCode::Char(' ') => "Space".to_string(),
         KeyCode::Char(c) => c.to_string(),
@@ -575,8 +948,8 @@ pub(crate) fn key_display(kc: &KeyCode) -> String {
 }
 
 /// Collect all keys bound to a given action, sorted for display consistency.
-fn keys_for_action<A: PartialEq>(map: &HashMap<KeyCode, A>, action: A) -> Vec<KeyCode> {
-    let mut keys: Vec<KeyCode> = map
+fn keys_for_action<A: PartialEq>(map: &HashMap<KeyCombo, A>, action: A) -> Vec<KeyCombo> {
+    let mut keys: Vec<KeyCombo> = map
         .iter()
         .filter(|(_, a)| **a == action)
         .map(|(k, _)| *k)
@@ -586,44 +959,124 @@ fn keys_for_action<A: PartialEq>(map: &HashMap<KeyCode, A>, action: A) -> Vec<Ke
 }
 
 /// Format a list of keycodes as a display string like "j/k" or "Esc/q".
-fn format_keys(keys: &[KeyCode]) -> String {
+fn format_keys(keys: &[KeyCombo]) -> String {
     keys.iter()
         .map(key_display)
         .collect::<Vec<_>>()
         .join("/")
 }
 
+/// Normal actions in help-bar order, with their labels.
+const NORMAL_HELP: &[(NormalAction, &str)] = &[
+    (NormalAction::Insert, "insert"),
+    (NormalAction::Quit, "quit"),
+    (NormalAction::SelectNext, "next"),
+    (NormalAction::SelectPrev, "prev"),
+    (NormalAction::HalfPageDown, "½pg dn"),
+    (NormalAction::HalfPageUp, "½pg up"),
+    (NormalAction::GoToTop, "top"),
+    (NormalAction::GoToBottom, "bottom"),
+    (NormalAction::NextUnseen, "next unseen"),
+    (NormalAction::ViewOutput, "view"),
+    (NormalAction::FocusToggle, "focus"),
+    (NormalAction::Interact, "interact"),
+    (NormalAction::ForceInteract, "line input"),
+    (NormalAction::ForcePtyInteract, "raw pty"),
+    (NormalAction::Retry, "retry"),
+    (NormalAction::Resume, "resume"),
+    (NormalAction::Duplicate, "duplicate"),
+    (NormalAction::ContinueSession, "continue"),
+    (NormalAction::EditTags, "tags"),
+    (NormalAction::Export, "export"),
+    (NormalAction::ExportJson, "json"),
+    (NormalAction::Search, "search"),
+    (NormalAction::MoveUp, "move up"),
+    (NormalAction::MoveDown, "move down"),
+    (NormalAction::CyclePriority, "priority"),
+    (NormalAction::IncreaseWorkers, "more wkrs"),
+    (NormalAction::DecreaseWorkers, "less wkrs"),
+    (NormalAction::TogglePause, "pause"),
+    (NormalAction::CycleSort, "sort"),
+    (NormalAction::ToggleGrouped, "group"),
+    (NormalAction::ToggleGroupFold, "fold group"),
+    (NormalAction::FoldAllGroups, "fold all"),
+    (NormalAction::UnfoldAllGroups, "unfold all"),
+    (NormalAction::ToggleMode, "mode"),
+    (NormalAction::ShrinkList, "shrink"),
+    (NormalAction::GrowList, "grow"),
+    (NormalAction::ShowHelp, "help"),
+    (NormalAction::ToggleSelect, "select"),
+    (NormalAction::SelectAllVisible, "sel all"),
+    (NormalAction::VisualSelect, "visual"),
+    (NormalAction::DeleteSelected, "delete"),
+    (NormalAction::KillSelected, "kill"),
+    (NormalAction::KillAll, "kill all"),
+    (NormalAction::Interrupt, "interrupt"),
+];
+
+/// Help-bar label of a Normal action.
+pub fn normal_label(action: NormalAction) -> &'static str {
+    NORMAL_HELP
+        .iter()
+        .find(|(a, _)| *a == action)
+        .map_or("?", |(_, label)| label)
+}
+
 impl Keymap {
     pub fn normal_help(&self) -> Vec<(String, &'static str)> {
-        let entries: &[(NormalAction, &str)] = &[
-            (NormalAction::Insert, "insert"),
-            (NormalAction::Quit, "quit"),
-            (NormalAction::SelectNext, "next"),
-            (NormalAction::SelectPrev, "prev"),
-            (NormalAction::HalfPageDown, "½pg dn"),
-            (NormalAction::HalfPageUp, "½pg up"),
-            (NormalAction::GoToTop, "top"),
-            (NormalAction::GoToBottom, "bottom"),
-            (NormalAction::ViewOutput, "view"),
-            (NormalAction::Interact, "interact"),
-            (NormalAction::Retry, "retry"),
-            (NormalAction::Resume, "resume"),
-            (NormalAction::Search, "search"),
-            (NormalAction::MoveUp, "move up"),
-            (NormalAction::MoveDown, "move down"),
-            (NormalAction::IncreaseWorkers, "more wkrs"),
-            (NormalAction::DecreaseWorkers, "less wkrs"),
-            (NormalAction::ToggleMode, "mode"),
-            (NormalAction::ShrinkList, "shrink"),
-            (NormalAction::GrowList, "grow"),
-            (NormalAction::ShowHelp, "help"),
-            (NormalAction::ToggleSelect, "select"),
-            (NormalAction::SelectAllVisible, "sel all"),
-            (NormalAction::VisualSelect, "visual"),
-            (NormalAction::DeleteSelected, "delete"),
-            (NormalAction::KillSelected, "kill"),
-        ];
-        self.build_help(&self.normal, entries)
+        NORMAL_HELP
+            .iter()
+            .filter_map(|&(action, label)| {
+                let keys = self.normal_bindings(action, "");
+                (!keys.is_empty()).then(|| (keys.join("/"), label))
+            })
+            .collect()
+    }
+
+    /// Every binding of a Normal action as text, single keys and sequences
+    /// alike, sorted. The keys of a sequence are joined with `sep`.
+    pub fn normal_bindings(&self, action: NormalAction, sep: &str) -> Vec<String> {
+        let mut keys: Vec<String> = keys_for_action(&self.normal, action).iter().map(key_display).collect();
+        keys.extend(
+            self.sequences
+                .iter()
+                .filter(|(_, a)| **a == action)
+                .map(|(sequence, _)| sequence.iter().map(key_display).collect::<Vec<_>>().join(sep)),
+        );
+        keys.sort();
+        keys
+    }
+
+    /// Whether `keys` complete a sequence, start a longer one, or neither.
+    pub fn resolve_sequence(&self, keys: &[KeyCombo]) -> SequenceMatch {
+        if let Some(action) = self.sequences.get(keys) {
+            return SequenceMatch::Action(*action);
+        }
+        if self.sequences.keys().any(|sequence| sequence.starts_with(keys)) {
+            SequenceMatch::Prefix
+        } else {
+            SequenceMatch::None
+        }
+    }
+
+    /// The keys that can follow `prefix`, each with the help label of the
+    /// action it leads to, sorted by key.
+    pub fn sequence_completions(&self, prefix: &[KeyCombo]) -> Vec<(String, &'static str)> {
+        let mut completions: Vec<_> = self
+            .sequences
+            .iter()
+            .filter(|(sequence, _)| sequence.len() > prefix.len() && sequence.starts_with(prefix))
+            .map(|(sequence, action)| {
+                let rest: String = sequence[prefix.len()..].iter().map(key_display).collect();
+                let label = NORMAL_HELP
+                    .iter()
+                    .find(|(a, _)| a == action)
+                    .map_or("?", |(_, label)| label);
+                (rest, label)
+            })
+            .collect();
+        completions.sort();
+        completions
     }
 
     pub fn insert_help(&self) -> Vec<(String, &'static str)> {
@@ -641,10 +1094,18 @@ impl Keymap {
             (ViewAction::ScrollDown, "down"),
             (ViewAction::ScrollUp, "up"),
             (ViewAction::Interact, "interact"),
+            (ViewAction::ForceInteract, "line input"),
+            (ViewAction::ForcePtyInteract, "raw pty"),
             (ViewAction::ToggleAutoscroll, "auto-scroll"),
             (ViewAction::KillWorker, "kill"),
             (ViewAction::Export, "export"),
+            (ViewAction::ExportJson, "json"),
             (ViewAction::ToggleSplit, "split"),
+            (ViewAction::CopyToClipboard, "copy"),
+            (ViewAction::Search, "search"),
+            (ViewAction::WorktreeDiff, "diff"),
+            (ViewAction::ApplyWorktree, "apply"),
+            (ViewAction::DiscardWorktree, "discard"),
         ];
         self.build_help(&self.view, entries)
     }
@@ -667,12 +1128,10 @@ impl Keymap {
 
     /// Look up the first key bound to a NormalAction for display in hints.
     pub fn normal_key_hint(&self, action: NormalAction) -> String {
-        let keys = keys_for_action(&self.normal, action);
-        if keys.is_empty() {
-            "?".to_string()
-        } else {
-            key_display(&keys[0])
-        }
+        self.normal_bindings(action, "")
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "?".to_string())
     }
 
     /// Look up the first key bound to a ViewAction for display in hints.
@@ -696,9 +1155,28 @@ impl Keymap {
         entries
     }
 
+    /// Quick prompts whose key is also bound elsewhere, sorted by key. A
+    /// view binding wins in the output view; a Normal binding (or sequence
+    /// start) wins over sending to a batch selection.
+    pub fn quick_prompt_conflicts(&self) -> Vec<(KeyCombo, QuickPromptConflict)> {
+        let mut conflicts = Vec::new();
+        for kc in self.quick_prompts.keys() {
+            if let Some(action) = self.view.get(kc) {
+                conflicts.push((*kc, QuickPromptConflict::View(*action)));
+            }
+            if let Some(action) = self.normal.get(kc) {
+                conflicts.push((*kc, QuickPromptConflict::Normal(*action)));
+            } else if self.resolve_sequence(&[*kc]) == SequenceMatch::Prefix {
+                conflicts.push((*kc, QuickPromptConflict::Sequence));
+            }
+        }
+        conflicts.sort_by_key(|(kc, _)| key_display(kc));
+        conflicts
+    }
+
     fn build_help<A: PartialEq + Copy>(
         &self,
-        map: &HashMap<KeyCode, A>,
+        map: &HashMap<KeyCombo, A>,
         entries: &[(A, &'static str)],
     ) -> Vec<(String, &'static str)> {
         // Group actions that share adjacent display slots and merge their keys
@@ -729,24 +1207,24 @@ mod tests {
 
     #[test]
     fn parse_key_special_names() {
-        assert_eq!(parse_key("Enter"), Some(KeyCode::Enter));
-        assert_eq!(parse_key("Esc"), Some(KeyCode::Esc));
-        assert_eq!(parse_key("Tab"), Some(KeyCode::Tab));
-        assert_eq!(parse_key("Backspace"), Some(KeyCode::Backspace));
-        assert_eq!(parse_key("Up"), Some(KeyCode::Up));
-        assert_eq!(parse_key("Down"), Some(KeyCode::Down));
-        assert_eq!(parse_key("Left"), Some(KeyCode::Left));
-        assert_eq!(parse_key("Right"), Some(KeyCode::Right));
-        assert_eq!(parse_key("Space"), Some(KeyCode::Char(' ')));
+        assert_eq!(parse_key("Enter"), Some(KeyCode::Enter.into()));
+        assert_eq!(parse_key("Esc"), Some(KeyCode::Esc.into()));
+        assert_eq!(parse_key("Tab"), Some(KeyCode::Tab.into()));
+        assert_eq!(parse_key("Backspace"), Some(KeyCode::Backspace.into()));
+        assert_eq!(parse_key("Up"), Some(KeyCode::Up.into()));
+        assert_eq!(parse_key("Down"), Some(KeyCode::Down.into()));
+        assert_eq!(parse_key("Left"), Some(KeyCode::Left.into()));
+        assert_eq!(parse_key("Right"), Some(KeyCode::Right.into()));
+        assert_eq!(parse_key("Space"), Some(KeyCode::Char(' ').into()));
     }
 
     #[test]
     fn parse_key_single_chars() {
-        assert_eq!(parse_key("q"), Some(KeyCode::Char('q')));
-        assert_eq!(parse_key("i"), Some(KeyCode::Char('i')));
-        assert_eq!(parse_key("+"), Some(KeyCode::Char('+')));
-        assert_eq!(parse_key("/"), Some(KeyCode::Char('/')));
-        assert_eq!(parse_key("K"), Some(KeyCode::Char('K')));
+        assert_eq!(parse_key("q"), Some(KeyCode::Char('q').into()));
+        assert_eq!(parse_key("i"), Some(KeyCode::Char('i').into()));
+        assert_eq!(parse_key("+"), Some(KeyCode::Char('+').into()));
+        assert_eq!(parse_key("/"), Some(KeyCode::Char('/').into()));
+        assert_eq!(parse_key("K"), Some(KeyCode::Char('K').into()));
     }
 
     #[test]
@@ -763,7 +1241,7 @@ mod tests {
     fn key_display_roundtrip() {
         let names = [
             "Enter", "Esc", "Tab", "Backspace", "Up", "Down", "Left", "Right", "Space",
-            "q", "i", "+", "/",
+            "q", "i", "+", "/", "C-x", "A-Enter", "C-A-k", "S-Tab", "C--",
         ];
         for name in names {
             let kc = parse_key(name).unwrap();
@@ -771,51 +1249,97 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_key_modifiers() {
+        assert_eq!(
+            parse_key("C-x"),
+            Some(KeyCombo::new(KeyCode::Char('x'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(parse_key("A-enter"), Some(KeyCombo::new(KeyCode::Enter, KeyModifiers::ALT)));
+        assert_eq!(
+            parse_key("C-A-Up"),
+            Some(KeyCombo::new(KeyCode::Up, KeyModifiers::CONTROL | KeyModifiers::ALT))
+        );
+        // Shift on a character is spelled as the capital
+        assert_eq!(parse_key("S-j"), parse_key("J"));
+        assert_eq!(parse_key("C-"), None);
+        assert_eq!(parse_key("C-nope"), None);
+        assert_eq!(parse_key("aé"), None);
+        assert_ne!(parse_key("C-x"), parse_key("x"));
+    }
+
+    #[test]
+    fn key_events_match_their_binding() {
+        let shifted = KeyEvent::new(KeyCode::Char('J'), KeyModifiers::SHIFT);
+        assert_eq!(KeyCombo::from(&shifted), KeyCode::Char('J').into());
+        let ctrl = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(Some(KeyCombo::from(&ctrl)), parse_key("C-x"));
+        let shift_tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT);
+        assert_eq!(Some(KeyCombo::from(&shift_tab)), parse_key("S-Tab"));
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(Some(KeyCombo::from(&back_tab)), parse_key("S-Tab"));
+        let bare_back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE);
+        assert_eq!(Some(KeyCombo::from(&bare_back_tab)), parse_key("S-Tab"));
+    }
+
+    #[test]
+    fn from_toml_binds_modifier_combos() {
+        let toml_str = r#"
+[normal]
+quit = ["C-q"]
+"#;
+        let config: TomlConfig = toml::from_str(toml_str).unwrap();
+        let km = Keymap::from_toml(config);
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('q'))), None);
+        assert_eq!(km.normal.get(&parse_key("C-q").unwrap()), Some(&NormalAction::Quit));
+        assert_eq!(format_keys(&keys_for_action(&km.normal, NormalAction::Quit)), "C-q");
+    }
+
     // ── Keymap::default ──
 
     #[test]
     fn default_normal_bindings() {
         let km = Keymap::default();
-        assert_eq!(km.normal.get(&KeyCode::Char('q')), Some(&NormalAction::Quit));
-        assert_eq!(km.normal.get(&KeyCode::Char('i')), Some(&NormalAction::Insert));
-        assert_eq!(km.normal.get(&KeyCode::Char('j')), Some(&NormalAction::SelectNext));
-        assert_eq!(km.normal.get(&KeyCode::Down), Some(&NormalAction::SelectNext));
-        assert_eq!(km.normal.get(&KeyCode::Enter), Some(&NormalAction::ViewOutput));
-        assert_eq!(km.normal.get(&KeyCode::Char('m')), Some(&NormalAction::ToggleMode));
-        assert_eq!(km.normal.get(&KeyCode::Char('r')), Some(&NormalAction::Retry));
-        assert_eq!(km.normal.get(&KeyCode::Char('/')), Some(&NormalAction::Search));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('q'))), Some(&NormalAction::Quit));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('i'))), Some(&NormalAction::Insert));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('j'))), Some(&NormalAction::SelectNext));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Down)), Some(&NormalAction::SelectNext));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Enter)), Some(&NormalAction::ViewOutput));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('m'))), Some(&NormalAction::ToggleMode));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('r'))), Some(&NormalAction::Retry));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('/'))), Some(&NormalAction::Search));
     }
 
     #[test]
     fn default_view_bindings() {
         let km = Keymap::default();
-        assert_eq!(km.view.get(&KeyCode::Esc), Some(&ViewAction::Back));
-        assert_eq!(km.view.get(&KeyCode::Char('q')), Some(&ViewAction::Back));
-        assert_eq!(km.view.get(&KeyCode::Char('f')), Some(&ViewAction::ToggleAutoscroll));
-        assert_eq!(km.view.get(&KeyCode::Char('x')), Some(&ViewAction::KillWorker));
-        assert_eq!(km.view.get(&KeyCode::Char('w')), Some(&ViewAction::Export));
+        assert_eq!(km.view.get(&KeyCombo::from(KeyCode::Esc)), Some(&ViewAction::Back));
+        assert_eq!(km.view.get(&KeyCombo::from(KeyCode::Char('q'))), Some(&ViewAction::Back));
+        assert_eq!(km.view.get(&KeyCombo::from(KeyCode::Char('f'))), Some(&ViewAction::ToggleAutoscroll));
+        assert_eq!(km.view.get(&KeyCombo::from(KeyCode::Char('x'))), Some(&ViewAction::KillWorker));
+        assert_eq!(km.view.get(&KeyCombo::from(KeyCode::Char('w'))), Some(&ViewAction::Export));
     }
 
     #[test]
     fn default_insert_bindings() {
         let km = Keymap::default();
-        assert_eq!(km.insert.get(&KeyCode::Esc), Some(&InsertAction::Cancel));
-        assert_eq!(km.insert.get(&KeyCode::Enter), Some(&InsertAction::Submit));
-        assert_eq!(km.insert.get(&KeyCode::Tab), Some(&InsertAction::AcceptSuggestion));
+        assert_eq!(km.insert.get(&KeyCombo::from(KeyCode::Esc)), Some(&InsertAction::Cancel));
+        assert_eq!(km.insert.get(&KeyCombo::from(KeyCode::Enter)), Some(&InsertAction::Submit));
+        assert_eq!(km.insert.get(&KeyCombo::from(KeyCode::Tab)), Some(&InsertAction::AcceptSuggestion));
     }
 
     #[test]
     fn default_interact_bindings() {
         let km = Keymap::default();
-        assert_eq!(km.interact.get(&KeyCode::Esc), Some(&InteractAction::Back));
-        assert_eq!(km.interact.get(&KeyCode::Enter), Some(&InteractAction::Send));
+        assert_eq!(km.interact.get(&KeyCombo::from(KeyCode::Esc)), Some(&InteractAction::Back));
+        assert_eq!(km.interact.get(&KeyCombo::from(KeyCode::Enter)), Some(&InteractAction::Send));
     }
 
     #[test]
     fn default_filter_bindings() {
         let km = Keymap::default();
-        assert_eq!(km.filter.get(&KeyCode::Esc), Some(&FilterAction::Cancel));
-        assert_eq!(km.filter.get(&KeyCode::Enter), Some(&FilterAction::Confirm));
+        assert_eq!(km.filter.get(&KeyCombo::from(KeyCode::Esc)), Some(&FilterAction::Cancel));
+        assert_eq!(km.filter.get(&KeyCombo::from(KeyCode::Enter)), Some(&FilterAction::Confirm));
     }
 
     // ── from_toml partial override ──
@@ -830,13 +1354,100 @@ quit = ["Q"]
         let km = Keymap::from_toml(config);
 
         // Old quit key removed, new one works
-        assert_eq!(km.normal.get(&KeyCode::Char('q')), None);
-        assert_eq!(km.normal.get(&KeyCode::Char('Q')), Some(&NormalAction::Quit));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('q'))), None);
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('Q'))), Some(&NormalAction::Quit));
 
         // Other bindings unchanged
-        assert_eq!(km.normal.get(&KeyCode::Char('i')), Some(&NormalAction::Insert));
-        assert_eq!(km.normal.get(&KeyCode::Char('j')), Some(&NormalAction::SelectNext));
-        assert_eq!(km.normal.get(&KeyCode::Enter), Some(&NormalAction::ViewOutput));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('i'))), Some(&NormalAction::Insert));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('j'))), Some(&NormalAction::SelectNext));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Enter)), Some(&NormalAction::ViewOutput));
+    }
+
+    #[test]
+    fn default_sequences_resolve() {
+        let km = Keymap::default();
+        let g = KeyCombo::from(KeyCode::Char('g'));
+        let z = KeyCombo::from(KeyCode::Char('z'));
+        assert_eq!(km.resolve_sequence(&[g]), SequenceMatch::Prefix);
+        assert_eq!(km.resolve_sequence(&[g, g]), SequenceMatch::Action(NormalAction::GoToTop));
+        assert_eq!(km.resolve_sequence(&[g, z]), SequenceMatch::None);
+        assert_eq!(km.resolve_sequence(&[KeyCode::Char('q').into()]), SequenceMatch::None);
+        assert_eq!(
+            km.sequence_completions(&[z]),
+            vec![
+                ("M".to_string(), "fold all"),
+                ("R".to_string(), "unfold all"),
+                ("a".to_string(), "fold group"),
+            ]
+        );
+        assert_eq!(km.normal_help().iter().find(|(_, l)| *l == "top").unwrap().0, "gg");
+    }
+
+    #[test]
+    fn from_toml_binds_sequences() {
+        let toml_str = r#"
+[normal]
+go_to_top = ["g t", "C-g C-g"]
+fold_all_groups = ["F"]
+"#;
+        let config: TomlConfig = toml::from_str(toml_str).unwrap();
+        let km = Keymap::from_toml(config);
+        let g = KeyCombo::from(KeyCode::Char('g'));
+        let ctrl_g = parse_key("C-g").unwrap();
+        assert_eq!(km.resolve_sequence(&[g, g]), SequenceMatch::None);
+        assert_eq!(km.resolve_sequence(&[g, KeyCode::Char('t').into()]), SequenceMatch::Action(NormalAction::GoToTop));
+        assert_eq!(km.resolve_sequence(&[ctrl_g, ctrl_g]), SequenceMatch::Action(NormalAction::GoToTop));
+        // A single key moves the action out of the sequence table
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('F'))), Some(&NormalAction::FoldAllGroups));
+        assert_eq!(km.normal_bindings(NormalAction::FoldAllGroups, " "), vec!["F"]);
+        assert_eq!(km.normal_bindings(NormalAction::GoToTop, " "), vec!["C-g C-g", "g t"]);
+    }
+
+    #[test]
+    fn parse_binding_splits_on_spaces() {
+        let g = KeyCombo::from(KeyCode::Char('g'));
+        assert_eq!(parse_binding("g g"), Some(vec![g, g]));
+        assert_eq!(parse_binding("g"), Some(vec![g]));
+        assert_eq!(parse_binding(" "), Some(vec![KeyCode::Char(' ').into()]));
+        assert_eq!(parse_binding("g nope"), None);
+    }
+
+    #[test]
+    fn from_toml_parses_force_interact_actions() {
+        let toml_str = r#"
+[normal]
+force_interact = ["S"]
+force_pty_interact = ["I"]
+
+[view]
+force_interact = ["S"]
+"#;
+        let config: TomlConfig = toml::from_str(toml_str).unwrap();
+        let km = Keymap::from_toml(config);
+
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('S'))), Some(&NormalAction::ForceInteract));
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('I'))), Some(&NormalAction::ForcePtyInteract));
+        assert_eq!(km.view.get(&KeyCombo::from(KeyCode::Char('S'))), Some(&ViewAction::ForceInteract));
+        // Plain interact keeps its default key
+        assert_eq!(km.normal.get(&KeyCombo::from(KeyCode::Char('s'))), Some(&NormalAction::Interact));
+    }
+
+    #[test]
+    fn force_interact_actions_unbound_by_default() {
+        let km = Keymap::default();
+        assert!(!km.normal.values().any(|a| matches!(a, NormalAction::ForceInteract | NormalAction::ForcePtyInteract)));
+        assert!(!km.view.values().any(|a| matches!(a, ViewAction::ForceInteract | ViewAction::ForcePtyInteract)));
+    }
+
+    #[test]
+    fn settings_parse_bell_on_complete() {
+        let config: TomlConfig = toml::from_str("[settings]\nbell_on_complete = true\n").unwrap();
+        assert_eq!(config.settings.unwrap().bell_on_complete, Some(true));
+
+        let config: TomlConfig = toml::from_str("[settings]\nmouse = false\n").unwrap();
+        assert_eq!(config.settings.unwrap().bell_on_complete, None);
+
+        assert!(toml::from_str::<TomlConfig>("[settings]\nbell_on_complete = \"yes\"\n").is_err());
     }
 
     #[test]
@@ -862,31 +1473,31 @@ quit = ["Q"]
     #[test]
     fn apply_bindings_removes_old_keys() {
         let mut map = HashMap::new();
-        map.insert(KeyCode::Char('q'), NormalAction::Quit);
-        map.insert(KeyCode::Char('i'), NormalAction::Insert);
+        map.insert(KeyCode::Char('q').into(), NormalAction::Quit);
+        map.insert(KeyCode::Char('i').into(), NormalAction::Insert);
 
         apply_bindings(&mut map, NormalAction::Quit, Some(vec!["x".to_string()]));
 
-        assert_eq!(map.get(&KeyCode::Char('q')), None);
-        assert_eq!(map.get(&KeyCode::Char('x')), Some(&NormalAction::Quit));
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('q'))), None);
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('x'))), Some(&NormalAction::Quit));
         // Unrelated binding untouched
-        assert_eq!(map.get(&KeyCode::Char('i')), Some(&NormalAction::Insert));
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('i'))), Some(&NormalAction::Insert));
     }
 
     #[test]
     fn apply_bindings_none_keeps_defaults() {
         let mut map = HashMap::new();
-        map.insert(KeyCode::Char('q'), NormalAction::Quit);
+        map.insert(KeyCode::Char('q').into(), NormalAction::Quit);
 
         apply_bindings(&mut map, NormalAction::Quit, None);
 
-        assert_eq!(map.get(&KeyCode::Char('q')), Some(&NormalAction::Quit));
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('q'))), Some(&NormalAction::Quit));
     }
 
     #[test]
     fn apply_bindings_multiple_keys() {
         let mut map = HashMap::new();
-        map.insert(KeyCode::Char('q'), NormalAction::Quit);
+        map.insert(KeyCode::Char('q').into(), NormalAction::Quit);
 
         apply_bindings(
             &mut map,
@@ -894,9 +1505,9 @@ quit = ["Q"]
             Some(vec!["x".to_string(), "X".to_string()]),
         );
 
-        assert_eq!(map.get(&KeyCode::Char('q')), None);
-        assert_eq!(map.get(&KeyCode::Char('x')), Some(&NormalAction::Quit));
-        assert_eq!(map.get(&KeyCode::Char('X')), Some(&NormalAction::Quit));
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('q'))), None);
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('x'))), Some(&NormalAction::Quit));
+        assert_eq!(map.get(&KeyCombo::from(KeyCode::Char('X'))), Some(&NormalAction::Quit));
     }
 
     // ── help bar generation ──
@@ -958,11 +1569,11 @@ c = "continue"
 
         assert_eq!(km.quick_prompts.len(), 2);
         assert_eq!(
-            km.quick_prompts.get(&KeyCode::Char('g')),
+            km.quick_prompts.get(&KeyCombo::from(KeyCode::Char('g'))),
             Some(&"let's go".to_string())
         );
         assert_eq!(
-            km.quick_prompts.get(&KeyCode::Char('c')),
+            km.quick_prompts.get(&KeyCombo::from(KeyCode::Char('c'))),
             Some(&"continue".to_string())
         );
     }
@@ -991,6 +1602,59 @@ g = "let's go"
         assert!(km.quick_prompt_help().is_empty());
     }
 
+    #[test]
+    fn quick_prompt_conflicts_lists_keys_bound_in_view_mode() {
+        let toml_str = r#"
+[view]
+back = ["Esc"]
+
+[quick_prompts]
+q = "quit it"
+x = "explain"
+Esc = "stop"
+"#;
+        let config: TomlConfig = toml::from_str(toml_str).unwrap();
+        let km = Keymap::from_toml(config);
+        // q is free in view mode once back is rebound, but still quits the
+        // list; x kills the worker in both
+        assert_eq!(
+            km.quick_prompt_conflicts(),
+            vec![
+                (KeyCode::Esc.into(), QuickPromptConflict::View(ViewAction::Back)),
+                (KeyCode::Char('q').into(), QuickPromptConflict::Normal(NormalAction::Quit)),
+                (KeyCode::Char('x').into(), QuickPromptConflict::View(ViewAction::KillWorker)),
+                (KeyCode::Char('x').into(), QuickPromptConflict::Normal(NormalAction::KillSelected)),
+            ]
+        );
+        assert!(Keymap::default().quick_prompt_conflicts().is_empty());
+    }
+
+    #[test]
+    fn quick_prompt_conflicts_include_sequence_starts() {
+        let mut km = Keymap::default();
+        km.quick_prompts.insert(KeyCode::Char('g').into(), "let's go".to_string());
+        km.quick_prompts.insert(KeyCode::Char('e').into(), "explain".to_string());
+        assert_eq!(
+            km.quick_prompt_conflicts(),
+            vec![(KeyCode::Char('g').into(), QuickPromptConflict::Sequence)]
+        );
+    }
+
+    #[test]
+    fn example_quick_prompts_do_not_conflict() {
+        let example = include_str!("../keymap_example.toml");
+        let section = example.split("# [quick_prompts]\n").nth(1).unwrap();
+        let uncommented: String = section
+            .lines()
+            .take_while(|l| l.starts_with("# ") && l.contains(" = "))
+            .map(|l| format!("{}\n", &l[2..]))
+            .collect();
+        let config: TomlConfig = toml::from_str(&format!("[quick_prompts]\n{uncommented}")).unwrap();
+        let km = Keymap::from_toml(config);
+        assert!(km.quick_prompts.len() >= 3);
+        assert_eq!(km.quick_prompt_conflicts(), vec![]);
+    }
+
     #[test]
     fn from_toml_quick_prompts_ignores_invalid_keys() {
         let toml_str = r#"
@@ -1003,7 +1667,7 @@ InvalidKey = "nope"
 
         assert_eq!(km.quick_prompts.len(), 1);
         assert_eq!(
-            km.quick_prompts.get(&KeyCode::Char('g')),
+            km.quick_prompts.get(&KeyCombo::from(KeyCode::Char('g'))),
             Some(&"go".to_string())
         );
     }
@@ -1,27 +1,39 @@
 mod app;
 mod cli;
+mod clipboard;
 mod editor;
+mod events;
+mod filters;
+mod inbox;
 mod keymap;
+mod notify;
+mod parser;
 mod persistence;
+mod process;
 mod prompt;
 mod pty_worker;
+mod theme;
 mod ui;
+mod webhook;
 mod worker;
 mod worktree;
 
-use std::io;
+use std::io::{self, Write};
 use std::time::Duration;
 
-use crossterm::event::{self, EnableBracketedPaste, DisableBracketedPaste, Event, KeyEventKind};
+use crossterm::event::{
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event,
+    KeyEventKind,
+};
 use crossterm::execute;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use tokio::sync::mpsc;
 
 use app::App;
 use cli::{CliAction, LaunchOptions};
-use worker::{SpawnResult, WorkerInput, WorkerMessage};
+use worker::{SessionMode, SpawnResult, WorkerMessage};
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
@@ -40,7 +52,7 @@ async fn main() -> io::Result<()> {
     let result = run_app(&mut terminal, launch_opts).await;
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), DisableMouseCapture, DisableBracketedPaste, LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
     if let Err(e) = result {
@@ -52,13 +64,19 @@ async fn main() -> io::Result<()> {
 
 async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, launch_opts: LaunchOptions) -> io::Result<()> {
     let mut app = App::new();
+    if app.mouse {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
 
     let LaunchOptions { prompts, worktree, run_path } = launch_opts;
     for text in prompts {
         app.add_prompt(text, run_path.clone(), worktree, Vec::new());
     }
 
+    // Unbounded on purpose: workers never block on a busy UI, and a
+    // `Finished` can't be dropped under output pressure, leaking its slot
     let (worker_tx, mut worker_rx) = mpsc::unbounded_channel::<WorkerMessage>();
+    app.message_tx = Some(worker_tx.clone());
 
     // Dedicated thread for crossterm event reading
     let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
@@ -74,32 +92,52 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, launch_o
         }
     });
 
+    if let Some(ref dir) = app.inbox_dir {
+        inbox::clear_outputs(dir);
+        inbox::take_stop_request(dir);
+        inbox::take_kill_all_request(dir);
+        inbox::take_drain_request(dir);
+        inbox::take_signal_requests(dir);
+        inbox::write_pid(dir);
+    }
+
     let mut tick_interval = tokio::time::interval(Duration::from_millis(100));
 
     loop {
         terminal.draw(|f| ui::render(f, &mut app))?;
 
-        // After draw: check if output panel size changed, resize PTY workers
-        if let Some(panel_size) = app.output_panel_size {
-            if app.last_pty_size != Some(panel_size) && panel_size.0 > 0 && panel_size.1 > 0 {
-                app.resize_pty_workers(panel_size.0, panel_size.1);
-            }
+        // After draw: fit the viewed PTY to the panel, return the rest to
+        // the default size
+        app.sync_pty_sizes();
+
+        if let Some(title) = app.take_title_update() {
+            execute!(terminal.backend_mut(), SetTitle(title))?;
+        }
+        // Between frames, so the bell byte can't split a draw's escape codes
+        if std::mem::take(&mut app.pending_bell) {
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
         }
 
         // Dispatch pending prompts to workers
-        while app.active_workers < app.max_workers {
+        while app.can_dispatch() {
             if let Some(idx) = app.next_pending_prompt_index() {
                 let prompt = &app.prompts[idx];
                 let id = prompt.id;
                 let text = prompt.text.clone();
                 let mut cwd = prompt.cwd.clone();
                 let mode = prompt.mode;
+                let env = prompt.env.clone();
                 let wants_worktree = prompt.worktree;
-                let resume_session_id = if prompt.resume {
-                    Some(prompt.session_id.clone().unwrap_or_default())
-                } else {
-                    None
-                };
+                let existing_worktree = prompt.worktree_path.clone();
+                let session = SessionMode::for_prompt(prompt);
+
+                // A missing cwd fails the prompt before anything is spawned,
+                // and before a worktree is created from it
+                if !app.validate_cwd(idx) {
+                    continue;
+                }
 
                 // Create git worktree if requested
                 if wants_worktree {
@@ -107,8 +145,15 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, launch_o
                         .map(std::path::PathBuf::from)
                         .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
                     match worktree::repo_root(&effective_cwd) {
+                        // A resumed prompt keeps the worktree it already has
+                        Some(root) if existing_worktree.as_deref().is_some_and(|path| {
+                            worktree::worktree_exists(&root, std::path::Path::new(path))
+                        }) => {
+                            cwd = existing_worktree;
+                        }
                         Some(root) => {
-                            match worktree::create_worktree(&root, id) {
+                            let template = app.worktree_branch_template.as_deref();
+                            match worktree::create_worktree(&root, id, &text, template) {
                                 Ok(wt_path) => {
                                     let wt_str = wt_path.to_string_lossy().to_string();
                                     cwd = Some(wt_str.clone());
@@ -117,43 +162,36 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, launch_o
                                     }
                                 }
                                 Err(e) => {
-                                    app.mark_running(idx);
-                                    app.active_workers += 1;
-                                    app.apply_message(WorkerMessage::SpawnError {
-                                        prompt_id: id,
-                                        error: format!("Worktree creation failed: {e}"),
-                                    });
+                                    app.fail_dispatch(idx, format!("Worktree creation failed: {e}"));
                                     continue;
                                 }
                             }
                         }
                         None => {
-                            app.mark_running(idx);
-                            app.active_workers += 1;
-                            app.apply_message(WorkerMessage::SpawnError {
-                                prompt_id: id,
-                                error: "Not inside a git repository — cannot create worktree".to_string(),
-                            });
+                            app.fail_dispatch(idx, "Not inside a git repository — cannot create worktree".to_string());
                             continue;
                         }
                     }
                 }
 
-                app.mark_running(idx);
-                app.active_workers += 1;
-                let pty_size = app.output_panel_size;
-                match worker::spawn_worker(id, text, cwd, mode, worker_tx.clone(), pty_size, resume_session_id)
+                app.start_dispatch(idx);
+                let pty_size = app.pty_size_for(id);
+                match worker::spawn_worker(id, text, cwd, &env, mode, worker_tx.clone(), pty_size, session, &app.agent, app.kill_grace, app.pty_scrollback_bytes)
                 {
                     SpawnResult::Pty {
                         input_sender,
                         pty_handle,
                     } => {
+                        if let Some(pid) = pty_handle.child.process_id() {
+                            app.record_worker_pid(id, pid);
+                        }
                         app.worker_inputs.insert(id, input_sender);
                         // Store PTY state on the prompt
                         if let Some(p) = app.prompts.iter_mut().find(|p| p.id == id) {
                             p.pty_state = Some(pty_handle.state.clone());
                         }
                         app.pty_handles.insert(id, pty_handle);
+                        app.pty_sizes.insert(id, pty_size);
                     }
                     SpawnResult::OneShot => {
                         // No input sender for one-shot
@@ -176,28 +214,33 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, launch_o
                     Event::Key(key) if key.kind == KeyEventKind::Press => {
                         app.handle_key(key);
                     }
-                    Event::Paste(text) if app.mode == app::AppMode::Insert => {
-                        for c in text.chars() {
-                            if c == '\n' {
-                                app.input.insert_newline();
-                            } else if c != '\r' {
-                                app.input.insert_char(c);
-                            }
-                        }
+                    Event::Paste(text) => {
+                        app.handle_paste(&text);
+                    }
+                    Event::Mouse(mouse) if app.mouse => {
+                        app.handle_mouse(mouse);
                     }
                     Event::Resize(_, _) => {
                         // Terminal resized — next draw will update output_panel_size
-                        // and resize_pty_workers will be called
+                        // and sync_pty_sizes will resize the viewed PTY
                     }
                     _ => {}
                 }
             }
             Some(msg) = worker_rx.recv() => {
-                app.apply_message(msg);
+                app.apply_queued_messages(msg, &mut worker_rx);
             }
             _ = tick_interval.tick() => {
                 app.tick = app.tick.wrapping_add(1);
                 app.clear_expired_status();
+                app.expire_pending_sequence();
+                app.report_persistence_error();
+                app.check_timeouts();
+                app.check_input_reminders();
+                app.check_drained();
+                if app.tick.is_multiple_of(5) {
+                    app.poll_inbox();
+                }
             }
         }
 
@@ -210,14 +253,16 @@ async fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, launch_o
         }
 
         if app.should_quit {
-            // Send Kill to all active workers
-            for (_id, sender) in app.worker_inputs.drain() {
-                let _ = sender.send(WorkerInput::Kill);
+            // SIGTERM all PTY workers, SIGKILL any still alive after the grace period
+            app.terminate_all_workers();
+            if app.terminal_title {
+                // Hand the title back to the shell rather than leave stale counts
+                execute!(terminal.backend_mut(), SetTitle(""))?;
+            }
+            if let Some(ref dir) = app.inbox_dir {
+                inbox::remove_status(dir);
+                inbox::remove_pid(dir);
             }
-            // Clear PTY handles (drops masters → children get SIGHUP)
-            app.pty_handles.clear();
-            // Brief sleep for cleanup
-            tokio::time::sleep(Duration::from_millis(100)).await;
             return Ok(());
         }
     }
@@ -239,7 +284,7 @@ fn open_editor(
 
     // Suspend terminal
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), DisableMouseCapture, DisableBracketedPaste, LeaveAlternateScreen)?;
 
     // Spawn editor
     let status = std::process::Command::new(&editor)
@@ -248,6 +293,9 @@ fn open_editor(
 
     // Restore terminal
     execute!(terminal.backend_mut(), EnterAlternateScreen, EnableBracketedPaste)?;
+    if app.mouse {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
     enable_raw_mode()?;
     terminal.clear()?;
 
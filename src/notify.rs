@@ -0,0 +1,112 @@
+use std::process::{Command, Stdio};
+
+use crate::prompt::{Prompt, PromptStatus};
+
+const MAX_BODY_CHARS: usize = 80;
+
+/// Title and body for a finished prompt's desktop notification.
+pub fn finished_message(prompt: &Prompt, exit_code: Option<i32>) -> (String, String) {
+    let status = match (&prompt.status, exit_code) {
+        (PromptStatus::Failed, Some(code)) if code != 0 => format!("failed (exit {code})"),
+        (PromptStatus::Failed, _) => "failed".to_string(),
+        _ => "completed".to_string(),
+    };
+    let title = format!("clhorde: #{} {status}", prompt.id);
+    (title, truncate(&prompt.text, MAX_BODY_CHARS))
+}
+
+/// Title and body for the reminder that a prompt is waiting for input.
+pub fn waiting_message(prompt: &Prompt) -> (String, String) {
+    let title = format!("clhorde: #{} is waiting for input", prompt.id);
+    (title, truncate(&prompt.text, MAX_BODY_CHARS))
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() <= max_chars && !text.trim().contains('\n') {
+        line.to_string()
+    } else {
+        let cut: String = line.chars().take(max_chars.saturating_sub(3)).collect();
+        format!("{cut}...")
+    }
+}
+
+/// Fire a desktop notification without blocking the UI. Uses `notify-send`
+/// on Linux and `osascript` on macOS; failures are ignored.
+pub fn send(title: String, body: String) {
+    std::thread::spawn(move || {
+        let mut cmd = if cfg!(target_os = "macos") {
+            let script = format!(
+                "display notification {} with title {}",
+                applescript_quote(&body),
+                applescript_quote(&title)
+            );
+            let mut c = Command::new("osascript");
+            c.args(["-e", &script]);
+            c
+        } else {
+            let mut c = Command::new("notify-send");
+            c.args(["--app-name=clhorde", &title, &body]);
+            c
+        };
+        let _ = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    });
+}
+
+fn applescript_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::PromptMode;
+
+    fn prompt(text: &str, status: PromptStatus) -> Prompt {
+        let mut p = Prompt::new(7, text.to_string(), None, PromptMode::OneShot);
+        p.status = status;
+        p
+    }
+
+    #[test]
+    fn completed_message() {
+        let (title, body) = finished_message(&prompt("fix the bug", PromptStatus::Completed), Some(0));
+        assert_eq!(title, "clhorde: #7 completed");
+        assert_eq!(body, "fix the bug");
+    }
+
+    #[test]
+    fn failed_message_includes_exit_code() {
+        let (title, _) = finished_message(&prompt("x", PromptStatus::Failed), Some(2));
+        assert_eq!(title, "clhorde: #7 failed (exit 2)");
+        let (title, _) = finished_message(&prompt("x", PromptStatus::Failed), None);
+        assert_eq!(title, "clhorde: #7 failed");
+    }
+
+    #[test]
+    fn waiting_message_names_the_prompt() {
+        let (title, body) = waiting_message(&prompt("refactor auth", PromptStatus::Idle));
+        assert_eq!(title, "clhorde: #7 is waiting for input");
+        assert_eq!(body, "refactor auth");
+    }
+
+    #[test]
+    fn body_is_truncated() {
+        let long = "a".repeat(200);
+        let (_, body) = finished_message(&prompt(&long, PromptStatus::Completed), None);
+        assert_eq!(body.chars().count(), MAX_BODY_CHARS);
+        assert!(body.ends_with("..."));
+
+        let (_, body) = finished_message(&prompt("first\nsecond", PromptStatus::Completed), None);
+        assert_eq!(body, "first...");
+    }
+
+    #[test]
+    fn applescript_quoting() {
+        assert_eq!(applescript_quote(r#"say "hi" \ bye"#), r#""say \"hi\" \\ bye""#);
+    }
+}
@@ -0,0 +1,115 @@
+/// One line of an agent's `stream-json` output, reduced to what clhorde uses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedEvent {
+    /// `system` init message carrying the session id.
+    SessionId(String),
+    /// Streamed assistant text.
+    TextDelta(String),
+    /// The agent started a tool call, by tool name.
+    ToolCall(String),
+    /// Tool output handed back to the agent, i.e. the tool call finished.
+    ToolResult,
+    /// Final `result` message of a turn.
+    TurnEnd,
+    /// Valid JSON of a kind clhorde doesn't render.
+    Other,
+    /// Not JSON at all (e.g. a stray log line).
+    Invalid,
+}
+
+pub fn parse_line(line: &str) -> ParsedEvent {
+    let json: serde_json::Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(_) => return ParsedEvent::Invalid,
+    };
+    match json["type"].as_str() {
+        Some("system") => match json["session_id"].as_str() {
+            Some(id) => ParsedEvent::SessionId(id.to_string()),
+            None => ParsedEvent::Other,
+        },
+        Some("result") => ParsedEvent::TurnEnd,
+        Some("user") => ParsedEvent::ToolResult,
+        Some("stream_event") => {
+            let event = &json["event"];
+            match event["type"].as_str() {
+                Some("content_block_delta") => match event["delta"]["text"].as_str() {
+                    Some(text) if !text.is_empty() => ParsedEvent::TextDelta(text.to_string()),
+                    _ => ParsedEvent::Other,
+                },
+                Some("content_block_start") if event["content_block"]["type"] == "tool_use" => {
+                    match event["content_block"]["name"].as_str() {
+                        Some(name) => ParsedEvent::ToolCall(name.to_string()),
+                        None => ParsedEvent::Other,
+                    }
+                }
+                _ => ParsedEvent::Other,
+            }
+        }
+        _ => ParsedEvent::Other,
+    }
+}
+
+/// Output line shown in place of a tool call, so work is visible apart
+/// from thinking.
+pub fn tool_marker(name: &str) -> String {
+    format!("🔧 running tool {name}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_session_id() {
+        let line = r#"{"type":"system","subtype":"init","session_id":"abc-123","tools":[]}"#;
+        assert_eq!(parse_line(line), ParsedEvent::SessionId("abc-123".into()));
+    }
+
+    #[test]
+    fn parses_text_delta() {
+        let line = r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}}"#;
+        assert_eq!(parse_line(line), ParsedEvent::TextDelta("Hello".into()));
+    }
+
+    #[test]
+    fn empty_text_delta_is_other() {
+        let line = r#"{"type":"stream_event","event":{"type":"content_block_delta","delta":{"type":"text_delta","text":""}}}"#;
+        assert_eq!(parse_line(line), ParsedEvent::Other);
+    }
+
+    #[test]
+    fn tool_input_delta_is_not_text() {
+        let line = r#"{"type":"stream_event","event":{"type":"content_block_delta","delta":{"type":"input_json_delta","partial_json":"{\"cmd\""}}}"#;
+        assert_eq!(parse_line(line), ParsedEvent::Other);
+    }
+
+    #[test]
+    fn parses_tool_call() {
+        let line = r#"{"type":"stream_event","event":{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"Bash","input":{}}}}"#;
+        assert_eq!(parse_line(line), ParsedEvent::ToolCall("Bash".into()));
+    }
+
+    #[test]
+    fn text_block_start_is_other() {
+        let line = r#"{"type":"stream_event","event":{"type":"content_block_start","content_block":{"type":"text","text":""}}}"#;
+        assert_eq!(parse_line(line), ParsedEvent::Other);
+    }
+
+    #[test]
+    fn parses_tool_result() {
+        let line = r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"ok"}]}}"#;
+        assert_eq!(parse_line(line), ParsedEvent::ToolResult);
+    }
+
+    #[test]
+    fn parses_turn_end() {
+        let line = r#"{"type":"result","subtype":"success","is_error":false,"result":"done"}"#;
+        assert_eq!(parse_line(line), ParsedEvent::TurnEnd);
+    }
+
+    #[test]
+    fn unknown_and_invalid_lines() {
+        assert_eq!(parse_line(r#"{"type":"assistant","message":{}}"#), ParsedEvent::Other);
+        assert_eq!(parse_line("not json"), ParsedEvent::Invalid);
+    }
+}
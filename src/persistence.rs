@@ -1,51 +1,149 @@
+use std::collections::BTreeMap;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::prompt::PromptMode;
+use crate::prompt::{PromptMode, PromptPriority};
 
-#[derive(Serialize, Deserialize)]
+/// Current `PromptFile` format. Files from before the field existed are
+/// version 1; `migrate` brings older files up to this one on load.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PromptFile {
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub prompt: String,
     pub options: PromptOptions,
     pub state: String,
     pub queue_rank: f64,
+    /// Manual sort position; older files fall back to `queue_rank`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_rank: Option<f64>,
     pub session_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub worktree_path: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
+    /// UUID of the prompt this one waits on (ids are reassigned on restore).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_session_id: Option<String>,
+    /// What a stream worker wrote to stderr, kept apart from `output`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+    /// Pid of the agent process, only while the prompt is running, so a
+    /// crashed instance's workers can be found on the next start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_pid: Option<u32>,
+}
+
+fn legacy_schema_version() -> u32 {
+    1
 }
 
+/// A persisted prompt together with its UUID (the file name), as written
+/// by `clhorde store export`.
 #[derive(Serialize, Deserialize)]
+pub struct StoredPrompt {
+    pub uuid: String,
+    #[serde(flatten)]
+    pub file: PromptFile,
+}
+
+/// A prompt record accepted by `clhorde store import`. Only `prompt` is
+/// required, so exported records and hand-written ones both parse.
+#[derive(Deserialize)]
+pub struct ImportRecord {
+    pub prompt: String,
+    #[serde(default)]
+    pub options: Option<PromptOptions>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PromptOptions {
     pub mode: String,
     pub context: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub worktree: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Total attempts (first run included) before a failure is final.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Extra environment variables for the worker process.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Default cap on output stored per prompt file (1 MiB).
+pub const DEFAULT_MAX_SAVED_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Keep at most the last `max` bytes of `output`, prefixed with a marker
+/// saying how much was dropped. The end of a transcript is the part worth
+/// keeping, since that's where the agent's answer is.
+pub fn cap_output(output: &str, max: usize) -> String {
+    if output.len() <= max {
+        return output.to_string();
+    }
+    let mut start = output.len() - max;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("{}{}", truncation_marker(start), &output[start..])
+}
+
+/// Line put in front of output whose first `dropped` bytes were cut.
+pub fn truncation_marker(dropped: usize) -> String {
+    format!("[… {dropped} bytes truncated …]\n")
 }
 
 pub fn default_prompts_dir() -> Option<PathBuf> {
     dirs::data_dir().map(|d| d.join("clhorde").join("prompts"))
 }
 
-pub fn save_prompt(dir: &Path, uuid: &str, data: &PromptFile) {
-    let _ = fs::create_dir_all(dir);
-    let path = dir.join(format!("{uuid}.json"));
-    let content = match serde_json::to_string_pretty(data) {
-        Ok(c) => c,
-        Err(_) => return,
-    };
-    let _ = fs::write(path, content);
+pub fn save_prompt(dir: &Path, uuid: &str, data: &PromptFile) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(data)?;
+    write_atomic(&dir.join(format!("{uuid}.json")), &content)
+}
+
+/// Write through a `.tmp` sibling and rename it over `path`, so a crash
+/// mid-write leaves the previous file intact. Loaders only read `*.json`,
+/// so a leftover temp file is ignored.
+fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
 }
 
 pub fn load_all_prompts(dir: &Path) -> Vec<(String, PromptFile)> {
+    load_and_migrate_prompts(dir).0
+}
+
+/// Like [`load_all_prompts`], also returning the UUIDs of files written by
+/// an older schema and migrated in memory. Nothing is rewritten here;
+/// callers that own the store save the upgraded records back.
+pub fn load_and_migrate_prompts(dir: &Path) -> (Vec<(String, PromptFile)>, Vec<String>) {
     let entries = match fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => return Vec::new(),
+        Err(_) => return (Vec::new(), Vec::new()),
     };
 
+    let mut migrated = Vec::new();
     let mut prompts: Vec<(String, PromptFile)> = entries
         .filter_map(|e| e.ok())
         .filter_map(|entry| {
@@ -55,7 +153,12 @@ pub fn load_all_prompts(dir: &Path) -> Vec<(String, PromptFile)> {
             }
             let uuid = path.file_stem()?.to_str()?.to_string();
             let content = fs::read_to_string(&path).ok()?;
-            let data: PromptFile = serde_json::from_str(&content).ok()?;
+            let mut value: serde_json::Value = serde_json::from_str(&content).ok()?;
+            let upgraded = migrate(&mut value);
+            let data: PromptFile = serde_json::from_value(value).ok()?;
+            if upgraded {
+                migrated.push(uuid.clone());
+            }
             Some((uuid, data))
         })
         .collect();
@@ -65,10 +168,54 @@ pub fn load_all_prompts(dir: &Path) -> Vec<(String, PromptFile)> {
             .partial_cmp(&b.1.queue_rank)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
-    prompts
+    (prompts, migrated)
 }
 
-pub fn prune_old_prompts(dir: &Path, max: usize) {
+/// Upgrade a prompt file from its `schema_version` to [`SCHEMA_VERSION`],
+/// one version at a time. Returns whether anything changed. Files from a
+/// newer clhorde are left alone and parse as far as serde allows.
+fn migrate(value: &mut serde_json::Value) -> bool {
+    let Some(fields) = value.as_object_mut() else {
+        return false;
+    };
+    let version = fields.get("schema_version").and_then(|v| v.as_u64()).unwrap_or(1);
+    if version >= u64::from(SCHEMA_VERSION) {
+        return false;
+    }
+    if version < 2 {
+        // v1 files were written before these had fallbacks; fill them in
+        // rather than dropping the prompt
+        fields.entry("state").or_insert_with(|| "completed".into());
+        fields.entry("queue_rank").or_insert_with(|| 0.0.into());
+        let options = fields
+            .entry("options")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(options) = options.as_object_mut() {
+            options.entry("mode").or_insert_with(|| "interactive".into());
+        }
+    }
+    fields.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    true
+}
+
+/// Expand a leading `~` (alone or before `/`) to `home`.
+pub fn expand_tilde(path: &str, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resolve the `archive_dir` setting, expanding a leading `~`.
+pub fn archive_dir(setting: &str) -> PathBuf {
+    expand_tilde(setting, dirs::home_dir().as_deref())
+}
+
+/// Keep the newest `max` prompt files. The rest are deleted, or moved into
+/// `archive` when set; a file that can't be archived is left in place.
+pub fn prune_old_prompts(dir: &Path, max: usize, archive: Option<&Path>) {
     let entries = match fs::read_dir(dir) {
         Ok(e) => e,
         Err(_) => return,
@@ -92,48 +239,148 @@ pub fn prune_old_prompts(dir: &Path, max: usize) {
     // UUID v7 sorts lexicographically by time — sort ascending
     files.sort();
 
-    // Delete the oldest (first) entries, keep the last `max`
+    // Drop the oldest (first) entries, keep the last `max`
     let to_delete = files.len() - max;
     for uuid in &files[..to_delete] {
         let path = dir.join(format!("{uuid}.json"));
-        let _ = fs::remove_file(path);
+        match archive {
+            Some(archive) => {
+                let _ = archive_file(&path, &archive.join(format!("{uuid}.json")));
+            }
+            None => {
+                let _ = fs::remove_file(path);
+            }
+        }
     }
 }
 
+/// Move `from` to `to`, copying when they're on different filesystems.
+fn archive_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
 pub fn delete_prompt_file(dir: &Path, uuid: &str) {
     let path = dir.join(format!("{uuid}.json"));
     let _ = fs::remove_file(path);
 }
 
+/// Machine-readable snapshot of a prompt, written by the JSON export.
+#[derive(Serialize, Deserialize)]
+pub struct PromptExport {
+    pub id: usize,
+    pub uuid: String,
+    pub text: String,
+    pub cwd: Option<String>,
+    pub tags: Vec<String>,
+    pub status: String,
+    pub mode: String,
+    pub elapsed_secs: Option<f64>,
+    pub output: Option<String>,
+}
+
+fn mode_name(mode: PromptMode) -> &'static str {
+    match mode {
+        PromptMode::Interactive => "interactive",
+        PromptMode::OneShot => "one_shot",
+    }
+}
+
+impl PromptExport {
+    pub fn from_prompt(prompt: &crate::prompt::Prompt) -> Self {
+        PromptExport {
+            id: prompt.id,
+            uuid: prompt.uuid.clone(),
+            text: prompt.text.clone(),
+            cwd: prompt.cwd.clone(),
+            tags: prompt.tags.clone(),
+            status: prompt.state_label().to_string(),
+            mode: mode_name(prompt.mode).to_string(),
+            elapsed_secs: prompt.elapsed_secs(),
+            output: prompt.output.clone(),
+        }
+    }
+}
+
 impl PromptFile {
     pub fn from_prompt(prompt: &crate::prompt::Prompt) -> Self {
-        let mode = match prompt.mode {
-            PromptMode::Interactive => "interactive",
-            PromptMode::OneShot => "one_shot",
-        };
+        let mode = mode_name(prompt.mode);
         let state = match prompt.status {
             crate::prompt::PromptStatus::Pending => "pending",
             crate::prompt::PromptStatus::Running => "running",
             crate::prompt::PromptStatus::Idle => "running",
             crate::prompt::PromptStatus::Completed => "completed",
-            crate::prompt::PromptStatus::Failed => "failed",
+            crate::prompt::PromptStatus::Failed => prompt.state_label(),
         };
         PromptFile {
+            schema_version: SCHEMA_VERSION,
             prompt: prompt.text.clone(),
             options: PromptOptions {
                 mode: mode.to_string(),
                 context: prompt.cwd.clone(),
                 worktree: if prompt.worktree { Some(true) } else { None },
+                priority: if prompt.priority == PromptPriority::Normal {
+                    None
+                } else {
+                    Some(prompt.priority.label().to_string())
+                },
+                timeout_secs: prompt.timeout_secs,
+                max_attempts: (prompt.max_attempts > 1).then_some(prompt.max_attempts),
+                env: prompt.env.clone(),
             },
             state: state.to_string(),
             queue_rank: prompt.queue_rank,
+            display_rank: Some(prompt.display_rank),
             session_id: prompt.session_id.clone(),
             worktree_path: prompt.worktree_path.clone(),
             tags: prompt.tags.clone(),
+            depends_on: None,
+            output: prompt.output.clone(),
+            error: prompt.error.clone(),
+            command: prompt.command.clone(),
+            resume_session_id: prompt.resume_session_id.clone(),
+            stderr: prompt.stderr.clone(),
+            worker_pid: prompt.worker_pid.filter(|_| state == "running"),
         }
     }
 }
 
+/// Runtime settings changed from the TUI (worker count, default mode,
+/// pause), restored on the next start.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionState {
+    pub max_workers: usize,
+    pub default_mode: PromptMode,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+pub fn default_state_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("clhorde").join("state.json"))
+}
+
+pub fn load_state(path: &Path) -> Option<SessionState> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// Written to a temp file and renamed, so a crash mid-write leaves the
+/// previous state intact.
+pub fn save_state(path: &Path, state: &SessionState) {
+    let Ok(content) = serde_json::to_string_pretty(state) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = write_atomic(path, &content);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,32 +392,140 @@ mod tests {
         dir
     }
 
+    #[test]
+    fn v1_file_migrates_with_defaults() {
+        let dir = temp_prompts_dir();
+        // Shape written by the first releases: no schema_version, none of
+        // the later fields
+        let v1 = r#"{
+  "prompt": "old prompt",
+  "options": { "mode": "one_shot", "context": "/tmp" },
+  "state": "completed",
+  "queue_rank": 3.0,
+  "session_id": "sess-1"
+}"#;
+        fs::write(dir.join("old.json"), v1).unwrap();
+
+        let (loaded, migrated) = load_and_migrate_prompts(&dir);
+        assert_eq!(migrated, vec!["old".to_string()]);
+        let pf = &loaded[0].1;
+        assert_eq!(pf.schema_version, SCHEMA_VERSION);
+        assert_eq!(pf.prompt, "old prompt");
+        assert_eq!(pf.options.mode, "one_shot");
+        assert_eq!(pf.session_id.as_deref(), Some("sess-1"));
+        assert_eq!(pf.display_rank, None);
+        assert!(pf.tags.is_empty());
+        assert!(pf.output.is_none() && pf.stderr.is_none() && pf.depends_on.is_none());
+        assert_eq!(pf.options.priority, None);
+        assert_eq!(pf.options.max_attempts, None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn v1_file_missing_required_fields_is_kept() {
+        let dir = temp_prompts_dir();
+        fs::write(dir.join("bare.json"), r#"{ "prompt": "just text" }"#).unwrap();
+
+        let (loaded, migrated) = load_and_migrate_prompts(&dir);
+        assert_eq!(migrated.len(), 1);
+        assert_eq!(loaded[0].1.prompt, "just text");
+        assert_eq!(loaded[0].1.state, "completed");
+        assert_eq!(loaded[0].1.options.mode, "interactive");
+        assert_eq!(loaded[0].1.queue_rank, 0.0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn current_files_are_not_migrated() {
+        let dir = temp_prompts_dir();
+        let prompt = crate::prompt::Prompt::new(1, "new".to_string(), None, PromptMode::Interactive);
+        save_prompt(&dir, &prompt.uuid, &PromptFile::from_prompt(&prompt)).unwrap();
+        let content = fs::read_to_string(dir.join(format!("{}.json", prompt.uuid))).unwrap();
+        assert!(content.contains("\"schema_version\": 2"));
+
+        let (loaded, migrated) = load_and_migrate_prompts(&dir);
+        assert_eq!(loaded.len(), 1);
+        assert!(migrated.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn interrupted_write_keeps_previous_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("prompts");
+        let prompt = crate::prompt::Prompt::new(1, "good".to_string(), None, PromptMode::OneShot);
+        save_prompt(&dir, &prompt.uuid, &PromptFile::from_prompt(&prompt)).unwrap();
+
+        // Crash after writing the temp file but before the rename
+        fs::write(dir.join(format!("{}.json.tmp", prompt.uuid)), "{\"prompt\": \"half").unwrap();
+
+        let loaded = load_all_prompts(&dir);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].0, prompt.uuid);
+        assert_eq!(loaded[0].1.prompt, "good");
+    }
+
+    #[test]
+    fn save_leaves_no_temp_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let prompt = crate::prompt::Prompt::new(1, "x".to_string(), None, PromptMode::OneShot);
+        save_prompt(tmp.path(), &prompt.uuid, &PromptFile::from_prompt(&prompt)).unwrap();
+        let names: Vec<_> = fs::read_dir(tmp.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, vec![std::ffi::OsString::from(format!("{}.json", prompt.uuid))]);
+    }
+
+    #[test]
+    fn save_into_non_directory_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("not-a-dir");
+        fs::write(&file, "").unwrap();
+        let data = PromptFile::from_prompt(&crate::prompt::Prompt::new(1, "x".to_string(), None, PromptMode::OneShot));
+        assert!(save_prompt(&file, "abc", &data).is_err());
+    }
+
     #[test]
     fn save_and_load_roundtrip() {
         let dir = temp_prompts_dir();
 
         let uuid1 = uuid::Uuid::now_v7().to_string();
         let data = PromptFile {
+            schema_version: SCHEMA_VERSION,
             prompt: "test prompt".to_string(),
             options: PromptOptions {
                 mode: "interactive".to_string(),
                 context: Some("/tmp".to_string()),
                 worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: [("RUST_LOG".to_string(), "debug".to_string())].into(),
             },
             state: "completed".to_string(),
             queue_rank: 1.0,
+            display_rank: None,
             session_id: Some("sess-123".to_string()),
             worktree_path: None,
             tags: Vec::new(),
+            depends_on: None,
+            output: None,
+            error: None,
+            command: None,
+            resume_session_id: None,
+            stderr: None,
+            worker_pid: None,
         };
 
-        save_prompt(&dir, &uuid1, &data);
+        save_prompt(&dir, &uuid1, &data).unwrap();
         let loaded = load_all_prompts(&dir);
         assert_eq!(loaded.len(), 1);
         assert_eq!(loaded[0].0, uuid1);
         assert_eq!(loaded[0].1.prompt, "test prompt");
         assert_eq!(loaded[0].1.options.mode, "interactive");
         assert_eq!(loaded[0].1.options.context, Some("/tmp".to_string()));
+        assert_eq!(loaded[0].1.options.env.get("RUST_LOG").map(String::as_str), Some("debug"));
         assert_eq!(loaded[0].1.state, "completed");
         assert_eq!(loaded[0].1.queue_rank, 1.0);
         assert_eq!(loaded[0].1.session_id, Some("sess-123".to_string()));
@@ -178,6 +533,59 @@ mod tests {
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn export_json_shape() {
+        let mut prompt = crate::prompt::Prompt::new(3, "hi".to_string(), Some("/tmp".to_string()), PromptMode::OneShot);
+        prompt.uuid = "0190-abc".to_string();
+        prompt.tags = vec!["api".to_string()];
+        prompt.status = crate::prompt::PromptStatus::Completed;
+        prompt.output = Some("done".to_string());
+        let now = std::time::Instant::now();
+        prompt.started_at = Some(now);
+        prompt.finished_at = Some(now + std::time::Duration::from_millis(1500));
+
+        let value = serde_json::to_value([PromptExport::from_prompt(&prompt)]).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "id": 3,
+                "uuid": "0190-abc",
+                "text": "hi",
+                "cwd": "/tmp",
+                "tags": ["api"],
+                "status": "completed",
+                "mode": "one_shot",
+                "elapsed_secs": 1.5,
+                "output": "done",
+            }])
+        );
+    }
+
+    #[test]
+    fn output_roundtrip() {
+        let dir = temp_prompts_dir();
+
+        let mut prompt = crate::prompt::Prompt::new(1, "hi".to_string(), None, PromptMode::OneShot);
+        prompt.status = crate::prompt::PromptStatus::Completed;
+        prompt.output = Some("hello\nworld\n".to_string());
+        save_prompt(&dir, &prompt.uuid, &PromptFile::from_prompt(&prompt)).unwrap();
+
+        let loaded = load_all_prompts(&dir);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].1.output.as_deref(), Some("hello\nworld\n"));
+        assert!(loaded[0].1.error.is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cap_output_keeps_tail() {
+        assert_eq!(cap_output("short", 10), "short");
+        assert_eq!(cap_output("0123456789", 4), "[… 6 bytes truncated …]\n6789");
+        // Never splits a multi-byte character
+        assert_eq!(cap_output("aé", 1), "[… 3 bytes truncated …]\n");
+    }
+
     #[test]
     fn load_empty_dir() {
         let dir = temp_prompts_dir();
@@ -200,19 +608,32 @@ mod tests {
         for (i, rank) in [(0, 3.0), (1, 1.0), (2, 2.0)] {
             let uuid = uuid::Uuid::now_v7().to_string();
             let data = PromptFile {
+                schema_version: SCHEMA_VERSION,
                 prompt: format!("prompt {i}"),
                 options: PromptOptions {
                     mode: "interactive".to_string(),
                     context: None,
                     worktree: None,
+                    priority: None,
+                    timeout_secs: None,
+                    max_attempts: None,
+                    env: BTreeMap::new(),
                 },
                 state: "completed".to_string(),
                 queue_rank: rank,
+                display_rank: None,
                 session_id: None,
                 worktree_path: None,
                 tags: Vec::new(),
+                depends_on: None,
+                output: None,
+                error: None,
+                command: None,
+                resume_session_id: None,
+                stderr: None,
+                worker_pid: None,
             };
-            save_prompt(&dir, &uuid, &data);
+            save_prompt(&dir, &uuid, &data).unwrap();
             std::thread::sleep(std::time::Duration::from_millis(1));
         }
 
@@ -249,6 +670,39 @@ mod tests {
         assert!(file.options.context.is_none());
     }
 
+    #[test]
+    fn prompt_file_priority() {
+        let mut prompt = crate::prompt::Prompt::new(1, "test".to_string(), None, PromptMode::OneShot);
+        let file = PromptFile::from_prompt(&prompt);
+        assert!(file.options.priority.is_none());
+
+        prompt.priority = PromptPriority::High;
+        let file = PromptFile::from_prompt(&prompt);
+        assert_eq!(file.options.priority, Some("high".to_string()));
+    }
+
+    #[test]
+    fn prompt_file_timeout() {
+        let mut prompt = crate::prompt::Prompt::new(1, "test".to_string(), None, PromptMode::OneShot);
+        prompt.timeout_secs = Some(300);
+        let file = PromptFile::from_prompt(&prompt);
+        assert_eq!(file.options.timeout_secs, Some(300));
+    }
+
+    #[test]
+    fn load_file_without_priority() {
+        let dir = temp_prompts_dir();
+        let uuid = uuid::Uuid::now_v7().to_string();
+        let json = r#"{"prompt":"old","options":{"mode":"interactive","context":null},"state":"completed","queue_rank":1.0,"session_id":null}"#;
+        fs::write(dir.join(format!("{uuid}.json")), json).unwrap();
+
+        let loaded = load_all_prompts(&dir);
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded[0].1.options.priority.is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn prune_keeps_newest() {
         let dir = temp_prompts_dir();
@@ -257,25 +711,38 @@ mod tests {
         for i in 0..5 {
             let uuid = uuid::Uuid::now_v7().to_string();
             let data = PromptFile {
+                schema_version: SCHEMA_VERSION,
                 prompt: format!("prompt {i}"),
                 options: PromptOptions {
                     mode: "interactive".to_string(),
                     context: None,
                     worktree: None,
+                    priority: None,
+                    timeout_secs: None,
+                    max_attempts: None,
+                    env: BTreeMap::new(),
                 },
                 state: "completed".to_string(),
                 queue_rank: i as f64,
+                display_rank: None,
                 session_id: None,
                 worktree_path: None,
                 tags: Vec::new(),
+                depends_on: None,
+                output: None,
+                error: None,
+                command: None,
+                resume_session_id: None,
+                stderr: None,
+                worker_pid: None,
             };
-            save_prompt(&dir, &uuid, &data);
+            save_prompt(&dir, &uuid, &data).unwrap();
             uuids.push(uuid);
             std::thread::sleep(std::time::Duration::from_millis(1));
         }
 
         assert_eq!(load_all_prompts(&dir).len(), 5);
-        prune_old_prompts(&dir, 3);
+        prune_old_prompts(&dir, 3, None);
 
         let remaining = load_all_prompts(&dir);
         assert_eq!(remaining.len(), 3);
@@ -287,27 +754,76 @@ mod tests {
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn prune_moves_files_into_archive() {
+        let dir = temp_prompts_dir();
+        let archive = dir.join("archive");
+        let mut uuids = Vec::new();
+        for i in 0..4 {
+            let prompt = crate::prompt::Prompt::new(i + 1, format!("prompt {i}"), None, PromptMode::OneShot);
+            save_prompt(&dir, &prompt.uuid, &PromptFile::from_prompt(&prompt)).unwrap();
+            uuids.push(prompt.uuid);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        prune_old_prompts(&dir, 2, Some(&archive));
+
+        let live: Vec<String> = load_all_prompts(&dir).into_iter().map(|(u, _)| u).collect();
+        assert_eq!(live.len(), 2);
+        assert!(live.contains(&uuids[2]) && live.contains(&uuids[3]));
+        let mut archived: Vec<String> = load_all_prompts(&archive).into_iter().map(|(_, p)| p.prompt).collect();
+        archived.sort();
+        assert_eq!(archived, vec!["prompt 0", "prompt 1"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn archive_dir_expands_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(archive_dir("~/old"), home.join("old"));
+        assert_eq!(archive_dir("~"), home);
+        assert_eq!(archive_dir("/var/clhorde"), PathBuf::from("/var/clhorde"));
+        let home = Path::new("/home/me");
+        assert_eq!(expand_tilde("~/src", Some(home)), home.join("src"));
+        assert_eq!(expand_tilde("~user/src", Some(home)), PathBuf::from("~user/src"));
+        assert_eq!(expand_tilde("~/src", None), PathBuf::from("~/src"));
+    }
+
     #[test]
     fn prune_noop_when_under_limit() {
         let dir = temp_prompts_dir();
 
         let uuid = uuid::Uuid::now_v7().to_string();
         let data = PromptFile {
+            schema_version: SCHEMA_VERSION,
             prompt: "only one".to_string(),
             options: PromptOptions {
                 mode: "interactive".to_string(),
                 context: None,
                 worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: BTreeMap::new(),
             },
             state: "completed".to_string(),
             queue_rank: 1.0,
+            display_rank: None,
             session_id: None,
             worktree_path: None,
             tags: Vec::new(),
+            depends_on: None,
+            output: None,
+            error: None,
+            command: None,
+            resume_session_id: None,
+            stderr: None,
+            worker_pid: None,
         };
-        save_prompt(&dir, &uuid, &data);
+        save_prompt(&dir, &uuid, &data).unwrap();
 
-        prune_old_prompts(&dir, 10);
+        prune_old_prompts(&dir, 10, None);
         assert_eq!(load_all_prompts(&dir).len(), 1);
 
         let _ = fs::remove_dir_all(&dir);
@@ -319,19 +835,32 @@ mod tests {
 
         let uuid = uuid::Uuid::now_v7().to_string();
         let data = PromptFile {
+            schema_version: SCHEMA_VERSION,
             prompt: "to delete".to_string(),
             options: PromptOptions {
                 mode: "interactive".to_string(),
                 context: None,
                 worktree: None,
+                priority: None,
+                timeout_secs: None,
+                max_attempts: None,
+                env: BTreeMap::new(),
             },
             state: "completed".to_string(),
             queue_rank: 1.0,
+            display_rank: None,
             session_id: None,
             worktree_path: None,
             tags: Vec::new(),
+            depends_on: None,
+            output: None,
+            error: None,
+            command: None,
+            resume_session_id: None,
+            stderr: None,
+            worker_pid: None,
         };
-        save_prompt(&dir, &uuid, &data);
+        save_prompt(&dir, &uuid, &data).unwrap();
         assert_eq!(load_all_prompts(&dir).len(), 1);
 
         delete_prompt_file(&dir, &uuid);
@@ -339,4 +868,19 @@ mod tests {
 
         let _ = fs::remove_dir_all(&dir);
     }
+
+    #[test]
+    fn session_state_roundtrip() {
+        let dir = temp_prompts_dir();
+        let path = dir.join("state.json");
+        assert_eq!(load_state(&path), None);
+        let state = SessionState { max_workers: 7, default_mode: PromptMode::OneShot, paused: true };
+        save_state(&path, &state);
+        assert_eq!(load_state(&path), Some(state));
+        assert!(!dir.join("state.json.tmp").exists());
+
+        fs::write(&path, "{ not json").unwrap();
+        assert_eq!(load_state(&path), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,186 @@
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Default time a worker gets to exit after SIGTERM before it is SIGKILLed.
+pub const DEFAULT_KILL_GRACE_MS: u64 = 2000;
+
+/// Signals a user may send to a worker without ending it for good.
+pub const SENDABLE_SIGNALS: [&str; 3] = ["INT", "TSTP", "QUIT"];
+
+/// Whether a process with this pid exists (`kill -0`). Unreaped zombies
+/// count as alive.
+pub fn is_alive(pid: u32) -> bool {
+    signal(pid, "0")
+}
+
+/// Executable name of a running process (`ps -o comm=`), without its
+/// directory. Linux truncates it to 15 characters.
+pub fn command_name(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let name = name.rsplit('/').next().unwrap_or_default().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+/// Send a signal via the `kill` CLI. Returns whether it was delivered.
+fn signal(pid: u32, sig: &str) -> bool {
+    Command::new("kill")
+        .args([&format!("-{sig}"), &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Check a signal name (`int`, `SIGINT`, ...) against `SENDABLE_SIGNALS`,
+/// returning its canonical form.
+pub fn sendable_signal(name: &str) -> Result<&'static str, String> {
+    let upper = name.trim().to_ascii_uppercase();
+    let bare = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SENDABLE_SIGNALS
+        .into_iter()
+        .find(|&sig| sig == bare)
+        .ok_or_else(|| format!("signal '{name}' not allowed (use {})", SENDABLE_SIGNALS.join(", ")))
+}
+
+/// Send a signal to every process in the group `pgid`. Returns whether it
+/// was delivered.
+pub fn signal_group(pgid: u32, sig: &str) -> bool {
+    Command::new("kill")
+        .args([&format!("-{sig}"), "--", &format!("-{pgid}")])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Send SIGTERM now and SIGKILL from a background thread if the process
+/// is still alive after `grace`, so agents get a chance to clean up.
+pub fn terminate(pid: u32, grace: Duration) {
+    if !signal(pid, "TERM") {
+        return;
+    }
+    std::thread::spawn(move || {
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if !is_alive(pid) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        signal(pid, "KILL");
+    });
+}
+
+/// Blocking variant of [`terminate`] for many processes at once (used on
+/// shutdown, when background threads would not outlive the app).
+/// `exited` reports whether the process at the given index has exited.
+pub fn terminate_all(pids: &[u32], grace: Duration, mut exited: impl FnMut(usize) -> bool) {
+    for &pid in pids {
+        signal(pid, "TERM");
+    }
+    let deadline = Instant::now() + grace;
+    let mut remaining: Vec<usize> = (0..pids.len()).collect();
+    while !remaining.is_empty() && Instant::now() < deadline {
+        remaining.retain(|&i| !exited(i));
+        if !remaining.is_empty() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+    for i in remaining {
+        signal(pids[i], "KILL");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    fn wait_for_exit(child: &mut std::process::Child) -> std::process::ExitStatus {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                return status;
+            }
+            assert!(Instant::now() < deadline, "process did not exit");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn command_name_of_child() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        assert_eq!(command_name(child.id()).as_deref(), Some("sleep"));
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(command_name(child.id()), None);
+    }
+
+    #[test]
+    fn terminate_sends_sigterm_first() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        terminate(child.id(), Duration::from_secs(5));
+        assert_eq!(wait_for_exit(&mut child).signal(), Some(15));
+    }
+
+    #[test]
+    fn terminate_escalates_to_sigkill() {
+        // Ignored signals survive exec, so `sleep` ignores SIGTERM
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; exec sleep 30"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        terminate(child.id(), Duration::from_millis(200));
+        assert_eq!(wait_for_exit(&mut child).signal(), Some(9));
+    }
+
+    #[test]
+    fn terminate_all_waits_then_kills() {
+        let mut children = [
+            Command::new("sleep").arg("30").spawn().unwrap(),
+            Command::new("sh")
+                .args(["-c", "trap '' TERM; exec sleep 30"])
+                .spawn()
+                .unwrap(),
+        ];
+        std::thread::sleep(Duration::from_millis(100));
+        let pids: Vec<u32> = children.iter().map(|c| c.id()).collect();
+        terminate_all(&pids, Duration::from_millis(200), |i| {
+            children[i].try_wait().ok().flatten().is_some()
+        });
+        assert_eq!(wait_for_exit(&mut children[0]).signal(), Some(15));
+        assert_eq!(wait_for_exit(&mut children[1]).signal(), Some(9));
+    }
+
+    #[test]
+    fn sendable_signal_accepts_only_the_allowlist() {
+        assert_eq!(sendable_signal("INT"), Ok("INT"));
+        assert_eq!(sendable_signal("sigtstp"), Ok("TSTP"));
+        assert_eq!(sendable_signal(" SIGQUIT "), Ok("QUIT"));
+        for name in ["KILL", "SIGTERM", "9", "HUP", "", "SIG"] {
+            let err = sendable_signal(name).unwrap_err();
+            assert!(err.contains("not allowed"), "{name}: {err}");
+        }
+    }
+
+    #[test]
+    fn signal_group_reaches_the_whole_group() {
+        use std::os::unix::process::CommandExt;
+        let mut child = Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        assert!(signal_group(child.id(), "INT"));
+        assert_eq!(wait_for_exit(&mut child).signal(), Some(2));
+    }
+
+    #[test]
+    fn is_alive_for_current_process() {
+        assert!(is_alive(std::process::id()));
+    }
+}
@@ -1,4 +1,8 @@
-use std::time::Instant;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Default cap on output held in memory per prompt (16 MiB).
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
 
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum PromptMode {
@@ -22,6 +26,42 @@ impl PromptMode {
     }
 }
 
+/// Dispatch priority. Higher-priority pending prompts are started first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub enum PromptPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl PromptPriority {
+    pub fn label(&self) -> &str {
+        match self {
+            PromptPriority::Low => "low",
+            PromptPriority::Normal => "normal",
+            PromptPriority::High => "high",
+        }
+    }
+
+    pub fn from_label(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(PromptPriority::Low),
+            "normal" => Some(PromptPriority::Normal),
+            "high" => Some(PromptPriority::High),
+            _ => None,
+        }
+    }
+
+    /// Cycle Normal → High → Low → Normal.
+    pub fn cycle(&self) -> Self {
+        match self {
+            PromptPriority::Normal => PromptPriority::High,
+            PromptPriority::High => PromptPriority::Low,
+            PromptPriority::Low => PromptPriority::Normal,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum PromptStatus {
     Pending,
@@ -33,6 +73,38 @@ pub enum PromptStatus {
 }
 
 impl PromptStatus {
+    /// All statuses, in lifecycle order.
+    pub const ALL: [PromptStatus; 5] = [
+        PromptStatus::Pending,
+        PromptStatus::Running,
+        PromptStatus::Idle,
+        PromptStatus::Completed,
+        PromptStatus::Failed,
+    ];
+
+    /// Status name as used in `status:` filter tokens and exports.
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptStatus::Pending => "pending",
+            PromptStatus::Running => "running",
+            PromptStatus::Idle => "idle",
+            PromptStatus::Completed => "completed",
+            PromptStatus::Failed => "failed",
+        }
+    }
+
+    /// Parse a status name as used in `status:` filter tokens.
+    pub fn from_label(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(PromptStatus::Pending),
+            "running" => Some(PromptStatus::Running),
+            "idle" => Some(PromptStatus::Idle),
+            "completed" => Some(PromptStatus::Completed),
+            "failed" => Some(PromptStatus::Failed),
+            _ => None,
+        }
+    }
+
     pub fn symbol(&self) -> &str {
         match self {
             PromptStatus::Pending => "⏳",
@@ -62,6 +134,9 @@ pub struct Prompt {
     pub uuid: String,
     /// Ordering rank for persistence/restore.
     pub queue_rank: f64,
+    /// Position in the manual sort view; moving a prompt there never
+    /// changes dispatch order.
+    pub display_rank: f64,
     /// Claude session ID (captured from stream-json init message).
     pub session_id: Option<String>,
     /// Whether this prompt should resume an existing claude session.
@@ -72,6 +147,54 @@ pub struct Prompt {
     pub worktree_path: Option<String>,
     /// User-defined tags for grouping/filtering (e.g. `@frontend`).
     pub tags: Vec<String>,
+    /// Dispatch priority (ties broken by `queue_rank`).
+    pub priority: PromptPriority,
+    /// Per-turn execution limit in seconds; the worker is killed once exceeded.
+    pub timeout_secs: Option<u64>,
+    /// Start of the current turn (reset on each `TurnComplete`).
+    pub turn_started_at: Option<Instant>,
+    /// Id of a prompt that must complete before this one is dispatched.
+    pub depends_on: Option<usize>,
+    /// Latest (bytes, lines) of output reported by the running worker.
+    pub progress: Option<(u64, u64)>,
+    /// Follow-up messages sent mid-turn to a stream worker, flushed one per
+    /// `TurnComplete`.
+    pub queued_inputs: VecDeque<String>,
+    /// Colored transcript of a finished PTY worker (not persisted; the plain
+    /// `output` is the fallback).
+    pub styled_output: Option<Vec<crate::pty_worker::StyledLine>>,
+    /// Command line the worker was last spawned with, for debugging.
+    pub command: Option<String>,
+    /// Pid of the current agent process (persisted while running).
+    pub worker_pid: Option<u32>,
+    /// Current attempt, starting at 1; bumped each time a failure is retried.
+    pub attempt: u32,
+    /// Total attempts allowed before a failure is final (1 = no auto-retry).
+    pub max_attempts: u32,
+    /// A retried prompt is not dispatched before this instant (backoff).
+    pub retry_at: Option<Instant>,
+    /// Set when the user kills the worker, so the failure isn't retried.
+    pub killed: bool,
+    /// Bytes dropped from the front of `output` by `append_output`.
+    pub output_truncated: usize,
+    /// Existing agent session this prompt's text is sent into, rather than
+    /// starting a new one (unlike `resume`, which reopens its own session).
+    pub resume_session_id: Option<String>,
+    /// Extra environment variables set on the worker process.
+    pub env: BTreeMap<String, String>,
+    /// Tail of what a stream worker wrote to stderr. The same lines are
+    /// also interleaved into `output` in arrival order.
+    pub stderr: Option<String>,
+    /// Parts of `output` that came from stderr, as offsets into everything
+    /// ever appended (so they survive truncation). Not persisted.
+    pub stderr_spans: Vec<std::ops::Range<usize>>,
+    /// Tool a running stream worker is executing right now (not persisted).
+    pub activity: Option<String>,
+    /// When the agent last handed control back: the end of a stream turn,
+    /// or a PTY worker's latest screen update.
+    pub awaiting_input_since: Option<Instant>,
+    /// The waiting reminder already fired for the current wait.
+    pub input_reminder_sent: bool,
 }
 
 impl Prompt {
@@ -90,11 +213,33 @@ impl Prompt {
             pty_state: None,
             uuid: uuid::Uuid::now_v7().to_string(),
             queue_rank: 0.0,
+            display_rank: 0.0,
             session_id: None,
             resume: false,
             worktree: false,
             worktree_path: None,
             tags: Vec::new(),
+            priority: PromptPriority::Normal,
+            timeout_secs: None,
+            turn_started_at: None,
+            depends_on: None,
+            progress: None,
+            queued_inputs: VecDeque::new(),
+            styled_output: None,
+            command: None,
+            worker_pid: None,
+            attempt: 1,
+            max_attempts: 1,
+            retry_at: None,
+            killed: false,
+            output_truncated: 0,
+            resume_session_id: None,
+            env: BTreeMap::new(),
+            stderr: None,
+            stderr_spans: Vec::new(),
+            activity: None,
+            awaiting_input_since: None,
+            input_reminder_sent: false,
         }
     }
 
@@ -104,10 +249,164 @@ impl Prompt {
         Some(end.duration_since(start).as_secs_f64())
     }
 
+    /// Seconds since the current turn started.
+    pub fn turn_elapsed_secs(&self) -> Option<f64> {
+        self.turn_started_at.map(|t| t.elapsed().as_secs_f64())
+    }
+
+    /// Whether the current turn has run past `timeout_secs`.
+    pub fn is_timed_out(&self) -> bool {
+        match (self.timeout_secs, self.turn_elapsed_secs()) {
+            (Some(limit), Some(elapsed)) => elapsed >= limit as f64,
+            _ => false,
+        }
+    }
+
+    /// Append to `output`, keeping memory bounded: once the text past the
+    /// truncation marker exceeds `max_bytes`, the front is cut so that the
+    /// last three quarters of the cap remain. Trimming below the cap keeps
+    /// the cost of the copy amortized over many chunks.
+    pub fn append_output(&mut self, text: &str, max_bytes: usize) {
+        let marker_len = self.marker_len();
+        let output = self.output.get_or_insert_with(String::new);
+        output.push_str(text);
+        if output.len() - marker_len <= max_bytes {
+            return;
+        }
+        let mut cut = output.len() - max_bytes / 4 * 3;
+        while !output.is_char_boundary(cut) {
+            cut += 1;
+        }
+        self.output_truncated += cut - marker_len;
+        output.replace_range(..cut, &crate::persistence::truncation_marker(self.output_truncated));
+    }
+
+    /// Append a stderr chunk to `output` on a line of its own, remembering
+    /// where it landed so the viewer can dim it, and keep a copy in `stderr`.
+    pub fn append_stderr(&mut self, text: &str, max_bytes: usize) {
+        if self.output.as_ref().is_some_and(|o| !o.is_empty() && !o.ends_with('\n')) {
+            self.append_output("\n", max_bytes);
+        }
+        let start = self.output_end();
+        self.append_output(text, max_bytes);
+        let end = self.output_end();
+        match self.stderr_spans.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => self.stderr_spans.push(start..end),
+        }
+        let dropped = self.output_truncated;
+        self.stderr_spans.retain(|span| span.end > dropped);
+
+        let stderr = self.stderr.get_or_insert_with(String::new);
+        stderr.push_str(text);
+        if stderr.len() > max_bytes {
+            let mut cut = stderr.len() - max_bytes;
+            while !stderr.is_char_boundary(cut) {
+                cut += 1;
+            }
+            stderr.drain(..cut);
+        }
+    }
+
+    /// Whether the byte at `offset` into `output` came from stderr.
+    pub fn is_stderr_at(&self, offset: usize) -> bool {
+        let marker_len = self.marker_len();
+        if offset < marker_len {
+            return false;
+        }
+        let offset = offset - marker_len + self.output_truncated;
+        self.stderr_spans.iter().any(|span| span.contains(&offset))
+    }
+
+    /// Whether the agent has been waiting on the user for at least `after`:
+    /// idle since its turn ended, or a PTY worker that went quiet.
+    pub fn is_awaiting_input(&self, after: Duration, now: Instant) -> bool {
+        let waiting = match self.status {
+            PromptStatus::Idle => true,
+            PromptStatus::Running => self.pty_state.is_some(),
+            _ => false,
+        };
+        waiting
+            && self
+                .awaiting_input_since
+                .is_some_and(|since| now.saturating_duration_since(since) >= after)
+    }
+
+    /// Start a new wait for input, re-arming the reminder.
+    pub fn mark_awaiting_input(&mut self) {
+        self.awaiting_input_since = Some(Instant::now());
+        self.input_reminder_sent = false;
+    }
+
+    /// Replace the output wholesale, e.g. with a finished PTY transcript.
+    pub fn set_output(&mut self, output: Option<String>) {
+        self.output = output;
+        self.output_truncated = 0;
+        self.stderr_spans.clear();
+    }
+
+    /// Length of the truncation marker at the front of `output`, if any.
+    fn marker_len(&self) -> usize {
+        match self.output_truncated {
+            0 => 0,
+            n => crate::persistence::truncation_marker(n).len(),
+        }
+    }
+
+    /// Bytes appended to `output` so far, counting truncated ones.
+    fn output_end(&self) -> usize {
+        let len = self.output.as_ref().map_or(0, String::len);
+        len - self.marker_len() + self.output_truncated
+    }
+
+    /// Whether the prompt failed because the user killed its worker.
+    pub fn is_killed(&self) -> bool {
+        self.killed && self.status == PromptStatus::Failed
+    }
+
+    /// Finished, but its output hasn't been looked at yet.
+    pub fn is_unseen(&self) -> bool {
+        !self.seen && matches!(self.status, PromptStatus::Completed | PromptStatus::Failed)
+    }
+
+    /// Status name as persisted and matched by `status:` filters. A killed
+    /// prompt is Failed internally but reports "killed".
+    pub fn state_label(&self) -> &'static str {
+        if self.is_killed() {
+            "killed"
+        } else {
+            self.status.label()
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        if self.is_killed() {
+            "🛑"
+        } else {
+            self.status.symbol()
+        }
+    }
+
     /// Human-readable elapsed time, e.g. "4.2s", "2m 30s", "1h 5m".
     pub fn elapsed_display(&self) -> Option<String> {
         self.elapsed_secs().map(format_duration)
     }
+
+    /// Add a (normalized) tag. Returns false if it was already present.
+    pub fn add_tag(&mut self, tag: String) -> bool {
+        if self.tags.contains(&tag) {
+            return false;
+        }
+        self.tags.push(tag);
+        true
+    }
+
+    /// Remove a (normalized) tag. Returns false if it was not present.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let before = self.tags.len();
+        self.tags.retain(|t| t != tag);
+        self.tags.len() != before
+    }
 }
 
 /// Format seconds into a human-readable duration string.
@@ -129,8 +428,46 @@ pub fn format_duration(secs: f64) -> String {
     }
 }
 
+/// Format whole seconds as a clock: "mm:ss" under an hour, "h:mm:ss" above.
+pub fn format_clock(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h == 0 {
+        format!("{m:02}:{s:02}")
+    } else {
+        format!("{h}:{m:02}:{s:02}")
+    }
+}
+
+/// Format a byte count compactly: "512B", "12.3K", "4.1M".
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes}B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1}K", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1}M", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Normalize a tag: trimmed, optional leading `@` stripped, lowercased.
+/// Returns `None` for empty tags or ones with characters `@tag` parsing
+/// would not accept.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('@').unwrap_or(tag);
+    if tag.is_empty() || !tag.chars().all(is_tag_char) {
+        return None;
+    }
+    Some(tag.to_lowercase())
+}
+
 /// Parse `@tag` prefixes from prompt text.
 /// Returns (tags, remaining_text) where tags are stripped from the text sent to Claude.
+/// Tags are normalized with [`normalize_tag`] and deduplicated.
 /// Example: `@frontend @urgent Fix the navbar` → (["frontend", "urgent"], "Fix the navbar")
 pub fn parse_tags(input: &str) -> (Vec<String>, String) {
     let mut tags = Vec::new();
@@ -140,14 +477,16 @@ pub fn parse_tags(input: &str) -> (Vec<String>, String) {
         if let Some(stripped) = rest.strip_prefix('@') {
             // Find the end of the tag word (alphanumeric, dash, underscore)
             let end = stripped
-                .find(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+                .find(|c: char| !is_tag_char(c))
                 .unwrap_or(stripped.len());
             if end == 0 {
                 // Bare `@` with no tag name — not a tag
                 break;
             }
-            let tag = &stripped[..end];
-            tags.push(tag.to_string());
+            let tag = stripped[..end].to_lowercase();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
             rest = &stripped[end..];
         } else {
             break;
@@ -156,10 +495,44 @@ pub fn parse_tags(input: &str) -> (Vec<String>, String) {
     (tags, rest.trim_start().to_string())
 }
 
+/// Pull a `!oneshot` / `!interactive` token out of the leading words of
+/// prompt text (it may sit among `@tag` prefixes), overriding the default
+/// mode for one prompt. Any other `!word` ends the prefix and stays in the
+/// text. Example: `@ui !oneshot Fix it` → (Some(OneShot), "@ui Fix it")
+pub fn parse_mode_token(input: &str) -> (Option<PromptMode>, String) {
+    let mut mode = None;
+    let mut kept = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        match &rest[..end] {
+            "!oneshot" | "!one-shot" => mode = Some(PromptMode::OneShot),
+            "!interactive" => mode = Some(PromptMode::Interactive),
+            word if word.len() > 1 && word.starts_with('@') => kept.push(word),
+            _ => break,
+        }
+        rest = rest[end..].trim_start();
+    }
+    if mode.is_none() {
+        return (None, input.to_string());
+    }
+    kept.push(rest);
+    (mode, kept.join(" ").trim().to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // ── PromptStatus ──
+
+    #[test]
+    fn status_from_label() {
+        assert_eq!(PromptStatus::from_label("idle"), Some(PromptStatus::Idle));
+        assert_eq!(PromptStatus::from_label("failed"), Some(PromptStatus::Failed));
+        assert_eq!(PromptStatus::from_label("done"), None);
+    }
+
     // ── PromptMode ──
 
     #[test]
@@ -187,6 +560,29 @@ mod tests {
         assert_eq!(PromptMode::OneShot.label(), "one-shot");
     }
 
+    // ── PromptPriority ──
+
+    #[test]
+    fn priority_ordering() {
+        assert!(PromptPriority::High > PromptPriority::Normal);
+        assert!(PromptPriority::Normal > PromptPriority::Low);
+    }
+
+    #[test]
+    fn priority_cycle() {
+        assert_eq!(PromptPriority::Normal.cycle(), PromptPriority::High);
+        assert_eq!(PromptPriority::High.cycle(), PromptPriority::Low);
+        assert_eq!(PromptPriority::Low.cycle(), PromptPriority::Normal);
+    }
+
+    #[test]
+    fn priority_label_roundtrip() {
+        for p in [PromptPriority::Low, PromptPriority::Normal, PromptPriority::High] {
+            assert_eq!(PromptPriority::from_label(p.label()), Some(p));
+        }
+        assert_eq!(PromptPriority::from_label("urgent"), None);
+    }
+
     // ── PromptStatus::symbol ──
 
     #[test]
@@ -213,6 +609,7 @@ mod tests {
         assert!(p.started_at.is_none());
         assert!(p.finished_at.is_none());
         assert!(!p.seen);
+        assert_eq!(p.priority, PromptPriority::Normal);
     }
 
     #[test]
@@ -228,6 +625,21 @@ mod tests {
         assert!(p.elapsed_secs().is_none());
     }
 
+    // ── format_clock ──
+
+    #[test]
+    fn format_clock_under_an_hour() {
+        assert_eq!(format_clock(0), "00:00");
+        assert_eq!(format_clock(75), "01:15");
+        assert_eq!(format_clock(3599), "59:59");
+    }
+
+    #[test]
+    fn format_clock_hours() {
+        assert_eq!(format_clock(3600), "1:00:00");
+        assert_eq!(format_clock(37_265), "10:21:05");
+    }
+
     // ── format_duration ──
 
     #[test]
@@ -251,6 +663,16 @@ mod tests {
         assert_eq!(format_duration(7261.0), "2h 1m");
     }
 
+    // ── format_bytes ──
+
+    #[test]
+    fn format_bytes_units() {
+        assert_eq!(format_bytes(0), "0B");
+        assert_eq!(format_bytes(1023), "1023B");
+        assert_eq!(format_bytes(12_595), "12.3K");
+        assert_eq!(format_bytes(4_300_000), "4.1M");
+    }
+
     // ── parse_tags ──
 
     #[test]
@@ -301,4 +723,134 @@ mod tests {
         assert_eq!(tags, vec!["my-tag", "another_tag"]);
         assert_eq!(text, "Do stuff");
     }
+
+    #[test]
+    fn parse_tags_lowercases_and_dedupes() {
+        let (tags, text) = parse_tags("@API @api @Docs Write it");
+        assert_eq!(tags, vec!["api", "docs"]);
+        assert_eq!(text, "Write it");
+    }
+
+    #[test]
+    fn normalize_tag_rules() {
+        assert_eq!(normalize_tag("  Frontend "), Some("frontend".to_string()));
+        assert_eq!(normalize_tag("@my-tag"), Some("my-tag".to_string()));
+        assert_eq!(normalize_tag("   "), None);
+        assert_eq!(normalize_tag("@"), None);
+        assert_eq!(normalize_tag("two words"), None);
+    }
+
+    #[test]
+    fn add_and_remove_tag() {
+        let mut p = Prompt::new(1, "x".to_string(), None, PromptMode::OneShot);
+        assert!(p.add_tag("api".to_string()));
+        assert!(!p.add_tag("api".to_string()));
+        assert_eq!(p.tags, vec!["api"]);
+        assert!(p.remove_tag("api"));
+        assert!(!p.remove_tag("api"));
+        assert!(p.tags.is_empty());
+    }
+
+    // ── output cap ──
+
+    #[test]
+    fn append_output_keeps_tail_under_cap() {
+        let mut p = Prompt::new(1, "x".to_string(), None, PromptMode::OneShot);
+        p.append_output("0123456789", 8);
+        assert_eq!(p.output_truncated, 4);
+        assert_eq!(p.output.as_deref(), Some("[… 4 bytes truncated …]\n456789"));
+        p.append_output("ab", 8);
+        assert_eq!(p.output.as_deref(), Some("[… 4 bytes truncated …]\n456789ab"));
+        p.append_output("c", 8);
+        assert_eq!(p.output_truncated, 7);
+        assert_eq!(p.output.as_deref(), Some("[… 7 bytes truncated …]\n789abc"));
+    }
+
+    #[test]
+    fn append_output_counts_every_dropped_byte() {
+        let mut p = Prompt::new(1, "x".to_string(), None, PromptMode::OneShot);
+        let chunk = "é".repeat(500);
+        for _ in 0..100 {
+            p.append_output(&chunk, 4096);
+        }
+        let marker = crate::persistence::truncation_marker(p.output_truncated);
+        let output = p.output.as_deref().unwrap();
+        assert!(output.starts_with(&marker));
+        assert!(output.len() - marker.len() <= 4096);
+        assert_eq!(p.output_truncated + output.len() - marker.len(), 100 * chunk.len());
+        p.set_output(None);
+        assert_eq!(p.output_truncated, 0);
+    }
+
+    #[test]
+    fn stderr_lands_on_its_own_line() {
+        let mut p = Prompt::new(1, "x".to_string(), None, PromptMode::OneShot);
+        p.append_output("partial", 1024);
+        p.append_stderr("Error: not logged in\n", 1024);
+        p.append_output("more", 1024);
+        let output = p.output.as_deref().unwrap();
+        assert_eq!(output, "partial\nError: not logged in\nmore");
+        assert!(!p.is_stderr_at(0));
+        assert!(p.is_stderr_at(output.find("Error").unwrap()));
+        assert!(!p.is_stderr_at(output.find("more").unwrap()));
+        assert_eq!(p.stderr.as_deref(), Some("Error: not logged in\n"));
+    }
+
+    #[test]
+    fn stderr_spans_follow_truncation() {
+        let mut p = Prompt::new(1, "x".to_string(), None, PromptMode::OneShot);
+        p.append_stderr("old\n", 16);
+        p.append_output("0123456789\n", 16);
+        p.append_stderr("new\n", 16);
+        assert!(p.output_truncated > 0);
+        assert_eq!(p.stderr_spans.len(), 1);
+        let output = p.output.as_deref().unwrap();
+        assert!(p.is_stderr_at(output.find("new").unwrap()));
+        assert!(!p.is_stderr_at(output.find('9').unwrap()));
+        p.set_output(None);
+        assert!(p.stderr_spans.is_empty());
+    }
+
+    // ── parse_mode_token ──
+
+    #[test]
+    fn mode_token_is_stripped() {
+        assert_eq!(
+            parse_mode_token("!oneshot Fix the navbar"),
+            (Some(PromptMode::OneShot), "Fix the navbar".to_string())
+        );
+        assert_eq!(
+            parse_mode_token("!interactive   Fix it"),
+            (Some(PromptMode::Interactive), "Fix it".to_string())
+        );
+    }
+
+    #[test]
+    fn mode_token_among_tags() {
+        assert_eq!(
+            parse_mode_token("@ui !oneshot @urgent Fix it"),
+            (Some(PromptMode::OneShot), "@ui @urgent Fix it".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_bang_token_is_left_alone() {
+        let input = "!x !oneshot do it";
+        assert_eq!(parse_mode_token(input), (None, input.to_string()));
+        assert_eq!(parse_mode_token("!oneshot !x do it"), (Some(PromptMode::OneShot), "!x do it".to_string()));
+    }
+
+    #[test]
+    fn mode_token_only_in_prefix() {
+        let input = "Fix it !oneshot";
+        assert_eq!(parse_mode_token(input), (None, input.to_string()));
+    }
+
+    #[test]
+    fn mode_token_keeps_multiline_body() {
+        assert_eq!(
+            parse_mode_token("!oneshot first\n  second"),
+            (Some(PromptMode::OneShot), "first\n  second".to_string())
+        );
+    }
 }
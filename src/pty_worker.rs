@@ -1,26 +1,85 @@
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use alacritty_terminal::event::VoidListener;
 use alacritty_terminal::grid::Dimensions;
 use alacritty_terminal::index::{Column, Line};
 use alacritty_terminal::term::cell::Flags;
-use alacritty_terminal::term::Config;
-use alacritty_terminal::vte::ansi::Processor;
+use alacritty_terminal::term::{Config, TermMode};
+use alacritty_terminal::vte::ansi::{Color, NamedColor, Processor};
 use alacritty_terminal::Term;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use tokio::sync::mpsc;
 
-use crate::worker::{WorkerInput, WorkerMessage};
+use crate::process;
+use crate::worker::{ProgressCounter, WorkerInput, WorkerMessage};
+
+/// Default size of the raw output kept per PTY worker (256 KiB).
+pub const DEFAULT_SCROLLBACK_BYTES: usize = 256 * 1024;
+/// Ceiling for the `pty_scrollback_bytes` setting (4 MiB per worker).
+pub const MAX_SCROLLBACK_BYTES: usize = 4 * 1024 * 1024;
+/// Size (cols, rows) of a PTY nobody is viewing.
+pub const DEFAULT_PTY_SIZE: (u16, u16) = (120, 40);
+
+/// Fixed-capacity buffer holding the most recent bytes written to it.
+pub struct RingBuffer {
+    buf: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    /// Capacity is clamped to `MAX_SCROLLBACK_BYTES`.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.min(MAX_SCROLLBACK_BYTES);
+        Self {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append bytes, dropping the oldest ones once capacity is exceeded.
+    pub fn push(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.buf.len() + bytes.len()).saturating_sub(self.capacity);
+        self.buf.drain(..overflow);
+        self.buf.extend(bytes);
+    }
+
+    /// The buffered bytes, oldest first.
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.buf.iter().copied().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
 
 pub struct PtyState {
     pub term: Term<VoidListener>,
     pub processor: Processor,
+    /// Raw PTY output, replayed to recover scrollback when the worker finishes.
+    pub scrollback: RingBuffer,
 }
 
 pub type SharedPtyState = Arc<Mutex<PtyState>>;
 
+/// A run of adjacent cells sharing the same colors and flags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyledRun {
+    pub text: String,
+    pub fg: Color,
+    pub bg: Color,
+    pub flags: Flags,
+}
+
+/// One transcript line as styled runs; kept for finished PTY prompts so
+/// the output viewer can replay colors.
+pub type StyledLine = Vec<StyledRun>;
+
 pub struct PtyHandle {
     pub state: SharedPtyState,
     pub master: Box<dyn portable_pty::MasterPty + Send>,
@@ -44,14 +103,18 @@ impl Dimensions for PtyDimensions {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_pty_worker(
     prompt_id: usize,
-    prompt_text: String,
+    program: &str,
+    args: Vec<String>,
     cwd: Option<String>,
+    env: &BTreeMap<String, String>,
     cols: u16,
     rows: u16,
     tx: mpsc::UnboundedSender<WorkerMessage>,
-    resume_session_id: Option<String>,
+    kill_grace: Duration,
+    scrollback_bytes: usize,
 ) -> Result<(mpsc::UnboundedSender<WorkerInput>, PtyHandle), String> {
     let pty_system = native_pty_system();
 
@@ -64,19 +127,12 @@ pub fn spawn_pty_worker(
         })
         .map_err(|e| format!("Failed to open PTY: {e}"))?;
 
-    let mut cmd = CommandBuilder::new("claude");
-    if let Some(ref session_id) = resume_session_id {
-        if session_id.is_empty() {
-            cmd.arg("--resume");
-        } else {
-            cmd.arg("--resume");
-            cmd.arg(session_id);
-        }
-    } else {
-        cmd.arg(&prompt_text);
-    }
-    cmd.arg("--dangerously-skip-permissions");
+    let mut cmd = CommandBuilder::new(program);
+    cmd.args(&args);
     cmd.env_remove("CLAUDECODE");
+    for (name, value) in env {
+        cmd.env(name, value);
+    }
     match cwd {
         Some(ref dir) => cmd.cwd(dir),
         None => cmd.cwd(std::env::current_dir().unwrap_or_default()),
@@ -85,9 +141,10 @@ pub fn spawn_pty_worker(
     let child = pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| format!("Failed to spawn claude in PTY: {e}"))?;
+        .map_err(|e| format!("Failed to spawn {program} in PTY: {e}"))?;
     // Drop slave after spawning
     drop(pair.slave);
+    let pid = child.process_id();
 
     let dims = PtyDimensions {
         cols: cols as usize,
@@ -97,7 +154,11 @@ pub fn spawn_pty_worker(
     let term = Term::new(config, &dims, VoidListener);
     let processor = Processor::new();
 
-    let state = Arc::new(Mutex::new(PtyState { term, processor }));
+    let state = Arc::new(Mutex::new(PtyState {
+        term,
+        processor,
+        scrollback: RingBuffer::new(scrollback_bytes),
+    }));
 
     let mut reader = pair
         .master
@@ -113,6 +174,7 @@ pub fn spawn_pty_worker(
     let reader_state = state.clone();
     std::thread::spawn(move || {
         let mut buf = [0u8; 4096];
+        let mut progress = ProgressCounter::default();
         loop {
             match reader.read(&mut buf) {
                 Ok(0) => break, // EOF — child exited
@@ -121,10 +183,16 @@ pub fn spawn_pty_worker(
                         let PtyState {
                             ref mut term,
                             ref mut processor,
+                            ref mut scrollback,
                         } = *pty;
                         processor.advance(term, &buf[..n]);
+                        scrollback.push(&buf[..n]);
                     }
                     let _ = tx.send(WorkerMessage::PtyUpdate { prompt_id });
+                    progress.record(&buf[..n]);
+                    if let Some((bytes, lines)) = progress.take_due(Instant::now()) {
+                        let _ = tx.send(WorkerMessage::Progress { prompt_id, bytes, lines });
+                    }
                 }
                 Err(_) => break,
             }
@@ -156,11 +224,15 @@ pub fn spawn_pty_worker(
                     let _ = writer.flush();
                 }
                 WorkerInput::Kill => {
+                    // SIGTERM, then SIGKILL after the grace period. The reader
+                    // thread reports Finished once the child exits.
+                    if let Some(pid) = pid {
+                        process::terminate(pid, kill_grace);
+                    }
                     break;
                 }
             }
         }
-        // Writer dropped here → PTY master EOF → child gets SIGHUP
     });
 
     Ok((
@@ -190,6 +262,9 @@ pub fn key_event_to_bytes(key: KeyEvent) -> Vec<u8> {
         }
         KeyCode::Enter => vec![b'\r'],
         KeyCode::Backspace => vec![0x7f],
+        // Terminals report Shift+Tab as BackTab, some as Tab with Shift held
+        KeyCode::BackTab => vec![0x1b, b'[', b'Z'],
+        KeyCode::Tab if key.modifiers.contains(KeyModifiers::SHIFT) => vec![0x1b, b'[', b'Z'],
         KeyCode::Tab => vec![b'\t'],
         KeyCode::Esc => vec![0x1b],
         KeyCode::Up => vec![0x1b, b'[', b'A'],
@@ -225,18 +300,97 @@ pub fn key_event_to_bytes(key: KeyEvent) -> Vec<u8> {
     bytes
 }
 
+/// Wrap pasted text in bracketed-paste markers (`ESC[200~` … `ESC[201~`) so
+/// the agent's line editor takes it as one paste instead of typed keys. End
+/// markers inside the text are dropped so a paste can't close itself early;
+/// removal repeats until none are left, so nested markers can't reassemble.
+pub fn bracketed_paste(text: &str) -> Vec<u8> {
+    let mut text = text.to_string();
+    while text.contains("\x1b[201~") {
+        text = text.replace("\x1b[201~", "");
+    }
+    let mut bytes = b"\x1b[200~".to_vec();
+    bytes.extend_from_slice(text.as_bytes());
+    bytes.extend_from_slice(b"\x1b[201~");
+    bytes
+}
+
+/// Whether the program in the terminal has turned on bracketed paste.
+pub fn bracketed_paste_enabled(state: &SharedPtyState) -> bool {
+    state
+        .lock()
+        .is_ok_and(|pty| pty.term.mode().contains(TermMode::BRACKETED_PASTE))
+}
+
 /// Extract visible text from the terminal grid for session persistence / export.
 pub fn extract_text_from_term(state: &SharedPtyState) -> String {
     let Ok(pty) = state.lock() else {
         return String::new();
     };
-    let grid = pty.term.grid();
-    let screen_lines = grid.screen_lines();
+    grid_text(&pty.term, 0)
+}
+
+/// Extract the full transcript of a finished worker: the buffered raw
+/// output is replayed into a fresh terminal so lines that scrolled off
+/// screen are included. Falls back to the visible screen.
+pub fn extract_transcript(state: &SharedPtyState) -> String {
+    with_transcript_term(state, grid_text).unwrap_or_default()
+}
+
+/// Like `extract_transcript`, but keeps each cell's colors and flags.
+pub fn extract_styled_transcript(state: &SharedPtyState) -> Vec<StyledLine> {
+    with_transcript_term(state, grid_styled).unwrap_or_default()
+}
+
+/// Uncolored lines, for text shown in front of a styled transcript.
+pub fn plain_styled_lines(text: &str) -> Vec<StyledLine> {
+    text.lines()
+        .map(|line| {
+            vec![StyledRun {
+                text: line.to_string(),
+                fg: Color::Named(NamedColor::Foreground),
+                bg: Color::Named(NamedColor::Background),
+                flags: Flags::empty(),
+            }]
+        })
+        .collect()
+}
+
+/// Run `f` on the terminal holding the full transcript, with the first
+/// line to read: a replay of the buffered raw output, or the live screen.
+fn with_transcript_term<R>(
+    state: &SharedPtyState,
+    f: impl FnOnce(&Term<VoidListener>, i32) -> R,
+) -> Option<R> {
+    let pty = state.lock().ok()?;
+    if pty.scrollback.is_empty() {
+        return Some(f(&pty.term, 0));
+    }
+    let dims = PtyDimensions {
+        cols: pty.term.grid().columns(),
+        lines: pty.term.grid().screen_lines(),
+    };
+    let config = Config {
+        scrolling_history: 100_000,
+        ..Config::default()
+    };
+    let mut term = Term::new(config, &dims, VoidListener);
+    let mut processor: Processor = Processor::new();
+    processor.advance(&mut term, &pty.scrollback.snapshot());
+    let history = term.grid().history_size() as i32;
+    Some(f(&term, -history))
+}
+
+/// Text of the grid from `first_line` (negative = scrollback history) to
+/// the bottom of the screen, with trailing blanks trimmed.
+fn grid_text(term: &Term<VoidListener>, first_line: i32) -> String {
+    let grid = term.grid();
+    let screen_lines = grid.screen_lines() as i32;
     let cols = grid.columns();
 
     let mut lines = Vec::new();
-    for row in 0..screen_lines {
-        let line = Line(row as i32);
+    for row in first_line..screen_lines {
+        let line = Line(row);
         let mut row_text = String::new();
         for col in 0..cols {
             let cell = &grid[line][Column(col)];
@@ -256,6 +410,62 @@ pub fn extract_text_from_term(state: &SharedPtyState) -> String {
     lines.join("\n")
 }
 
+/// Styled runs of the grid from `first_line`, trimmed like `grid_text` so
+/// line numbers match the plain transcript.
+fn grid_styled(term: &Term<VoidListener>, first_line: i32) -> Vec<StyledLine> {
+    let grid = term.grid();
+    let screen_lines = grid.screen_lines() as i32;
+    let cols = grid.columns();
+    let is_blank = |cell: &alacritty_terminal::term::cell::Cell| {
+        cell.c.is_whitespace() && cell.bg == Color::Named(NamedColor::Background)
+    };
+
+    let mut lines: Vec<StyledLine> = Vec::new();
+    for row in first_line..screen_lines {
+        let line = Line(row);
+        let end = (0..cols)
+            .rposition(|col| !is_blank(&grid[line][Column(col)]))
+            .map_or(0, |col| col + 1);
+        let mut runs: StyledLine = Vec::new();
+        for col in 0..end {
+            let cell = &grid[line][Column(col)];
+            if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                continue;
+            }
+            // Only flags that affect rendering; layout flags would split runs
+            let flags = cell.flags & !(Flags::WIDE_CHAR | Flags::WRAPLINE);
+            match runs.last_mut() {
+                Some(run) if run.fg == cell.fg && run.bg == cell.bg && run.flags == flags => {
+                    run.text.push(cell.c);
+                }
+                _ => runs.push(StyledRun {
+                    text: cell.c.to_string(),
+                    fg: cell.fg,
+                    bg: cell.bg,
+                    flags,
+                }),
+            }
+        }
+        lines.push(runs);
+    }
+
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Terminal state with no process behind it, for tests.
+#[cfg(test)]
+pub fn test_state(cols: usize, lines: usize, scrollback_bytes: usize) -> SharedPtyState {
+    let dims = PtyDimensions { cols, lines };
+    Arc::new(Mutex::new(PtyState {
+        term: Term::new(Config::default(), &dims, VoidListener),
+        processor: Processor::new(),
+        scrollback: RingBuffer::new(scrollback_bytes),
+    }))
+}
+
 /// Resize the PTY and the alacritty_terminal Term.
 pub fn resize_pty(handle: &PtyHandle, cols: u16, rows: u16) {
     let _ = handle.master.resize(PtySize {
@@ -292,6 +502,94 @@ mod tests {
         KeyEvent::new(code, KeyModifiers::ALT)
     }
 
+    fn feed(state: &SharedPtyState, bytes: &[u8]) {
+        let mut pty = state.lock().unwrap();
+        let PtyState {
+            ref mut term,
+            ref mut processor,
+            ref mut scrollback,
+        } = *pty;
+        processor.advance(term, bytes);
+        scrollback.push(bytes);
+    }
+
+    // ── RingBuffer ──
+
+    #[test]
+    fn ring_buffer_keeps_most_recent_bytes() {
+        let mut ring = RingBuffer::new(8);
+        ring.push(b"hello");
+        assert_eq!(ring.snapshot(), b"hello");
+        ring.push(b" world");
+        assert_eq!(ring.snapshot(), b"lo world");
+        ring.push(b"0123456789");
+        assert_eq!(ring.snapshot(), b"23456789");
+    }
+
+    #[test]
+    fn ring_buffer_capacity_is_capped() {
+        let mut ring = RingBuffer::new(usize::MAX);
+        ring.push(&vec![b'x'; MAX_SCROLLBACK_BYTES + 10]);
+        assert_eq!(ring.snapshot().len(), MAX_SCROLLBACK_BYTES);
+    }
+
+    #[test]
+    fn ring_buffer_zero_capacity_stays_empty() {
+        let mut ring = RingBuffer::new(0);
+        ring.push(b"abc");
+        assert!(ring.is_empty());
+    }
+
+    // ── transcript extraction ──
+
+    #[test]
+    fn transcript_includes_scrolled_off_lines() {
+        let state = test_state(20, 3, DEFAULT_SCROLLBACK_BYTES);
+        for i in 1..=6 {
+            feed(&state, format!("line {i}\r\n").as_bytes());
+        }
+        assert!(!extract_text_from_term(&state).contains("line 1"));
+        let transcript = extract_transcript(&state);
+        assert!(transcript.starts_with("line 1\nline 2"));
+        assert!(transcript.ends_with("line 6"));
+    }
+
+    #[test]
+    fn styled_transcript_keeps_colors() {
+        let state = test_state(20, 3, DEFAULT_SCROLLBACK_BYTES);
+        for i in 1..=4 {
+            feed(&state, format!("line {i}\r\n").as_bytes());
+        }
+        feed(&state, b"\x1b[31mred\x1b[0m plain   ");
+        let styled = extract_styled_transcript(&state);
+        let plain = extract_transcript(&state);
+        assert_eq!(styled.len(), plain.lines().count());
+        assert_eq!(styled[0][0].text, "line 1");
+
+        let last = styled.last().unwrap();
+        assert_eq!(last[0].text, "red");
+        assert_eq!(last[0].fg, Color::Named(NamedColor::Red));
+        assert_eq!(last[1].text, " plain");
+        assert_eq!(last[1].fg, Color::Named(NamedColor::Foreground));
+    }
+
+    #[test]
+    fn styled_transcript_merges_runs_and_flags() {
+        let state = test_state(20, 3, 0);
+        feed(&state, b"\x1b[1mbold\x1b[0m");
+        let styled = extract_styled_transcript(&state);
+        assert_eq!(styled.len(), 1);
+        assert_eq!(styled[0].len(), 1);
+        assert!(styled[0][0].flags.contains(Flags::BOLD));
+    }
+
+    #[test]
+    fn transcript_without_scrollback_uses_screen() {
+        let state = test_state(20, 3, 0);
+        feed(&state, b"only screen");
+        assert_eq!(extract_transcript(&state), "only screen");
+    }
+
     #[test]
     fn key_char_simple() {
         assert_eq!(key_event_to_bytes(key(KeyCode::Char('a'))), b"a");
@@ -336,6 +634,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bracketed_paste_frames_text() {
+        assert_eq!(bracketed_paste("a\nb"), b"\x1b[200~a\nb\x1b[201~".to_vec());
+        assert_eq!(bracketed_paste(""), b"\x1b[200~\x1b[201~".to_vec());
+        // An embedded end marker can't end the paste early
+        assert_eq!(
+            bracketed_paste("x\x1b[201~rm -rf\r"),
+            b"\x1b[200~xrm -rf\r\x1b[201~".to_vec()
+        );
+        assert_eq!(
+            bracketed_paste("x\x1b[20\x1b[201~1~y"),
+            b"\x1b[200~xy\x1b[201~".to_vec()
+        );
+    }
+
+    #[test]
+    fn bracketed_paste_follows_terminal_mode() {
+        let state = test_state(20, 5, 1024);
+        assert!(!bracketed_paste_enabled(&state));
+        feed(&state, b"\x1b[?2004h");
+        assert!(bracketed_paste_enabled(&state));
+        feed(&state, b"\x1b[?2004l");
+        assert!(!bracketed_paste_enabled(&state));
+    }
+
     #[test]
     fn key_ctrl_c() {
         // Ctrl+C = byte 3 (0x03)
@@ -379,6 +702,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn key_shift_tab() {
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(key_event_to_bytes(back_tab), b"\x1b[Z");
+        let shift_tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::SHIFT);
+        assert_eq!(key_event_to_bytes(shift_tab), b"\x1b[Z");
+    }
+
+    #[test]
+    fn key_sequence_table() {
+        let alt = KeyModifiers::ALT;
+        let none = KeyModifiers::NONE;
+        let cases: &[(KeyCode, KeyModifiers, &[u8])] = &[
+            (KeyCode::F(1), none, b"\x1bOP"),
+            (KeyCode::F(2), none, b"\x1bOQ"),
+            (KeyCode::F(3), none, b"\x1bOR"),
+            (KeyCode::F(4), none, b"\x1bOS"),
+            (KeyCode::F(5), none, b"\x1b[15~"),
+            (KeyCode::F(6), none, b"\x1b[17~"),
+            (KeyCode::F(7), none, b"\x1b[18~"),
+            (KeyCode::F(8), none, b"\x1b[19~"),
+            (KeyCode::F(9), none, b"\x1b[20~"),
+            (KeyCode::F(10), none, b"\x1b[21~"),
+            (KeyCode::F(11), none, b"\x1b[23~"),
+            (KeyCode::F(12), none, b"\x1b[24~"),
+            (KeyCode::F(13), none, b""),
+            (KeyCode::Home, none, b"\x1b[H"),
+            (KeyCode::End, none, b"\x1b[F"),
+            (KeyCode::PageUp, none, b"\x1b[5~"),
+            (KeyCode::PageDown, none, b"\x1b[6~"),
+            (KeyCode::Insert, none, b"\x1b[2~"),
+            (KeyCode::Delete, none, b"\x1b[3~"),
+            (KeyCode::BackTab, KeyModifiers::SHIFT, b"\x1b[Z"),
+            (KeyCode::Up, none, b"\x1b[A"),
+            (KeyCode::Left, none, b"\x1b[D"),
+            // Alt prefixes the whole sequence with ESC
+            (KeyCode::Char('b'), alt, b"\x1bb"),
+            (KeyCode::Home, alt, b"\x1b\x1b[H"),
+            (KeyCode::F(5), alt, b"\x1b\x1b[15~"),
+            (KeyCode::PageDown, alt, b"\x1b\x1b[6~"),
+            (KeyCode::BackTab, KeyModifiers::SHIFT | alt, b"\x1b\x1b[Z"),
+            (KeyCode::Enter, alt, b"\x1b\r"),
+            (KeyCode::Esc, alt, b"\x1b"),
+        ];
+        for (code, mods, expected) in cases {
+            let event = KeyEvent::new(*code, *mods);
+            assert_eq!(key_event_to_bytes(event), expected.to_vec(), "{code:?} {mods:?}");
+        }
+    }
+
     #[test]
     fn key_page_keys() {
         assert_eq!(
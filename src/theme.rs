@@ -0,0 +1,106 @@
+use std::str::FromStr;
+
+use ratatui::style::Color;
+
+use crate::keymap::TomlTheme;
+use crate::prompt::PromptStatus;
+
+/// Theme keys accepted in the `[theme]` config section.
+pub const THEME_KEYS: &[&str] = &[
+    "selected", "pending", "running", "idle", "completed", "failed", "border", "title",
+];
+
+/// Colors for the semantic roles of the TUI, from the `[theme]` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Background of the selected row in the prompt list.
+    pub selected: Color,
+    pub pending: Color,
+    pub running: Color,
+    pub idle: Color,
+    pub completed: Color,
+    pub failed: Color,
+    /// Borders of unfocused panels.
+    pub border: Color,
+    /// Prompt list title.
+    pub title: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            selected: Color::Rgb(40, 40, 60),
+            pending: Color::Yellow,
+            running: Color::Cyan,
+            idle: Color::Magenta,
+            completed: Color::Green,
+            failed: Color::Red,
+            border: Color::Rgb(80, 80, 100),
+            title: Color::Cyan,
+        }
+    }
+}
+
+impl Theme {
+    /// Build from the config; unset or unparseable colors keep their default.
+    pub fn from_toml(toml: &TomlTheme) -> Self {
+        let default = Self::default();
+        let pick = |value: &Option<String>, fallback: Color| {
+            value.as_deref().and_then(parse_color).unwrap_or(fallback)
+        };
+        Self {
+            selected: pick(&toml.selected, default.selected),
+            pending: pick(&toml.pending, default.pending),
+            running: pick(&toml.running, default.running),
+            idle: pick(&toml.idle, default.idle),
+            completed: pick(&toml.completed, default.completed),
+            failed: pick(&toml.failed, default.failed),
+            border: pick(&toml.border, default.border),
+            title: pick(&toml.title, default.title),
+        }
+    }
+
+    pub fn status(&self, status: &PromptStatus) -> Color {
+        match status {
+            PromptStatus::Pending => self.pending,
+            PromptStatus::Running => self.running,
+            PromptStatus::Idle => self.idle,
+            PromptStatus::Completed => self.completed,
+            PromptStatus::Failed => self.failed,
+        }
+    }
+}
+
+/// Parse a color name (`"red"`, `"light-blue"`), hex (`"#ff8800"`) or
+/// 256-color index (`"208"`).
+pub fn parse_color(s: &str) -> Option<Color> {
+    Color::from_str(s.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_forms() {
+        assert_eq!(parse_color("red"), Some(Color::Red));
+        assert_eq!(parse_color("LightBlue"), Some(Color::LightBlue));
+        assert_eq!(parse_color("#ff8800"), Some(Color::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+        assert_eq!(parse_color("not-a-color"), None);
+    }
+
+    #[test]
+    fn theme_table_with_bad_value_falls_back() {
+        let toml: TomlTheme = toml::from_str(
+            "running = \"#00ff00\"\nfailed = \"blurple\"\nborder = \"dark-gray\"\n",
+        )
+        .unwrap();
+        let theme = Theme::from_toml(&toml);
+        let default = Theme::default();
+        assert_eq!(theme.running, Color::Rgb(0, 255, 0));
+        assert_eq!(theme.failed, default.failed);
+        assert_eq!(theme.border, Color::DarkGray);
+        assert_eq!(theme.pending, default.pending);
+    }
+}
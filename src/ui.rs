@@ -3,17 +3,33 @@ use alacritty_terminal::index::{Column, Line as ALine};
 use alacritty_terminal::term::cell::Flags as CellFlags;
 use alacritty_terminal::vte::ansi::{Color as AColor, NamedColor};
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
-
-use crate::app::{App, AppMode};
-use crate::keymap::{NormalAction, ViewAction};
-use crate::prompt::{PromptMode, PromptStatus};
+use ratatui::widgets::{
+    Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
+    ScrollbarState, Wrap,
+};
+
+use crate::app::{self, App, AppMode, FocusedPane, ListRow, SortMode};
+use crate::keymap::{self, NormalAction};
+use crate::prompt::{format_bytes, format_clock, PromptMode, PromptPriority, PromptStatus};
 use crate::pty_worker::SharedPtyState;
 
+/// Frames of the tool-activity spinner, one per tick.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Smallest terminal (cols, rows) the normal layout is drawn in.
+const MIN_TERMINAL_SIZE: (u16, u16) = (40, 10);
+
 pub fn render(f: &mut Frame, app: &mut App) {
+    // Set again below if a PTY is on screen this frame
+    app.viewed_pty = None;
+    let area = f.area();
+    if area.width < MIN_TERMINAL_SIZE.0 || area.height < MIN_TERMINAL_SIZE.1 {
+        render_too_small(f, area);
+        return;
+    }
     let input_bar_height = if app.mode == AppMode::Insert && app.input.is_multiline() {
         (app.input.line_count() as u16 + 2).clamp(3, 10) // +2 for borders
     } else {
@@ -42,6 +58,10 @@ pub fn render(f: &mut Frame, app: &mut App) {
         render_quick_prompts_popup(f, app, chunks[1]);
     }
 
+    if app.mode == AppMode::Normal && !app.pending_sequence.is_empty() {
+        render_sequence_hints(f, app, chunks[1]);
+    }
+
     if app.confirm_quit {
         render_quit_confirmation(f, f.area());
     }
@@ -50,6 +70,14 @@ pub fn render(f: &mut Frame, app: &mut App) {
         render_batch_delete_confirmation(f, app, f.area());
     }
 
+    if app.confirm_kill_all {
+        render_kill_all_confirmation(f, app, f.area());
+    }
+
+    if let Some(prompt_id) = app.confirm_discard_worktree {
+        render_discard_worktree_confirmation(f, prompt_id, f.area());
+    }
+
     if app.show_help_overlay {
         render_help_overlay(f, app, f.area());
     }
@@ -87,10 +115,10 @@ fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let selected_info: Vec<Span> = if let Some(prompt) = app.selected_prompt() {
         let (status_char, status_color) = match prompt.status {
             PromptStatus::Pending => ("·", Color::DarkGray),
-            PromptStatus::Running => ("▶", Color::Cyan),
-            PromptStatus::Idle => ("◆", Color::Magenta),
-            PromptStatus::Completed => ("✓", Color::Green),
-            PromptStatus::Failed => ("✗", Color::Red),
+            PromptStatus::Running => ("▶", app.theme.running),
+            PromptStatus::Idle => ("◆", app.theme.idle),
+            PromptStatus::Completed => ("✓", app.theme.completed),
+            PromptStatus::Failed => ("✗", app.theme.failed),
         };
         let mut parts = vec![
             sep.clone(),
@@ -114,16 +142,6 @@ fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
         vec![]
     };
 
-    // --- Session elapsed time ---
-    let session_secs = app.session_start.elapsed().as_secs();
-    let session_str = if session_secs < 60 {
-        format!("{session_secs}s")
-    } else if session_secs < 3600 {
-        format!("{}m{}s", session_secs / 60, session_secs % 60)
-    } else {
-        format!("{}h{}m", session_secs / 3600, (session_secs % 3600) / 60)
-    };
-
     // --- Build spans ---
     let mut spans = vec![
         Span::raw(" "),
@@ -138,6 +156,32 @@ fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             Style::default().fg(Color::Black).bg(Color::LightBlue).add_modifier(Modifier::BOLD),
         ));
     }
+    if app.paused {
+        spans.push(Span::styled(
+            " PAUSED ",
+            Style::default().fg(Color::Black).bg(Color::LightRed).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.draining {
+        let label = if app.active_workers == 0 { " DRAINED " } else { " DRAINING " };
+        spans.push(Span::styled(
+            label,
+            Style::default().fg(Color::Black).bg(Color::LightMagenta).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if !app.persistence_healthy.get() {
+        spans.push(Span::styled(
+            " NOT SAVING ",
+            Style::default().fg(Color::Black).bg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(ref warning) = app.version_warning {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" {warning} "),
+            Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
     spans.extend([
         sep.clone(),
         Span::styled(bar_filled, Style::default().fg(Color::Cyan)),
@@ -174,12 +218,6 @@ fn render_status_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
 
     spans.extend(selected_info);
 
-    spans.push(sep.clone());
-    spans.push(Span::styled(
-        session_str,
-        Style::default().fg(Color::DarkGray),
-    ));
-
     spans.push(sep);
     spans.push(Span::styled(
         format!("[{}]", app.default_mode.label()),
@@ -214,6 +252,15 @@ fn render_main_area(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
     render_output_viewer(f, app, chunks[1]);
 }
 
+/// Thick border for the panel holding Normal-mode focus.
+fn focus_border(app: &App, pane: FocusedPane) -> BorderType {
+    if app.mode == AppMode::Normal && app.focused_pane == pane {
+        BorderType::Thick
+    } else {
+        BorderType::Plain
+    }
+}
+
 fn truncate_prompt(text: &str, max_chars: usize) -> String {
     let char_count = text.chars().count();
     if char_count <= max_chars {
@@ -242,6 +289,21 @@ fn tag_color(tag: &str) -> Color {
     PALETTE[hash % PALETTE.len()]
 }
 
+/// Header row for a tag group in the grouped list view.
+fn group_header_item(tag: &str, count: usize, collapsed: bool) -> ListItem<'static> {
+    let (label, color) = if tag.is_empty() {
+        ("untagged".to_string(), Color::DarkGray)
+    } else {
+        (format!("@{tag}"), tag_color(tag))
+    };
+    let fold = if collapsed { "▸ " } else { "▾ " };
+    ListItem::new(Line::from(vec![
+        Span::styled(fold, Style::default().fg(Color::DarkGray)),
+        Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::styled(format!(" ({count})"), Style::default().fg(Color::DarkGray)),
+    ]))
+}
+
 fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
     // In Normal mode with a selected prompt, reserve space for preview pane
     let show_preview = app.mode == AppMode::Normal && app.list_state.selected().is_some();
@@ -263,30 +325,61 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
     let moved_id = app.recently_moved.and_then(|(id, t)| {
         if t.elapsed().as_millis() < 300 { Some(id) } else { None }
     });
-    let visible_indices = app.visible_prompt_indices().to_vec();
+    let rows = app.list_rows();
 
     // Available width for content: list_area minus borders (2) minus highlight symbol "▶ " (2)
     let content_width = (list_area.width as usize).saturating_sub(4);
 
-    let items: Vec<ListItem> = visible_indices
+    let items: Vec<ListItem> = rows
         .iter()
-        .map(|&idx| {
+        .map(|row| {
+            let idx = match row {
+                ListRow::Prompt(idx) => *idx,
+                ListRow::Group { tag, count, collapsed, .. } => {
+                    return group_header_item(tag, *count, *collapsed);
+                }
+            };
             let prompt = &app.prompts[idx];
-            let elapsed = prompt
-                .elapsed_display()
-                .map(|d| format!(" ({d})"))
-                .unwrap_or_default();
+            // Live workers show a ticking clock; finished ones their total
+            let elapsed = match prompt.status {
+                PromptStatus::Running | PromptStatus::Idle => prompt
+                    .elapsed_secs()
+                    .map(|s| format!(" ({})", format_clock(s as u64))),
+                _ => prompt.elapsed_display().map(|d| format!(" ({d})")),
+            }
+            .unwrap_or_default();
+            // Live output counter while the worker is alive
+            let progress = match (&prompt.status, prompt.progress) {
+                (PromptStatus::Running | PromptStatus::Idle, Some((bytes, lines))) => {
+                    format!(" {} {lines}L", format_bytes(bytes))
+                }
+                _ => String::new(),
+            };
+            let queued = match prompt.queued_inputs.len() {
+                0 => String::new(),
+                n => format!(" {n} queued"),
+            };
+            let attempt = if prompt.attempt > 1 {
+                format!(" attempt {}/{}", prompt.attempt, prompt.max_attempts)
+            } else {
+                String::new()
+            };
+            // Spinner with the tool a stream worker is running right now
+            let activity = match (&prompt.status, &prompt.activity) {
+                (PromptStatus::Running, Some(tool)) => {
+                    format!(" {} {tool}", SPINNER[tick as usize % SPINNER.len()])
+                }
+                _ => String::new(),
+            };
 
-            let is_unseen_done = !prompt.seen
-                && (prompt.status == PromptStatus::Completed
-                    || prompt.status == PromptStatus::Failed);
+            let is_unseen_done = prompt.is_unseen();
+            let is_waiting = app.is_awaiting_input(prompt);
 
             let status_style = match prompt.status {
-                PromptStatus::Pending => Style::default().fg(Color::Yellow),
-                PromptStatus::Running => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
-                PromptStatus::Idle => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
-                PromptStatus::Completed => Style::default().fg(Color::Green),
-                PromptStatus::Failed => Style::default().fg(Color::Red),
+                PromptStatus::Running | PromptStatus::Idle => Style::default()
+                    .fg(app.theme.status(&prompt.status))
+                    .add_modifier(Modifier::BOLD),
+                _ => Style::default().fg(app.theme.status(&prompt.status)),
             };
 
             // Calculate display width of all non-text spans to give remaining space to prompt text
@@ -294,11 +387,31 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
             let id_str = format!("#{} ", prompt.id);
             let is_selected = app.is_selected(prompt.id);
             // "● " = 2 display cols when selected
-            let mut overhead = 3 + id_str.len() + elapsed.len();
+            let mut overhead =
+                3 + id_str.len() + elapsed.len() + progress.len() + queued.len() + attempt.len()
+                    + activity.chars().count();
             if is_selected {
                 overhead += 2;
             }
 
+            if prompt.priority != PromptPriority::Normal {
+                overhead += 2; // "▲ " / "▼ "
+            }
+
+            let dep_str = prompt.depends_on.map(|dep| format!("⇠#{dep} "));
+            if let Some(ref d) = dep_str {
+                overhead += d.chars().count();
+            }
+
+            // "↪" plus the start of the session id this prompt continues
+            let session_str = prompt
+                .resume_session_id
+                .as_deref()
+                .map(|s| format!("↪{} ", s.chars().take(8).collect::<String>()));
+            if let Some(ref s) = session_str {
+                overhead += s.chars().count();
+            }
+
             if prompt.worktree {
                 overhead += 5; // " [WT]"
             }
@@ -313,7 +426,9 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
                 overhead += if dir.len() > 20 { 22 } else { dir.len() + 3 };
             }
 
-            if prompt.status == PromptStatus::Idle {
+            if is_waiting {
+                overhead += 13; // " " + " ⏳ WAITING " (the hourglass is 2 wide)
+            } else if prompt.status == PromptStatus::Idle {
                 overhead += 7; // " " + " IDLE "
             } else if is_unseen_done {
                 overhead += if prompt.status == PromptStatus::Completed { 8 } else { 9 };
@@ -331,8 +446,22 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
                 Span::styled(display, Style::default().fg(Color::Magenta))
             });
 
-            let status_tag = if prompt.status == PromptStatus::Idle {
-                let bright = (tick / 5) % 2 == 0;
+            let status_tag = if is_waiting {
+                // Escalates IDLE once the agent has waited past `input_reminder_secs`
+                let bright = (tick / 5).is_multiple_of(2);
+                let style = if bright {
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                };
+                Some(Span::styled(" ⏳ WAITING ", style))
+            } else if prompt.status == PromptStatus::Idle {
+                let bright = (tick / 5).is_multiple_of(2);
                 let style = if bright {
                     Style::default()
                         .fg(Color::Black)
@@ -345,18 +474,15 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
                 };
                 Some(Span::styled(" IDLE ", style))
             } else if is_unseen_done {
-                let tag = if prompt.status == PromptStatus::Completed {
-                    " READY "
+                let (tag, tag_color) = if prompt.status == PromptStatus::Completed {
+                    (" READY ", Color::Green)
+                } else if prompt.is_killed() {
+                    (" KILLED ", Color::Yellow)
                 } else {
-                    " FAILED "
-                };
-                let tag_color = if prompt.status == PromptStatus::Completed {
-                    Color::Green
-                } else {
-                    Color::Red
+                    (" FAILED ", Color::Red)
                 };
                 // Pulse between bright and dim every ~500ms (5 ticks at 100ms)
-                let bright = (tick / 5) % 2 == 0;
+                let bright = (tick / 5).is_multiple_of(2);
                 let style = if bright {
                     Style::default()
                         .fg(Color::Black)
@@ -378,15 +504,37 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
             }
             spans.extend([
                 Span::styled(
-                    format!("{} ", prompt.status.symbol()),
+                    format!("{} ", prompt.symbol()),
                     status_style,
                 ),
                 Span::styled(
                     id_str,
                     Style::default().fg(Color::DarkGray),
                 ),
+            ]);
+            match prompt.priority {
+                PromptPriority::High => spans.push(Span::styled(
+                    "▲ ",
+                    Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                )),
+                PromptPriority::Low => {
+                    spans.push(Span::styled("▼ ", Style::default().fg(Color::DarkGray)))
+                }
+                PromptPriority::Normal => {}
+            }
+            if let Some(d) = dep_str {
+                spans.push(Span::styled(d, Style::default().fg(Color::DarkGray)));
+            }
+            if let Some(s) = session_str {
+                spans.push(Span::styled(s, Style::default().fg(Color::Cyan)));
+            }
+            spans.extend([
                 Span::raw(truncated),
                 Span::styled(elapsed, Style::default().fg(Color::DarkGray)),
+                Span::styled(progress, Style::default().fg(Color::DarkGray)),
+                Span::styled(queued, Style::default().fg(Color::Magenta)),
+                Span::styled(attempt, Style::default().fg(Color::Yellow)),
+                Span::styled(activity, Style::default().fg(Color::Cyan)),
             ]);
             if prompt.worktree {
                 spans.push(Span::styled(" [WT]", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)));
@@ -416,14 +564,14 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
                 // Flash highlight for recently reordered prompt
                 item.style(Style::default().bg(Color::Rgb(60, 60, 30)).add_modifier(Modifier::BOLD))
             } else if prompt.status == PromptStatus::Idle {
-                let bg = if (tick / 5) % 2 == 0 {
+                let bg = if (tick / 5).is_multiple_of(2) {
                     Color::Rgb(45, 30, 50)
                 } else {
                     Color::Rgb(35, 25, 40)
                 };
                 item.style(Style::default().bg(bg))
             } else if is_unseen_done {
-                let bg = if (tick / 5) % 2 == 0 {
+                let bg = if (tick / 5).is_multiple_of(2) {
                     Color::Rgb(40, 50, 30)
                 } else {
                     Color::Rgb(30, 35, 25)
@@ -436,28 +584,54 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
         .collect();
 
     // Build title with optional filter indicator
-    let title = if let Some(ref filter) = app.filter_text {
-        format!(" Prompts [filter: {filter}] ")
-    } else {
-        " Prompts ".to_string()
-    };
+    let mut title = " Prompts ".to_string();
+    if let Some(ref filter) = app.filter_text {
+        let fallback = if app.filter_regex_invalid { " (bad regex, substring)" } else { "" };
+        title.push_str(&format!("[filter: {filter}{fallback}] "));
+    }
+    if app.sort_mode != SortMode::Queue {
+        title.push_str(&format!("[sort: {}] ", app.sort_mode.label()));
+    }
+
+    if app.grouped {
+        title.push_str("[grouped] ");
+    }
 
-    // Map the real selection index to the position in the filtered list
+    // Map the real selection index to its row; a folded group's first
+    // prompt selects the header
     let mut filtered_list_state = ListState::default();
     if let Some(selected) = app.list_state.selected() {
-        let filtered_pos = visible_indices.iter().position(|&i| i == selected);
+        let filtered_pos = rows.iter().position(|row| match row {
+            ListRow::Prompt(i) => *i == selected,
+            ListRow::Group { collapsed, first, .. } => *collapsed && *first == selected,
+        });
         filtered_list_state.select(filtered_pos);
     }
 
+    // Footer: session runtime, prompts completed this session and the
+    // prompts that may need attention
+    let attention = app.attention_summary().label();
+    let footer = format!(
+        " {} · {} done{} ",
+        format_clock(app.session_start.elapsed().as_secs()),
+        app.session_completed_count(),
+        if attention.is_empty() { String::new() } else { format!(" · {attention}") },
+    );
+
     let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Rgb(80, 80, 100)))
+                .border_type(focus_border(app, FocusedPane::List))
+                .border_style(Style::default().fg(app.theme.border))
                 .title(Span::styled(
                     title,
-                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
-                )),
+                    Style::default().fg(app.theme.title).add_modifier(Modifier::BOLD),
+                ))
+                .title_bottom(
+                    Line::from(Span::styled(footer, Style::default().fg(Color::DarkGray)))
+                        .right_aligned(),
+                ),
         )
         .highlight_style(if moved_id.is_some() {
             Style::default()
@@ -465,12 +639,14 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
                 .add_modifier(Modifier::BOLD)
         } else {
             Style::default()
-                .bg(Color::Rgb(40, 40, 60))
+                .bg(app.theme.selected)
                 .add_modifier(Modifier::BOLD)
         })
         .highlight_symbol("▶ ");
 
     f.render_stateful_widget(list, list_area, &mut filtered_list_state);
+    app.list_area = Some(list_area);
+    app.list_offset = filtered_list_state.offset();
 
     // Render prompt preview pane
     if let Some(preview_rect) = preview_area {
@@ -494,8 +670,8 @@ fn render_prompt_list(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect)
 }
 
 fn render_output_viewer(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
-    // Check if we should render the PTY grid
-    if let Some(prompt) = app.selected_prompt() {
+    // Check if we should render the PTY grid (an open worktree diff covers it)
+    if let Some(prompt) = app.selected_prompt().filter(|_| app.selected_worktree_diff().is_none()) {
         if prompt.pty_state.is_some() {
             let pty_state = prompt.pty_state.clone().unwrap();
             let id = prompt.id;
@@ -546,8 +722,9 @@ fn render_pty_output_viewer(
         Color::Cyan
     };
 
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
+        .border_type(focus_border(app, FocusedPane::Output))
         .border_style(Style::default().fg(border_color))
         .title(vec![
             Span::styled(
@@ -557,12 +734,16 @@ fn render_pty_output_viewer(
             live_indicator,
             status_indicator,
         ]);
+    if let Some(line) = app.selected_prompt().and_then(command_title) {
+        block = block.title_bottom(line);
+    }
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
     // Update output panel size for PTY resize tracking
     app.output_panel_size = Some((inner.width, inner.height));
+    app.viewed_pty = Some(id);
 
     // Render PTY grid content
     render_pty_grid(f, pty_state, inner);
@@ -686,76 +867,27 @@ fn convert_flags(flags: CellFlags) -> Modifier {
 }
 
 fn render_text_output_viewer(f: &mut Frame, app: &mut App, area: ratatui::layout::Rect) {
-    let (title, content) = match app.selected_prompt() {
-        Some(prompt) => {
+    let diff = app.selected_worktree_diff().map(|view| view.diff.as_deref().unwrap_or("Running git diff..."));
+    let showing_diff = diff.is_some();
+    let (title, content) = match (app.selected_prompt(), diff) {
+        (Some(prompt), Some(diff)) => {
+            let wt_path = prompt.worktree_path.as_deref().unwrap_or(".");
+            (format!(" Diff: #{} [{wt_path}] ", prompt.id), diff.to_string())
+        }
+        (Some(prompt), None) => {
             let cwd_str = prompt.cwd.as_deref().unwrap_or(".");
             let wt_tag = if prompt.worktree_path.is_some() { " [WT]" } else { "" };
             let title = format!(" Output: #{} [{}]{wt_tag} ", prompt.id, cwd_str);
-            let content = match &prompt.status {
-                PromptStatus::Pending => "(pending)".to_string(),
-                PromptStatus::Running => {
-                    let elapsed = prompt.elapsed_display().unwrap_or_else(|| "0.0s".into());
-                    match &prompt.output {
-                        Some(output) => {
-                            format!("Running... ({elapsed})\n\n{output}")
-                        }
-                        None => format!("Running... ({elapsed})"),
-                    }
-                }
-                PromptStatus::Idle => {
-                    let elapsed = prompt.elapsed_display().unwrap_or_else(|| "0.0s".into());
-                    let hint = if prompt.mode == PromptMode::Interactive {
-                        let key = app.keymap.view_key_hint(ViewAction::Interact);
-                        format!(" — press '{key}' to interact")
-                    } else {
-                        String::new()
-                    };
-                    match &prompt.output {
-                        Some(output) => {
-                            format!("{output}\n\n— Idle ({elapsed}){hint}")
-                        }
-                        None => format!("Idle ({elapsed}){hint}"),
-                    }
-                }
-                PromptStatus::Completed => {
-                    prompt.output.clone().unwrap_or_else(|| "(no output)".to_string())
-                }
-                PromptStatus::Failed => {
-                    let mut text = String::from("FAILED");
-                    if let Some(err) = &prompt.error {
-                        text.push_str(&format!(":\n{err}"));
-                    }
-                    if let Some(output) = &prompt.output {
-                        if !output.is_empty() {
-                            text.push_str(&format!("\n\nOutput:\n{output}"));
-                        }
-                    }
-                    text
-                }
-            };
+            let content = app.output_view_content(prompt);
             (title, content)
         }
-        None => (" Output ".to_string(), "Select a prompt to view output".to_string()),
+        (None, _) => (" Output ".to_string(), "Select a prompt to view output".to_string()),
     };
 
-    // Auto-scroll: compute scroll offset to show the bottom of content
-    if app.auto_scroll && matches!(app.mode, AppMode::ViewOutput | AppMode::Interact) {
-        if let Some(prompt) = app.selected_prompt() {
-            if prompt.status == PromptStatus::Running {
-                // Estimate total lines (rough: count newlines + wrapping)
-                let inner_height = area.height.saturating_sub(2); // borders
-                let line_count = content.lines().count() as u16;
-                if line_count > inner_height {
-                    app.scroll_offset = line_count.saturating_sub(inner_height);
-                }
-            }
-        }
-    }
-
-    let auto_scroll_indicator = if app.auto_scroll {
-        Span::styled(" [auto-scroll] ", Style::default().fg(Color::Green))
-    } else {
-        Span::raw("")
+    let auto_scroll_indicator = match (app.auto_scroll, app.output_at_bottom) {
+        (true, true) => Span::styled(" [auto-scroll] ", Style::default().fg(Color::Green)),
+        (true, false) => Span::styled(" [scrolled up] ", Style::default().fg(Color::DarkGray)),
+        (false, _) => Span::raw(""),
     };
 
     // Status message indicator (transient, shown for 3s)
@@ -765,43 +897,229 @@ fn render_text_output_viewer(f: &mut Frame, app: &mut App, area: ratatui::layout
         Span::raw("")
     };
 
-    let output_border_color = match app.selected_prompt().map(|p| &p.status) {
-        Some(PromptStatus::Running) => Color::Cyan,
-        Some(PromptStatus::Idle) => Color::Magenta,
-        Some(PromptStatus::Completed) => Color::Green,
-        Some(PromptStatus::Failed) => Color::Red,
-        Some(PromptStatus::Pending) => Color::Yellow,
-        None => Color::Rgb(80, 80, 100),
+    let output_border_color = match app.selected_prompt() {
+        Some(prompt) => app.theme.status(&prompt.status),
+        None => app.theme.border,
     };
 
-    let paragraph = Paragraph::new(content)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(output_border_color))
-                .title(vec![
-                    Span::styled(title, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-                    auto_scroll_indicator,
-                    status_indicator,
-                ]),
+    let styled = app.selected_prompt().and_then(styled_output_lines);
+    let text: Vec<Line> = match (&app.output_search, styled) {
+        _ if showing_diff => diff_lines(&content),
+        (Some(query), _) => highlight_matches(&content, query, app.output_search_line),
+        (None, Some(lines)) => lines,
+        (None, None) => plain_output_lines(&content, app.selected_prompt()),
+    };
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(focus_border(app, FocusedPane::Output))
+        .border_style(Style::default().fg(output_border_color))
+        .title(vec![
+            Span::styled(title, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+            auto_scroll_indicator,
+            status_indicator,
+        ]);
+    if let Some(line) = app.selected_prompt().and_then(command_title) {
+        block = block.title_bottom(line);
+    }
+
+    let inner = block.inner(area);
+    let key = (app.selected_prompt().map(|p| p.id), showing_diff, content.len(), text.len(), inner.width);
+    let rows = match app.output_rows {
+        Some((cached, rows)) if cached == key => rows,
+        _ => {
+            let rows = wrapped_height(&text, inner.width);
+            app.output_rows = Some((key, rows));
+            rows
+        }
+    };
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false });
+    let max_offset = rows.saturating_sub(inner.height);
+
+    // Auto-scroll: keep the bottom of a running prompt's output in view,
+    // unless the user scrolled up since
+    let running = !showing_diff && app.selected_prompt().is_some_and(|p| p.status == PromptStatus::Running);
+    let following = matches!(app.mode, AppMode::ViewOutput | AppMode::Interact);
+    app.output_width = inner.width;
+    if app.auto_scroll && app.output_at_bottom && running && following {
+        app.scroll_offset = max_offset;
+    }
+    app.scroll_offset = app.scroll_offset.min(max_offset);
+    app.output_scroll_max = max_offset;
+    if following {
+        app.output_at_bottom = app.scroll_offset >= max_offset;
+    }
+
+    f.render_widget(paragraph.block(block).scroll((app.scroll_offset, 0)), area);
+
+    if max_offset > 0 {
+        let mut state = ScrollbarState::new(max_offset as usize + 1)
+            .position(app.scroll_offset as usize)
+            .viewport_content_length(inner.height as usize);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight).begin_symbol(None).end_symbol(None),
+            area.inner(Margin { vertical: 1, horizontal: 0 }),
+            &mut state,
+        );
+    }
+}
+
+/// Rows the lines take once wrapped to `width` columns, so scrolling
+/// counts the screen lines a long output line spills onto.
+fn wrapped_height(lines: &[Line], width: u16) -> u16 {
+    let rows: usize = lines
+        .iter()
+        .map(|line| app::wrapped_rows(line.spans.iter().flat_map(|span| span.content.chars()), width))
+        .sum();
+    rows.min(u16::MAX as usize) as u16
+}
+
+/// Color a unified diff the way `git diff --color` would: additions green,
+/// removals red, hunk headers cyan, file headers bold.
+fn diff_lines(content: &str) -> Vec<Line<'_>> {
+    content
+        .lines()
+        .map(|line| {
+            let style = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff --git") {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else if line.starts_with('+') {
+                Style::default().fg(Color::Green)
+            } else if line.starts_with('-') {
+                Style::default().fg(Color::Red)
+            } else if line.starts_with("@@") {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            };
+            Line::styled(line, style)
+        })
+        .collect()
+}
+
+/// Bottom border line with the command the prompt's worker was spawned with.
+fn command_title(prompt: &crate::prompt::Prompt) -> Option<Line<'static>> {
+    let command = prompt.command.as_deref()?;
+    Some(Line::from(Span::styled(
+        format!(" $ {command} "),
+        Style::default().fg(Color::DarkGray),
+    )))
+}
+
+/// Split content into lines, dimming the ones a stream worker wrote to
+/// stderr. The prompt's output always ends the content when it has any.
+fn plain_output_lines<'a>(content: &'a str, prompt: Option<&crate::prompt::Prompt>) -> Vec<Line<'a>> {
+    let base = prompt.and_then(|p| {
+        let output = p.output.as_deref()?;
+        (!p.stderr_spans.is_empty() && content.ends_with(output)).then(|| (p, content.len() - output.len()))
+    });
+    let dim = Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM);
+    let mut offset = 0;
+    content
+        .split_inclusive('\n')
+        .map(|raw| {
+            let start = offset;
+            offset += raw.len();
+            let line = raw.trim_end_matches('\n').trim_end_matches('\r');
+            match base {
+                Some((p, base)) if start >= base && p.is_stderr_at(start - base) => Line::styled(line, dim),
+                _ => Line::from(line),
+            }
+        })
+        .collect()
+}
+
+/// Colored lines for a finished PTY prompt, laid out like
+/// `output_view_content`. `None` when there is no styled transcript.
+fn styled_output_lines(prompt: &crate::prompt::Prompt) -> Option<Vec<Line<'static>>> {
+    let styled = prompt.styled_output.as_ref()?;
+    let mut lines = Vec::new();
+    match prompt.status {
+        PromptStatus::Completed => {}
+        PromptStatus::Failed => {
+            match &prompt.error {
+                Some(err) => {
+                    lines.push(Line::from("FAILED:"));
+                    lines.extend(err.lines().map(|l| Line::from(l.to_string())));
+                }
+                None => lines.push(Line::from("FAILED")),
+            }
+            lines.push(Line::default());
+            lines.push(Line::from("Output:"));
+        }
+        _ => return None,
+    }
+    lines.extend(styled.iter().map(|runs| {
+        Line::from(
+            runs.iter()
+                .map(|run| Span::styled(run.text.clone(), cell_style(run.fg, run.bg, run.flags)))
+                .collect::<Vec<_>>(),
         )
-        .wrap(Wrap { trim: false })
-        .scroll((app.scroll_offset, 0));
-    f.render_widget(paragraph, area);
+    }));
+    Some(lines)
+}
+
+/// Split content into lines with search matches highlighted; matches on the
+/// current line get a brighter style.
+fn highlight_matches<'a>(content: &'a str, query: &str, current: Option<usize>) -> Vec<Line<'a>> {
+    let hit = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let current_hit = Style::default()
+        .fg(Color::Black)
+        .bg(Color::LightRed)
+        .add_modifier(Modifier::BOLD);
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let matches = app::find_matches(line, query);
+            if matches.is_empty() {
+                return Line::from(line);
+            }
+            let style = if current == Some(i) { current_hit } else { hit };
+            let mut spans = Vec::new();
+            let mut pos = 0;
+            for (start, end) in matches {
+                if start > pos {
+                    spans.push(Span::raw(&line[pos..start]));
+                }
+                spans.push(Span::styled(&line[start..end], style));
+                pos = end;
+            }
+            if pos < line.len() {
+                spans.push(Span::raw(&line[pos..]));
+            }
+            Line::from(spans)
+        })
+        .collect()
 }
 
 fn render_input_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let (title, content, style, border_color): (String, String, Style, Color) = match app.mode {
         AppMode::Insert => {
             let wt_tag = if app.worktree_pending { " [WT]" } else { "" };
+            let dup_tag = match (&app.duplicate_source, &app.continue_session) {
+                (Some(id), _) => format!(" [dup #{id}]"),
+                (None, Some((id, _))) => format!(" [↪ session of #{id}]"),
+                (None, None) => String::new(),
+            };
+            let field_tag = match app.input.placeholder() {
+                Some(name) => format!(" [{{{name}}} — Tab next]"),
+                None => String::new(),
+            };
             let line_tag = if app.input.is_multiline() {
                 let (row, _) = app.input.cursor();
                 format!(" [L{}/{}]", row + 1, app.input.line_count())
             } else {
                 String::new()
             };
+            let title = match &app.history_search {
+                Some(search) => {
+                    let failing = if search.matched.is_none() && !search.query.is_empty() { "failing " } else { "" };
+                    format!(" ({failing}reverse-i-search)`{}' — Ctrl+R older, Enter keep, Esc cancel ", search.query)
+                }
+                None => format!(" Input (Enter to submit, Esc to cancel){dup_tag}{wt_tag}{field_tag}{line_tag} "),
+            };
             (
-                format!(" Input (Enter to submit, Esc to cancel){wt_tag}{line_tag} "),
+                title,
                 app.input.to_string(),
                 Style::default().fg(Color::White),
                 if app.worktree_pending { Color::Cyan } else { Color::Green },
@@ -819,6 +1137,18 @@ fn render_input_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             Style::default().fg(Color::White),
             Color::Cyan,
         ),
+        AppMode::ViewOutput if app.output_search_input.is_some() => (
+            " Search output (Enter to search, Esc to cancel) ".to_string(),
+            format!("/{}", app.output_search_input.as_deref().unwrap_or("")),
+            Style::default().fg(Color::White),
+            Color::Yellow,
+        ),
+        AppMode::Normal if app.tag_input.is_some() => (
+            " Tags (+tag adds, -tag removes, Enter to apply, Esc to cancel) ".to_string(),
+            app.tag_input.clone().unwrap_or_default(),
+            Style::default().fg(Color::White),
+            Color::Magenta,
+        ),
         AppMode::PtyInteract => (
             " PTY Interactive (Esc to exit) ".to_string(),
             String::new(),
@@ -831,7 +1161,7 @@ fn render_input_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
                 format!(" Input (press '{key}' to enter a prompt) "),
                 String::new(),
                 Style::default().fg(Color::DarkGray),
-                Color::Rgb(80, 80, 100),
+                app.theme.border,
             )
         }
     };
@@ -863,6 +1193,20 @@ fn render_input_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             let y = area.y + 1;
             f.set_cursor_position((x, y));
         }
+        AppMode::Normal => {
+            if let Some(ref input) = app.tag_input {
+                let x = area.x + input.chars().count() as u16 + 1;
+                let y = area.y + 1;
+                f.set_cursor_position((x, y));
+            }
+        }
+        AppMode::ViewOutput => {
+            if let Some(ref query) = app.output_search_input {
+                let x = area.x + query.chars().count() as u16 + 2; // +1 border, +1 for '/'
+                let y = area.y + 1;
+                f.set_cursor_position((x, y));
+            }
+        }
         _ => {}
     }
 }
@@ -973,6 +1317,20 @@ fn render_template_suggestions(f: &mut Frame, app: &App, input_area: Rect) {
     f.render_widget(list, popup_area);
 }
 
+/// Shown instead of the layout when the terminal is below
+/// `MIN_TERMINAL_SIZE`, where the split panes would degenerate.
+fn render_too_small(f: &mut Frame, area: Rect) {
+    let (min_w, min_h) = MIN_TERMINAL_SIZE;
+    let text = vec![
+        Line::from(Span::styled("Terminal too small", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Line::from(format!("{}x{}, need {min_w}x{min_h}", area.width, area.height)),
+    ];
+    // Centered vertically; lines that wrap on a narrow terminal run on below
+    let top = area.height.saturating_sub(text.len() as u16) / 2;
+    let centered = Rect { y: area.y + top, height: area.height - top, ..area };
+    f.render_widget(Paragraph::new(text).alignment(Alignment::Center).wrap(Wrap { trim: true }), centered);
+}
+
 fn render_quit_confirmation(f: &mut Frame, area: Rect) {
     let width = 44;
     let height = 5;
@@ -1051,8 +1409,126 @@ fn render_batch_delete_confirmation(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, popup_area);
 }
 
+fn render_kill_all_confirmation(f: &mut Frame, app: &App, area: Rect) {
+    let count = app.live_worker_count();
+    let msg = format!("  Kill {count} running worker{}? ", if count == 1 { "" } else { "s" });
+    let width = (msg.len() as u16 + 8).max(36);
+    let height = 5;
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    let popup_area = Rect {
+        x,
+        y,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+
+    let text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::raw(msg),
+            Span::styled("y", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::raw("/"),
+            Span::styled("n", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        ]),
+    ];
+
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title(Span::styled(
+                    " Confirm Kill All ",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                )),
+        )
+        .style(Style::default().bg(Color::Rgb(40, 25, 25)));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+fn render_discard_worktree_confirmation(f: &mut Frame, prompt_id: usize, area: Rect) {
+    let msg = format!("  Delete the worktree of #{prompt_id} and its uncommitted changes? ");
+    let width = (msg.len() as u16 + 8).max(36);
+    let height = 5;
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+    let popup_area = Rect {
+        x,
+        y,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+
+    let text = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::raw(msg),
+            Span::styled("y", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::raw("/"),
+            Span::styled("n", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        ]),
+    ];
+
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red))
+                .title(Span::styled(
+                    " Discard Worktree ",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                )),
+        )
+        .style(Style::default().bg(Color::Rgb(40, 25, 25)));
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
+/// Which-key style popup listing the keys that can complete the pending
+/// sequence, in the bottom-right corner of the main area.
+fn render_sequence_hints(f: &mut Frame, app: &App, main_area: Rect) {
+    let completions = app.keymap.sequence_completions(&app.pending_sequence);
+    if completions.is_empty() {
+        return;
+    }
+    let prefix: String = app.pending_sequence.iter().map(keymap::key_display).collect();
+    let lines: Vec<Line> = completions
+        .iter()
+        .map(|(key, label)| {
+            Line::from(vec![
+                Span::styled(format!(" {key:>3} "), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{label} "), Style::default().fg(Color::Gray)),
+            ])
+        })
+        .collect();
+
+    let title = format!(" {prefix}… ");
+    let content_width = lines.iter().map(|l| l.width() as u16).max().unwrap_or(0);
+    let width = (content_width.max(title.chars().count() as u16) + 2).min(main_area.width);
+    let height = (lines.len() as u16 + 2).min(main_area.height);
+    let popup_area = Rect {
+        x: main_area.x + main_area.width.saturating_sub(width + 1),
+        y: main_area.y + main_area.height.saturating_sub(height),
+        width,
+        height,
+    };
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(Span::styled(title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+    );
+    f.render_widget(Clear, popup_area);
+    f.render_widget(paragraph, popup_area);
+}
+
 fn render_quick_prompts_popup(f: &mut Frame, app: &App, main_area: Rect) {
-    let qp = app.keymap.quick_prompt_help();
+    let qp = app.quick_prompt_matches();
 
     // Compute the output panel area (matches render_main_area split)
     let list_pct = if app.list_collapsed { 0 } else { app.list_ratio };
@@ -1063,15 +1539,19 @@ fn render_quick_prompts_popup(f: &mut Frame, app: &App, main_area: Rect) {
         .split(main_area)[1];
 
     let lines: Vec<Line> = if qp.is_empty() {
-        vec![Line::from(Span::styled(
-            "  No quick prompts configured.",
-            Style::default().fg(Color::DarkGray),
-        ))]
+        let empty = if app.quick_prompt_filter.is_empty() {
+            "  No quick prompts configured."
+        } else {
+            "  No matching quick prompts."
+        };
+        vec![Line::from(Span::styled(empty, Style::default().fg(Color::DarkGray)))]
     } else {
         qp.iter()
-            .map(|(key, msg)| {
-                Line::from(vec![
-                    Span::raw("  "),
+            .enumerate()
+            .map(|(i, (key, msg))| {
+                let highlighted = i == app.quick_prompt_index;
+                let line = Line::from(vec![
+                    Span::raw(if highlighted { "▶ " } else { "  " }),
                     Span::styled(
                         format!("{key:>3}"),
                         Style::default()
@@ -1080,11 +1560,22 @@ fn render_quick_prompts_popup(f: &mut Frame, app: &App, main_area: Rect) {
                     ),
                     Span::raw("  "),
                     Span::styled(msg.as_str(), Style::default().fg(Color::Gray)),
-                ])
+                ]);
+                if highlighted {
+                    line.style(Style::default().bg(Color::Rgb(50, 50, 70)))
+                } else {
+                    line
+                }
             })
             .collect()
     };
 
+    let hint = if app.quick_prompt_filter.is_empty() {
+        " j/k move · Enter send · type to filter · Esc close ".to_string()
+    } else {
+        format!(" filter: {} ", app.quick_prompt_filter)
+    };
+
     let content_height = lines.len() as u16 + 2; // +2 for borders
     let max_width: u16 = lines
         .iter()
@@ -1092,8 +1583,11 @@ fn render_quick_prompts_popup(f: &mut Frame, app: &App, main_area: Rect) {
         .max()
         .unwrap_or(30)
         + 4; // padding
+    let max_width = max_width.max(hint.chars().count() as u16 + 2);
     let width = max_width.min(60).min(output_area.width.saturating_sub(4));
     let height = content_height.min(output_area.height.saturating_sub(2));
+    // Keep the highlighted row in view when the list is taller than the popup
+    let scroll = (app.quick_prompt_index as u16).saturating_sub(height.saturating_sub(3));
 
     // Center in the output panel
     let x = output_area.x + (output_area.width.saturating_sub(width)) / 2;
@@ -1117,12 +1611,10 @@ fn render_quick_prompts_popup(f: &mut Frame, app: &App, main_area: Rect) {
                         .fg(Color::Yellow)
                         .add_modifier(Modifier::BOLD),
                 ))
-                .title_bottom(Line::from(Span::styled(
-                    " Esc to close ",
-                    Style::default().fg(Color::DarkGray),
-                ))),
+                .title_bottom(Line::from(Span::styled(hint, Style::default().fg(Color::DarkGray)))),
         )
-        .style(Style::default().bg(Color::Rgb(30, 30, 40)));
+        .style(Style::default().bg(Color::Rgb(30, 30, 40)))
+        .scroll((scroll, 0));
 
     f.render_widget(Clear, popup_area);
     f.render_widget(paragraph, popup_area);
@@ -1172,7 +1664,6 @@ fn render_help_overlay(f: &mut Frame, app: &App, area: Rect) {
     add_section("NORMAL", &normal, &[
         ("Ctrl+D", "half page down"),
         ("Ctrl+U", "half page up"),
-        ("gg", "go to top"),
     ]);
 
     // SELECTION
@@ -1182,6 +1673,7 @@ fn render_help_overlay(f: &mut Frame, app: &App, area: Rect) {
         (&app.keymap.normal_key_hint(NormalAction::SelectAllVisible), "select all visible"),
         (&app.keymap.normal_key_hint(NormalAction::DeleteSelected), "delete selected"),
         (&app.keymap.normal_key_hint(NormalAction::KillSelected), "kill selected"),
+        (&app.keymap.normal_key_hint(NormalAction::KillAll), "kill all workers"),
         ("Esc", "clear selection"),
     ]);
 
@@ -1190,8 +1682,12 @@ fn render_help_overlay(f: &mut Frame, app: &App, area: Rect) {
     add_section("INSERT", &insert, &[
         ("Shift+Enter", "insert newline"),
         ("Ctrl+E", "open $EDITOR"),
-        ("Ctrl+W", "toggle worktree"),
+        ("Ctrl+T", "toggle worktree"),
         ("Left/Right", "move cursor"),
+        ("Alt+B/Alt+F", "word left/right"),
+        ("Ctrl+W", "delete word"),
+        ("Ctrl+K", "delete to line end"),
+        ("Ctrl+Z/Ctrl+Y", "undo/redo"),
         ("Home/End", "line start/end"),
         ("Up/Down", "navigate lines / history"),
         (":name+Tab", "expand template"),
@@ -1303,9 +1799,10 @@ fn render_help_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
                 let is_finished = matches!(p.status, PromptStatus::Completed | PromptStatus::Failed);
                 let is_interactive = p.mode == PromptMode::Interactive;
                 help.retain(|(_, desc)| match *desc {
-                    "move up" | "move down" => is_pending,
+                    "move up" | "move down" | "priority" => is_pending,
                     "interact" => is_interactive && is_running,
                     "retry" | "resume" => is_finished,
+                    "continue" => is_finished && p.session_id.is_some(),
                     _ => true,
                 });
             }
@@ -1315,13 +1812,22 @@ fn render_help_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
             let mut help = app.keymap.insert_help();
             help.push(("S-Ret".to_string(), "newline"));
             help.push(("C-e".to_string(), "editor"));
-            help.push(("C-w".to_string(), "worktree"));
+            help.push(("C-t".to_string(), "worktree"));
+            help.push(("C-w".to_string(), "del word"));
             help
         }
         AppMode::ViewOutput => app.keymap.view_help(),
         AppMode::Interact => app.keymap.interact_help(),
         AppMode::PtyInteract => vec![("Esc".to_string(), "exit PTY mode")],
-        AppMode::Filter => app.keymap.filter_help(),
+        AppMode::Filter => {
+            let mut help = app.keymap.filter_help();
+            help.push(("@tag".to_string(), "by tag"));
+            help.push(("status:failed".to_string(), "by status"));
+            help.push(("/re/".to_string(), "regex"));
+            help.push(("!name".to_string(), "saved"));
+            help.push((">name".to_string(), "save"));
+            help
+        }
     };
 
     let mut spans: Vec<Span> = vec![Span::raw(" ")];
@@ -1406,3 +1912,131 @@ fn render_help_bar(f: &mut Frame, app: &App, area: ratatui::layout::Rect) {
     let paragraph = Paragraph::new(Line::from(spans));
     f.render_widget(paragraph, area);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::new_test_app;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn draw(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| render(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn wrapped_height_counts_screen_rows() {
+        let lines = vec![Line::from("one two three four"), Line::default(), Line::from("x")];
+        assert_eq!(wrapped_height(&lines, 40), 3);
+        // "one two three four" breaks at word boundaries into 3 rows of 9 columns
+        assert_eq!(wrapped_height(&lines, 9), 5);
+        assert_eq!(wrapped_height(&lines, 0), 0);
+
+        let long = [Line::from("a".repeat(25))];
+        assert_eq!(wrapped_height(&long, 10), 3);
+        // Wide characters take two columns; control characters aren't drawn
+        assert_eq!(wrapped_height(&[Line::from("漢字 漢字")], 6), 2);
+        assert_eq!(wrapped_height(&[Line::from("a\tb")], 2), 1);
+    }
+
+    #[test]
+    fn worktree_diff_replaces_the_output() {
+        let mut app = new_test_app();
+        app.add_prompt("p".to_string(), None, true, Vec::new());
+        app.prompts[0].output = Some("agent output".to_string());
+        app.prompts[0].status = PromptStatus::Completed;
+        app.prompts[0].worktree_path = Some("/wt".to_string());
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+        app.worktree_diff = Some(crate::app::WorktreeDiffView {
+            prompt_id: 1,
+            diff: Some("@@ -1 +1 @@\n-old\n+new".to_string()),
+        });
+
+        let screen = draw(&mut app, 80, 20);
+        assert!(screen.contains("Diff: #1 [/wt]"), "{screen:?}");
+        assert!(screen.contains("+new"), "{screen:?}");
+        assert!(!screen.contains("agent output"), "{screen:?}");
+        assert_eq!(diff_lines("+new")[0].style.fg, Some(Color::Green));
+        assert_eq!(diff_lines("--- a/x")[0].style.fg, None);
+    }
+
+    #[test]
+    fn output_scroll_is_clamped_to_the_wrapped_bottom() {
+        let mut app = new_test_app();
+        app.add_prompt("p".to_string(), None, false, Vec::new());
+        let output: Vec<String> = (0..30).map(|i| format!("line {i}")).collect();
+        app.prompts[0].output = Some(output.join("\n"));
+        app.prompts[0].status = PromptStatus::Completed;
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+        app.scroll_offset = 1000;
+        let screen = draw(&mut app, 60, 20);
+        assert!(app.scroll_offset > 0 && app.scroll_offset < 30, "{}", app.scroll_offset);
+        assert!(screen.contains("line 29"), "{screen:?}");
+    }
+
+    #[test]
+    fn follow_pauses_while_scrolled_up() {
+        let mut app = new_test_app();
+        app.add_prompt("p".to_string(), None, false, Vec::new());
+        let lines = |n: usize| (0..n).map(|i| format!("line {i}")).collect::<Vec<_>>().join("\n");
+        app.prompts[0].output = Some(lines(30));
+        app.prompts[0].status = PromptStatus::Running;
+        app.list_state.select(Some(0));
+        app.mode = AppMode::ViewOutput;
+        draw(&mut app, 100, 20);
+        let bottom = app.output_scroll_max;
+        assert!(bottom > 0);
+        assert_eq!(app.scroll_offset, bottom);
+
+        // Scrolling up pauses follow: new output doesn't move the view
+        app.handle_key(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE));
+        app.prompts[0].output = Some(lines(40));
+        let screen = draw(&mut app, 100, 20);
+        assert_eq!(app.scroll_offset, bottom - 1);
+        assert!(screen.contains("[scrolled up]"), "{screen:?}");
+
+        // Scrolling back to the end resumes it
+        for _ in 0..20 {
+            app.handle_key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+        }
+        assert!(app.output_at_bottom);
+        app.prompts[0].output = Some(lines(50));
+        draw(&mut app, 100, 20);
+        assert_eq!(app.scroll_offset, app.output_scroll_max);
+        assert!(app.output_scroll_max > bottom + 10);
+
+        // Toggling auto-scroll while paused jumps back to the bottom
+        app.handle_key(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::NONE));
+        draw(&mut app, 100, 20);
+        assert!(!app.output_at_bottom);
+        app.handle_key(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE));
+        assert!(app.auto_scroll);
+        draw(&mut app, 100, 20);
+        assert_eq!(app.scroll_offset, app.output_scroll_max);
+    }
+
+    #[test]
+    fn tiny_terminal_shows_a_message_instead_of_the_layout() {
+        let mut app = new_test_app();
+        app.add_prompt("hello".to_string(), None, false, Vec::new());
+        let screen = draw(&mut app, 10, 5);
+        assert!(screen.contains("Terminal"), "{screen:?}");
+        assert!(!screen.contains("hello"));
+    }
+
+    #[test]
+    fn minimum_size_draws_the_normal_layout() {
+        let mut app = new_test_app();
+        app.add_prompt("hello".to_string(), None, false, Vec::new());
+        let (width, height) = MIN_TERMINAL_SIZE;
+        let screen = draw(&mut app, width, height);
+        assert!(!screen.contains("Terminal too small"));
+        assert!(screen.contains("hello"), "{screen:?}");
+    }
+}
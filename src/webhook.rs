@@ -0,0 +1,151 @@
+#[cfg(feature = "webhook")]
+use std::io::{ErrorKind, Write};
+#[cfg(feature = "webhook")]
+use std::process::{Command, Stdio};
+#[cfg(feature = "webhook")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::prompt::Prompt;
+use crate::worker::WorkerMessage;
+
+/// Seconds `curl` gets for the whole request.
+#[cfg(feature = "webhook")]
+const TIMEOUT_SECS: &str = "10";
+
+/// Set once `curl` turns out to be missing, so it's reported only once.
+#[cfg(feature = "webhook")]
+static CURL_MISSING: AtomicBool = AtomicBool::new(false);
+
+/// Body POSTed to `completion_webhook_url` when a prompt finishes.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CompletionPayload {
+    pub id: usize,
+    pub uuid: String,
+    pub text: String,
+    /// Same labels as `status:` filters: `completed`, `failed` or `killed`.
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub elapsed_secs: Option<f64>,
+}
+
+impl CompletionPayload {
+    pub fn from_prompt(prompt: &Prompt, exit_code: Option<i32>) -> Self {
+        Self {
+            id: prompt.id,
+            uuid: prompt.uuid.clone(),
+            text: prompt.text.clone(),
+            status: prompt.state_label().to_string(),
+            exit_code,
+            elapsed_secs: prompt.elapsed_secs(),
+        }
+    }
+}
+
+/// POST the payload with `curl` on a background thread, so a slow endpoint
+/// never blocks the UI. One attempt only; a failure comes back as
+/// `WorkerMessage::WebhookFailed` for the status bar. Requires the
+/// `webhook` feature (on by default).
+#[cfg(feature = "webhook")]
+pub fn post(url: String, payload: &CompletionPayload, tx: Option<mpsc::UnboundedSender<WorkerMessage>>) {
+    if CURL_MISSING.load(Ordering::Relaxed) {
+        return;
+    }
+    let Ok(body) = serde_json::to_string(payload) else {
+        return;
+    };
+    let prompt_id = payload.id;
+    std::thread::spawn(move || {
+        if let Err(error) = run_curl(&url, &body) {
+            if let Some(tx) = tx {
+                let _ = tx.send(WorkerMessage::WebhookFailed { prompt_id, error });
+            }
+        }
+    });
+}
+
+/// Without the `webhook` feature nothing is sent; the first finished prompt
+/// reports that once.
+#[cfg(not(feature = "webhook"))]
+pub fn post(_url: String, payload: &CompletionPayload, tx: Option<mpsc::UnboundedSender<WorkerMessage>>) {
+    use std::sync::Once;
+    static REPORTED: Once = Once::new();
+    REPORTED.call_once(|| {
+        if let Some(tx) = tx {
+            let error = "clhorde was built without the webhook feature".to_string();
+            let _ = tx.send(WorkerMessage::WebhookFailed { prompt_id: payload.id, error });
+        }
+    });
+}
+
+#[cfg(feature = "webhook")]
+fn run_curl(url: &str, body: &str) -> Result<(), String> {
+    // `--url` keeps a URL starting with `-` from being read as an option
+    let mut child = Command::new("curl")
+        .args(["-sS", "--fail", "--max-time", TIMEOUT_SECS, "-X", "POST"])
+        .args(["-H", "Content-Type: application/json", "--data-binary", "@-", "--url", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            if e.kind() == ErrorKind::NotFound {
+                CURL_MISSING.store(true, Ordering::Relaxed);
+                "curl not found on PATH; completion webhooks are off until restart".to_string()
+            } else {
+                format!("could not run curl: {e}")
+            }
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(body.as_bytes());
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{PromptMode, PromptStatus};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn payload_matches_schema() {
+        let mut prompt = Prompt::new(7, "fix the bug".to_string(), None, PromptMode::OneShot);
+        prompt.status = PromptStatus::Failed;
+        let start = Instant::now();
+        prompt.started_at = Some(start);
+        prompt.finished_at = Some(start + Duration::from_millis(2500));
+
+        let payload = CompletionPayload::from_prompt(&prompt, Some(2));
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": 7,
+                "uuid": prompt.uuid,
+                "text": "fix the bug",
+                "status": "failed",
+                "exit_code": 2,
+                "elapsed_secs": 2.5,
+            })
+        );
+    }
+
+    #[test]
+    fn killed_prompt_without_exit_code() {
+        let mut prompt = Prompt::new(1, "x".to_string(), None, PromptMode::Interactive);
+        prompt.status = PromptStatus::Failed;
+        prompt.killed = true;
+        let json = serde_json::to_value(CompletionPayload::from_prompt(&prompt, None)).unwrap();
+        assert_eq!(json["status"], "killed");
+        assert!(json["exit_code"].is_null());
+        assert!(json["elapsed_secs"].is_null());
+    }
+}
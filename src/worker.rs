@@ -1,19 +1,50 @@
+use std::collections::BTreeMap;
 use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 use tokio::sync::mpsc;
 
-use crate::prompt::PromptMode;
+use crate::parser::{self, ParsedEvent};
+use crate::prompt::{Prompt, PromptMode};
 use crate::pty_worker::PtyHandle;
 
 #[allow(dead_code)]
 pub enum WorkerMessage {
-    OutputChunk { prompt_id: usize, text: String },
+    OutputChunk { prompt_id: usize, stream: OutputStream, text: String },
     TurnComplete { prompt_id: usize },
     Finished { prompt_id: usize, exit_code: Option<i32> },
     SpawnError { prompt_id: usize, error: String },
     PtyUpdate { #[allow(dead_code)] prompt_id: usize },
     SessionId { prompt_id: usize, session_id: String },
+    /// Running totals of output produced so far (throttled, see `ProgressCounter`).
+    Progress { prompt_id: usize, bytes: u64, lines: u64 },
+    /// Result of `worktree_autocommit` after a prompt completed: the short
+    /// hash of the new commit, or `None` if there was nothing to commit.
+    WorktreeCommit { prompt_id: usize, result: Result<Option<String>, String> },
+    /// Result of `worktree::diff` for the worktree diff view.
+    WorktreeDiff { prompt_id: usize, result: Result<String, String> },
+    /// Result of `worktree::apply`: the merge commit's short hash, or
+    /// `None` if there was nothing to merge.
+    WorktreeApplied { prompt_id: usize, result: Result<Option<String>, String> },
+    /// Result of removing a worktree the user discarded.
+    WorktreeDiscarded { prompt_id: usize, result: Result<(), String> },
+    /// Tool the stream worker is currently running, `None` once it's done.
+    Activity { prompt_id: usize, activity: Option<String> },
+    /// The resolved command line, sent just before the worker is spawned.
+    Command { prompt_id: usize, command: String },
+    /// A one-shot worker process started (PTY pids are read from the handle).
+    Spawned { prompt_id: usize, pid: u32 },
+    /// The completion webhook for a prompt could not be delivered.
+    WebhookFailed { prompt_id: usize, error: String },
+}
+
+/// Which of the worker's output streams a chunk came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputStream {
+    Stdout,
+    /// Only stream workers split this out; a PTY merges both into one terminal.
+    Stderr,
 }
 
 pub enum WorkerInput {
@@ -22,6 +53,60 @@ pub enum WorkerInput {
     Kill,
 }
 
+/// How a worker attaches to an agent session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionMode {
+    /// Start a new session with the prompt.
+    New,
+    /// Reopen a session (an empty id lets the agent pick). Interactive
+    /// workers don't resend the prompt, which the session already has.
+    Resume(String),
+    /// Send the prompt as a new message in an existing session.
+    Continue(String),
+}
+
+impl SessionMode {
+    /// How `prompt`'s next worker attaches to a session.
+    pub fn for_prompt(prompt: &Prompt) -> Self {
+        if prompt.resume {
+            SessionMode::Resume(prompt.session_id.clone().unwrap_or_default())
+        } else if let Some(ref id) = prompt.resume_session_id {
+            SessionMode::Continue(id.clone())
+        } else {
+            SessionMode::New
+        }
+    }
+}
+
+/// Minimum time between `Progress` messages from one worker.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Running byte/line totals of a worker's output. Counts are updated per
+/// chunk so nothing is re-scanned; `take_due` throttles reporting.
+#[derive(Default)]
+pub struct ProgressCounter {
+    bytes: u64,
+    lines: u64,
+    last_sent: Option<Instant>,
+}
+
+impl ProgressCounter {
+    pub fn record(&mut self, chunk: &[u8]) {
+        self.bytes += chunk.len() as u64;
+        self.lines += chunk.iter().filter(|&&b| b == b'\n').count() as u64;
+    }
+
+    /// The current totals, if at least `PROGRESS_INTERVAL` has passed since
+    /// they were last reported.
+    pub fn take_due(&mut self, now: Instant) -> Option<(u64, u64)> {
+        if self.last_sent.is_some_and(|t| now.duration_since(t) < PROGRESS_INTERVAL) {
+            return None;
+        }
+        self.last_sent = Some(now);
+        Some((self.bytes, self.lines))
+    }
+}
+
 /// Result of spawning a worker.
 pub enum SpawnResult {
     /// Interactive PTY worker.
@@ -35,37 +120,191 @@ pub enum SpawnResult {
     Error(String),
 }
 
-/// Spawns a claude worker. For interactive mode, uses PTY when `pty_size` is
-/// provided. For one-shot mode, uses stream-json as before.
+/// Program and extra arguments used to launch the agent.
+///
+/// `args` are appended after the built-in arguments, with `{prompt}` and
+/// `{cwd}` substituted per prompt. A non-empty `wrapper` (e.g. `firejail` or
+/// `docker run`) is run instead, with the agent's argv appended to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub wrapper: Vec<String>,
+}
+
+impl Default for AgentCommand {
+    fn default() -> Self {
+        Self {
+            program: "claude".to_string(),
+            args: Vec::new(),
+            wrapper: Vec::new(),
+        }
+    }
+}
+
+impl AgentCommand {
+    /// Resolve from the `agent_command`/`agent_args`/`worker_wrapper`
+    /// settings. The `CLHORDE_AGENT` env var overrides the configured program.
+    pub fn from_settings(
+        command: Option<String>,
+        args: Option<Vec<String>>,
+        wrapper: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            wrapper: wrapper.unwrap_or_default(),
+            ..Self::resolve(std::env::var("CLHORDE_AGENT").ok(), command, args)
+        }
+    }
+
+    fn resolve(env: Option<String>, command: Option<String>, args: Option<Vec<String>>) -> Self {
+        let program = env
+            .filter(|s| !s.trim().is_empty())
+            .or(command.filter(|s| !s.trim().is_empty()))
+            .unwrap_or_else(|| "claude".to_string());
+        Self {
+            program,
+            args: args.unwrap_or_default(),
+            wrapper: Vec::new(),
+        }
+    }
+
+    fn expand_args(&self, prompt: &str, cwd: &str) -> Vec<String> {
+        self.args
+            .iter()
+            .map(|a| a.replace("{prompt}", prompt).replace("{cwd}", cwd))
+            .collect()
+    }
+
+    /// Arguments (excluding the program) for a one-shot stream-json worker.
+    pub fn oneshot_args(&self, prompt: &str, cwd: &str, session: &SessionMode) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "-p",
+            prompt,
+            "--output-format",
+            "stream-json",
+            "--verbose",
+            "--include-partial-messages",
+            "--dangerously-skip-permissions",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        match session {
+            SessionMode::New => {}
+            SessionMode::Resume(id) if id.is_empty() => args.push("--resume".to_string()),
+            SessionMode::Resume(id) | SessionMode::Continue(id) => {
+                args.extend(["--resume".to_string(), id.clone()])
+            }
+        }
+        args.extend(self.expand_args(prompt, cwd));
+        args
+    }
+
+    /// Arguments (excluding the program) for an interactive PTY worker.
+    /// When resuming, the session replaces the prompt argument.
+    pub fn pty_args(&self, prompt: &str, cwd: &str, session: &SessionMode) -> Vec<String> {
+        let mut args = match session {
+            SessionMode::New => vec![prompt.to_string()],
+            SessionMode::Resume(id) if id.is_empty() => vec!["--resume".to_string()],
+            SessionMode::Resume(id) => vec!["--resume".to_string(), id.clone()],
+            SessionMode::Continue(id) => vec!["--resume".to_string(), id.clone(), prompt.to_string()],
+        };
+        args.push("--dangerously-skip-permissions".to_string());
+        args.extend(self.expand_args(prompt, cwd));
+        args
+    }
+
+    /// Program and full argv to spawn for the agent `args`: the agent itself,
+    /// or the wrapper with `{cwd}` substituted and the agent appended.
+    pub fn wrap(&self, args: Vec<String>, cwd: &str) -> (String, Vec<String>) {
+        let Some((program, wrapper_args)) = self.wrapper.split_first() else {
+            return (self.program.clone(), args);
+        };
+        let argv = wrapper_args
+            .iter()
+            .map(|a| a.replace("{cwd}", cwd))
+            .chain(std::iter::once(self.program.clone()))
+            .chain(args)
+            .collect();
+        (program.replace("{cwd}", cwd), argv)
+    }
+}
+
+/// Render `program` and `args` as a shell-pasteable command line, quoting
+/// only the arguments that need it.
+pub fn command_line(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Spawns a claude worker. For interactive mode, uses a PTY of `pty_size`
+/// (cols, rows). For one-shot mode, uses stream-json as before.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_worker(
     prompt_id: usize,
     prompt_text: String,
     cwd: Option<String>,
+    env: &BTreeMap<String, String>,
     mode: PromptMode,
     tx: mpsc::UnboundedSender<WorkerMessage>,
-    pty_size: Option<(u16, u16)>,
-    resume_session_id: Option<String>,
+    pty_size: (u16, u16),
+    session: SessionMode,
+    agent: &AgentCommand,
+    kill_grace: Duration,
+    scrollback_bytes: usize,
 ) -> SpawnResult {
+    let effective_cwd = cwd.clone().unwrap_or_else(|| {
+        std::env::current_dir()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    });
     match mode {
         PromptMode::Interactive => {
-            let (cols, rows) = pty_size.unwrap_or((80, 24));
+            let (cols, rows) = pty_size;
+            let (program, args) = agent.wrap(agent.pty_args(&prompt_text, &effective_cwd, &session), &effective_cwd);
+            let command = command_line(&program, &args);
+            let _ = tx.send(WorkerMessage::Command { prompt_id, command: command.clone() });
             match crate::pty_worker::spawn_pty_worker(
                 prompt_id,
-                prompt_text,
+                &program,
+                args,
                 cwd,
+                env,
                 cols,
                 rows,
                 tx,
-                resume_session_id,
+                kill_grace,
+                scrollback_bytes,
             ) {
                 Ok((input_sender, pty_handle)) => {
                     SpawnResult::Pty { input_sender, pty_handle }
                 }
-                Err(e) => SpawnResult::Error(e),
+                Err(e) => SpawnResult::Error(format!("{e}\ncommand: {command}")),
             }
         }
         PromptMode::OneShot => {
-            spawn_oneshot(prompt_id, prompt_text, cwd, tx, resume_session_id);
+            let (program, args) = agent.wrap(agent.oneshot_args(&prompt_text, &effective_cwd, &session), &effective_cwd);
+            let _ = tx.send(WorkerMessage::Command {
+                prompt_id,
+                command: command_line(&program, &args),
+            });
+            spawn_oneshot(prompt_id, program, args, cwd, env.clone(), tx);
             SpawnResult::OneShot
         }
     }
@@ -73,56 +312,47 @@ pub fn spawn_worker(
 
 fn spawn_oneshot(
     prompt_id: usize,
-    prompt_text: String,
+    program: String,
+    args: Vec<String>,
     cwd: Option<String>,
+    env: BTreeMap<String, String>,
     tx: mpsc::UnboundedSender<WorkerMessage>,
-    resume_session_id: Option<String>,
 ) {
     std::thread::spawn(move || {
-        let mut cmd = Command::new("claude");
-        cmd.args(["-p"])
-            .arg(&prompt_text)
-            .args([
-                "--output-format",
-                "stream-json",
-                "--verbose",
-                "--include-partial-messages",
-                "--dangerously-skip-permissions",
-            ])
-            .env_remove("CLAUDECODE");
-        if let Some(ref session_id) = resume_session_id {
-            if session_id.is_empty() {
-                cmd.arg("--resume");
-            } else {
-                cmd.args(["--resume", session_id]);
-            }
-        }
+        let mut cmd = Command::new(&program);
+        cmd.args(&args).env_remove("CLAUDECODE").envs(&env);
         if let Some(ref dir) = cwd {
             cmd.current_dir(dir);
         }
         let mut child = match cmd
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
-            .stderr(Stdio::null())
+            .stderr(Stdio::piped())
             .spawn()
         {
             Ok(child) => child,
             Err(e) => {
                 let _ = tx.send(WorkerMessage::SpawnError {
                     prompt_id,
-                    error: format!("Failed to spawn claude: {e}"),
+                    error: format!("Failed to spawn {program}: {e}\ncommand: {}", command_line(&program, &args)),
                 });
                 return;
             }
         };
 
+        let _ = tx.send(WorkerMessage::Spawned { prompt_id, pid: child.id() });
         let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
 
         // Reader thread: parse JSON lines from stdout, extract text deltas
         let reader_tx = tx.clone();
         let reader_handle = std::thread::spawn(move || {
             read_stream_json(prompt_id, stdout, &reader_tx);
         });
+        let stderr_tx = tx.clone();
+        let stderr_handle = std::thread::spawn(move || {
+            read_stderr(prompt_id, stderr, &stderr_tx);
+        });
 
         let exit_code = match child.wait() {
             Ok(status) => status.code(),
@@ -130,6 +360,7 @@ fn spawn_oneshot(
         };
 
         let _ = reader_handle.join();
+        let _ = stderr_handle.join();
 
         let _ = tx.send(WorkerMessage::Finished {
             prompt_id,
@@ -138,13 +369,39 @@ fn spawn_oneshot(
     });
 }
 
-/// Parses stream-json lines from stdout, sends OutputChunk messages.
+/// Forwards stderr line by line as `OutputStream::Stderr` chunks, so they
+/// interleave with stdout in arrival order. Invalid UTF-8 is replaced
+/// rather than ending the stream, so the pipe is drained until EOF.
+fn read_stderr<R: std::io::Read>(prompt_id: usize, stderr: R, tx: &mpsc::UnboundedSender<WorkerMessage>) {
+    let mut reader = BufReader::new(stderr);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches('\n').trim_end_matches('\r');
+        let _ = tx.send(WorkerMessage::OutputChunk {
+            prompt_id,
+            stream: OutputStream::Stderr,
+            text: format!("{line}\n"),
+        });
+    }
+}
+
+/// Parses stream-json lines from stdout, sends OutputChunk messages. Tool
+/// calls become a marker line of their own.
 fn read_stream_json(
     prompt_id: usize,
     stdout: std::process::ChildStdout,
     tx: &mpsc::UnboundedSender<WorkerMessage>,
 ) {
     let reader = BufReader::new(stdout);
+    let mut progress = ProgressCounter::default();
+    let mut at_line_start = true;
+    let mut in_tool = false;
     for line in reader.lines() {
         let line = match line {
             Ok(l) => l,
@@ -154,31 +411,240 @@ fn read_stream_json(
             continue;
         }
 
-        let json: serde_json::Value = match serde_json::from_str(&line) {
-            Ok(v) => v,
-            Err(_) => continue,
+        let text = match parser::parse_line(&line) {
+            ParsedEvent::SessionId(session_id) => {
+                let _ = tx.send(WorkerMessage::SessionId { prompt_id, session_id });
+                continue;
+            }
+            ParsedEvent::TextDelta(text) => {
+                if std::mem::take(&mut in_tool) {
+                    let _ = tx.send(WorkerMessage::Activity { prompt_id, activity: None });
+                }
+                text
+            }
+            ParsedEvent::ToolCall(name) => {
+                in_tool = true;
+                let _ = tx.send(WorkerMessage::Activity { prompt_id, activity: Some(name.clone()) });
+                let marker = parser::tool_marker(&name);
+                if at_line_start { marker } else { format!("\n{marker}") }
+            }
+            ParsedEvent::ToolResult => {
+                if std::mem::take(&mut in_tool) {
+                    let _ = tx.send(WorkerMessage::Activity { prompt_id, activity: None });
+                }
+                continue;
+            }
+            // The process exiting reports the end of a one-shot turn
+            ParsedEvent::TurnEnd | ParsedEvent::Other | ParsedEvent::Invalid => continue,
         };
+        at_line_start = text.ends_with('\n');
+        progress.record(text.as_bytes());
+        let _ = tx.send(WorkerMessage::OutputChunk {
+            prompt_id,
+            stream: OutputStream::Stdout,
+            text,
+        });
+        if let Some((bytes, lines)) = progress.take_due(Instant::now()) {
+            let _ = tx.send(WorkerMessage::Progress { prompt_id, bytes, lines });
+        }
+    }
+}
 
-        // Capture session_id from init message
-        if json["type"] == "system" {
-            if let Some(session_id) = json["session_id"].as_str() {
-                let _ = tx.send(WorkerMessage::SessionId {
-                    prompt_id,
-                    session_id: session_id.to_string(),
-                });
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    // ── ProgressCounter ──
+
+    #[test]
+    fn progress_counts_bytes_and_lines() {
+        let mut progress = ProgressCounter::default();
+        progress.record(b"one\ntwo\n");
+        progress.record(b"thr");
+        assert_eq!(progress.take_due(Instant::now()), Some((11, 2)));
+    }
+
+    #[test]
+    fn progress_is_throttled() {
+        let mut progress = ProgressCounter::default();
+        let start = Instant::now();
+        assert!(progress.take_due(start).is_some());
+        progress.record(b"x");
+        assert_eq!(progress.take_due(start + Duration::from_millis(500)), None);
+        assert_eq!(progress.take_due(start + PROGRESS_INTERVAL), Some((1, 0)));
+    }
+
+    // ── read_stderr ──
+
+    #[test]
+    fn stderr_is_forwarded_per_line() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        read_stderr(3, &b"Error: not logged in\nhint: run login"[..], &tx);
+        let mut chunks = Vec::new();
+        while let Ok(WorkerMessage::OutputChunk { prompt_id, stream, text }) = rx.try_recv() {
+            assert_eq!(prompt_id, 3);
+            assert_eq!(stream, OutputStream::Stderr);
+            chunks.push(text);
         }
+        assert_eq!(chunks, ["Error: not logged in\n", "hint: run login\n"]);
+    }
 
-        // Extract streaming text deltas
-        if json["type"] == "stream_event" {
-            if let Some(text) = json["event"]["delta"]["text"].as_str() {
-                if !text.is_empty() {
-                    let _ = tx.send(WorkerMessage::OutputChunk {
-                        prompt_id,
-                        text: text.to_string(),
-                    });
-                }
-            }
+    #[test]
+    fn stderr_survives_invalid_utf8() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        read_stderr(1, &b"bad \xff byte\r\nstill read\n"[..], &tx);
+        let mut chunks = Vec::new();
+        while let Ok(WorkerMessage::OutputChunk { text, .. }) = rx.try_recv() {
+            chunks.push(text);
         }
+        assert_eq!(chunks, ["bad \u{FFFD} byte\n", "still read\n"]);
+    }
+
+    // ── AgentCommand::resolve ──
+
+    #[test]
+    fn resolve_defaults_to_claude() {
+        let agent = AgentCommand::resolve(None, None, None);
+        assert_eq!(agent, AgentCommand::default());
+        assert_eq!(agent.program, "claude");
+    }
+
+    #[test]
+    fn resolve_uses_config_command() {
+        let agent = AgentCommand::resolve(None, Some("my-wrapper".into()), Some(strings(&["--x"])));
+        assert_eq!(agent.program, "my-wrapper");
+        assert_eq!(agent.args, strings(&["--x"]));
+    }
+
+    #[test]
+    fn resolve_env_overrides_config() {
+        let agent = AgentCommand::resolve(Some("env-agent".into()), Some("my-wrapper".into()), None);
+        assert_eq!(agent.program, "env-agent");
+    }
+
+    #[test]
+    fn resolve_ignores_empty_values() {
+        let agent = AgentCommand::resolve(Some("".into()), Some("  ".into()), None);
+        assert_eq!(agent.program, "claude");
+    }
+
+    // ── argv building ──
+
+    #[test]
+    fn oneshot_args_default() {
+        let agent = AgentCommand::default();
+        assert_eq!(
+            agent.oneshot_args("hello", "/tmp", &SessionMode::New),
+            strings(&[
+                "-p",
+                "hello",
+                "--output-format",
+                "stream-json",
+                "--verbose",
+                "--include-partial-messages",
+                "--dangerously-skip-permissions",
+            ])
+        );
+    }
+
+    #[test]
+    fn oneshot_args_resume_and_placeholders() {
+        let agent = AgentCommand {
+            program: "wrap".into(),
+            args: strings(&["--add-dir", "{cwd}", "--title={prompt}"]),
+            wrapper: Vec::new(),
+        };
+        let args = agent.oneshot_args("fix it", "/repo", &SessionMode::Resume("abc".into()));
+        assert_eq!(
+            &args[7..],
+            &strings(&["--resume", "abc", "--add-dir", "/repo", "--title=fix it"])[..]
+        );
+    }
+
+    #[test]
+    fn pty_args_default() {
+        let agent = AgentCommand::default();
+        assert_eq!(
+            agent.pty_args("hello", "/tmp", &SessionMode::New),
+            strings(&["hello", "--dangerously-skip-permissions"])
+        );
+    }
+
+    #[test]
+    fn pty_args_resume_replaces_prompt() {
+        let agent = AgentCommand::default();
+        assert_eq!(
+            agent.pty_args("hello", "/tmp", &SessionMode::Resume(String::new())),
+            strings(&["--resume", "--dangerously-skip-permissions"])
+        );
+        assert_eq!(
+            agent.pty_args("hello", "/tmp", &SessionMode::Resume("abc".into())),
+            strings(&["--resume", "abc", "--dangerously-skip-permissions"])
+        );
+    }
+
+    #[test]
+    fn continue_args_keep_prompt() {
+        let agent = AgentCommand::default();
+        let session = SessionMode::Continue("abc".into());
+        assert_eq!(
+            agent.pty_args("next step", "/tmp", &session),
+            strings(&["--resume", "abc", "next step", "--dangerously-skip-permissions"])
+        );
+        let args = agent.oneshot_args("next step", "/tmp", &session);
+        assert_eq!(&args[..2], &strings(&["-p", "next step"])[..]);
+        assert_eq!(&args[7..], &strings(&["--resume", "abc"])[..]);
+    }
+
+    #[test]
+    fn pty_args_with_placeholders() {
+        let agent = AgentCommand {
+            program: "wrap".into(),
+            args: strings(&["--cwd", "{cwd}"]),
+            wrapper: Vec::new(),
+        };
+        assert_eq!(
+            agent.pty_args("hi", "/repo", &SessionMode::New),
+            strings(&["hi", "--dangerously-skip-permissions", "--cwd", "/repo"])
+        );
+    }
+
+    #[test]
+    fn wrap_without_wrapper_runs_agent() {
+        let agent = AgentCommand::default();
+        let (program, argv) = agent.wrap(strings(&["-p", "hi"]), "/repo");
+        assert_eq!(program, "claude");
+        assert_eq!(argv, strings(&["-p", "hi"]));
+    }
+
+    #[test]
+    fn wrap_prepends_wrapper_with_cwd() {
+        let agent = AgentCommand {
+            wrapper: strings(&["docker", "run", "--rm", "-it", "-v", "{cwd}:{cwd}", "-w", "{cwd}", "img"]),
+            ..AgentCommand::default()
+        };
+        let args = agent.pty_args("hi", "/repo", &SessionMode::New);
+        let (program, argv) = agent.wrap(args, "/repo");
+        assert_eq!(program, "docker");
+        assert_eq!(
+            argv,
+            strings(&[
+                "run", "--rm", "-it", "-v", "/repo:/repo", "-w", "/repo", "img",
+                "claude", "hi", "--dangerously-skip-permissions",
+            ])
+        );
+    }
+
+    #[test]
+    fn command_line_quotes_only_when_needed() {
+        assert_eq!(
+            command_line("claude", &strings(&["-p", "fix the bug", "--output-format", "stream-json"])),
+            "claude -p 'fix the bug' --output-format stream-json"
+        );
+        assert_eq!(command_line("claude", &strings(&["it's", ""])), "claude 'it'\\''s' ''");
     }
 }
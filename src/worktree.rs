@@ -30,6 +30,25 @@ pub fn repo_root(path: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Name of the branch checked out at `path`; `None` outside a repo or on a
+/// detached HEAD.
+pub fn current_branch(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["-C", &path.to_string_lossy(), "rev-parse", "--abbrev-ref", "HEAD"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if branch.is_empty() || branch == "HEAD" {
+        None
+    } else {
+        Some(branch)
+    }
+}
+
 /// Get the repo directory name for worktree naming.
 pub fn repo_name(root: &Path) -> String {
     root.file_name()
@@ -77,31 +96,153 @@ pub fn worktree_exists(repo_root: &Path, worktree_path: &Path) -> bool {
     false
 }
 
-/// Create a detached worktree: git worktree add --detach <path> HEAD
-/// Returns the worktree path on success. If the worktree already exists
-/// (e.g. when resuming a prompt), it is reused.
-pub fn create_worktree(repo_root: &Path, prompt_id: usize) -> Result<PathBuf, String> {
+/// Find the repo a worktree belongs to among its sibling directories
+/// (worktrees are created next to their repo).
+pub fn owning_repo(worktree_path: &Path) -> Option<PathBuf> {
+    let parent = worktree_path.parent()?;
+    let mut siblings: Vec<PathBuf> = std::fs::read_dir(parent)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && path != worktree_path)
+        .collect();
+    siblings.sort();
+    siblings.into_iter().find_map(|path| {
+        let root = repo_root(&path)?;
+        worktree_exists(&root, worktree_path).then_some(root)
+    })
+}
+
+/// Words of the prompt text kept in a `{slug}`.
+const SLUG_WORDS: usize = 5;
+/// Maximum length of a `{slug}`.
+const SLUG_MAX_LEN: usize = 40;
+
+/// Git-ref-safe slug from the first few words of a prompt: lowercase ASCII
+/// alphanumerics joined by dashes, other characters dropped, truncated.
+pub fn slugify(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '/')
+        .map(|w| {
+            w.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .collect::<String>()
+                .to_ascii_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .take(SLUG_WORDS)
+        .collect();
+    let mut slug = words.join("-");
+    if slug.len() > SLUG_MAX_LEN {
+        slug.truncate(SLUG_MAX_LEN);
+        slug.truncate(slug.trim_end_matches('-').len());
+    }
+    if slug.is_empty() {
+        slug.push_str("prompt");
+    }
+    slug
+}
+
+/// Expand a `worktree_branch_template` such as `clhorde/{id}-{slug}`.
+pub fn expand_branch_template(template: &str, prompt_id: usize, prompt_text: &str) -> String {
+    template
+        .replace("{id}", &prompt_id.to_string())
+        .replace("{slug}", &slugify(prompt_text))
+}
+
+/// First of `base`, `base-2`, `base-3`, ... that is not `taken`.
+fn unique_name(base: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{base}-{n}"))
+        .find(|name| !taken(name))
+        .unwrap_or_default()
+}
+
+/// Check if a local branch exists: git show-ref --verify refs/heads/<branch>
+pub fn branch_exists(repo_root: &Path, branch: &str) -> bool {
+    Command::new("git")
+        .args([
+            "-C",
+            &repo_root.to_string_lossy(),
+            "show-ref",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{branch}"),
+        ])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Check a name is a valid branch: git check-ref-format --branch <name>
+fn valid_branch_name(branch: &str) -> bool {
+    Command::new("git")
+        .args(["check-ref-format", "--branch", branch])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Create a worktree for a prompt. Without a branch template this is a
+/// detached worktree at `../<repo>-wt-<id>`, reused if it already exists
+/// (e.g. when resuming a prompt). With a template such as
+/// `clhorde/{id}-{slug}`, a new branch of that name is created at
+/// `../<repo>-wt-<branch>` (slashes become dashes); if the branch or
+/// directory is taken, `-2`, `-3`, ... is appended.
+/// Returns the worktree path on success.
+pub fn create_worktree(
+    repo_root: &Path,
+    prompt_id: usize,
+    prompt_text: &str,
+    branch_template: Option<&str>,
+) -> Result<PathBuf, String> {
     let name = repo_name(repo_root);
-    let wt_dir = format!("{name}-wt-{prompt_id}");
     let parent = repo_root
         .parent()
         .ok_or_else(|| "Cannot determine parent directory of repo root".to_string())?;
-    let wt_path = parent.join(&wt_dir);
 
-    if worktree_exists(repo_root, &wt_path) {
+    let Some(template) = branch_template else {
+        let wt_path = parent.join(format!("{name}-wt-{prompt_id}"));
+        if worktree_exists(repo_root, &wt_path) {
+            return Ok(wt_path);
+        }
+        worktree_add(repo_root, &wt_path, None)?;
         return Ok(wt_path);
+    };
+
+    let base = expand_branch_template(template, prompt_id, prompt_text);
+    if !valid_branch_name(&base) {
+        return Err(format!("Invalid worktree branch name '{base}' from template '{template}'"));
+    }
+    let wt_path_for = |branch: &str| parent.join(format!("{name}-wt-{}", branch.replace('/', "-")));
+    let branch = unique_name(&base, |b| {
+        wt_path_for(b).exists() || branch_exists(repo_root, b)
+    });
+    let wt_path = wt_path_for(&branch);
+    worktree_add(repo_root, &wt_path, Some(&branch))?;
+    Ok(wt_path)
+}
+
+/// git worktree add (--detach | -b <branch>) <path> HEAD
+fn worktree_add(repo_root: &Path, wt_path: &Path, branch: Option<&str>) -> Result<(), String> {
+    let root = repo_root.to_string_lossy();
+    let path = wt_path.to_string_lossy();
+    let mut args = vec!["-C", &root, "worktree", "add"];
+    match branch {
+        Some(branch) => args.extend(["-b", branch]),
+        None => args.push("--detach"),
     }
+    args.extend([&*path, "HEAD"]);
 
     let output = Command::new("git")
-        .args([
-            "-C",
-            &repo_root.to_string_lossy(),
-            "worktree",
-            "add",
-            "--detach",
-            &wt_path.to_string_lossy(),
-            "HEAD",
-        ])
+        .args(&args)
         .stderr(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .output()
@@ -112,7 +253,186 @@ pub fn create_worktree(repo_root: &Path, prompt_id: usize) -> Result<PathBuf, St
         return Err(format!("git worktree add failed: {stderr}"));
     }
 
-    Ok(wt_path)
+    Ok(())
+}
+
+/// Commit everything in a worktree: git add -A && git commit -m <message>
+/// Returns the short hash of the new commit, or `None` if there were no
+/// changes to commit.
+pub fn commit_all(worktree_path: &Path, message: &str) -> Result<Option<String>, String> {
+    if !is_git_repo(worktree_path) {
+        return Err(format!("{} is not a git worktree", worktree_path.display()));
+    }
+    let run = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(worktree_path)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run git {}: {e}", args[0]))
+    };
+
+    let add = run(&["add", "-A"])?;
+    if !add.status.success() {
+        return Err(format!("git add failed: {}", String::from_utf8_lossy(&add.stderr)));
+    }
+    // diff --cached --quiet exits 0 when nothing is staged
+    if run(&["diff", "--cached", "--quiet"])?.status.success() {
+        return Ok(None);
+    }
+    let commit = run(&["commit", "-q", "-m", message])?;
+    if !commit.status.success() {
+        return Err(format!("git commit failed: {}", String::from_utf8_lossy(&commit.stderr)));
+    }
+    let rev = run(&["rev-parse", "--short", "HEAD"])?;
+    Ok(Some(String::from_utf8_lossy(&rev.stdout).trim().to_string()))
+}
+
+/// What the agent changed in a worktree: `git diff --stat` and the full
+/// diff against the commit the worktree was branched from (so auto-committed
+/// work still shows), then any untracked files. Empty when nothing changed.
+pub fn diff(worktree_path: &Path) -> Result<String, String> {
+    if !is_git_repo(worktree_path) {
+        return Err(format!("{} is not a git worktree", worktree_path.display()));
+    }
+    let run = |args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(worktree_path)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run git {}: {e}", args[0]))?;
+        if !output.status.success() {
+            return Err(format!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let base = fork_point(worktree_path).unwrap_or_else(|| "HEAD".to_string());
+    let stat = run(&["diff", "--no-color", "--stat", &base])?;
+    let patch = run(&["diff", "--no-color", &base])?;
+    let untracked = run(&["ls-files", "--others", "--exclude-standard"])?;
+
+    let mut out = String::new();
+    if !stat.is_empty() {
+        out.push_str(&stat);
+        out.push('\n');
+        out.push_str(&patch);
+    }
+    if !untracked.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str("Untracked files:\n");
+        for file in untracked.lines() {
+            out.push_str("  ");
+            out.push_str(file);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// Commit a worktree was branched from: where its HEAD meets the HEAD of
+/// the repo it belongs to. `None` if that repo can't be found.
+fn fork_point(worktree_path: &Path) -> Option<String> {
+    let rev = |dir: &Path, args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()?;
+        let rev = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !rev.is_empty()).then_some(rev)
+    };
+    let main_head = rev(&owning_repo(worktree_path)?, &["rev-parse", "HEAD"])?;
+    rev(worktree_path, &["merge-base", "HEAD", &main_head])
+}
+
+/// Merge a worktree's work into the checkout of the repo it belongs to.
+/// Uncommitted changes are committed first (with `message`), then the
+/// worktree's HEAD is merged with `--no-ff`. A conflicting merge is aborted,
+/// leaving the checkout as it was. Returns the short hash of the merge
+/// commit, or `None` when the checkout already has everything. Nothing is
+/// committed unless the merge is going to be attempted.
+pub fn apply(worktree_path: &Path, prompt_id: usize, message: &str) -> Result<Option<String>, String> {
+    let root = owning_repo(worktree_path)
+        .ok_or_else(|| format!("Cannot find the repo {} belongs to", worktree_path.display()))?;
+    let git = |dir: &Path, args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run git {}: {e}", args[0]))
+    };
+    let stdout = |output: &std::process::Output| String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    // Aborting a conflicted merge can't be trusted to restore local edits
+    let status = git(&root, &["status", "--porcelain", "--untracked-files=no"])?;
+    if !stdout(&status).is_empty() {
+        return Err(format!("{} has uncommitted changes", root.display()));
+    }
+    // Uncommitted work always needs a merge; otherwise the checkout may
+    // already contain the worktree's HEAD
+    let pending = git(worktree_path, &["status", "--porcelain"])?;
+    if stdout(&pending).is_empty() {
+        let head = stdout(&git(worktree_path, &["rev-parse", "HEAD"])?);
+        if git(&root, &["merge-base", "--is-ancestor", &head, "HEAD"])?.status.success() {
+            return Ok(None);
+        }
+    } else {
+        commit_all(worktree_path, message)?;
+    }
+    let head = stdout(&git(worktree_path, &["rev-parse", "HEAD"])?);
+
+    let merge = git(&root, &["merge", "--no-ff", "-m", &merge_subject(prompt_id, message), &head])?;
+    if !merge.status.success() {
+        let conflicts = git(&root, &["diff", "--name-only", "--diff-filter=U"])
+            .map(|output| stdout(&output))
+            .unwrap_or_default();
+        // A merge git refused to start (e.g. it would overwrite untracked
+        // files) leaves nothing to abort
+        let merging = git(&root, &["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_ok_and(|o| o.status.success());
+        let abort_error = match merging.then(|| git(&root, &["merge", "--abort"])) {
+            None => None,
+            Some(Ok(output)) if output.status.success() => None,
+            Some(Ok(output)) => Some(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+            Some(Err(e)) => Some(e),
+        };
+        if let Some(error) = abort_error {
+            return Err(format!(
+                "git merge failed and so did git merge --abort ({error}); {} is left mid-merge",
+                root.display()
+            ));
+        }
+        return Err(if conflicts.is_empty() {
+            format!("git merge failed: {}", String::from_utf8_lossy(&merge.stderr).trim())
+        } else {
+            format!("merge conflicts in {}; nothing was merged", conflicts.lines().collect::<Vec<_>>().join(", "))
+        });
+    }
+    Ok(Some(stdout(&git(&root, &["rev-parse", "--short", "HEAD"])?)))
+}
+
+/// Subject of the merge commit `apply` makes: `Merge clhorde #<id>:` and
+/// the first line of the prompt, cut short if it's long.
+fn merge_subject(prompt_id: usize, text: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let first = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+    if first.is_empty() {
+        return format!("Merge clhorde #{prompt_id}");
+    }
+    let mut line: String = first.chars().take(MAX_CHARS).collect();
+    if first.chars().count() > MAX_CHARS {
+        line.push('…');
+    }
+    format!("Merge clhorde #{prompt_id}: {line}")
 }
 
 /// Remove a worktree: git worktree remove <path> --force
@@ -206,14 +526,25 @@ mod tests {
         let prompt_id = 42;
 
         // First call: creates the worktree.
-        let wt = create_worktree(&repo, prompt_id).expect("first create");
+        let wt = create_worktree(&repo, prompt_id, "", None).expect("first create");
         assert!(wt.is_dir(), "worktree directory should exist");
 
         // Second call with the same id: should succeed by reusing.
-        let wt2 = create_worktree(&repo, prompt_id).expect("second create (reuse)");
+        let wt2 = create_worktree(&repo, prompt_id, "", None).expect("second create (reuse)");
         assert_eq!(wt, wt2);
     }
 
+    #[test]
+    fn current_branch_of_repo() {
+        let (_tmp, repo) = make_temp_repo();
+        Command::new("git")
+            .args(["-C", &repo.to_string_lossy(), "checkout", "-q", "-b", "feature/x"])
+            .status()
+            .expect("git checkout");
+        assert_eq!(current_branch(&repo).as_deref(), Some("feature/x"));
+        assert_eq!(current_branch(Path::new("/tmp")), None);
+    }
+
     #[test]
     fn worktree_exists_false_for_non_worktree() {
         let (_tmp, repo) = make_temp_repo();
@@ -225,7 +556,7 @@ mod tests {
     #[test]
     fn worktree_exists_true_after_creation() {
         let (_tmp, repo) = make_temp_repo();
-        let wt = create_worktree(&repo, 99).expect("create");
+        let wt = create_worktree(&repo, 99, "", None).expect("create");
         assert!(worktree_exists(&repo, &wt));
     }
 
@@ -235,4 +566,199 @@ mod tests {
         let missing = repo.parent().unwrap().join("does-not-exist");
         assert!(!worktree_exists(&repo, &missing));
     }
+
+    #[test]
+    fn slugify_keeps_first_words() {
+        assert_eq!(slugify("Fix the navbar's hover state on mobile"), "fix-the-navbars-hover-state");
+        assert_eq!(slugify("  Add   API/v2 endpoint!  "), "add-api-v2-endpoint");
+        assert_eq!(slugify("snake_case and kebab-case"), "snake-case-and-kebab-case");
+    }
+
+    #[test]
+    fn slugify_truncates_without_trailing_dash() {
+        let slug = slugify("internationalization localization accessibility documentation");
+        assert!(slug.len() <= SLUG_MAX_LEN);
+        assert!(!slug.ends_with('-'));
+        assert!(slug.starts_with("internationalization-localization"));
+    }
+
+    #[test]
+    fn slugify_falls_back_when_empty() {
+        assert_eq!(slugify("!!! ???"), "prompt");
+        assert_eq!(slugify("日本語"), "prompt");
+    }
+
+    #[test]
+    fn expand_branch_template_substitutes_placeholders() {
+        assert_eq!(
+            expand_branch_template("clhorde/{id}-{slug}", 7, "Refactor worker spawn"),
+            "clhorde/7-refactor-worker-spawn"
+        );
+        assert_eq!(expand_branch_template("wt-{id}", 3, "anything"), "wt-3");
+    }
+
+    #[test]
+    fn unique_name_appends_counter_on_collision() {
+        assert_eq!(unique_name("a", |_| false), "a");
+        let taken = ["a", "a-2"];
+        assert_eq!(unique_name("a", |n| taken.contains(&n)), "a-3");
+    }
+
+    #[test]
+    fn create_worktree_with_template_makes_unique_branches() {
+        let (_tmp, repo) = make_temp_repo();
+        let template = Some("clhorde/{slug}");
+
+        let wt = create_worktree(&repo, 1, "Fix tests", template).expect("first create");
+        assert!(wt.ends_with("testrepo-wt-clhorde-fix-tests"));
+        assert!(branch_exists(&repo, "clhorde/fix-tests"));
+
+        let wt2 = create_worktree(&repo, 2, "Fix tests", template).expect("second create");
+        assert!(wt2.ends_with("testrepo-wt-clhorde-fix-tests-2"));
+        assert!(branch_exists(&repo, "clhorde/fix-tests-2"));
+    }
+
+    #[test]
+    fn create_worktree_rejects_invalid_template() {
+        let (_tmp, repo) = make_temp_repo();
+        let err = create_worktree(&repo, 1, "x", Some("bad..name")).unwrap_err();
+        assert!(err.contains("Invalid worktree branch name"));
+    }
+
+    #[test]
+    fn commit_all_commits_changes_once() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 5, "", None).expect("create");
+        fs::write(wt.join("new.txt"), "hello").unwrap();
+
+        let hash = commit_all(&wt, "Add new.txt").expect("commit");
+        assert!(hash.is_some_and(|h| !h.is_empty()));
+        assert_eq!(commit_all(&wt, "again").expect("no-op"), None);
+    }
+
+    /// Commit `content` to `file` in the main checkout.
+    fn commit_in_repo(repo: &Path, file: &str, content: &str) {
+        fs::write(repo.join(file), content).unwrap();
+        for args in [&["add", "-A"][..], &["commit", "-q", "-m", file]] {
+            Command::new("git").arg("-C").arg(repo).args(args).status().expect("git");
+        }
+    }
+
+    #[test]
+    fn apply_merges_worktree_changes_once() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 6, "", None).expect("create");
+        commit_in_repo(&repo, "main.txt", "main\n");
+        fs::write(wt.join("agent.txt"), "agent\n").unwrap();
+
+        let hash = apply(&wt, 6, "Add agent.txt\n\nwith details").expect("apply");
+        assert!(hash.is_some_and(|h| !h.is_empty()));
+        let subject = Command::new("git").arg("-C").arg(&repo).args(["log", "-1", "--format=%s"]).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&subject.stdout).trim(), "Merge clhorde #6: Add agent.txt");
+        assert_eq!(fs::read_to_string(repo.join("agent.txt")).unwrap(), "agent\n");
+        assert_eq!(apply(&wt, 6, "again").expect("no-op"), None);
+    }
+
+    #[test]
+    fn apply_aborts_a_conflicting_merge() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 7, "", None).expect("create");
+        commit_in_repo(&repo, "shared.txt", "main\n");
+        fs::write(wt.join("shared.txt"), "agent\n").unwrap();
+
+        let err = apply(&wt, 7, "Edit shared.txt").unwrap_err();
+        assert!(err.contains("merge conflicts in shared.txt"), "{err}");
+        assert_eq!(fs::read_to_string(repo.join("shared.txt")).unwrap(), "main\n");
+        let status = Command::new("git").arg("-C").arg(&repo).args(["status", "--porcelain"]).output().unwrap();
+        assert!(status.stdout.is_empty(), "{}", String::from_utf8_lossy(&status.stdout));
+    }
+
+    #[test]
+    fn apply_reports_a_merge_git_refused_to_start() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 10, "", None).expect("create");
+        fs::write(wt.join("agent.txt"), "agent\n").unwrap();
+        fs::write(repo.join("agent.txt"), "untracked\n").unwrap();
+
+        let err = apply(&wt, 10, "Add agent.txt").unwrap_err();
+        assert!(err.starts_with("git merge failed:"), "{err}");
+        assert_eq!(fs::read_to_string(repo.join("agent.txt")).unwrap(), "untracked\n");
+    }
+
+    #[test]
+    fn merge_subject_uses_first_line() {
+        assert_eq!(merge_subject(3, "\n  Fix it\nmore"), "Merge clhorde #3: Fix it");
+        assert_eq!(merge_subject(3, ""), "Merge clhorde #3");
+        let long = "x".repeat(80);
+        assert_eq!(merge_subject(4, &long), format!("Merge clhorde #4: {}…", "x".repeat(60)));
+    }
+
+    #[test]
+    fn apply_refuses_a_dirty_checkout() {
+        let (_tmp, repo) = make_temp_repo();
+        commit_in_repo(&repo, "main.txt", "main\n");
+        let wt = create_worktree(&repo, 8, "", None).expect("create");
+        fs::write(wt.join("agent.txt"), "agent\n").unwrap();
+        fs::write(repo.join("main.txt"), "edited\n").unwrap();
+
+        let err = apply(&wt, 8, "Add agent.txt").unwrap_err();
+        assert!(err.ends_with("has uncommitted changes"), "{err}");
+        assert!(!repo.join("agent.txt").exists());
+        // The worktree's changes are left uncommitted for the next try
+        let status = Command::new("git").arg("-C").arg(&wt).args(["status", "--porcelain"]).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&status.stdout), "?? agent.txt\n");
+    }
+
+    #[test]
+    fn apply_merges_committed_work_without_new_changes() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 9, "", None).expect("create");
+        fs::write(wt.join("agent.txt"), "agent\n").unwrap();
+        commit_all(&wt, "Add agent.txt").expect("commit");
+
+        assert!(apply(&wt, 9, "Merge agent work").expect("apply").is_some());
+        assert_eq!(fs::read_to_string(repo.join("agent.txt")).unwrap(), "agent\n");
+        assert_eq!(apply(&wt, 9, "again").expect("no-op"), None);
+    }
+
+    #[test]
+    fn diff_covers_committed_uncommitted_and_untracked_changes() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 11, "", None).expect("create");
+        assert_eq!(diff(&wt).expect("clean diff"), "");
+
+        fs::write(wt.join("committed.txt"), "one\n").unwrap();
+        commit_all(&wt, "Add committed.txt").expect("commit");
+        fs::write(wt.join("committed.txt"), "one\ntwo\n").unwrap();
+        fs::write(wt.join("untracked.txt"), "x\n").unwrap();
+
+        let out = diff(&wt).expect("diff");
+        assert!(out.contains("committed.txt | 2 ++"), "{out}");
+        assert!(out.contains("+two"), "{out}");
+        assert!(out.ends_with("Untracked files:\n  untracked.txt\n"), "{out}");
+    }
+
+    #[test]
+    fn diff_errors_outside_repo() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(diff(tmp.path()).is_err());
+    }
+
+    #[test]
+    fn commit_all_errors_outside_repo() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(commit_all(tmp.path(), "msg").is_err());
+    }
+
+    #[test]
+    fn owning_repo_finds_sibling_repo() {
+        let (_tmp, repo) = make_temp_repo();
+        let wt = create_worktree(&repo, 8, "", None).expect("create");
+        let owner = owning_repo(&wt).expect("owner");
+        assert_eq!(owner.canonicalize().unwrap(), repo.canonicalize().unwrap());
+
+        let stray = repo.parent().unwrap().join("stray");
+        fs::create_dir(&stray).unwrap();
+        assert!(owning_repo(&stray).is_none());
+    }
 }